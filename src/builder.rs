@@ -0,0 +1,256 @@
+//! Builds a [`RenderingContext`]: the document, its style sheets, the target media, and where its resources come from.
+use std::{hash::Hash, path::PathBuf};
+
+use crate::{
+    dom::{NodeKind, TDocumentObjectModelExplorer, TElement, TText},
+    layout::px_to_units,
+    resource::ResourceLoader,
+    style::{
+        cascade::{Origin, StyleSheet, PRINT_MEDIA},
+        values::r#box::BoxContent,
+    },
+    RenderingContext, RenderingContextArgs,
+};
+
+/// Configures a [`RenderingContext`].
+///
+/// The style sheets cascade in this order: the user agent style sheet, the style sheets of the document,
+/// from its `style` elements and the `link` elements to style sheets, in tree order, then the given style sheets.
+pub struct RenderingContextBuilder<'a, Dom> {
+    dom: &'a Dom,
+    stylesheets: Vec<StyleSheet>,
+    user_agent_stylesheet: bool,
+    media: String,
+    viewport: Option<BoxContent<f64>>,
+    resource_loader: Option<Box<dyn ResourceLoader + 'a>>,
+    fonts_directories: Vec<PathBuf>,
+    bucket_size: Option<usize>,
+    cache_size: Option<usize>,
+}
+
+impl<'a, Dom> RenderingContextBuilder<'a, Dom>
+where Dom: TDocumentObjectModelExplorer + Sync, Dom::NodeId: Hash + Copy + Eq
+{
+    pub fn new(dom: &'a Dom) -> Self {
+        Self {
+            dom,
+            stylesheets: Vec::new(),
+            user_agent_stylesheet: true,
+            media: PRINT_MEDIA.to_owned(),
+            viewport: None,
+            resource_loader: None,
+            fonts_directories: Vec::new(),
+            bucket_size: None,
+            cache_size: None,
+        }
+    }
+
+    /// Adds a style sheet, after the ones of the document.
+    pub fn stylesheet(mut self, stylesheet: StyleSheet) -> Self {
+        self.stylesheets.push(stylesheet);
+        self
+    }
+
+    /// Adds style sheets, after the ones of the document.
+    pub fn stylesheets(mut self, stylesheets: impl IntoIterator<Item = StyleSheet>) -> Self {
+        self.stylesheets.extend(stylesheets);
+        self
+    }
+
+    /// Cascades the user agent style sheet first, which is the default.
+    pub fn user_agent_stylesheet(mut self, enabled: bool) -> Self {
+        self.user_agent_stylesheet = enabled;
+        self
+    }
+
+    /// The media type matched by the @media rules of the style sheets of the document, `print` by default.
+    pub fn media(mut self, media: &str) -> Self {
+        self.media = media.to_owned();
+        self
+    }
+
+    /// The size of the target media, in CSS pixels: the viewport of the document,
+    /// and the size of the pages whose size is auto.
+    pub fn viewport(mut self, viewport: BoxContent<f64>) -> Self {
+        self.viewport = Some(viewport);
+        self
+    }
+
+    /// Loads the style sheets linked by the document. Without a loader, they are ignored.
+    pub fn resource_loader(mut self, loader: impl ResourceLoader + 'a) -> Self {
+        self.resource_loader = Some(Box::new(loader));
+        self
+    }
+
+    /// Searches the fonts of a directory before the fonts of the system.
+    pub fn fonts_directory(mut self, path: impl Into<PathBuf>) -> Self {
+        self.fonts_directories.push(path.into());
+        self
+    }
+
+    /// The initial capacity of the arenas holding the nodes, boxes and fragments, and their components, 100 by default.
+    ///
+    /// The arenas grow past it.
+    pub fn bucket_size(mut self, bucket_size: usize) -> Self {
+        self.bucket_size = Some(bucket_size);
+        self
+    }
+
+    /// The initial capacity of the maps binding components to the nodes, boxes and fragments.
+    pub fn cache_size(mut self, cache_size: usize) -> Self {
+        self.cache_size = Some(cache_size);
+        self
+    }
+
+    /// Collects the style sheets, and creates the context.
+    ///
+    /// The style sheets of the document which cannot be loaded, or parsed, are ignored.
+    pub fn build(self) -> RenderingContext<'a, Dom> {
+        let mut ctx = RenderingContext::new(RenderingContextArgs {
+            dom: self.dom,
+            bucket_size: self.bucket_size,
+            cache_size: self.cache_size,
+        });
+
+        if let Some(viewport) = &self.viewport {
+            ctx.fragments.initial_containing_block = BoxContent {
+                width: px_to_units(viewport.width),
+                height: px_to_units(viewport.height),
+            };
+        }
+
+        for directory in &self.fonts_directories {
+            ctx.add_fonts_directory(directory);
+        }
+
+        if self.user_agent_stylesheet {
+            ctx.stylesheets.push(StyleSheet::user_agent());
+        }
+
+        ctx.stylesheets.extend(self.document_stylesheets());
+        ctx.stylesheets.extend(self.stylesheets);
+        ctx
+    }
+
+    /// Parses the contents of the `style` elements, and the style sheets of the `link` elements, in tree order.
+    fn document_stylesheets(&self) -> Vec<StyleSheet> {
+        let mut stylesheets = Vec::new();
+
+        if let Some(root) = self.dom.root() {
+            self.collect_stylesheets(&root, &mut stylesheets);
+        }
+
+        stylesheets
+    }
+
+    fn collect_stylesheets(&self, node: &Dom::NodeId, stylesheets: &mut Vec<StyleSheet>) {
+        let dom = self.dom;
+
+        if dom.kind(node) != NodeKind::Element {
+            return;
+        }
+
+        stylesheets.extend(self.element_stylesheet(node));
+
+        for child in dom.iter_children(node) {
+            self.collect_stylesheets(&child, stylesheets);
+        }
+    }
+
+    fn element_stylesheet(&self, node: &Dom::NodeId) -> Option<StyleSheet> {
+        let dom = self.dom;
+        let element = dom.borrow_element(node)?;
+
+        let css = match element.local_name() {
+            "style" => dom
+                .iter_children(node)
+                .filter_map(|child| dom.borrow_text(&child).map(|text| text.text().to_owned()))
+                .collect::<String>(),
+            "link" if element.attribute("rel").is_some_and(is_stylesheet_link) => {
+                let data = self.resource_loader.as_ref()?.load(element.attribute("href")?)?;
+                String::from_utf8_lossy(&data).into_owned()
+            },
+            _ => return None,
+        };
+
+        Some(StyleSheet::parse_for_media(&css, Origin::Author, &self.media))
+    }
+}
+
+fn is_stylesheet_link(rel: &str) -> bool {
+    rel.split_ascii_whitespace().any(|keyword| keyword.eq_ignore_ascii_case("stylesheet"))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        dom::{DocumentObjectModel, Element},
+        ecs::systems::tree::TreeExplorer,
+        layout::px_to_units,
+        resource::ResourceLoader,
+        style::{cascade::{Origin, StyleSheet}, values::r#box::BoxContent},
+        RenderingContext,
+    };
+
+    struct Loader;
+
+    impl ResourceLoader for Loader {
+        fn load(&self, url: &str) -> Option<Vec<u8>> {
+            (url == "page.css").then(|| b"@page { margin: 10px } div { height: 30px }".to_vec())
+        }
+    }
+
+    #[test]
+    fn test_builder() {
+        let mut dom = DocumentObjectModel::default();
+        let html = dom.insert_element(Element::new("html"), None);
+        let head = dom.insert_element(Element::new("head"), Some(html));
+        dom.insert_element(Element::new("link").with_attribute("rel", "stylesheet").with_attribute("href", "page.css"), Some(head));
+        let style = dom.insert_element(Element::new("style"), Some(head));
+        dom.insert_text("@media screen { div { height: 50px } } body { margin: 0 }", Some(style));
+        let body = dom.insert_element(Element::new("body"), Some(html));
+        dom.insert_element(Element::new("div"), Some(body));
+
+        let builder = || RenderingContext::builder(&dom)
+            .viewport(BoxContent { width: 200.0, height: 100.0 })
+            .resource_loader(Loader)
+            .stylesheet(StyleSheet::parse("div { width: 40px }", Origin::Author));
+
+        let mut ctx = builder().build();
+        let fragments = ctx.render().unwrap();
+
+        // The page is as large as the viewport, and its margins come from the linked style sheet.
+        let page = fragments.pages[0];
+        let page_box = fragments.boxes.borrow(&page).unwrap().clone();
+        assert_eq!((page_box.outer().width, page_box.outer().height), (px_to_units(200.0), px_to_units(100.0)));
+        assert_eq!(page_box.margin.top, px_to_units(10.0));
+
+        // The rules of the screen media are ignored, and the given style sheet comes last.
+        let root = fragments.first_child(page).unwrap();
+        let body = fragments.last_child(root).unwrap();
+        let div = fragments.first_child(body).unwrap();
+        let div_box = fragments.boxes.borrow(&div).unwrap().clone();
+        assert_eq!((div_box.content.width, div_box.content.height), (px_to_units(40.0), px_to_units(30.0)));
+
+        let rendered = fragments.dump().to_string();
+
+        // Step-wise, the phases give the same result.
+        let mut stepwise = builder().build();
+        stepwise.style();
+        assert!(stepwise.generate_boxes().is_some());
+        let root = stepwise.generate_fragments().unwrap();
+        assert_eq!(stepwise.lay_out(), Some(root));
+        assert_eq!(stepwise.fragments().dump().to_string(), rendered);
+
+        // The phases which already ran are not run again.
+        assert_eq!(stepwise.render().unwrap().dump().to_string(), rendered);
+        assert_eq!(ctx.render().unwrap().dump().to_string(), rendered);
+
+        // The rules of the screen media apply to the screen.
+        let mut screen = builder().media("screen").build();
+        let fragments = screen.render().unwrap();
+        let root = fragments.first_child(fragments.pages[0]).unwrap();
+        let div = fragments.first_child(fragments.last_child(root).unwrap()).unwrap();
+        assert_eq!(fragments.boxes.borrow(&div).unwrap().content.height, px_to_units(50.0));
+    }
+}
@@ -1,10 +1,9 @@
-use std::{hash::Hash, ops::Deref};
-
-use pb_arena::{sync::Arena, ArenaId};
+use std::ops::Deref;
 
 use crate::{ecs::{
+    arena::{Arena, ArenaId},
     component::{ComponentRef, Components}, 
-    systems::tree::{Tree, TreeExplorer}
+    systems::tree::{Tree, TreeExplorer, TreeMutator}
 }, style::Styles};
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
@@ -13,10 +12,34 @@ pub enum NodeKind {
     Text,
 }
 
+/// An interface to read the content of a text node.
+pub trait TText {
+    fn text(&self) -> &str;
+}
+
+/// An interface to read the content of an element.
+pub trait TElement {
+    /// Returns the local name of the element (eg. `div`).
+    fn local_name(&self) -> &str;
+
+    /// Returns the value of an attribute, if set.
+    fn attribute(&self, name: &str) -> Option<&str>;
+
+    fn id(&self) -> Option<&str> {
+        self.attribute("id")
+    }
+
+    fn has_class(&self, class: &str) -> bool {
+        self.attribute("class")
+            .map(|classes| classes.split_ascii_whitespace().any(|c| c == class))
+            .unwrap_or_default()
+    }
+}
+
 /// An interface to explore a document.
 pub trait TDocumentObjectModelExplorer: TreeExplorer {
-    type Element;
-    type Text;
+    type Element: TElement;
+    type Text: TText;
 
     type ElementRef<'a>: Deref<Target = Self::Element> where Self: 'a;
     type TextRef<'a>: Deref<Target = Self::Text> where Self: 'a;
@@ -28,8 +51,58 @@ pub trait TDocumentObjectModelExplorer: TreeExplorer {
 }
 
 pub type NodeId = ArenaId;
-pub struct Element;
-pub struct Text;
+
+pub struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+}
+
+impl Element {
+    pub fn new(name: &str) -> Self {
+        Self { name: name.to_owned(), attributes: vec![] }
+    }
+
+    pub fn with_attribute(mut self, name: &str, value: &str) -> Self {
+        self.set_attribute(name, value);
+        self
+    }
+
+    pub fn set_attribute(&mut self, name: &str, value: &str) {
+        match self.attributes.iter_mut().find(|(attr, _)| attr.eq_ignore_ascii_case(name)) {
+            Some((_, current)) => *current = value.to_owned(),
+            None => self.attributes.push((name.to_owned(), value.to_owned())),
+        }
+    }
+}
+
+impl TElement for Element {
+    fn local_name(&self) -> &str {
+        &self.name
+    }
+
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(attr, _)| attr.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+pub struct Text {
+    data: String
+}
+
+impl From<&str> for Text {
+    fn from(value: &str) -> Self {
+        Self { data: value.to_owned() }
+    }
+}
+
+impl TText for Text {
+    fn text(&self) -> &str {
+        &self.data
+    }
+}
 
 #[derive(Clone)]
 pub struct DocumentObjectModel {
@@ -39,6 +112,51 @@ pub struct DocumentObjectModel {
     texts: Components<NodeId, Text>,
 }
 
+impl Default for DocumentObjectModel {
+    fn default() -> Self {
+        Self::new(100, 100)
+    }
+}
+
+impl DocumentObjectModel {
+    pub fn new(bucket_size: usize, cache_size: usize) -> Self {
+        Self {
+            nodes: Arena::new(bucket_size),
+            tree: Tree::new(),
+            elements: Components::new(bucket_size, cache_size),
+            texts: Components::new(bucket_size, cache_size),
+        }
+    }
+
+    /// Insert an element in the document.
+    /// 
+    /// If no parent is given, the element becomes the root of the document.
+    pub fn insert_element(&mut self, element: Element, maybe_parent: Option<NodeId>) -> NodeId {
+        let node = self.insert_node(NodeKind::Element, maybe_parent);
+        self.elements.bind(&node, element);
+        node
+    }
+
+    /// Insert a text node in the document.
+    pub fn insert_text(&mut self, text: &str, maybe_parent: Option<NodeId>) -> NodeId {
+        let node = self.insert_node(NodeKind::Text, maybe_parent);
+        self.texts.bind(&node, Text::from(text));
+        node
+    }
+
+    fn insert_node(&mut self, kind: NodeKind, maybe_parent: Option<NodeId>) -> NodeId {
+        let node = self.nodes.alloc(kind);
+        self.tree.bind_edges(&node);
+
+        match maybe_parent {
+            Some(parent) => self.tree.attach_child(&parent, node),
+            None => self.tree.set_root(node),
+        }
+
+        node
+    }
+}
+
 impl Deref for DocumentObjectModel {
     type Target = Tree<NodeId>;
 
@@ -87,6 +205,6 @@ impl<'a, Dom> Clone for DomHandler<'a, Dom>
 where Dom: TDocumentObjectModelExplorer
 {
     fn clone(&self) -> Self {
-        Self { dom: self.dom.clone(), styles: self.styles.clone() }
+        Self { dom: self.dom, styles: self.styles.clone() }
     }
 }
\ No newline at end of file
@@ -0,0 +1,236 @@
+use std::{
+    cell::UnsafeCell,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    ptr::NonNull,
+    sync::{
+        atomic::{AtomicIsize, Ordering},
+        Arc, RwLock,
+    },
+};
+
+/// Identifier of a cell allocated in an [Arena].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ArenaId(usize);
+
+/// A shared borrow of an arena cell.
+pub struct ArenaRef<'a, T> {
+    _phantom: PhantomData<&'a T>,
+    ptr: NonNull<Entry<T>>,
+}
+
+impl<T> Deref for ArenaRef<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.ptr.as_ref().data.get() }
+    }
+}
+
+impl<T> Drop for ArenaRef<'_, T> {
+    fn drop(&mut self) {
+        unsafe {
+            self.ptr.as_ref().lock.fetch_sub(1, Ordering::Release);
+        }
+    }
+}
+
+/// An exclusive borrow of an arena cell.
+pub struct ArenaMutRef<'a, T> {
+    _phantom: PhantomData<&'a mut T>,
+    ptr: NonNull<Entry<T>>,
+}
+
+impl<T> Deref for ArenaMutRef<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.ptr.as_ref().data.get() }
+    }
+}
+
+impl<T> DerefMut for ArenaMutRef<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *self.ptr.as_ref().data.get() }
+    }
+}
+
+impl<T> Drop for ArenaMutRef<'_, T> {
+    fn drop(&mut self) {
+        unsafe {
+            self.ptr.as_ref().lock.store(0, Ordering::Release);
+        }
+    }
+}
+
+/// A cell of the arena.
+///
+/// The lock counts the number of shared borrows, or is set to -1
+/// if the cell is exclusively borrowed.
+struct Entry<T> {
+    lock: AtomicIsize,
+    data: UnsafeCell<T>,
+}
+
+impl<T> Entry<T> {
+    fn borrow<'a>(ptr: NonNull<Self>) -> Option<ArenaRef<'a, T>> {
+        let lock = unsafe { &ptr.as_ref().lock };
+        let mut current = lock.load(Ordering::Acquire);
+
+        while current >= 0 {
+            match lock.compare_exchange_weak(current, current + 1, Ordering::AcqRel, Ordering::Acquire) {
+                Ok(_) => return Some(ArenaRef { _phantom: PhantomData, ptr }),
+                Err(actual) => current = actual,
+            }
+        }
+
+        None
+    }
+
+    fn borrow_mut<'a>(ptr: NonNull<Self>) -> Option<ArenaMutRef<'a, T>> {
+        let lock = unsafe { &ptr.as_ref().lock };
+
+        lock.compare_exchange(0, -1, Ordering::AcqRel, Ordering::Acquire)
+            .ok()
+            .map(|_| ArenaMutRef { _phantom: PhantomData, ptr })
+    }
+}
+
+struct Inner<T> {
+    entries: RwLock<Vec<NonNull<Entry<T>>>>,
+}
+
+unsafe impl<T: Send> Send for Inner<T> {}
+unsafe impl<T: Send + Sync> Sync for Inner<T> {}
+
+impl<T> Drop for Inner<T> {
+    fn drop(&mut self) {
+        let entries = self.entries.get_mut().unwrap_or_else(|err| err.into_inner());
+
+        for entry in entries.drain(..) {
+            unsafe {
+                drop(Box::from_raw(entry.as_ptr()));
+            }
+        }
+    }
+}
+
+/// A thread-safe arena allocator.
+///
+/// Cells are never moved nor freed before the arena is dropped, clones
+/// of the arena share the same pool of cells.
+pub struct Arena<T>(Arc<Inner<T>>);
+
+impl<T> Clone for Arena<T> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<T> Arena<T> {
+    pub fn new(bucket_size: usize) -> Self {
+        Self(Arc::new(Inner {
+            entries: RwLock::new(Vec::with_capacity(bucket_size)),
+        }))
+    }
+
+    pub fn alloc(&mut self, data: T) -> ArenaId {
+        let entry = Box::new(Entry {
+            lock: AtomicIsize::new(0),
+            data: UnsafeCell::new(data),
+        });
+
+        let mut entries = self.0.entries.write().unwrap_or_else(|err| err.into_inner());
+        entries.push(NonNull::from(Box::leak(entry)));
+        ArenaId(entries.len() - 1)
+    }
+
+    pub fn borrow<'a>(&'a self, id: &ArenaId) -> Option<ArenaRef<'a, T>> {
+        self.get_entry_ptr(id).and_then(Entry::borrow)
+    }
+
+    pub fn borrow_mut<'a>(&'a self, id: &ArenaId) -> Option<ArenaMutRef<'a, T>> {
+        self.get_entry_ptr(id).and_then(Entry::borrow_mut)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.entries.read().unwrap_or_else(|err| err.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn get_entry_ptr(&self, id: &ArenaId) -> Option<NonNull<Entry<T>>> {
+        self.0
+            .entries
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+            .get(id.0)
+            .copied()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Arena;
+
+    #[test]
+    fn test_can_borrow_last_allocated_cell() {
+        let mut arena = Arena::<String>::new(2);
+        let first = arena.alloc("first".to_owned());
+        let second = arena.alloc("second".to_owned());
+
+        assert_eq!(arena.borrow(&first).as_deref().map(String::as_str), Some("first"));
+        assert_eq!(arena.borrow(&second).as_deref().map(String::as_str), Some("second"));
+    }
+
+    #[test]
+    fn test_exclusive_borrow() {
+        let mut arena = Arena::<u32>::new(2);
+        let id = arena.alloc(1);
+
+        let shared = arena.borrow(&id);
+        assert!(arena.borrow_mut(&id).is_none());
+        drop(shared);
+
+        *arena.borrow_mut(&id).unwrap() = 2;
+        assert_eq!(arena.borrow(&id).as_deref(), Some(&2));
+    }
+
+    #[test]
+    fn test_cells_survive_growth() {
+        let mut arena = Arena::<String>::new(2);
+        let first = arena.alloc("first".to_owned());
+        let address = |arena: &Arena<String>| arena.borrow(&first).map(|cell| &*cell as *const String);
+        let before = address(&arena);
+
+        // The pool grows past its initial capacity, without moving the cells.
+        let ids = (0..100).map(|index| arena.alloc(index.to_string())).collect::<Vec<_>>();
+        assert_eq!(arena.len(), 101);
+        assert_eq!(address(&arena), before);
+        assert!(ids.iter().enumerate().all(|(index, id)| *arena.borrow(id).unwrap() == index.to_string()));
+    }
+
+    #[test]
+    fn test_borrow_across_inserts() {
+        let arena = Arena::<String>::new(2);
+        let mut shared = arena.clone();
+        let id = shared.alloc("first".to_owned());
+
+        // A clone shares the pool, so cells are allocated while others are borrowed.
+        let first = arena.borrow(&id).unwrap();
+        let mut last = None;
+        for index in 0..100 {
+            let id = shared.alloc(index.to_string());
+            *shared.borrow_mut(&id).unwrap() += "!";
+            last = Some(id);
+        }
+
+        assert_eq!(*first, "first");
+        assert!(shared.borrow_mut(&id).is_none());
+        drop(first);
+        assert!(shared.borrow_mut(&id).is_some());
+        assert_eq!(arena.borrow(&last.unwrap()).as_deref().map(String::as_str), Some("99!"));
+    }
+}
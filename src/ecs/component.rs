@@ -1,9 +1,6 @@
 use std::{hash::Hash, ops::Deref};
 
-use pb_arena::{
-    sync::{Arena, ArenaMutRef, ArenaRef},
-    ArenaId,
-};
+use super::arena::{Arena, ArenaId, ArenaMutRef, ArenaRef};
 use pb_atomic_hash_map::AtomicHashMap;
 
 pub type ComponentRef<'a, Component> = ArenaRef<'a, Component>;
@@ -19,7 +16,7 @@ impl<EntityId: Hash, Component> Default for Components<EntityId, Component> {
     fn default() -> Self {
         Self {
             entities: AtomicHashMap::new(100),
-            arena: Arena::new(100, 100),
+            arena: Arena::new(100),
         }
     }
 }
@@ -45,7 +42,7 @@ impl<Entity: Hash + Copy, Component> Components<Entity, Component> {
     pub fn new(bucket_size: usize, cache_size: usize) -> Self {
         Self {
             entities: AtomicHashMap::new(cache_size),
-            arena: Arena::new(bucket_size, cache_size),
+            arena: Arena::new(bucket_size),
         }
     }
 
@@ -59,8 +56,22 @@ impl<Entity: Hash + Copy, Component> Components<Entity, Component> {
     }
 
     pub fn share_from(&mut self, entity: &Entity, from: &Entity) {
-        let component_id = self.entities.borrow(from).unwrap();
-        self.entities.insert(*entity, *component_id);
+        if let Some(component_id) = self.component_id(from) {
+            self.entities.insert(*entity, component_id);
+        }
+    }
+
+    /// Returns the identifier of the component bound to the entity, if any.
+    ///
+    /// The identifier can be used to bind the same component to an entity
+    /// of another holder sharing the same pool.
+    pub fn component_id(&self, entity: &Entity) -> Option<ArenaId> {
+        self.entities.borrow(entity).map(|component_id| *component_id)
+    }
+
+    /// Bind an already allocated component to the entity.
+    pub fn bind_id(&mut self, entity: &Entity, component_id: ArenaId) {
+        self.entities.insert(*entity, component_id);
     }
 
     // Bind a component to an entity
@@ -1,2 +1,3 @@
+pub mod arena;
 pub mod component;
 pub mod systems;
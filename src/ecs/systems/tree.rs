@@ -1,6 +1,4 @@
-use std::{borrow::{Borrow, BorrowMut}, hash::Hash, ops::Deref};
-
-use pb_atomic_linked_list::AtomicQueue;
+use std::{borrow::Borrow, collections::VecDeque, hash::Hash, ops::Deref};
 
 use crate::ecs::component::Components;
 
@@ -24,7 +22,7 @@ pub trait TreeExplorer {
 }
 
 pub struct AscendingTreeWalker<'a, Tree: TreeExplorer> {
-    queue: AtomicQueue<Tree::NodeId>,
+    queue: VecDeque<Tree::NodeId>,
     tree: &'a Tree,
 }
 
@@ -32,11 +30,11 @@ impl<Tree: TreeExplorer> Iterator for AscendingTreeWalker<'_, Tree> {
     type Item = Tree::NodeId;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(node) = self.queue.dequeue() {
+        if let Some(node) = self.queue.pop_front() {
             self.tree
-                .parent(&node)
+                .parent(node)
                 .into_iter()
-                .for_each(|child| self.queue.enqueue(child));
+                .for_each(|parent| self.queue.push_back(parent));
             
             return Some(node);
         }
@@ -48,19 +46,17 @@ impl<Tree: TreeExplorer> Iterator for AscendingTreeWalker<'_, Tree> {
 
 /// Breadth-first tree walking
 pub struct TreeWalker<'a, Tree: TreeExplorer> {
-    queue: AtomicQueue<Tree::NodeId>,
+    queue: VecDeque<Tree::NodeId>,
     tree: &'a Tree,
 }
 
 pub fn walk_ascendants<'tree, Tree: TreeExplorer>(tree: &'tree Tree, from: &Tree::NodeId) -> AscendingTreeWalker<'tree, Tree> {
-    let mut queue = AtomicQueue::new();
-    queue.enqueue(*from);
+    let queue = VecDeque::from([*from]);
     AscendingTreeWalker { queue, tree }
 }
 /// Breadth-first tree walking
 pub fn walk<Tree: TreeExplorer>(tree: &Tree) -> TreeWalker<'_, Tree> {
-    let mut queue = AtomicQueue::new();
-    tree.root().iter().for_each(|node| queue.enqueue(*node));
+    let queue = tree.root().into_iter().collect();
     TreeWalker { queue, tree }
 }
 
@@ -68,8 +64,7 @@ pub fn walk_from<'a, Tree: TreeExplorer>(
     tree: &'a Tree,
     node: &Tree::NodeId,
 ) -> TreeWalker<'a, Tree> {
-    let mut queue = AtomicQueue::new();
-    queue.enqueue(*node);
+    let queue = VecDeque::from([*node]);
     TreeWalker { queue, tree }
 }
 
@@ -77,10 +72,10 @@ impl<Tree: TreeExplorer> Iterator for TreeWalker<'_, Tree> {
     type Item = Tree::NodeId;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(node) = self.queue.dequeue() {
+        if let Some(node) = self.queue.pop_front() {
             self.tree
-                .iter_children(&node)
-                .for_each(|child| self.queue.enqueue(child));
+                .iter_children(node)
+                .for_each(|child| self.queue.push_back(child));
             return Some(node);
         }
 
@@ -120,6 +115,11 @@ pub trait TreeMutator: TreeExplorer {
 
     /// Push a new parent 
     fn push_parent(&mut self, node: &Self::NodeId, parent: Self::NodeId);
+
+    /// Move a node, and its next siblings, under a childless parent
+    ///
+    /// The previous sibling of the node, if any, is given so that only the moved nodes are visited.
+    fn move_siblings(&mut self, previous: Option<&Self::NodeId>, node: &Self::NodeId, parent: &Self::NodeId);
 }
 
 pub struct TreeEdges<EntityId> {
@@ -138,17 +138,13 @@ impl<EntityId> Default for TreeEdges<EntityId> {
     }
 }
 
+#[derive(Default)]
 pub enum ChildIter<'a, Tree: TreeExplorer> {
+    #[default]
     Empty,
     SiblingIter(SiblingIter<'a, Tree>),
 }
 
-impl<Tree: TreeExplorer> Default for ChildIter<'_, Tree> {
-    fn default() -> Self {
-        Self::Empty
-    }
-}
-
 impl<Tree: TreeExplorer> Iterator for ChildIter<'_, Tree> {
     type Item = Tree::NodeId;
 
@@ -162,14 +158,14 @@ impl<Tree: TreeExplorer> Iterator for ChildIter<'_, Tree> {
 
 pub struct SiblingIter<'a, Tree: TreeExplorer> {
     tree: &'a Tree,
-    current: Tree::NodeId,
+    current: Option<Tree::NodeId>,
 }
 
 impl<'a, Tree: TreeExplorer> SiblingIter<'a, Tree> {
     pub fn new(tree: &'a Tree, head_sibling: Tree::NodeId) -> Self {
         Self {
             tree,
-            current: head_sibling,
+            current: Some(head_sibling),
         }
     }
 }
@@ -178,9 +174,9 @@ impl<Tree: TreeExplorer> Iterator for SiblingIter<'_, Tree> {
     type Item = Tree::NodeId;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.tree
-            .next_sibling(&self.current)
-            .inspect(|next_sibling| self.current = *next_sibling)
+        let current = self.current?;
+        self.current = self.tree.next_sibling(current);
+        Some(current)
     }
 }
 
@@ -212,6 +208,10 @@ impl<NodeId: Hash + Copy + Eq + 'static> Tree<NodeId> {
     pub fn bind_edges(&mut self, node: &NodeId) {
         self.edges.bind_default(node);
     }
+
+    pub fn set_root(&mut self, root: NodeId) {
+        self.root = Some(root);
+    }
 }
 
 impl<NodeId: Hash + Copy + Eq + 'static> Default for Tree<NodeId> {
@@ -247,7 +247,7 @@ impl<NodeId: Hash + Copy + Eq + 'static> TreeExplorer for Tree<NodeId> {
         if let Some(parent) = self.parent(&node_id) {
             for sibling in self.iter_children(&parent) {
                 if let Some(edges) = self.edges.borrow(&sibling) {
-                    if edges.sibling == Some(node.borrow().clone()) {
+                    if edges.sibling == Some(*node.borrow()) {
                         return Some(sibling);
                     }
                 }
@@ -291,39 +291,33 @@ impl<NodeId: Hash + Copy + Eq> TreeMutator for Tree<NodeId> {
 
     fn attach_child(&mut self, parent: &Self::NodeId, child: Self::NodeId) {
         if let Some(tail_sibling) = self.last_child(parent) {
-            self.push_sibling(&tail_sibling, child);
+            self.edges.borrow_mut(&tail_sibling).unwrap().sibling = Some(child);
         } else if let Some(mut edges) = self.edges.borrow_mut(parent) {
             edges.child = Some(child)
         }
 
         if let Some(mut edges) = self.edges.borrow_mut(&child) {
             edges.parent = Some(*parent);
+            edges.sibling = None;
         }
     }
 
     fn push_sibling(&mut self, node: &Self::NodeId, new_sibling: Self::NodeId) {
-        let mut maybe_old_sibling: Option<Self::NodeId> = None;
-
-        if let Some(mut edges) = self.edges.borrow_mut(node) {
-            maybe_old_sibling = edges.sibling;
-            edges.sibling = Some(new_sibling);
-
-            self.edges.borrow_mut(&new_sibling).unwrap().parent = edges.parent;
-        }
-
-        if let Some(old_sibling) = maybe_old_sibling {
-            self.push_sibling(&new_sibling, old_sibling);
+        let (parent, old_sibling) = match self.edges.borrow_mut(node) {
+            Some(mut edges) => (edges.parent, edges.sibling.replace(new_sibling)),
+            None => return,
+        };
+
+        if let Some(mut edges) = self.edges.borrow_mut(&new_sibling) {
+            edges.parent = parent;
+            edges.sibling = old_sibling;
         }
     }
 
     fn pop_sibling(&mut self, node: &Self::NodeId) -> Option<Self::NodeId> {
-        if let Some(mut edges) = self.edges.borrow_mut(node) {
-            let sibling = edges.sibling;
-            edges.sibling = None;
-            return sibling;
-        }
-
-        None
+        self.edges
+            .borrow_mut(node)
+            .and_then(|mut edges| edges.sibling.take())
     }
 
     fn split_children<F: Fn(&Self::NodeId) -> bool>(
@@ -335,47 +329,102 @@ impl<NodeId: Hash + Copy + Eq> TreeMutator for Tree<NodeId> {
         self.iter_children(parent)
             .find(predicate)
             .and_then(|split_at| match mode {
-                SplitMode::Before => self.previous_sibling(&split_at),
+                SplitMode::Before => self.previous_sibling(split_at),
                 SplitMode::After => Some(split_at),
             })
             .and_then(|left| self.pop_sibling(&left).map(|right| Split { left, right }))
     }
     
     fn remove_child(&mut self, child: Self::NodeId) {
+        let next = self.next_sibling(child);
+
         if let Some(parent) = self.parent(child) {
-            // The child we want to remove is the head of the siblings ll
-            if let Some(head) = self.first_child(child) {
-                if head == child {
-                    self.edges.borrow_mut(&parent).unwrap().child = self.next_sibling(head)
-                }
-            }
-            // The child is in a sibling ll
-            if let Some(previous) = self.previous_sibling(child) {
-                self.pop_sibling(&previous);
+            if self.first_child(parent) == Some(child) {
+                // The child is the head of the siblings ll
+                self.edges.borrow_mut(&parent).unwrap().child = next;
+            } else if let Some(previous) = self.previous_sibling(child) {
+                // The child is in the siblings ll
+                self.edges.borrow_mut(&previous).unwrap().sibling = next;
             }
         }
 
+        if let Some(mut edges) = self.edges.borrow_mut(&child) {
+            edges.parent = None;
+            edges.sibling = None;
+        }
     }
     
+    fn move_siblings(&mut self, previous: Option<&Self::NodeId>, node: &Self::NodeId, parent: &Self::NodeId) {
+        // Cut the siblings ll before the node
+        match previous {
+            Some(previous) => {
+                self.pop_sibling(previous);
+            },
+            None => {
+                if let Some(mut edges) = self.parent(node).and_then(|old_parent| self.edges.borrow_mut(&old_parent)) {
+                    edges.child = None;
+                }
+            },
+        }
+
+        if let Some(mut edges) = self.edges.borrow_mut(parent) {
+            edges.child = Some(*node);
+        }
+
+        let moved = self.iter_siblings(node).collect::<Vec<_>>();
+        for sibling in moved {
+            if let Some(mut edges) = self.edges.borrow_mut(&sibling) {
+                edges.parent = Some(*parent);
+            }
+        }
+    }
+
     fn push_parent(&mut self, node: &Self::NodeId, parent: Self::NodeId) {
-        if let Some(previous) = self.previous_sibling(node) {
-            self.push_sibling(&previous, parent);
+        let maybe_grand_parent = self.parent(node);
+        let maybe_previous = self.previous_sibling(node);
+        let next = self.next_sibling(node);
+
+        // The new parent takes the place of the node among its siblings
+        if let Some(mut edges) = self.edges.borrow_mut(&parent) {
+            edges.parent = maybe_grand_parent;
+            edges.sibling = next;
+        }
+
+        if let Some(previous) = maybe_previous {
+            self.edges.borrow_mut(&previous).unwrap().sibling = Some(parent);
+        } else if let Some(grand_parent) = maybe_grand_parent {
+            self.edges.borrow_mut(&grand_parent).unwrap().child = Some(parent);
+        }
+
+        if self.root == Some(*node) {
+            self.root = Some(parent);
+        }
+
+        if let Some(mut edges) = self.edges.borrow_mut(node) {
+            edges.parent = None;
+            edges.sibling = None;
         }
 
-        self.remove_child(*node);
         self.attach_child(&parent, *node);
     }
     
     fn interpose_child(&mut self, parent: &Self::NodeId, new_child: Self::NodeId) {
-        // reattach all the children of the parent to the new children
-        if let Some(child) = self.first_child(parent) {
-            self.iter_siblings(&child)
-            .for_each(|child| {
-                self.edges.borrow_mut(&child).unwrap().parent = Some(new_child);
-            });
+        // reattach all the children of the parent to the new child
+        let children = self.iter_children(parent).collect::<Vec<_>>();
+        
+        children.iter().for_each(|child| {
+            self.edges.borrow_mut(child).unwrap().parent = Some(new_child);
+        });
+
+        if let Some(mut edges) = self.edges.borrow_mut(&new_child) {
+            edges.parent = Some(*parent);
+            edges.sibling = None;
+            edges.child = children.first().copied();
         }
 
-        self.attach_child(parent, new_child);
+        if let Some(mut edges) = self.edges.borrow_mut(parent) {
+            edges.child = Some(new_child);
+        }
     }
 }
 
@@ -415,7 +464,7 @@ where
     }
 
     fn next_sibling<N: Borrow<Self::NodeId>>(&self, node: N) -> Option<Self::NodeId> {
-        self.deref().previous_sibling(node)
+        self.deref().next_sibling(node)
     }
 
     fn last_sibling<N: Borrow<Self::NodeId>>(&self, head_sibling: N) -> Option<Self::NodeId> {
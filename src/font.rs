@@ -1,8 +1,382 @@
-use font_kit::font::Font;
+//! Selects the faces of the text, following the font matching algorithm of [CSS FONTS 4](https://www.w3.org/TR/css-fonts-4/#font-matching-algorithm)
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, OnceLock},
+};
+
+use font_kit::{
+    family_handle::FamilyHandle,
+    family_name::FamilyName as SystemFamilyName,
+    font::Font,
+    handle::Handle,
+    properties::{Properties, Stretch, Style, Weight},
+    source::{Source, SystemSource},
+    sources::fs::FsSource,
+};
+use rustybuzz::Face;
+
+use crate::style::{
+    font::{FamilyName, FontStyle, GenericFamily},
+    properties::computed,
+};
+
+/// The number of CSS pixels in a point.
+pub const PX_PER_PT: f32 = 4.0 / 3.0;
+
+/// The families searched for a glyph, once the families of the element have none.
+const FALLBACK_FAMILIES: [GenericFamily; 3] = [GenericFamily::SansSerif, GenericFamily::Serif, GenericFamily::Monospace];
 
 #[derive(Clone)]
 pub struct SizedFont {
     points: f32,
     font: Font,
+    // The raw data of the face, shared by its sizes.
+    data: Arc<Vec<u8>>,
+    // The index of the face in its collection.
+    index: u32,
+    // The face parsed from the data on first use, shared by its sizes.
+    parsed: Arc<OnceLock<Option<ParsedFace>>>,
+}
+
+/// A face parsed from the raw data it borrows.
+struct ParsedFace {
+    // Declared before the data, so that it is dropped first.
+    face: Face<'static>,
+    _data: Arc<Vec<u8>>,
+}
+
+impl ParsedFace {
+    fn parse(data: &Arc<Vec<u8>>, index: u32) -> Option<Self> {
+        // SAFETY: the data is on the heap, where it is neither moved nor mutated while shared,
+        // and the face is dropped before its own reference to the data.
+        let bytes: &'static [u8] = unsafe { std::slice::from_raw_parts(data.as_ptr(), data.len()) };
+        let face = Face::from_slice(bytes, index)?;
+        Some(Self { face, _data: data.clone() })
+    }
+}
+
+impl SizedFont {
+    pub fn new(font: Font, points: f32) -> Self {
+        let data = font.copy_font_data().unwrap_or_default();
+        let index = match font.handle() {
+            Some(Handle::Path { font_index, .. } | Handle::Memory { font_index, .. }) => font_index,
+            None => 0,
+        };
+
+        Self { points, font, data, index, parsed: Arc::default() }
+    }
+
+    pub fn points(&self) -> f32 {
+        self.points
+    }
+
+    /// The size of the font, in CSS pixels.
+    pub fn pixels(&self) -> f32 {
+        self.points * PX_PER_PT
+    }
+
+    pub fn font(&self) -> &Font {
+        &self.font
+    }
+
+    /// The raw data of the face.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// The index of the face in its font collection.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Identifies the face, whatever its size: the address of the data shared by its sizes, and its index.
+    pub fn face_id(&self) -> (usize, u32) {
+        (Arc::as_ptr(&self.data) as usize, self.index)
+    }
+
+    /// The parsed face, to shape text with and read its glyphs from, or nothing if the data cannot be parsed.
+    ///
+    /// The face is parsed once, on first use.
+    pub fn face(&self) -> Option<&Face<'_>> {
+        self.parsed
+            .get_or_init(|| ParsedFace::parse(&self.data, self.index))
+            .as_ref()
+            .map(|parsed| &parsed.face)
+    }
+
+    /// Returns the same face, at another size.
+    pub fn resize(&self, points: f32) -> Self {
+        Self { points, ..self.clone() }
+    }
+
+    /// Returns the metrics of the face, at the size of the font.
+    pub fn metrics(&self) -> FontMetrics {
+        let metrics = self.font.metrics();
+        let scale = self.pixels() as f64 / metrics.units_per_em as f64;
+
+        FontMetrics {
+            ascent: metrics.ascent as f64 * scale,
+            descent: -metrics.descent as f64 * scale,
+            line_gap: metrics.line_gap as f64 * scale,
+            x_height: metrics.x_height as f64 * scale,
+            cap_height: metrics.cap_height as f64 * scale,
+        }
+    }
+
+    /// Checks if the face has a glyph for the character.
+    pub fn has_glyph(&self, ch: char) -> bool {
+        self.font.glyph_for_char(ch).is_some()
+    }
+}
+
+/// The vertical metrics of a sized face, in CSS pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FontMetrics {
+    /// The height of the face above the baseline.
+    pub ascent: f64,
+    /// The depth of the face below the baseline.
+    pub descent: f64,
+    /// The recommended gap between two lines.
+    pub line_gap: f64,
+    /// The height of the lowercase letters.
+    pub x_height: f64,
+    /// The height of the uppercase letters.
+    pub cap_height: f64,
+}
+
+impl FontMetrics {
+    /// Estimates the metrics of a face from its size, when there is no face to read them from.
+    pub fn estimate(font_size: f64) -> Self {
+        Self {
+            ascent: 0.8 * font_size,
+            descent: 0.2 * font_size,
+            line_gap: 0.0,
+            x_height: 0.5 * font_size,
+            cap_height: 0.7 * font_size,
+        }
+    }
+}
+
+/// The key of a matched face: a family, and the weight, style and stretch it is matched against.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct FaceKey {
+    family: FamilyName,
+    weight: u64,
+    style: FontStyle,
+    stretch: u64,
+}
+
+/// Selects the faces of the system, and caches them across the document.
+#[derive(Clone, Default)]
+pub struct FontCache {
+    // The faces of each family, or nothing if the system does not have it.
+    families: HashMap<FamilyName, Option<Vec<Font>>>,
+    // The face matched in a family, if any.
+    faces: HashMap<FaceKey, Option<SizedFont>>,
+    // The fonts of the added directories, searched before the ones of the system.
+    directories: Vec<Arc<FsSource>>,
+}
+
+impl FontCache {
+    /// Indexes the fonts of a directory, and its subdirectories.
+    ///
+    /// A family found in the added directories is preferred to a family of the same name installed on the system.
+    pub fn add_directory(&mut self, path: &Path) {
+        self.directories.push(Arc::new(FsSource::in_path(path)));
+        self.families.clear();
+        self.faces.clear();
+    }
+
+    /// Returns the faces to render the text of an element with, at its font size.
+    ///
+    /// The first one is the face matched in the first available family of its `font-family` list, which is the primary font.
+    /// The following ones are the faces matched in the other families of the list,
+    /// then in fallback generic families, to look for the glyphs the primary font lacks.
+    pub fn select(&mut self, props: &computed::Properties) -> Vec<SizedFont> {
+        let points = props.font_size.0 as f32 / PX_PER_PT;
+        let fallbacks = FALLBACK_FAMILIES.iter().map(|generic| FamilyName::Generic(*generic));
+        let mut faces: Vec<SizedFont> = Vec::new();
+
+        for family in props.font_family.0.iter().cloned().chain(fallbacks) {
+            let key = FaceKey {
+                family,
+                weight: props.font_weight.0.to_bits(),
+                style: props.font_style,
+                stretch: props.font_stretch.0.to_bits(),
+            };
+
+            let Some(face) = self.match_face(&key) else {
+                continue;
+            };
+
+            if !faces.iter().any(|other| other.face_id() == face.face_id()) {
+                faces.push(face.resize(points));
+            }
+        }
+
+        faces
+    }
+
+    /// Matches a face of the family.
+    fn match_face(&mut self, key: &FaceKey) -> Option<SizedFont> {
+        if let Some(face) = self.faces.get(key) {
+            return face.clone();
+        }
+
+        let query = Properties {
+            style: match key.style {
+                FontStyle::Normal => Style::Normal,
+                FontStyle::Italic => Style::Italic,
+                FontStyle::Oblique => Style::Oblique,
+            },
+            weight: Weight(f64::from_bits(key.weight) as f32),
+            stretch: Stretch(f64::from_bits(key.stretch) as f32 / 100.0),
+        };
+
+        let face = self.family(&key.family).and_then(|fonts| {
+            let candidates = fonts.iter().map(Font::properties).collect::<Vec<_>>();
+            let index = find_best_match(&candidates, &query)?;
+            Some(SizedFont::new(fonts[index].clone(), 12.0))
+        });
+
+        self.faces.insert(key.clone(), face.clone());
+        face
+    }
+
+    /// Returns the faces of the family, loading them on first use.
+    fn family(&mut self, family: &FamilyName) -> Option<&Vec<Font>> {
+        self.families
+            .entry(family.clone())
+            .or_insert_with(|| {
+                let name = match family {
+                    FamilyName::Named(name) => SystemFamilyName::Title(name.clone()),
+                    FamilyName::Generic(GenericFamily::Serif) => SystemFamilyName::Serif,
+                    FamilyName::Generic(GenericFamily::SansSerif | GenericFamily::SystemUi) => SystemFamilyName::SansSerif,
+                    FamilyName::Generic(GenericFamily::Cursive) => SystemFamilyName::Cursive,
+                    FamilyName::Generic(GenericFamily::Fantasy) => SystemFamilyName::Fantasy,
+                    FamilyName::Generic(GenericFamily::Monospace) => SystemFamilyName::Monospace,
+                };
+
+                let handles = self
+                    .directories
+                    .iter()
+                    .find_map(|source| select_family(source.as_ref(), &name))
+                    .or_else(|| select_family(&SystemSource::new(), &name))?;
+
+                let fonts = handles
+                    .fonts()
+                    .iter()
+                    .filter_map(|handle| handle.load().ok())
+                    .collect::<Vec<_>>();
+
+                (!fonts.is_empty()).then_some(fonts)
+            })
+            .as_ref()
+    }
 }
 
+/// Returns the faces of a family of the source, if it has any.
+fn select_family(source: &dyn Source, name: &SystemFamilyName) -> Option<FamilyHandle> {
+    let handles = match name {
+        SystemFamilyName::Title(name) => source.select_family_by_name(name),
+        generic => source.select_family_by_generic_name(generic),
+    };
+
+    handles.ok().filter(|handles| !handles.is_empty())
+}
+
+/// Returns the index of the candidate face which best matches the query.
+///
+/// ```spec
+/// The set of faces is narrowed, in order, by font-stretch, font-style and font-weight.
+/// Each step keeps the faces with the closest value, according to the rules of the property.
+/// ```
+fn find_best_match(candidates: &[Properties], query: &Properties) -> Option<usize> {
+    let mut matching = (0..candidates.len()).collect::<Vec<_>>();
+
+    // ```spec
+    // If the desired stretch value is less than or equal to 100%, stretch values below the desired stretch value are checked in descending order
+    // followed by stretch values above the desired stretch value in ascending order until a match is found.
+    // Otherwise, stretch values above the desired stretch value are checked in ascending order followed by stretch values below in descending order.
+    // ```
+    let desired = query.stretch.0;
+    narrow(&mut matching, |index| candidates[index].stretch.0, |stretch| {
+        let (first, second) = if desired <= 1.0 { (stretch <= desired, stretch > desired) } else { (stretch >= desired, stretch < desired) };
+        (!first && second, (stretch - desired).abs())
+    });
+
+    // ```spec
+    // If the value of font-style is italic, italic faces are checked first, then oblique, then normal faces.
+    // If the value is oblique, oblique faces are checked first, then italic faces and then normal faces.
+    // If the value is normal, normal faces are checked first, then oblique faces, then italic faces.
+    // ```
+    let order = match query.style {
+        Style::Italic => [Style::Italic, Style::Oblique, Style::Normal],
+        Style::Oblique => [Style::Oblique, Style::Italic, Style::Normal],
+        Style::Normal => [Style::Normal, Style::Oblique, Style::Italic],
+    };
+    narrow(&mut matching, |index| order.iter().position(|style| *style == candidates[index].style).unwrap_or(order.len()) as f32, |rank| (false, rank));
+
+    // ```spec
+    // If the desired weight is inclusively between 400 and 500, weights greater than or equal to the target weight are checked in ascending order until 500 is hit and checked,
+    // followed by weights less than the target weight in descending order, followed by weights greater than 500, until a match is found.
+    // If the desired weight is less than 400, weights less than or equal to the desired weight are checked in descending order
+    // followed by weights above the desired weight in ascending order until a match is found.
+    // If the desired weight is greater than 500, weights greater than or equal to the desired weight are checked in ascending order
+    // followed by weights below the desired weight in descending order until a match is found.
+    // ```
+    let desired = query.weight.0;
+    narrow(&mut matching, |index| candidates[index].weight.0, |weight| {
+        let tier = if (400.0..=500.0).contains(&desired) {
+            if (desired..=500.0).contains(&weight) { 0 } else if weight < desired { 1 } else { 2 }
+        } else if desired < 400.0 {
+            if weight <= desired { 0 } else { 1 }
+        } else if weight >= desired { 0 } else { 1 };
+
+        (tier > 0, (tier as f32) * 10000.0 + (weight - desired).abs())
+    });
+
+    matching.first().copied()
+}
+
+/// Keeps the candidates whose value is the closest, by their preference.
+///
+/// The preference of a value is whether it is checked after the others, then its distance to the desired value.
+fn narrow(matching: &mut Vec<usize>, value: impl Fn(usize) -> f32, preference: impl Fn(f32) -> (bool, f32)) {
+    let best = matching
+        .iter()
+        .map(|index| value(*index))
+        .min_by(|a, b| preference(*a).partial_cmp(&preference(*b)).unwrap_or(std::cmp::Ordering::Equal));
+
+    if let Some(best) = best {
+        matching.retain(|index| value(*index) == best);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use font_kit::properties::{Properties, Stretch, Style, Weight};
+
+    use super::find_best_match;
+
+    #[test]
+    fn test_find_best_match() {
+        let face = |weight: f32, style: Style| Properties { weight: Weight(weight), style, stretch: Stretch::NORMAL };
+        let candidates = [
+            face(300.0, Style::Normal),
+            face(400.0, Style::Normal),
+            face(700.0, Style::Normal),
+            face(400.0, Style::Italic),
+            Properties { stretch: Stretch::CONDENSED, ..face(400.0, Style::Normal) },
+        ];
+
+        assert_eq!(find_best_match(&candidates, &face(400.0, Style::Normal)), Some(1));
+        assert_eq!(find_best_match(&candidates, &face(600.0, Style::Normal)), Some(2));
+        assert_eq!(find_best_match(&candidates, &face(450.0, Style::Normal)), Some(1));
+        assert_eq!(find_best_match(&candidates, &face(200.0, Style::Normal)), Some(0));
+        assert_eq!(find_best_match(&candidates, &face(700.0, Style::Oblique)), Some(3));
+        assert_eq!(find_best_match(&candidates, &Properties { stretch: Stretch::SEMI_CONDENSED, ..face(400.0, Style::Normal) }), Some(4));
+        assert_eq!(find_best_match(&[], &face(400.0, Style::Normal)), None);
+    }
+}
@@ -0,0 +1,86 @@
+//! Parses HTML documents, with [html5ever](https://github.com/servo/html5ever), into a [`DocumentObjectModel`].
+use html5ever::{parse_document, tendril::TendrilSink};
+use markup5ever_rcdom::{Handle, NodeData, RcDom};
+
+use crate::dom::{DocumentObjectModel, Element, NodeId};
+
+/// A parsed HTML document.
+///
+/// Its style sheets are collected by the [`RenderingContextBuilder`](crate::RenderingContextBuilder).
+pub struct HtmlDocument {
+    pub dom: DocumentObjectModel,
+}
+
+impl HtmlDocument {
+    /// Parses a document, following the tree construction of the HTML standard,
+    /// so that the root is always an `html` element, with a `head` and a `body`.
+    ///
+    /// Comments, doctypes and processing instructions are dropped.
+    pub fn parse(input: &str) -> Self {
+        let rcdom = parse_document(RcDom::default(), Default::default()).one(input);
+        let mut document = Self { dom: DocumentObjectModel::default() };
+
+        for child in rcdom.document.children.borrow().iter() {
+            document.insert(child, None);
+        }
+
+        document
+    }
+
+    fn insert(&mut self, handle: &Handle, parent: Option<NodeId>) {
+        match &handle.data {
+            NodeData::Element { name, attrs, .. } => {
+                let mut element = Element::new(&name.local);
+
+                for attribute in attrs.borrow().iter() {
+                    element.set_attribute(&attribute.name.local, &attribute.value);
+                }
+
+                let node = self.dom.insert_element(element, parent);
+
+                for child in handle.children.borrow().iter() {
+                    self.insert(child, Some(node));
+                }
+            },
+            NodeData::Text { contents } if parent.is_some() => {
+                self.dom.insert_text(&contents.borrow(), parent);
+            },
+            _ => {},
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        dom::{TDocumentObjectModelExplorer, TElement, TText},
+        ecs::systems::tree::TreeExplorer,
+    };
+
+    use super::HtmlDocument;
+
+    #[test]
+    fn test_parse() {
+        let document = HtmlDocument::parse(r#"<!DOCTYPE html>
+            <style>p { color: red }</style>
+            <!-- a comment -->
+            <p class="intro">Hello <b>world</b>"#);
+
+        let dom = &document.dom;
+        let root = dom.root().unwrap();
+        assert_eq!(dom.borrow_element(&root).unwrap().local_name(), "html");
+
+        let [head, body] = dom.iter_children(root).collect::<Vec<_>>().try_into().unwrap();
+        assert_eq!(dom.borrow_element(&head).unwrap().local_name(), "head");
+        assert_eq!(dom.borrow_element(&body).unwrap().local_name(), "body");
+
+        let p = dom.iter_children(body).find(|child| dom.borrow_element(child).is_some()).unwrap();
+        assert_eq!(dom.borrow_element(&p).unwrap().attribute("class"), Some("intro"));
+
+        let text = dom.first_child(p).unwrap();
+        assert_eq!(dom.borrow_text(&text).unwrap().text(), "Hello ");
+
+        let style = dom.first_child(head).unwrap();
+        assert_eq!(dom.borrow_text(&dom.first_child(style).unwrap()).unwrap().text(), "p { color: red }");
+    }
+}
@@ -0,0 +1,373 @@
+//! Implements the block formatting context from [CSS 2.1](https://www.w3.org/TR/CSS21/visuren.html#block-formatting)
+//!
+//! ```spec
+//! In a block formatting context, boxes are laid out one after the other, vertically, beginning at the top of a containing block.
+//! The vertical distance between two sibling boxes is determined by the 'margin' properties.
+//! Vertical margins between adjacent block-level boxes in a block formatting context collapse.
+//! ```
+use crate::{
+    ecs::systems::tree::TreeExplorer,
+    style::{
+        values::{
+            numeric::{AutoOrPixelsOrPercentage, ResolutionContext},
+            r#box::{BoxEdges, Point},
+        },
+    },
+};
+
+use super::{
+    fragment_tree::{Fragment, FragmentKind, FragmentTree},
+    px_to_units, units_to_px, Block, Inline, Layout,
+};
+
+/// A set of adjoining margins, which collapse into a single margin.
+///
+/// ```spec
+/// When two or more margins collapse, the resulting margin width is the maximum of the collapsing margins' widths.
+/// In the case of negative margins, the maximum of the absolute values of the negative adjoining margins is deducted from the maximum of the positive adjoining margins.
+/// If there are no positive margins, the maximum of the absolute values of the adjoining margins is deducted from zero.
+/// ```
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CollapsedMargin {
+    positive: i32,
+    negative: i32,
+}
+
+impl CollapsedMargin {
+    pub fn new(margin: i32) -> Self {
+        Self {
+            positive: margin.max(0),
+            negative: margin.min(0),
+        }
+    }
+
+    /// Collapses another set of adjoining margins into this one.
+    pub fn adjoin(&mut self, other: Self) {
+        self.positive = self.positive.max(other.positive);
+        self.negative = self.negative.min(other.negative);
+    }
+
+    /// The width of the resulting margin.
+    pub fn value(&self) -> i32 {
+        self.positive + self.negative
+    }
+}
+
+/// The outcome of the layout of a block-level box, as seen by its parent.
+pub(super) struct BlockLevelLayout {
+    /// The height of the border box.
+    pub height: i32,
+    /// The margins adjoining the top border edge, including the ones of the descendants collapsing with it.
+    top: CollapsedMargin,
+    /// The margins adjoining the bottom border edge, including the ones of the descendants collapsing with it.
+    bottom: CollapsedMargin,
+    /// Whether the top and bottom margins of the box are adjoining, and collapse through it.
+    collapse_through: bool,
+}
+
+impl Layout<Block> for FragmentTree {
+    type Element = Fragment;
+
+    /// Lays out the fragment, and its whole subtree, within the content box of its parent,
+    /// or within the initial containing block if it has none.
+    fn layout(&mut self, element: &Self::Element) {
+        let containing_block = self
+            .parent(element)
+            .and_then(|parent| self.boxes.borrow(&parent).map(|r#box| r#box.content.clone()))
+            .unwrap_or_else(|| self.initial_containing_block.clone());
+
+        let mut ctx = self.resolution_context();
+
+        let laid_out = self.layout_block_level(element, containing_block.width, Some(containing_block.height), &mut ctx);
+        let margin_left = self.boxes.borrow(element).map(|r#box| r#box.margin.left).unwrap_or_default();
+
+        if let Some(mut position) = self.positions.borrow_mut(element) {
+            *position = Point { x: margin_left, y: laid_out.top.value() };
+        }
+    }
+}
+
+impl FragmentTree {
+    /// Lays out a block-level box participating in a block formatting context.
+    ///
+    /// The position of the box is set by its parent, once its margins have been collapsed.
+    pub(super) fn layout_block_level(&mut self, fragment: &Fragment, containing_width: i32, containing_height: Option<i32>, ctx: &mut ResolutionContext) -> BlockLevelLayout {
+        let used = self.used_properties(fragment, ctx);
+        let basis = units_to_px(containing_width);
+        let length = |value: &AutoOrPixelsOrPercentage| value.px(basis).map(px_to_units);
+
+        let padding = BoxEdges {
+            top: length(&used.padding.top).unwrap_or_default(),
+            bottom: length(&used.padding.bottom).unwrap_or_default(),
+            left: length(&used.padding.left).unwrap_or_default(),
+            right: length(&used.padding.right).unwrap_or_default(),
+        };
+
+        let border = BoxEdges {
+            top: px_to_units(used.border.top),
+            bottom: px_to_units(used.border.bottom),
+            left: px_to_units(used.border.left),
+            right: px_to_units(used.border.right),
+        };
+
+        let edges = padding.left + padding.right + border.left + border.right;
+        let (width, margin_left, margin_right) = if self.is(fragment, FragmentKind::AtomicInline) {
+            // ```spec
+            // A computed value of 'auto' for 'margin-left' or 'margin-right' becomes a used value of '0'.
+            // If 'width' is 'auto', the used value is the shrink-to-fit width.
+            // ```
+            let margin_left = length(&used.margin.left).unwrap_or_default();
+            let margin_right = length(&used.margin.right).unwrap_or_default();
+            let width = length(&used.width).unwrap_or_else(|| {
+                let (min_content, max_content) = self.preferred_widths(fragment, ctx);
+                shrink_to_fit(containing_width - margin_left - margin_right - edges, min_content, max_content)
+            });
+            (width, margin_left, margin_right)
+        } else {
+            block_level_widths(containing_width, length(&used.width), length(&used.margin.left), length(&used.margin.right), edges)
+        };
+
+        // ```spec
+        // If 'margin-top', or 'margin-bottom' are 'auto', their used value is 0.
+        // The percentage is calculated with respect to the height of the generated box's containing block.
+        // If the height of the containing block is not specified explicitly, the value computes to 'auto'.
+        // ```
+        let margin_top = length(&used.margin.top).unwrap_or_default();
+        let margin_bottom = length(&used.margin.bottom).unwrap_or_default();
+        let height = match used.height {
+            AutoOrPixelsOrPercentage::Percentage(_) => containing_height
+                .and_then(|height| used.height.px(units_to_px(height)))
+                .map(px_to_units),
+            _ => length(&used.height),
+        };
+
+        if let Some(mut r#box) = self.boxes.borrow_mut(fragment) {
+            r#box.content.width = width;
+            r#box.padding = padding.clone();
+            r#box.border = border.clone();
+            r#box.margin = BoxEdges {
+                top: margin_top,
+                bottom: margin_bottom,
+                left: margin_left,
+                right: margin_right,
+            };
+        }
+
+        // ```spec
+        // Margins of the root element's box do not collapse.
+        // Margins of elements that establish new block formatting contexts do not collapse with their in-flow children.
+        // The top margin of an in-flow block element collapses with its first in-flow block-level child's top margin
+        // if the element has no top border, no top padding, and the child has no clearance.
+        // The bottom margin of an in-flow block box with a 'height' of 'auto' collapses with its last in-flow block-level child's bottom margin
+        // if the box has no bottom padding and no bottom border and the child's bottom margin does not collapse with a top margin that has clearance.
+        // ```
+        let establishes_bfc = self.is(fragment, FragmentKind::BlockFormattingContextRoot);
+        let collapses_with_first_child = !establishes_bfc && padding.top == 0 && border.top == 0;
+        let collapses_with_last_child = !establishes_bfc && padding.bottom == 0 && border.bottom == 0 && height.is_none();
+
+        let mut top = CollapsedMargin::new(margin_top);
+        // Whether the margins met so far are still adjoining the top margin of the box.
+        let mut adjoining_top = collapses_with_first_child;
+        let mut cursor = 0;
+        let mut pending = CollapsedMargin::default();
+
+        if self.contains_only_inline_level_content(fragment) {
+            Layout::<Inline>::layout(self, fragment);
+
+            let line_boxes = self
+                .iter_children(fragment)
+                .filter(|child| self.is(child, FragmentKind::LineBox))
+                .collect::<Vec<_>>();
+
+            // ```spec
+            // A box's own margins collapse if [...] it contains no line boxes.
+            // ```
+            if !line_boxes.is_empty() {
+                adjoining_top = false;
+                cursor = line_boxes
+                    .iter()
+                    .filter_map(|line_box| self.boxes.borrow(line_box).map(|r#box| r#box.outer().height))
+                    .sum();
+            }
+        } else {
+            let children = self
+                .iter_children(fragment)
+                .filter(|child| self.kind(child).is_block_level())
+                .collect::<Vec<_>>();
+
+            for child in children {
+                let laid_out = self.layout_block_level(&child, width, height, ctx);
+
+                let y = if adjoining_top {
+                    top.adjoin(laid_out.top);
+
+                    if laid_out.collapse_through {
+                        top.adjoin(laid_out.bottom);
+                    } else {
+                        adjoining_top = false;
+                        cursor = laid_out.height;
+                        pending = laid_out.bottom;
+                    }
+
+                    0
+                } else {
+                    pending.adjoin(laid_out.top);
+                    let y = cursor + pending.value();
+
+                    if laid_out.collapse_through {
+                        pending.adjoin(laid_out.bottom);
+                    } else {
+                        cursor = y + laid_out.height;
+                        pending = laid_out.bottom;
+                    }
+
+                    y
+                };
+
+                let x = self.boxes.borrow(&child).map(|r#box| r#box.margin.left).unwrap_or_default();
+                if let Some(mut position) = self.positions.borrow_mut(&child) {
+                    *position = Point { x, y };
+                }
+            }
+        }
+
+        // ```spec
+        // top and bottom margins of a box that does not establish a new block formatting context
+        // and that has zero computed 'min-height', zero or 'auto' computed 'height', and no in-flow children
+        // ```
+        let collapse_through = adjoining_top && collapses_with_last_child;
+        let mut bottom = CollapsedMargin::new(margin_bottom);
+
+        let content_height = if adjoining_top {
+            height.unwrap_or_default()
+        } else if collapses_with_last_child {
+            bottom.adjoin(pending);
+            cursor
+        } else {
+            height.unwrap_or(cursor + pending.value())
+        };
+
+        if let Some(mut r#box) = self.boxes.borrow_mut(fragment) {
+            r#box.content.height = content_height;
+        }
+
+        BlockLevelLayout {
+            height: content_height + padding.top + padding.bottom + border.top + border.bottom,
+            top,
+            bottom,
+            collapse_through,
+        }
+    }
+
+    /// Returns the preferred minimum width and the preferred width of the content of a block container.
+    pub(super) fn preferred_widths(&mut self, fragment: &Fragment, ctx: &mut ResolutionContext) -> (i32, i32) {
+        if self.contains_only_inline_level_content(fragment) {
+            return self.preferred_inline_widths(fragment, ctx);
+        }
+
+        let children = self
+            .iter_children(fragment)
+            .filter(|child| self.kind(child).is_block_level())
+            .collect::<Vec<_>>();
+
+        children
+            .into_iter()
+            .map(|child| self.preferred_outer_widths(&child, ctx))
+            .fold((0, 0), |(min, max), (child_min, child_max)| (min.max(child_min), max.max(child_max)))
+    }
+
+    /// Returns the preferred widths of the margin box of a block-level box, or of an atomic inline.
+    ///
+    /// A specified width is its preferred width, percentages which refer to the width being found are treated as 'auto'.
+    pub(super) fn preferred_outer_widths(&mut self, fragment: &Fragment, ctx: &mut ResolutionContext) -> (i32, i32) {
+        let used = self.used_properties(fragment, ctx);
+        let length = |value: &AutoOrPixelsOrPercentage| value.px(0.0).map(px_to_units).unwrap_or_default();
+        let edges = length(&used.margin.left) + length(&used.margin.right)
+            + length(&used.padding.left) + length(&used.padding.right)
+            + px_to_units(used.border.left) + px_to_units(used.border.right);
+
+        let (min_content, max_content) = match used.width {
+            AutoOrPixelsOrPercentage::Pixels(width) => (px_to_units(width), px_to_units(width)),
+            _ => self.preferred_widths(fragment, ctx),
+        };
+
+        (min_content + edges, max_content + edges)
+    }
+}
+
+/// Returns the shrink-to-fit width of a box, from the width available for its content box.
+///
+/// ```spec
+/// the shrink-to-fit width is: min(max(preferred minimum width, available width), preferred width).
+/// ```
+fn shrink_to_fit(available: i32, min_content: i32, max_content: i32) -> i32 {
+    min_content.max(available).min(max_content)
+}
+
+/// Returns the used width, left margin and right margin of a block-level non-replaced element in normal flow.
+///
+/// ```spec
+/// 'margin-left' + 'border-left-width' + 'padding-left' + 'width' + 'padding-right' + 'border-right-width' + 'margin-right' = width of containing block
+/// If 'width' is not 'auto' and 'border-left-width' + 'padding-left' + 'width' + 'padding-right' + 'border-right-width' (plus any of 'margin-left' or 'margin-right' that are not 'auto') is larger than the width of the containing block, then any 'auto' values for 'margin-left' or 'margin-right' are, for the following rules, treated as zero.
+/// If all of the above have a computed value other than 'auto', the values are said to be "over-constrained" and one of the used values will have to be different from its computed value. If the 'direction' property of the containing block has the value 'ltr', the specified value of 'margin-right' is ignored and the value is calculated so as to make the equality true.
+/// If there is exactly one value specified as 'auto', its used value follows from the equality.
+/// If 'width' is set to 'auto', any other 'auto' values become '0' and 'width' follows from the resulting equality.
+/// If both 'margin-left' and 'margin-right' are 'auto', their used values are equal. This horizontally centers the element with respect to the edges of the containing block.
+/// ```
+fn block_level_widths(containing_width: i32, width: Option<i32>, margin_left: Option<i32>, margin_right: Option<i32>, edges: i32) -> (i32, i32, i32) {
+    let Some(width) = width else {
+        let margin_left = margin_left.unwrap_or_default();
+        let width = (containing_width - edges - margin_left - margin_right.unwrap_or_default()).max(0);
+        return (width, margin_left, containing_width - edges - margin_left - width);
+    };
+
+    let remaining = containing_width - edges - width;
+    let overflows = remaining < margin_left.unwrap_or_default() + margin_right.unwrap_or_default();
+
+    match (margin_left, margin_right) {
+        (None, None) if !overflows => {
+            let margin_left = remaining / 2;
+            (width, margin_left, remaining - margin_left)
+        },
+        (None, Some(margin_right)) if !overflows => (width, remaining - margin_right, margin_right),
+        (margin_left, _) => {
+            let margin_left = margin_left.unwrap_or_default();
+            (width, margin_left, remaining - margin_left)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{block_level_widths, shrink_to_fit, CollapsedMargin};
+
+    #[test]
+    fn test_block_level_widths() {
+        assert_eq!(block_level_widths(100, None, Some(10), None, 4), (86, 10, 0));
+        assert_eq!(block_level_widths(100, Some(50), None, None, 0), (50, 25, 25));
+        assert_eq!(block_level_widths(100, Some(50), None, Some(10), 0), (50, 40, 10));
+        // Over-constrained: margin-right is ignored.
+        assert_eq!(block_level_widths(100, Some(50), Some(10), Some(10), 0), (50, 10, 40));
+        // Too wide: auto margins are treated as zero.
+        assert_eq!(block_level_widths(100, Some(120), None, None, 0), (120, 0, -20));
+    }
+
+    #[test]
+    fn test_shrink_to_fit() {
+        assert_eq!(shrink_to_fit(100, 20, 50), 50);
+        assert_eq!(shrink_to_fit(30, 20, 50), 30);
+        assert_eq!(shrink_to_fit(10, 20, 50), 20);
+    }
+
+    #[test]
+    fn test_collapsed_margin() {
+        let mut margin = CollapsedMargin::new(20);
+        margin.adjoin(CollapsedMargin::new(30));
+        margin.adjoin(CollapsedMargin::new(-10));
+        assert_eq!(margin.value(), 20);
+
+        let mut margin = CollapsedMargin::new(-5);
+        margin.adjoin(CollapsedMargin::new(-15));
+        assert_eq!(margin.value(), -15);
+    }
+}
@@ -1,10 +1,10 @@
 use std::{borrow::Borrow, hash::Hash};
 
-use pb_arena::{sync::Arena, ArenaId};
 use pb_atomic_hash_map::AtomicHashMap;
 
 use crate::{
     ecs::{
+        arena::{Arena, ArenaId},
         component::Components, 
         systems::tree::{
             walk_ascendants, Tree, TreeEdges, TreeExplorer, TreeMutator
@@ -51,6 +51,10 @@ impl BoxNodeKind {
             BoxNodeKind::TextSequence => false,
         }
     }
+
+    pub fn is_text_sequence(&self) -> bool {
+        matches!(self, BoxNodeKind::TextSequence)
+    }
 }
 
 pub type BoxEdges = TreeEdges<BoxNode>;
@@ -89,6 +93,8 @@ impl BoxFlags {
     
     pub const ROOT: u16 = 0b10000;
 
+    pub const ANONYMOUS: u16 = 0b100000;
+
     pub fn is_inline_level(&self) -> bool {
         (*self & Self::LEVEL_MASK) == Self::INLINE_LEVEL
     }
@@ -97,20 +103,30 @@ impl BoxFlags {
         (*self & Self::LEVEL_MASK) == Self::BLOCK_LEVEL
     }
 
+    /// Block-level containers and atomic inline-level containers (inline-block)
+    /// are block containers.
     pub fn is_block_container(&self) -> bool {
-        return self.is_container() && self.is_block_level()
+        self.is_container() && (self.is_block_level() || self.is_atomic())
     }
 
     pub fn is_atomic_inline_level(&self) -> bool {
-        return self.is_inline_level() && self.is_atomic()
+        self.is_inline_level() && self.is_atomic()
     }
     
     fn is_container(&self) -> bool {
-        return (*self & Self::CONTAINER_MASK) == Self::CONTAINER
+        (*self & Self::CONTAINER_MASK) == Self::CONTAINER
     }
 
     fn is_atomic(&self) -> bool {
-        return (*self & Self::ATOMIC_MASK) == Self::ATOMIC
+        (*self & Self::ATOMIC_MASK) == Self::ATOMIC
+    }
+
+    pub fn is_root_inline_box(&self) -> bool {
+        self.is_inline_level() && (*self & Self::ROOT) == Self::ROOT
+    }
+
+    pub fn is_anonymous(&self) -> bool {
+        (*self & Self::ANONYMOUS) == Self::ANONYMOUS
     }
 
     pub const fn run_in_level() -> Self {
@@ -125,12 +141,20 @@ impl BoxFlags {
         Self(Self::BLOCK_LEVEL | Self::CONTAINER)
     }
 
+    pub const fn anonymous_block_container() -> Self {
+        Self(Self::BLOCK_LEVEL | Self::CONTAINER | Self::ANONYMOUS)
+    }
+
     pub const fn inline_level() -> Self {
         Self(Self::INLINE_LEVEL)
     }
 
+    pub const fn atomic_inline_level() -> Self {
+        Self(Self::INLINE_LEVEL | Self::CONTAINER | Self::ATOMIC)
+    }
+
     pub const fn root_inline_box() -> Self {
-        Self(Self::INLINE_LEVEL | Self::ROOT)
+        Self(Self::INLINE_LEVEL | Self::ROOT | Self::ANONYMOUS)
     }
 }
 
@@ -152,7 +176,7 @@ impl<DomNodeId> BoxTree<DomNodeId> {
         Self {
             tree: Tree::new(),
             dom: AtomicHashMap::new(bucket_size),
-            nodes: Arena::new(bucket_size, cache_size),
+            nodes: Arena::new(bucket_size),
             boxes: Components::new(bucket_size, cache_size),
             style: Styles::new_shared(style),
             text_sequences: Components::new(bucket_size, cache_size),
@@ -163,7 +187,14 @@ impl<DomNodeId> BoxTree<DomNodeId> {
 
 pub enum ComputedProperties {
     StyleId(ComputedStyleId),
-    SameAs(BoxNode)
+    SameAs(BoxNode),
+    /// The properties of an anonymous box generated inside the given box.
+    /// 
+    /// ```spec
+    /// The properties of anonymous boxes are inherited from the enclosing non-anonymous box.
+    /// Non-inherited properties have their initial value.
+    /// ```
+    Anonymous(BoxNode)
 }
 
 impl From<ComputedStyleId> for ComputedProperties {
@@ -270,13 +301,17 @@ impl<DomNodeId> TreeMutator for BoxTree<DomNodeId>
     fn interpose_child(&mut self, parent: &Self::NodeId, new_child: Self::NodeId) {
         self.tree.interpose_child(parent, new_child);
     }
+
+    fn move_siblings(&mut self, previous: Option<&Self::NodeId>, node: &Self::NodeId, parent: &Self::NodeId) {
+        self.tree.move_siblings(previous, node, parent);
+    }
 }
 
 impl<DomNodeId> BoxTree<DomNodeId> {
     /// Returns the formatting context in which the nodes is participant.
     pub fn get_formatting_context(&self, node: &BoxNode) -> Option<FormattingContextId> {
         for asc in walk_ascendants(self, node).skip(1) {
-            if let Some(fc) = self.formatting_contexts.establishes.borrow(&asc).as_deref().copied() {
+            if let Some(fc) = self.formatting_contexts.establishes.borrow(asc).as_deref().copied() {
                 return Some(fc)
             }
         }
@@ -286,11 +321,17 @@ impl<DomNodeId> BoxTree<DomNodeId> {
 
 impl<DomNodeId> BoxTree<DomNodeId> 
 {
+    pub fn set_root(&mut self, root: BoxNode) {
+        self.tree.set_root(root);
+    }
+
     // Insert a text sequence in the box tree
-    pub fn insert_text_sequence(&mut self, text: &str, props: computed::Properties, maybe_parent: Option<BoxNode>) -> BoxNode {
+    pub fn insert_text_sequence<Props>(&mut self, text: &str, props: Props, maybe_parent: Option<BoxNode>) -> BoxNode 
+    where ComputedProperties: From<Props>
+    {
         let node = BoxNode(self.nodes.alloc(BoxNodeKind::TextSequence));
         self.text_sequences.bind(&node, TextSequence::from(text));
-        //self.computed_values.bind(&node, props);
+        self.bind_computed_properties(&node, props);
         self.tree.bind_edges(&node);
         maybe_parent.inspect(|parent| self.tree.attach_child(parent, node));
         node
@@ -303,11 +344,29 @@ impl<DomNodeId> BoxTree<DomNodeId>
         let node = BoxNode(self.nodes.alloc(BoxNodeKind::Box(flags))); 
         
         self.boxes.bind_default(&node);
+        self.bind_computed_properties(&node, props);
         self.tree.bind_edges(&node);
         maybe_parent.inspect(|parent| self.tree.attach_child(parent, node));
         node
     }
 
+    fn bind_computed_properties<Props>(&mut self, node: &BoxNode, props: Props) 
+    where ComputedProperties: From<Props>
+    {
+        match ComputedProperties::from(props) {
+            ComputedProperties::StyleId(style_id) => self.style.computed.bind_id(node, style_id.0),
+            ComputedProperties::SameAs(other) => self.style.computed.share_from(node, &other),
+            ComputedProperties::Anonymous(parent) => {
+                let properties = self.style.computed
+                    .borrow(&parent)
+                    .map(|parent| computed::Properties::inherit(&parent))
+                    .unwrap_or_default();
+
+                self.style.computed.bind(node, properties)
+            },
+        }
+    }
+
     pub fn kind<BN: Borrow<BoxNode>>(&self, box_node: BN) -> BoxNodeKind {
         *self.nodes.borrow(&box_node.borrow().0).unwrap()
     }
@@ -325,4 +384,19 @@ impl<DomNodeId> BoxTree<DomNodeId>
             .map(|child| self.kind(child))
             .any(|kind| kind.is_inline_level())
     }
+
+    pub fn has_block_level_boxes<BN: Borrow<BoxNode>>(&self, box_node: BN) -> bool {
+        self
+            .iter_children(box_node)
+            .map(|child| self.kind(child))
+            .any(|kind| kind.is_block_level())
+    }
+
+    /// Checks if the box is a text sequence made only of collapsible white spaces.
+    pub fn is_collapsible_white_space<BN: Borrow<BoxNode>>(&self, box_node: BN) -> bool {
+        self.text_sequences
+            .borrow(box_node.borrow())
+            .map(|seq| seq.is_white_space())
+            .unwrap_or_default()
+    }
 }
\ No newline at end of file
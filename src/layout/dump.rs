@@ -0,0 +1,260 @@
+//! Serializes the box tree and the fragment tree, as indented text or as JSON,
+//! so that they can be compared against golden files, or attached to bug reports.
+//!
+//! The dumps only depend on the content of the trees, never on the ids of their nodes.
+//! Lengths are given in CSS pixels, and edges in the order top, right, bottom, left.
+use std::fmt::{self, Display, Write};
+
+use serde_json::{json, Map, Value};
+
+use crate::{
+    ecs::systems::tree::TreeExplorer,
+    style::values::r#box::{Box, BoxEdges},
+};
+
+use super::{
+    box_tree::{BoxFlags, BoxNode, BoxNodeKind, BoxTree},
+    formatting_context::FormattingContextKind,
+    fragment_tree::{Fragment, FragmentTree},
+    units_to_px,
+};
+
+/// A node of a dumped tree: its kind, its fields in a stable order, then its children.
+pub struct DumpNode {
+    pub kind: String,
+    pub fields: Vec<(&'static str, Value)>,
+    pub children: Vec<DumpNode>,
+}
+
+/// The dumped roots of a tree.
+///
+/// Displays as indented text, one node per line: its kind, then its fields as `name=value`.
+pub struct TreeDump(pub Vec<DumpNode>);
+
+impl TreeDump {
+    pub fn to_json(&self) -> Value {
+        Value::Array(self.0.iter().map(DumpNode::to_json).collect())
+    }
+}
+
+impl Display for TreeDump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|node| node.write_text(f, 0))
+    }
+}
+
+impl DumpNode {
+    fn new(kind: impl Into<String>) -> Self {
+        Self { kind: kind.into(), fields: Vec::new(), children: Vec::new() }
+    }
+
+    fn field(&mut self, name: &'static str, value: impl Into<Value>) {
+        self.fields.push((name, value.into()));
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut object = Map::new();
+        object.insert("kind".to_owned(), Value::from(self.kind.as_str()));
+
+        for (name, value) in &self.fields {
+            object.insert((*name).to_owned(), value.clone());
+        }
+
+        if !self.children.is_empty() {
+            object.insert("children".to_owned(), Value::Array(self.children.iter().map(Self::to_json).collect()));
+        }
+
+        Value::Object(object)
+    }
+
+    fn write_text(&self, f: &mut impl Write, depth: usize) -> fmt::Result {
+        write!(f, "{:indent$}{}", "", self.kind, indent = depth * 2)?;
+
+        for (name, value) in &self.fields {
+            write!(f, " {name}=")?;
+            write_value(f, value)?;
+        }
+
+        writeln!(f)?;
+        self.children.iter().try_for_each(|child| child.write_text(f, depth + 1))
+    }
+}
+
+/// Writes the value of a field: keywords as is, other strings quoted and escaped, and numbers without trailing zeros.
+fn write_value(f: &mut impl Write, value: &Value) -> fmt::Result {
+    match value {
+        Value::String(string) if !string.is_empty() && string.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '-') => {
+            write!(f, "{string}")
+        },
+        Value::String(string) => write!(f, "{string:?}"),
+        Value::Number(number) => {
+            let formatted = format!("{:.3}", number.as_f64().unwrap_or_default());
+            write!(f, "{}", formatted.trim_end_matches('0').trim_end_matches('.'))
+        },
+        Value::Array(values) => {
+            write!(f, "[")?;
+
+            for (index, value) in values.iter().enumerate() {
+                if index > 0 {
+                    write!(f, ", ")?;
+                }
+                write_value(f, value)?;
+            }
+
+            write!(f, "]")
+        },
+        value => write!(f, "{value}"),
+    }
+}
+
+fn px(units: i32) -> Value {
+    json!(units_to_px(units))
+}
+
+fn edges(edges: &BoxEdges<i32>) -> Option<Value> {
+    let edges = [edges.top, edges.right, edges.bottom, edges.left];
+    edges.iter().any(|edge| *edge != 0).then(|| Value::Array(edges.map(px).to_vec()))
+}
+
+fn flags(flags: &BoxFlags) -> Vec<&'static str> {
+    let level = match *flags & BoxFlags::LEVEL_MASK {
+        BoxFlags::BLOCK_LEVEL => "block-level",
+        BoxFlags::INLINE_LEVEL => "inline-level",
+        BoxFlags::RUN_IN_LEVEL => "run-in-level",
+        _ => "no-level",
+    };
+
+    [
+        (true, level),
+        (*flags & BoxFlags::CONTAINER_MASK == BoxFlags::CONTAINER, "container"),
+        (*flags & BoxFlags::ATOMIC_MASK == BoxFlags::ATOMIC, "atomic"),
+        (*flags & BoxFlags::ROOT == BoxFlags::ROOT, "root"),
+        (flags.is_anonymous(), "anonymous"),
+    ]
+    .into_iter()
+    .filter_map(|(set, flag)| set.then_some(flag))
+    .collect()
+}
+
+impl<DomNodeId> BoxTree<DomNodeId> {
+    /// Dumps the box tree, from its root: the kind and flags of the boxes,
+    /// the formatting contexts they establish, and the text of the text sequences.
+    pub fn dump(&self) -> TreeDump {
+        TreeDump(self.root().map(|root| self.dump_node(&root)).into_iter().collect())
+    }
+
+    fn dump_node(&self, node: &BoxNode) -> DumpNode {
+        let mut dump = match self.kind(node) {
+            BoxNodeKind::Box(box_flags) => {
+                let mut dump = DumpNode::new("Box");
+                dump.field("flags", flags(&box_flags));
+                dump
+            },
+            BoxNodeKind::TextSequence => DumpNode::new("TextSequence"),
+        };
+
+        match self.formatting_contexts.kind(node) {
+            Some(FormattingContextKind::BlockFormattingContext) => dump.field("establishes", "block"),
+            Some(FormattingContextKind::InlineFormattingContext) => dump.field("establishes", "inline"),
+            None => {},
+        }
+
+        if let Some(text) = self.text_sequences.borrow(node) {
+            dump.field("text", text.as_str());
+        }
+
+        dump.children = self.iter_children(node).map(|child| self.dump_node(&child)).collect();
+        dump
+    }
+}
+
+impl FragmentTree {
+    /// Dumps the page boxes, or the root fragment if the tree is not paginated:
+    /// the kind of the fragments, their position and the size of their content box,
+    /// their non-zero edges, the metrics of the line boxes and the text of the text sequences.
+    pub fn dump(&self) -> TreeDump {
+        let roots = match self.pages.is_empty() {
+            true => self.root().into_iter().collect(),
+            false => self.pages.clone(),
+        };
+
+        TreeDump(roots.iter().map(|fragment| self.dump_node(fragment)).collect())
+    }
+
+    fn dump_node(&self, fragment: &Fragment) -> DumpNode {
+        let mut dump = DumpNode::new(format!("{:?}", self.kind(fragment)));
+        let position = self.positions.borrow(fragment).map(|position| *position).unwrap_or_default();
+        let r#box: Box<i32> = self.boxes.borrow(fragment).map(|r#box| r#box.clone()).unwrap_or_default();
+
+        dump.field("x", px(position.x));
+        dump.field("y", px(position.y));
+        dump.field("width", px(r#box.content.width));
+        dump.field("height", px(r#box.content.height));
+
+        for (name, value) in [("margin", &r#box.margin), ("border", &r#box.border), ("padding", &r#box.padding)] {
+            if let Some(value) = edges(value) {
+                dump.field(name, value);
+            }
+        }
+
+        if let Some(line_box) = self.line_boxes.borrow(fragment) {
+            dump.field("logical-width", px(line_box.logical_width));
+            dump.field("baseline", px(line_box.baseline));
+        }
+
+        if let Some(text) = self.text_sequences.borrow(fragment) {
+            dump.field("text", text.as_str());
+        }
+
+        dump.children = self.iter_children(fragment).map(|child| self.dump_node(&child)).collect();
+        dump
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use crate::{
+        dom::{DocumentObjectModel, Element},
+        testing::lay_out,
+    };
+
+    #[test]
+    fn test_dump() {
+        let mut dom = DocumentObjectModel::default();
+        let html = dom.insert_element(Element::new("html"), None);
+        let div = dom.insert_element(Element::new("div"), Some(html));
+        dom.insert_element(Element::new("div"), Some(div));
+        dom.insert_text("\"Hi\"", Some(html));
+
+        let (ctx, _) = lay_out(&dom, r#"
+            html, div { display: block }
+            @page { size: 200px 100px; margin: 10px }
+            div { height: 20px; padding: 1px 2px; border-top: 3px solid }
+            div div { height: 5px; padding: 0; border: none; margin-left: 4.5px }
+        "#);
+
+        assert_eq!(ctx.boxes.dump().to_string(), concat!(
+            "Box flags=[block-level, container] establishes=block\n",
+            "  Box flags=[block-level, container]\n",
+            "    Box flags=[block-level, container]\n",
+            "  Box flags=[block-level, container, anonymous] establishes=inline\n",
+            "    Box flags=[inline-level, root, anonymous]\n",
+            "      TextSequence text=\"\\\"Hi\\\"\"\n",
+        ));
+
+        let dump = ctx.fragments.dump();
+        let page = dump.to_string();
+        let mut lines = page.lines();
+        assert_eq!(lines.next(), Some("Page x=10 y=10 width=180 height=80 margin=[10, 10, 10, 10]"));
+        // The root is as high as its blocks, and the line of text set in the font of the tests.
+        assert_eq!(lines.next(), Some("  BlockFormattingContextRoot x=0 y=0 width=180 height=43.625"));
+        assert_eq!(lines.next(), Some("    BlockBox x=0 y=0 width=176 height=20 border=[3, 0, 0, 0] padding=[1, 2, 1, 2]"));
+        assert_eq!(lines.next(), Some("      BlockBox x=4.5 y=0 width=171.5 height=5 margin=[0, 0, 0, 4.5]"));
+
+        let json = dump.to_json();
+        assert_eq!(json[0]["kind"], json!("Page"));
+        assert_eq!(json[0]["children"][0]["children"][0]["padding"], json!([1.0, 2.0, 1.0, 2.0]));
+    }
+}
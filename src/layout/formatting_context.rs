@@ -1,6 +1,6 @@
 use std::hash::Hash;
 
-use pb_arena::{sync::Arena, ArenaId};
+use crate::ecs::arena::{Arena, ArenaId};
 use pb_atomic_hash_map::AtomicHashMap;
 
 #[derive(Clone, Copy, Hash, PartialEq, Eq)]
@@ -23,7 +23,7 @@ where NodeId: Hash + Copy + Eq
 {
     pub fn new(bucket_size: usize, cache_size: usize) -> Self {
         Self {
-            formatting_contexts: Arena::new(bucket_size, cache_size),
+            formatting_contexts: Arena::new(bucket_size),
             establishes: AtomicHashMap::new(cache_size),
         }
     }
@@ -33,6 +33,12 @@ where NodeId: Hash + Copy + Eq
         self.establishes.insert(*node, fci);
         fci
     }
+
+    /// Returns the kind of formatting context the node established for its content, if any.
+    pub fn kind(&self, node: &NodeId) -> Option<FormattingContextKind> {
+        let fci = self.establishes.borrow(node).as_deref().copied()?;
+        self.formatting_contexts.borrow(&fci.0).map(|fc| fc.kind())
+    }
 }
 
 pub enum FormattingContext {
@@ -57,6 +63,7 @@ impl FormattingContext {
     }
 }
 
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub enum FormattingContextKind {
     InlineFormattingContext,
     BlockFormattingContext
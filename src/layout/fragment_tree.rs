@@ -1,260 +1,300 @@
-
-use pb_arena::{Arena, ArenaId, ArenaRef, ArenaRefMut};
+use std::borrow::Borrow;
 
 use crate::{
-    r#box::Box, 
     ecs::{
-        component::{ComponentMutRef, ComponentRef, Components}, 
-        systems::{boxes::BoxSystem, tree::{TreeEdges, TreeSystem}}
-    }
+        arena::{Arena, ArenaId},
+        component::Components,
+        systems::tree::{Tree, TreeEdges, TreeExplorer, TreeMutator},
+    },
+    font::{FontCache, SizedFont},
+    style::{
+        properties::used,
+        values::{
+            numeric::ResolutionContext,
+            r#box::{Box, BoxContent, Point},
+        },
+        Styles,
+    },
 };
 
-use super::{box_tree::{BoxNode, TextSequence}, fragmentation::IsFragmentable, Block, Inline, Lay, Layout};
+use super::{box_tree::BoxNode, shaping::GlyphRun, text_sequence::TextSequence, units_to_px};
 
-pub type Fragment = ArenaId;
+#[derive(Hash, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Fragment(ArenaId);
 
+pub type FragmentEdges = TreeEdges<Fragment>;
 
+/// The fragment tree, which is the result of the layout of the box tree.
+///
+/// Fragments are sized and positioned in layout units.
+#[derive(Clone)]
 pub struct FragmentTree {
-    pub root: Option<Fragment>,
+    tree: Tree<Fragment>,
     // The fragments
-    pub fragments: Arena<FragmentKind>,
+    fragments: Arena<FragmentKind>,
     // The box which fragment came from
     pub sources: Components<Fragment, BoxNode>,
-    // The edges of the fragment
-    pub edges: Components<Fragment, FragmentEdges>,
     // The boxes of the fragment
     pub boxes: Components<Fragment, Box<i32>>,
+    // The position of the border box of the fragment, relative to the content box of its parent.
+    pub positions: Components<Fragment, Point<i32>>,
+    // The style of the fragment, shared with the box it came from.
+    pub style: Styles<Fragment>,
     // Text sequences
     pub text_sequences: Components<Fragment, TextSequence>,
+    // The shaped glyphs of the text sequences
+    pub glyph_runs: Components<Fragment, Vec<GlyphRun>>,
     // Line boxes data
-    pub line_boxes: Components<Fragment, LineBox>
+    pub line_boxes: Components<Fragment, LineBox>,
+    /// The containing block of the root fragment.
+    pub initial_containing_block: BoxContent<i32>,
+    /// The page boxes, in order, once the root is fragmented into pages.
+    pub pages: Vec<Fragment>,
+    // The faces selected for the text of the document.
+    pub(crate) fonts: FontCache,
 }
 
 impl FragmentTree {
-    pub fn kind(&self, node: &Fragment) -> FragmentKind {
-        *self.fragments.borrow(*node).unwrap()
+    pub fn new<OtherStyleId: std::hash::Hash + Copy>(style: &Styles<OtherStyleId>, initial_containing_block: BoxContent<i32>, bucket_size: usize, cache_size: usize) -> Self {
+        Self {
+            tree: Tree::new(),
+            fragments: Arena::new(bucket_size),
+            sources: Components::new(bucket_size, cache_size),
+            boxes: Components::new(bucket_size, cache_size),
+            positions: Components::new(bucket_size, cache_size),
+            style: Styles::new_shared(style),
+            text_sequences: Components::new(bucket_size, cache_size),
+            glyph_runs: Components::new(bucket_size, cache_size),
+            line_boxes: Components::new(bucket_size, cache_size),
+            initial_containing_block,
+            pages: Vec::new(),
+            fonts: FontCache::default(),
+        }
+    }
+
+    pub fn set_root(&mut self, root: Fragment) {
+        self.tree.set_root(root);
+    }
+
+    pub fn kind<F: Borrow<Fragment>>(&self, fragment: F) -> FragmentKind {
+        *self.fragments.borrow(&fragment.borrow().0).unwrap()
     }
+
     // Checks the kind of the fragment
-    pub fn is(&self, node: &Fragment, kind: FragmentKind) -> bool {
-        self.fragments
-        .borrow(*node)
-        .map(|fragment| *fragment == kind)
-        .unwrap_or_default()
+    pub fn is<F: Borrow<Fragment>>(&self, fragment: F, kind: FragmentKind) -> bool {
+        self.kind(fragment) == kind
     }
 
     /// Checks if the fragment is a break.
-    pub fn is_break(&self, fragment: &Fragment) -> bool {
+    pub fn is_break<F: Borrow<Fragment>>(&self, fragment: F) -> bool {
         self.is(fragment, FragmentKind::Break)
     }
 
     /// Checks if the fragment contains a break.
-    pub fn is_breakable(&self, fragment: &Fragment) -> bool {
+    pub fn is_breakable<F: Borrow<Fragment>>(&self, fragment: F) -> bool {
         self
             .iter_children(fragment)
-            .any(|child| self.is_break(&child))
+            .any(|child| self.is_break(child))
     }
 
     /// Checks if the fragment is an inline-level content.
-    pub fn is_inline_level_content(&self, fragment: &Fragment) -> bool {
+    pub fn is_inline_level_content<F: Borrow<Fragment>>(&self, fragment: F) -> bool {
         self.kind(fragment).is_inline_level_content()
     }
 
     /// Returns true if the fragment contains only inline-level content.
-    pub fn contains_only_inline_level_content(&self, fragment: &Fragment) -> bool {
-        self.iter_children(fragment).all(|child| self.is_inline_level_content(&child))
+    pub fn contains_only_inline_level_content<F: Borrow<Fragment>>(&self, fragment: F) -> bool {
+        self.iter_children(fragment).all(|child| self.is_inline_level_content(child))
     }
 
-    pub fn insert_break(&mut self) -> Fragment {
-        let fragment = self.fragments.alloc(FragmentKind::Break);
-        self.bind_default_edges(&fragment);
+    /// Insert a fragment of the given kind, generated by the source box.
+    pub fn insert_fragment(&mut self, kind: FragmentKind, source: BoxNode, maybe_parent: Option<Fragment>) -> Fragment {
+        let fragment = Fragment(self.fragments.alloc(kind));
+        self.sources.bind(&fragment, source);
+        self.boxes.bind_default(&fragment);
+        self.positions.bind_default(&fragment);
+        self.tree.bind_edges(&fragment);
+        maybe_parent.inspect(|parent| self.tree.attach_child(parent, fragment));
         fragment
     }
 
-    /// Insert a text sequence
-    pub fn insert_text_sequence(&mut self, seq: TextSequence) -> Fragment {
-        let fragment = self.fragments.alloc(FragmentKind::TextSequence);
-        self.text_sequences.bind(&fragment, seq);
-        fragment
+    pub fn insert_break(&mut self) -> Fragment {
+        self.insert_generated_fragment(FragmentKind::Break, None)
     }
 
-    // Insert an atomic inline block
-    // Atomic inlines cannot be fragmented.
-    pub fn insert_atomic_inline(&mut self, r#box: Box<i32>, source: BoxNode, maybe_parent: Option<Fragment>) -> Fragment {
-        let fragment = self.fragments.alloc(FragmentKind::AtomicInline);
-        self.bind_box(&fragment, r#box);
-        self.sources.bind(&fragment, source);
-        maybe_parent.map(|parent| self.attach_child(&parent, fragment));
+    /// Insert a fragment which is not generated by a box of the document, such as a page box or a page-margin box.
+    pub fn insert_generated_fragment(&mut self, kind: FragmentKind, maybe_parent: Option<Fragment>) -> Fragment {
+        let fragment = Fragment(self.fragments.alloc(kind));
+        self.boxes.bind_default(&fragment);
+        self.positions.bind_default(&fragment);
+        self.tree.bind_edges(&fragment);
+        maybe_parent.inspect(|parent| self.tree.attach_child(parent, fragment));
         fragment
     }
 
-    // Insert a common box fragment
-    pub fn insert_inline_box(&mut self, bx: Box<i32>, source: BoxNode, maybe_parent: Option<Fragment>) -> Fragment {
-        let fragment = self.fragments.alloc(FragmentKind::InlineBox);
-        self.bind_box(&fragment, bx);
-        self.bind_default_edges(&fragment);
-        self.sources.bind(&fragment, source);
-        maybe_parent.map(|parent| self.attach_child(&parent, fragment));
+    /// Insert a text sequence
+    pub fn insert_text_sequence(&mut self, seq: TextSequence, source: BoxNode, maybe_parent: Option<Fragment>) -> Fragment {
+        let fragment = self.insert_fragment(FragmentKind::TextSequence, source, maybe_parent);
+        self.text_sequences.bind(&fragment, seq);
         fragment
     }
 
     // Insert a line box fragment
     pub fn insert_line_box(&mut self, logical_width: i32, source: BoxNode, maybe_parent: Option<Fragment>) -> Fragment {
-        let fragment = self.fragments.alloc(FragmentKind::LineBox);
-        self.line_boxes.bind(&fragment, LineBox {logical_width});
-        self.bind_default_edges(&fragment);
-        self.bind_default_box(&fragment);
-        self.sources.bind(&fragment, source);
-        maybe_parent.map(|parent| self.attach_child(&parent, fragment));
+        let fragment = self.insert_fragment(FragmentKind::LineBox, source, maybe_parent);
+        self.line_boxes.bind(&fragment, LineBox { logical_width, baseline: 0 });
         fragment
     }
 
+    /// Returns the context against which the lengths of the fragments are resolved.
+    ///
+    /// The root font size is the one of the root fragment, and the viewport is the initial containing block.
+    pub fn resolution_context(&self) -> ResolutionContext {
+        ResolutionContext {
+            root_font_size: self
+                .root()
+                .and_then(|root| self.style.computed.borrow(&root).map(|props| props.font_size.0))
+                .unwrap_or(ResolutionContext::default().root_font_size),
+            viewport: BoxContent {
+                width: units_to_px(self.initial_containing_block.width),
+                height: units_to_px(self.initial_containing_block.height),
+            },
+            ..Default::default()
+        }
+    }
+
+    /// Resolves the relative lengths of the fragment's computed style.
+    pub fn used_properties(&self, fragment: &Fragment, ctx: &mut ResolutionContext) -> used::Properties {
+        let computed = self
+            .style
+            .computed
+            .borrow(fragment)
+            .map(|props| props.clone())
+            .unwrap_or_default();
+
+        ctx.font_size = computed.font_size.0;
+        used::Properties::resolve(&computed, ctx)
+    }
+
+    /// Returns the fonts of the fragment, sized by its computed font size.
+    ///
+    /// The first one is the primary font, the others are searched for the glyphs it lacks.
+    /// Returns nothing if the system has no font.
+    pub fn fonts(&mut self, fragment: &Fragment) -> Vec<SizedFont> {
+        let props = self.style.computed.borrow(fragment).map(|props| props.clone()).unwrap_or_default();
+        self.fonts.select(&props)
+    }
+
     // Clone a fragment but do not keep its edges.
     pub fn clone_fragment(&mut self, src: &Fragment) -> Fragment {
-        let kind = self.kind(src);
-        let clone: ArenaId = self.fragments.alloc(kind);
+        let clone = Fragment(self.fragments.alloc(self.kind(src)));
 
         self.boxes.clone_component(src, &clone);
+        self.positions.clone_component(src, &clone);
         self.line_boxes.clone_component(src, &clone);
         self.sources.clone_component(src, &clone);
         self.text_sequences.clone_component(src, &clone);
+        self.glyph_runs.clone_component(src, &clone);
+        self.style.computed.share_from(&clone, src);
+        self.tree.bind_edges(&clone);
 
         clone
     }
-
 }
 
-impl IsFragmentable<Inline> for FragmentTree {
-    fn is_fragmentable(&self, fragment: &Fragment) -> bool {
-        if self.is(fragment, FragmentKind::AtomicInline) {
-            return false;
-        }
+impl TreeExplorer for FragmentTree
+{
+    type NodeId = Fragment;
+    type ChildIter<'a> = <Tree<Fragment> as TreeExplorer>::ChildIter<'a>
+    where
+        Self: 'a;
 
-        if !self.has_children(fragment) {
-            return false;
-        }
+    fn root(&self) -> Option<Self::NodeId> {
+        self.tree.root()
+    }
 
-        return true;
+    fn parent<N: Borrow<Self::NodeId>>(&self, node: N) -> Option<Self::NodeId> {
+        self.tree.parent(node)
     }
-}
 
+    fn is_leaf<N: Borrow<Self::NodeId>>(&self, node: N) -> bool {
+        self.tree.is_leaf(node)
+    }
 
-impl Layout<Block> for FragmentTree {
-    type Element = Fragment;
+    fn previous_sibling<N: Borrow<Self::NodeId>>(&self, node: N) -> Option<Self::NodeId> {
+       self.tree.previous_sibling(node)
+    }
 
-    fn layout(&mut self, element: &Self::Element) {
-        
+    fn next_sibling<N: Borrow<Self::NodeId>>(&self, node: N) -> Option<Self::NodeId> {
+        self.tree.next_sibling(node)
     }
-}
 
-impl Layout<Inline> for FragmentTree {
-    type Element = Fragment;
+    fn last_sibling<N: Borrow<Self::NodeId>>(&self, head_sibling: N) -> Option<Self::NodeId> {
+        self.tree.last_sibling(head_sibling)
+    }
 
-    fn layout(&mut self, element: &Self::Element) {
-        
+    fn first_child<N: Borrow<Self::NodeId>>(&self, parent: N) -> Option<Self::NodeId> {
+        self.tree.first_child(parent)
     }
-}
 
-impl FragmentTree {
-        /// Compute, or recompute the box of a fragment.
-    /// 
-    /// Depending on the kind of fragment, the function can be called
-    /// recursively.
-    pub fn compute_box(&self, from: &Fragment) -> Box<i32> {
-        match self.kind(from) {
-            FragmentKind::TextSequence => {
-                let line_box = self
-                .iter_children(from)
-                .map(|child| self.compute_box(&child))
-                .reduce(Lay::<Inline>::lay)
-                .unwrap_or_default();
-
-                let mut r#box = self.borrow_mut_box(from).unwrap();
-                *r#box = line_box.clone();
-                line_box
-            },
-            // No dimensions for a line break
-            FragmentKind::Break => {
-                let zero = Box::<i32>::default();
-                let mut r#box = self.borrow_mut_box(from).unwrap();
-                *r#box = zero.clone();
-                zero
-            },
-            FragmentKind::LineBox => {
-                let line_box = self
-                .iter_children(from)
-                .map(|child| self.compute_box(&child))
-                .reduce(Lay::<Inline>::lay)
-                .unwrap_or_default();
-
-                let mut r#box = self.borrow_mut_box(from).unwrap();
-                *r#box = line_box.clone();
-                line_box
-            },
-            FragmentKind::AtomicInline => {
-                return self.borrow_box(from).unwrap().clone()
-            },
-            FragmentKind::InlineBox => {
-                let line_box = self
-                .iter_children(from)
-                .map(|child| self.compute_box(&child))
-                .reduce(Lay::<Inline>::lay)
-                .unwrap_or_default();
-
-                let mut r#box = self.borrow_mut_box(from).unwrap();
-                *r#box = line_box.clone();
-                line_box
-            },
-        }
+    fn last_child<N: Borrow<Self::NodeId>>(&self, parent: N) -> Option<Self::NodeId> {
+        self.tree.last_child(parent)
     }
-}
 
-impl<'a> BoxSystem<'a, i32> for FragmentTree {
-    type EntityId = Fragment;
-    type BoxRef = ComponentRef<'a, Box<i32>>;
-    type BoxMutRef = ComponentMutRef<'a, Box<i32>>;
+    fn iter_children<N: Borrow<Self::NodeId>>(&self, parent: N) -> Self::ChildIter<'_> {
+        self.tree.iter_children(parent)
+    }
+}
 
-    fn borrow_box(&'a self, entity: &Self::EntityId) -> Option<Self::BoxRef> {
-        self.boxes.borrow(entity)
+impl TreeMutator for FragmentTree
+{
+    fn split_children<F: Fn(&Self::NodeId) -> bool>(
+        &mut self,
+        parent: &Self::NodeId,
+        predicate: F,
+        mode: crate::ecs::systems::tree::SplitMode,
+    ) -> Option<crate::ecs::systems::tree::Split<Self::NodeId>> {
+        self.tree.split_children(parent, predicate, mode)
     }
 
-    fn borrow_mut_box(&'a self, entity: &Self::EntityId) -> Option<Self::BoxMutRef> {
-        self.boxes.borrow_mut(entity)
+    fn attach_children(
+        &mut self,
+        parent: &Self::NodeId,
+        children: impl Iterator<Item = Self::NodeId>,
+    ) {
+        self.tree.attach_children(parent, children);
     }
 
-    fn bind_box(&mut self, entity: &Self::EntityId, bx: Box<i32>) {
-        self.boxes.bind(entity, bx)
+    fn attach_child(&mut self, parent: &Self::NodeId, child: Self::NodeId) {
+        self.tree.attach_child(parent, child);
     }
-}
 
-impl TreeSystem for FragmentTree {
-    type EntityId = Fragment;
-    type EdgesRef<'a> = ArenaRef<'a, FragmentEdges>;
-    type EdgesMutRef<'a> = ArenaRefMut<'a, FragmentEdges>;
+    fn push_sibling(&mut self, node: &Self::NodeId, new_sibling: Self::NodeId) {
+        self.tree.push_sibling(node, new_sibling);
+    }
 
-    fn root(&self) -> Option<Self::EntityId> {
-        self.root.clone()
+    fn pop_sibling(&mut self, node: &Self::NodeId) -> Option<Self::NodeId> {
+        self.tree.pop_sibling(node)
     }
 
-    fn borrow_edges(&self, node: &Self::EntityId) -> Option<Self::EdgesRef<'_>> {
-        self.edges.borrow(node)
+    fn remove_child(&mut self, child: Self::NodeId) {
+        self.tree.remove_child(child);
     }
 
-    fn borrow_mut_edges(&self, node: &Self::EntityId) -> Option<Self::EdgesMutRef<'_>> {
-        self.edges.borrow_mut(node)
+    fn push_parent(&mut self, node: &Self::NodeId, parent: Self::NodeId) {
+        self.tree.push_parent(node, parent);
     }
 
-    fn bind_edges(&mut self, node: &Self::EntityId, edges: crate::ecs::systems::tree::TreeEdges<Self::EntityId>) {
-        self.edges.bind(&node, edges)
+    fn interpose_child(&mut self, parent: &Self::NodeId, new_child: Self::NodeId) {
+        self.tree.interpose_child(parent, new_child);
     }
 
-    fn clone_node(&mut self, node: &Self::EntityId) -> Self::EntityId {
-        self.clone_fragment(node)
+    fn move_siblings(&mut self, previous: Option<&Self::NodeId>, node: &Self::NodeId, parent: &Self::NodeId) {
+        self.tree.move_siblings(previous, node, parent);
     }
 }
 
-pub type FragmentEdges = TreeEdges<Fragment>;
-
-#[derive(PartialEq, Eq, Clone, Copy)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
 pub enum FragmentKind {
     // A text sequence
     TextSequence,
@@ -266,25 +306,29 @@ pub enum FragmentKind {
     AtomicInline,
     // A box participating in an inline-level layout
     InlineBox,
-    // A block container
-    BlockContainer,
-    //
-    BlockBox
+    // A block container which establishes a new block formatting context
+    BlockFormattingContextRoot,
+    // A block-level box participating in the block formatting context of its parent
+    BlockBox,
+    // A page box, whose content box is the page area holding a fragment of the root
+    Page,
+    // A page-margin box, in the margin area of a page box
+    MarginBox,
 }
 
 impl FragmentKind {
     pub fn is_inline_level_content(&self) -> bool {
-        matches!(self, Self::InlineBox | Self::TextSequence)
+        matches!(self, Self::InlineBox | Self::AtomicInline | Self::TextSequence)
+    }
+
+    pub fn is_block_level(&self) -> bool {
+        matches!(self, Self::BlockBox | Self::BlockFormattingContextRoot)
     }
 }
 
 #[derive(Clone)]
 pub struct LineBox {
-    logical_width: i32
+    pub logical_width: i32,
+    /// The position of the baseline, from the top of the line box.
+    pub baseline: i32,
 }
-
-
-
-
-
-
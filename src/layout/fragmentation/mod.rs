@@ -1,49 +1,60 @@
 use std::collections::VecDeque;
 
-use crate::{
-    ecs::systems::{boxes::BoxSystem, tree::TreeSystem},
-    r#box::Box,
-};
+use crate::ecs::systems::tree::{TreeExplorer, TreeMutator};
+
+use crate::style::text::WhiteSpace;
 
 use super::{
     fragment_tree::{Fragment, FragmentKind, FragmentTree},
-    Block, Inline, Lay, Layout,
+    line_breaking::BreakOpportunity,
+    Inline,
 };
 
+pub mod page;
+
 pub trait IsFragmentable<FormattingContext> {
     fn is_fragmentable(&self, fragment: &Fragment) -> bool;
 }
 
-/// Perform fragmentation each time a break is encountered.
-pub fn fragment(fragment_tree: &mut FragmentTree, fragment: &Fragment) {
-    if !fragment_tree.is_breakable(fragment) {
-        return;
+impl IsFragmentable<Inline> for FragmentTree {
+    /// Atomic inlines and text sequences are monolithic,
+    /// inline boxes can be fragmented between their children.
+    fn is_fragmentable(&self, fragment: &Fragment) -> bool {
+        self.is(fragment, FragmentKind::InlineBox) && !self.is_leaf(fragment)
     }
+}
 
-    let children = fragment_tree
-        .iter_children(fragment)
-        .map(|child| (child, fragment_tree.is_break(fragment)))
-        .collect::<Vec<_>>();
-    let mut previous = *fragment;
-    let clusters = children
-        .split(|(_, is_break)| *is_break)
-        .collect::<Vec<_>>();
-
-    if clusters.len() <= 1 {
-        return;
+/// Perform fragmentation each time a break is encountered.
+///
+/// The children following each break are moved into a clone of the fragment,
+/// and the break is moved between the fragment and its clone, among their siblings.
+///
+/// Returns the fragment and its clones, in order.
+pub fn fragment(fragment_tree: &mut FragmentTree, fragment: &Fragment) -> Vec<Fragment> {
+    if !fragment_tree.is_breakable(fragment) {
+        return vec![*fragment];
     }
 
-    for cluster in clusters {
-        let brk = fragment_tree.insert_break();
-        let clone = fragment_tree.clone_fragment(&fragment);
+    let children = fragment_tree.iter_children(fragment).collect::<Vec<_>>();
+    let mut clones = Vec::new();
 
-        fragment_tree.attach_children(&clone, cluster.iter().map(|(child, _)| child).copied());
+    // The children are moved from the last break, so that each of them is moved once.
+    for (index, brk) in children.iter().enumerate().rev() {
+        if !fragment_tree.is_break(brk) {
+            continue;
+        }
 
-        fragment_tree.push_sibling(&previous, brk);
-        fragment_tree.push_sibling(&brk, clone);
+        let clone = fragment_tree.clone_fragment(fragment);
+        let previous = index.checked_sub(1).map(|previous| &children[previous]);
+        fragment_tree.move_siblings(previous, brk, &clone);
+        fragment_tree.remove_child(*brk);
 
-        previous = clone;
+        fragment_tree.push_sibling(fragment, *brk);
+        fragment_tree.push_sibling(brk, clone);
+        clones.push(clone);
     }
+
+    std::iter::once(*fragment).chain(clones.into_iter().rev()).collect()
 }
 
 /// Breaking introduces break fragment
@@ -60,109 +71,182 @@ pub struct OverflowBreak {
 }
 
 impl Break<Inline> for OverflowBreak {
+    /// Inserts a break after the content fitting in the max length.
+    ///
+    /// ```spec
+    /// If content does not fit within the line, the UA must break at the last soft wrap opportunity that fits.
+    /// If there are none, the UA may break at an emergency opportunity,
+    /// and otherwise breaks at the first soft wrap opportunity, letting the content overflow.
+    /// ```
+    ///
+    /// The boxes of the fragment and its descendants must be computed.
     fn r#break(&self, fragment_tree: &mut FragmentTree, fragment: &Fragment) -> bool {
-        let r#box = fragment_tree.borrow_box(&fragment).unwrap();
+        let overflows = fragment_tree
+            .boxes
+            .borrow(fragment)
+            .map(|r#box| r#box.outer().width > self.max_length)
+            .unwrap_or_default();
 
         // We don't need to fragment it.
-        if r#box.content.width <= self.max_length {
+        if !overflows {
             return false;
         }
 
-        drop(r#box);
+        let mut leaves = Vec::new();
+        let mut x = 0;
+        collect_leaves(fragment_tree, fragment, &mut x, &mut leaves);
 
-        // We cannot fragment it.
-        if IsFragmentable::<Inline>::is_fragmentable(fragment_tree, fragment) {
-            return false;
+        // There is no point in breaking after the last content of the line.
+        leaves.pop();
+
+        let fits = |leaf: &&Leaf, emergency: bool| {
+            leaf.fit_end <= self.max_length
+                && match leaf.opportunity {
+                    Some(BreakOpportunity::Emergency) => emergency,
+                    Some(_) => true,
+                    None => false,
+                }
+        };
+
+        let at = leaves
+            .iter()
+            .rev()
+            .find(|leaf| fits(leaf, false))
+            .or_else(|| leaves.iter().rev().find(|leaf| fits(leaf, true)))
+            .or_else(|| leaves.iter().find(|leaf| leaf.opportunity.is_some()));
+
+        match at {
+            Some(leaf) => {
+                let brk = fragment_tree.insert_break();
+                fragment_tree.push_sibling(&leaf.fragment, brk);
+                true
+            },
+            None => false,
         }
+    }
+}
 
-        let mut acc = Box::<i32>::default();
-
-        // We find a possible break point
-        let child_boxes = fragment_tree
-            .iter_children(fragment)
-            .map(|child| {
-                (
-                    child,
-                    fragment_tree.is_break(&child),
-                    fragment_tree.borrow_box(&child).map(|c| c.clone()),
-                )
-            })
-            .collect::<Vec<_>>();
-
-        for (child, is_break, maybe_box) in child_boxes {
-            if is_break {
-                return true;
-            }
+/// A monolithic content of a line, after which the line may end.
+struct Leaf {
+    fragment: Fragment,
+    /// Where the line would end, if it is broken after the leaf.
+    fit_end: i32,
+    opportunity: Option<BreakOpportunity>,
+}
 
-            if let Some(r#box) = maybe_box {
-                acc = Lay::<Inline>::lay(acc, r#box);
-            }
+/// Collects the monolithic contents of the fragment, in order.
+fn collect_leaves(fragment_tree: &FragmentTree, fragment: &Fragment, x: &mut i32, leaves: &mut Vec<Leaf>) {
+    let children = fragment_tree.iter_children(fragment).collect::<Vec<_>>();
+
+    for child in children {
+        let r#box = fragment_tree.boxes.borrow(&child).map(|r#box| r#box.clone()).unwrap_or_default();
 
-            if acc.content.width > self.max_length {
-                // We can fragment it
-                if IsFragmentable::<Inline>::is_fragmentable(fragment_tree, fragment) {
-                    let sub_break = Self {
-                        max_length: acc.content.width - self.max_length,
-                    };
-
-                    return sub_break.r#break(fragment_tree, &child);
-                } else {
-                    // we need to break before or after the monolith.
-                    // We have fragments before we break it before
-                    let maybe_at = if let Some(previous) = fragment_tree.before(&child) {
-                        Some(previous)
-                    } else if let Some(next) = fragment_tree.after(&child) {
-                        // We break afterwards
-                        Some(next)
-                    } else {
-                        None
-                    };
-
-                    if let Some(at) = maybe_at {
-                        let brk = fragment_tree.insert_break();
-                        fragment_tree.push_sibling(&at, brk);
-                        return true;
-                    }
+        match fragment_tree.kind(child) {
+            FragmentKind::InlineBox => {
+                *x += r#box.margin.left + r#box.border.left + r#box.padding.left;
+                let first = leaves.len();
+                collect_leaves(fragment_tree, &child, x, leaves);
+
+                let closing = r#box.margin.right + r#box.border.right + r#box.padding.right;
+                *x += closing;
+
+                // The line ends with the box when it breaks after the box's last content.
+                if let Some(last) = leaves[first..].last_mut() {
+                    last.fit_end += closing;
                 }
-            }
+            },
+            FragmentKind::TextSequence => {
+                let start = *x;
+                *x += r#box.outer().width;
+
+                // ```spec
+                // If spaces or tabs at the end of a line are non-collapsible but have white-space set to pre-wrap the UA must hang them.
+                // A sequence of collapsible spaces at the end of a line is removed.
+                // ```
+                let hangs = fragment_tree
+                    .style
+                    .computed
+                    .borrow(&child)
+                    .map(|props| props.white_space.collapses_spaces() || props.white_space == WhiteSpace::PreWrap)
+                    .unwrap_or_default();
+
+                let (is_white_space, opportunity) = fragment_tree
+                    .text_sequences
+                    .borrow(&child)
+                    .map(|seq| (seq.is_white_space(), seq.break_after()))
+                    .unwrap_or_default();
+
+                let fit_end = if hangs && is_white_space { start } else { *x };
+                leaves.push(Leaf { fragment: child, fit_end, opportunity });
+            },
+            FragmentKind::AtomicInline => {
+                *x += r#box.outer().width;
+                leaves.push(Leaf { fragment: child, fit_end: *x, opportunity: Some(BreakOpportunity::Soft) });
+            },
+            _ => {},
         }
-
-        return false;
     }
 }
 
-pub struct LineBreak;
+/// Splits a text sequence at its break opportunities.
+///
+/// A break is inserted after each part ending with a mandatory break.
+pub struct LineBreak {
+    /// The break opportunities, as byte offsets in the text.
+    pub opportunities: Vec<(usize, BreakOpportunity)>,
+}
 
 impl<U> Break<U> for LineBreak {
-    /// Fragment a text sequence on a line break.
+    /// Fragment a text sequence on its break opportunities.
     ///
     /// # Arguments
     /// - `text_sequence` - Must be a text sequence fragment
     fn r#break(&self, fragment_tree: &mut FragmentTree, node: &Fragment) -> bool {
-        if let FragmentKind::TextSequence = fragment_tree.kind(node) {
-            let mut seq = fragment_tree.text_sequences.borrow_mut(node).unwrap();
-            let mut frag_seq = seq.split_by_line_breaks().collect::<VecDeque<_>>();
+        if !fragment_tree.is(node, FragmentKind::TextSequence) {
+            return false;
+        }
 
-            if let Some(head) = frag_seq.pop_front() {
-                *seq = head;
-            }
+        let mut parts = match fragment_tree.text_sequences.borrow_mut(node) {
+            Some(mut seq) => {
+                let mut parts = seq.split_at_break_opportunities(&self.opportunities).into_iter().collect::<VecDeque<_>>();
+
+                if let Some(head) = parts.pop_front() {
+                    *seq = head;
+                }
+
+                parts
+            },
+            None => return false,
+        };
+
+        let source = *fragment_tree.sources.borrow(node).unwrap();
+        let mut previous = *node;
+        let mut has_break = false;
 
-            drop(seq);
+        loop {
+            let mandatory = fragment_tree
+                .text_sequences
+                .borrow(&previous)
+                .map(|seq| seq.break_after() == Some(BreakOpportunity::Mandatory))
+                .unwrap_or_default();
 
-            let mut previous = *node;
-            let has_break = frag_seq.len() > 0;
-            while let Some(sub_seq) = frag_seq.pop_front() {
+            if mandatory {
                 let brk = fragment_tree.insert_break();
                 fragment_tree.push_sibling(&previous, brk);
-
-                let seq_frag = fragment_tree.insert_text_sequence(sub_seq);
-                fragment_tree.push_sibling(&brk, seq_frag);
-                previous = seq_frag;
+                previous = brk;
+                has_break = true;
             }
 
-            return has_break;
+            let Some(sub_seq) = parts.pop_front() else {
+                break;
+            };
+
+            let seq_frag = fragment_tree.insert_text_sequence(sub_seq, source, None);
+            fragment_tree.style.computed.share_from(&seq_frag, node);
+            fragment_tree.push_sibling(&previous, seq_frag);
+            previous = seq_frag;
         }
 
-        return false;
+        has_break
     }
 }
@@ -0,0 +1,410 @@
+//! Implements the fragmentation of the block flow into pages from [CSS FRAGMENTATION 3](https://www.w3.org/TR/css-break-3/)
+//!
+//! ```spec
+//! A fragmentation break is a break in the content that causes it to be split across fragmentainers.
+//! In paged media, each page box is a fragmentainer.
+//! ```
+use crate::{
+    ecs::systems::tree::{TreeExplorer, TreeMutator},
+    layout::{
+        fragment_tree::{Fragment, FragmentKind, FragmentTree},
+        Block,
+    },
+    style::{fragmentation::BreakBetween, page::Page},
+};
+
+use super::{fragment, Break};
+
+/// Break if the block flow overflows the page, or if a break is forced.
+pub struct PageBreak {
+    pub page_height: i32,
+}
+
+/// The breaking rules violated by a break point, in the order they are relaxed.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Violation {
+    None,
+    /// ```spec
+    /// Rule 1: A fragmentation break at a Class A break point is not allowed if break-after or break-before is avoid.
+    /// Rule 2: A fragmentation break is not allowed if an ancestor has break-inside: avoid.
+    /// ```
+    Avoid,
+    /// ```spec
+    /// Rule 3: Breaking at a Class C break point is allowed only if the number of line boxes between the break
+    /// and the start of the enclosing block is orphans or more, and the number of line boxes between the break
+    /// and the end of the box is widows or more.
+    /// ```
+    OrphansOrWidows,
+}
+
+/// A place between two sibling block-level boxes, or two line boxes, where the flow may break.
+struct BreakPoint {
+    /// The box following the break.
+    fragment: Fragment,
+    /// Where the content preceding the break ends, from the top of the page.
+    end: i32,
+    forced: bool,
+    violation: Violation,
+}
+
+impl Break<Block> for PageBreak {
+    /// Inserts a break before the content which does not fit in the page, or at the first forced break.
+    ///
+    /// ```spec
+    /// When an unforced break is needed, the UA should break at the last possible break point that fits.
+    /// If that doesn't provide enough break opportunities to keep the fragmentainer contents from overflowing,
+    /// the rules are relaxed: first the avoid rules, then orphans and widows.
+    /// If there is still no break point that fits, the content overflows, and the UA breaks at the first possible break point.
+    /// ```
+    ///
+    /// The root must be laid out, at the top of the page.
+    fn r#break(&self, fragment_tree: &mut FragmentTree, root: &Fragment) -> bool {
+        let r#box = fragment_tree.boxes.borrow(root).map(|r#box| r#box.clone()).unwrap_or_default();
+        let top = fragment_tree.positions.borrow(root).map(|position| position.y).unwrap_or_default();
+        let bottom = top + r#box.outer().height - r#box.margin.top;
+
+        let mut points = Vec::new();
+        let content_top = top + r#box.border.top + r#box.padding.top;
+        let avoid_inside = avoids_break_inside(fragment_tree, root);
+        collect_break_points(fragment_tree, root, content_top, avoid_inside, self.page_height, &mut points);
+
+        // Breaks past the first forced one belong to the next pages.
+        let forced = points.iter().position(|point| point.forced);
+        let candidates = &points[..forced.unwrap_or(points.len())];
+
+        let at = match forced.map(|index| &points[index]) {
+            Some(point) if point.end <= self.page_height => Some(point),
+            _ if bottom <= self.page_height => None,
+            _ => {
+                let fits = |violation: Violation| candidates
+                    .iter()
+                    .rev()
+                    .find(|point| point.end <= self.page_height && point.violation <= violation);
+
+                fits(Violation::None)
+                    .or_else(|| fits(Violation::Avoid))
+                    .or_else(|| fits(Violation::OrphansOrWidows))
+                    .or_else(|| candidates.first())
+            },
+        };
+
+        let Some(previous) = at.and_then(|point| fragment_tree.previous_sibling(point.fragment)) else {
+            return false;
+        };
+
+        let brk = fragment_tree.insert_break();
+        fragment_tree.push_sibling(&previous, brk);
+        true
+    }
+}
+
+/// Fragments the block flow of the root into pages,
+/// whose heights are given from their index and the root of their content.
+///
+/// The content following each break is moved into clones of its ancestors,
+/// which are positioned from the top of the next page.
+///
+/// Returns the root fragments of the pages, in order, or nothing for the blank pages.
+pub fn paginate(fragment_tree: &mut FragmentTree, root: &Fragment, mut page_height: impl FnMut(&FragmentTree, usize, &Fragment) -> i32) -> Vec<Option<Fragment>> {
+    let mut pages = vec![Some(*root)];
+    let mut page = *root;
+
+    loop {
+        let page_break = PageBreak { page_height: page_height(fragment_tree, pages.len() - 1, &page) };
+
+        if !Break::<Block>::r#break(&page_break, fragment_tree, &page) {
+            break;
+        }
+
+        let Some((next, forced)) = split_page(fragment_tree, &page) else {
+            break;
+        };
+
+        // ```spec
+        // Force one or two page breaks so that the next page is formatted as a left or right page.
+        // A blank page is a page generated by a forced break, which has no content from the document.
+        // ```
+        let is_right = Page { index: pages.len(), name: None, blank: false }.is_right();
+        if forced.and_then(|value| value.requires_right_page()).is_some_and(|right| right != is_right) {
+            pages.push(None);
+        }
+
+        pages.push(Some(next));
+        page = next;
+    }
+
+    pages
+}
+
+/// Returns the name of the page starting with the fragment.
+///
+/// ```spec
+/// The start page value of a box is the start page value of its first in-flow child, if it is not auto,
+/// or the used value of its page property otherwise, where auto takes the used value of the parent.
+/// ```
+pub fn page_name(fragment_tree: &FragmentTree, fragment: &Fragment) -> Option<String> {
+    edge_page_name(fragment_tree, fragment, true)
+}
+
+/// Returns the page name at the start, or at the end, of the fragment.
+fn edge_page_name(fragment_tree: &FragmentTree, fragment: &Fragment, start: bool) -> Option<String> {
+    let own_name = |fragment: &Fragment| fragment_tree
+        .style
+        .computed
+        .borrow(fragment)
+        .and_then(|props| props.page.name().map(str::to_owned));
+
+    let mut name = None;
+    let mut current = Some(*fragment);
+
+    while let Some(fragment) = current {
+        name = own_name(&fragment).or(name);
+
+        let mut children = fragment_tree
+            .iter_children(fragment)
+            .filter(|child| fragment_tree.kind(child).is_block_level());
+
+        current = if start { children.next() } else { children.last() };
+    }
+
+    let mut ancestor = fragment_tree.parent(fragment);
+
+    while name.is_none() {
+        let Some(fragment) = ancestor else {
+            break;
+        };
+
+        name = own_name(&fragment);
+        ancestor = fragment_tree.parent(fragment);
+    }
+
+    name
+}
+
+/// Collects the break points within the block container, in document order.
+///
+/// The content top is the position of the content box of the fragment, from the top of the page.
+///
+/// The collection stops at the first break point past the page height, as the following ones cannot fit in the page.
+fn collect_break_points(
+    fragment_tree: &FragmentTree,
+    fragment: &Fragment,
+    content_top: i32,
+    avoid_inside: bool,
+    page_height: i32,
+    points: &mut Vec<BreakPoint>,
+) {
+    let children = fragment_tree
+        .iter_children(fragment)
+        .filter(|child| fragment_tree.kind(child).is_block_level() || fragment_tree.is(child, FragmentKind::LineBox))
+        .collect::<Vec<_>>();
+
+    let (orphans, widows) = fragment_tree
+        .style
+        .computed
+        .borrow(fragment)
+        .map(|props| (*props.orphans as usize, *props.widows as usize))
+        .unwrap_or((2, 2));
+
+    let lines = children.iter().filter(|child| fragment_tree.is(*child, FragmentKind::LineBox)).count();
+    let mut previous_bottom = content_top;
+
+    for (index, child) in children.iter().enumerate() {
+        if points.last().is_some_and(|point| point.end > page_height) {
+            return;
+        }
+
+        let r#box = fragment_tree.boxes.borrow(child).map(|r#box| r#box.clone()).unwrap_or_default();
+        let top = content_top + fragment_tree.positions.borrow(child).map(|position| position.y).unwrap_or_default();
+
+        if index > 0 {
+            let is_line = fragment_tree.is(child, FragmentKind::LineBox);
+            let (forced, avoid) = if is_line {
+                (false, false)
+            } else {
+                let (forced, avoid) = break_between(fragment_tree, &children[index - 1], child);
+                (forced.is_some(), avoid)
+            };
+
+            let violation = if is_line && (index < orphans || lines - index < widows) {
+                Violation::OrphansOrWidows
+            } else if avoid || avoid_inside {
+                Violation::Avoid
+            } else {
+                Violation::None
+            };
+
+            points.push(BreakPoint { fragment: *child, end: previous_bottom, forced, violation });
+        }
+
+        if fragment_tree.kind(child).is_block_level() {
+            let avoid_inside = avoid_inside || avoids_break_inside(fragment_tree, child);
+            collect_break_points(fragment_tree, child, top + r#box.border.top + r#box.padding.top, avoid_inside, page_height, points);
+        }
+
+        previous_bottom = top + r#box.outer().height - r#box.margin.top - r#box.margin.bottom;
+    }
+}
+
+/// Returns the value forcing the break between two sibling block-level boxes, if any, and whether the break should be avoided.
+///
+/// ```spec
+/// When multiple break-before or break-after values meet at a break point, they are combined:
+/// the values of the box following the break, and of its first descendants, propagate to the break before it,
+/// and the values of the box preceding the break, and of its last descendants, propagate to the break after it.
+/// Forced break values take precedence over avoid values, and the last forced value in document order wins.
+/// A forced page break is also introduced where the page name changes between the boxes.
+/// ```
+fn break_between(fragment_tree: &FragmentTree, previous: &Fragment, next: &Fragment) -> (Option<BreakBetween>, bool) {
+    let chain = |start: &Fragment, first: bool| {
+        let mut values = Vec::new();
+        let mut current = Some(*start);
+
+        while let Some(fragment) = current {
+            if let Some(props) = fragment_tree.style.computed.borrow(&fragment) {
+                values.push(if first { props.break_before } else { props.break_after });
+            }
+
+            let mut children = fragment_tree
+                .iter_children(fragment)
+                .filter(|child| fragment_tree.kind(child).is_block_level());
+
+            current = if first { children.next() } else { children.last() };
+        }
+
+        values
+    };
+
+    let values = chain(previous, false).into_iter().chain(chain(next, true)).collect::<Vec<_>>();
+    let forced = values
+        .iter()
+        .copied()
+        .rev()
+        .find(BreakBetween::forces_page_break)
+        .or_else(|| {
+            let changes_page = edge_page_name(fragment_tree, previous, false) != edge_page_name(fragment_tree, next, true);
+            changes_page.then_some(BreakBetween::Page)
+        });
+
+    let avoid = forced.is_none() && values.iter().any(BreakBetween::avoids_page_break);
+    (forced, avoid)
+}
+
+fn avoids_break_inside(fragment_tree: &FragmentTree, fragment: &Fragment) -> bool {
+    fragment_tree
+        .style
+        .computed
+        .borrow(fragment)
+        .map(|props| props.break_inside.avoids_page_break())
+        .unwrap_or_default()
+}
+
+/// Finds the break inserted in the block flow of the fragment.
+fn find_break(fragment_tree: &FragmentTree, fragment: &Fragment) -> Option<Fragment> {
+    fragment_tree.iter_children(fragment).find_map(|child| {
+        if fragment_tree.is_break(child) {
+            Some(child)
+        } else if fragment_tree.kind(child).is_block_level() {
+            find_break(fragment_tree, &child)
+        } else {
+            None
+        }
+    })
+}
+
+/// Splits the page at its break, and returns the root of the next page, with the value forcing the break, if any.
+///
+/// ```spec
+/// When a box breaks, its content box extends to fill any remaining fragmentainer space,
+/// and its border and padding are sliced at the break (box-decoration-break: slice).
+/// When an unforced break occurs between block-level boxes, any margins adjoining the break truncate to zero.
+/// When a forced break occurs there, adjoining margins before the break are truncated, but margins after the break are preserved.
+/// ```
+fn split_page(fragment_tree: &mut FragmentTree, root: &Fragment) -> Option<(Fragment, Option<BreakBetween>)> {
+    let brk = find_break(fragment_tree, root)?;
+    let previous = fragment_tree.previous_sibling(brk)?;
+    let next = fragment_tree.next_sibling(brk)?;
+    let forced = if fragment_tree.is(next, FragmentKind::LineBox) {
+        None
+    } else {
+        break_between(fragment_tree, &previous, &next).0
+    };
+
+    let mut ancestors = vec![fragment_tree.parent(brk)?];
+    while ancestors.last() != Some(root) {
+        ancestors.push(fragment_tree.parent(ancestors.last()?)?);
+    }
+    ancestors.reverse();
+
+    // The content boxes of the ancestors, from the top of the page.
+    let mut content_boxes = Vec::with_capacity(ancestors.len());
+    let mut top = 0;
+    for ancestor in &ancestors {
+        let r#box = fragment_tree.boxes.borrow(ancestor).map(|r#box| r#box.clone()).unwrap_or_default();
+        top += fragment_tree.positions.borrow(ancestor).map(|position| position.y).unwrap_or_default();
+        top += r#box.border.top + r#box.padding.top;
+        content_boxes.push((top, top + r#box.content.height));
+    }
+
+    let content_top = content_boxes.last()?.0;
+    let previous_box = fragment_tree.boxes.borrow(&previous).map(|r#box| r#box.clone()).unwrap_or_default();
+    let previous_top = fragment_tree.positions.borrow(&previous).map(|position| position.y).unwrap_or_default();
+    // Where the content of the page ends.
+    let end = content_top + previous_top + previous_box.outer().height - previous_box.margin.top - previous_box.margin.bottom;
+
+    let next_margin = fragment_tree.boxes.borrow(&next).map(|r#box| r#box.margin.top).unwrap_or_default();
+    let next_top = fragment_tree.positions.borrow(&next).map(|position| position.y).unwrap_or_default();
+    // Where the content of the next page starts.
+    let start = content_top + next_top - if forced.is_some() { next_margin } else { 0 };
+
+    if forced.is_none() {
+        if let Some(mut r#box) = fragment_tree.boxes.borrow_mut(&next) {
+            r#box.margin.top = 0;
+        }
+    }
+
+    let mut clones = Vec::with_capacity(ancestors.len());
+    for ancestor in ancestors.iter().rev() {
+        let parts = fragment(fragment_tree, ancestor);
+        clones.push(*parts.last()?);
+    }
+    clones.reverse();
+
+    // The root of the page has no parent, the break left after it is dropped.
+    fragment_tree.pop_sibling(root);
+
+    for (level, (ancestor, clone)) in ancestors.iter().zip(&clones).enumerate() {
+        let (content_top, content_bottom) = content_boxes[level];
+
+        if let Some(mut r#box) = fragment_tree.boxes.borrow_mut(ancestor) {
+            r#box.content.height = (end - content_top).max(0);
+            r#box.padding.bottom = 0;
+            r#box.border.bottom = 0;
+            r#box.margin.bottom = 0;
+        }
+
+        if let Some(mut r#box) = fragment_tree.boxes.borrow_mut(clone) {
+            r#box.content.height = (content_bottom - start).max(0);
+            r#box.padding.top = 0;
+            r#box.border.top = 0;
+            r#box.margin.top = 0;
+        }
+
+        if let Some(mut position) = fragment_tree.positions.borrow_mut(clone) {
+            position.y = 0;
+        }
+
+        let moved = fragment_tree
+            .iter_children(clone)
+            .filter(|child| Some(child) != clones.get(level + 1))
+            .collect::<Vec<_>>();
+
+        for child in moved {
+            if let Some(mut position) = fragment_tree.positions.borrow_mut(&child) {
+                position.y += content_top - start;
+            }
+        }
+    }
+
+    clones.first().map(|clone| (*clone, forced))
+}
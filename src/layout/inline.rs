@@ -0,0 +1,884 @@
+//! Implements the inline formatting context from [CSS 2.1](https://www.w3.org/TR/CSS21/visuren.html#inline-formatting)
+//!
+//! ```spec
+//! In an inline formatting context, boxes are laid out horizontally, one after the other, beginning at the top of a containing block.
+//! The rectangular area that contains the boxes that form a line is called a line box.
+//! When several inline-level boxes cannot fit horizontally within a single line box, they are distributed among two or more vertically-stacked line boxes.
+//! When an inline box exceeds the width of a line box, it is split into several boxes and these boxes are distributed across several line boxes.
+//! ```
+use std::{collections::{HashMap, VecDeque}, ops::Range};
+
+use crate::{
+    ecs::systems::tree::{TreeExplorer, TreeMutator},
+    font::{FontMetrics, SizedFont},
+    style::{
+        inline::VerticalAlign,
+        values::{
+            numeric::{AutoOrPixelsOrPercentage, ResolutionContext},
+            r#box::{Box, BoxContent, BoxEdges, Point},
+        },
+    },
+};
+
+use super::{
+    fragment_tree::{Fragment, FragmentKind, FragmentTree},
+    fragmentation::{self, Break, LineBreak, OverflowBreak},
+    line_breaking::{break_opportunities, tailor, BreakOpportunity, OBJECT_REPLACEMENT_CHARACTER},
+    shaping::{shape_with_fallback, GlyphRun},
+    px_to_units, text_sequence::TextSequence, units_to_px, Inline, Lay, Layout,
+};
+
+/// The estimated advance of a character, relative to the font size.
+///
+/// Text sequences are measured with it when there is no font to shape them with.
+const ESTIMATED_ADVANCE: f64 = 0.5;
+
+impl Lay<Inline> for Box<i32> {
+    /// Lays the boxes next to each other, on the same line.
+    fn lay(self, other: Self) -> Self {
+        let (left, right) = (self.outer(), other.outer());
+
+        Self {
+            content: BoxContent {
+                width: left.width + right.width,
+                height: left.height.max(right.height),
+            },
+            ..Default::default()
+        }
+    }
+}
+
+/// The vertical metrics of an inline-level box, relative to its baseline.
+#[derive(Debug, Clone, Copy, Default)]
+struct Strut {
+    /// The height of the content area above the baseline.
+    ascent: i32,
+    /// The depth of the content area below the baseline.
+    descent: i32,
+    x_height: i32,
+    font_size: i32,
+    line_height: i32,
+}
+
+impl Strut {
+    /// ```spec
+    /// The difference between the line height and the height of the content area is the leading,
+    /// half of which is added above the content area, and the other half below.
+    /// ```
+    fn top(&self) -> i32 {
+        -(self.ascent + (self.line_height - self.ascent - self.descent) / 2)
+    }
+
+    fn bottom(&self) -> i32 {
+        self.top() + self.line_height
+    }
+}
+
+/// The preferred widths of inline-level content, accumulated from its leaves in order.
+#[derive(Debug, Clone, Copy)]
+struct PreferredWidths {
+    /// The widest content between two break opportunities.
+    min_content: i32,
+    /// The widest line, which only ends at forced line breaks.
+    max_content: i32,
+    /// The width of the content since the last break opportunity.
+    word: i32,
+    /// The width of the content since the last forced line break.
+    line: i32,
+    after_collapsible_space: bool,
+}
+
+impl Default for PreferredWidths {
+    fn default() -> Self {
+        Self { min_content: 0, max_content: 0, word: 0, line: 0, after_collapsible_space: true }
+    }
+}
+
+impl PreferredWidths {
+    /// Adds content to the current word, and to the current line.
+    ///
+    /// The spaces which end the word hang when the line is broken after it, hence the narrower width for the word.
+    fn push(&mut self, word: i32, line: i32) {
+        self.word += word;
+        self.line += line;
+    }
+
+    /// The line may end here.
+    fn soft_break(&mut self) {
+        self.min_content = self.min_content.max(self.word);
+        self.word = 0;
+    }
+
+    /// The line must end here.
+    fn forced_break(&mut self) {
+        self.soft_break();
+        self.max_content = self.max_content.max(self.line);
+        self.line = 0;
+    }
+}
+
+/// A subtree aligned with the top or the bottom of its line box.
+struct LineRelativeSubtree {
+    vertical_align: VerticalAlign,
+    /// The range of the aligned boxes of the subtree.
+    range: Range<usize>,
+    /// The bounds of the subtree, relative to its baseline.
+    bounds: (i32, i32),
+}
+
+impl Layout<Inline> for FragmentTree {
+    type Element = Fragment;
+
+    /// Lays out the inline-level content of a block container into line boxes.
+    ///
+    /// The width of the content box of the block container must be resolved.
+    /// The line boxes are stacked as its children, and hold the inline-level content they contain.
+    fn layout(&mut self, element: &Self::Element) {
+        let available = self.boxes.borrow(element).map(|r#box| r#box.content.width).unwrap_or_default();
+        let mut ctx = self.resolution_context();
+
+        // ```spec
+        // A sequence of collapsible spaces at the beginning of a line is removed.
+        // ```
+        let mut after_collapsible_space = true;
+        let mut leaves = Vec::new();
+        let roots = self.iter_children(element).collect::<Vec<_>>();
+        for root in roots.iter() {
+            self.prepare_inline_content(root, available, &mut ctx, &mut after_collapsible_space, &mut leaves);
+        }
+
+        self.break_at_opportunities(&leaves);
+        for root in roots.iter() {
+            self.measure_inline_content(root);
+        }
+
+        // The content starts on a single line box, which forced line breaks split into the first candidate lines.
+        let source = *self.sources.borrow(element).unwrap();
+        let line_box = self.insert_line_box(available, source, None);
+        self.interpose_child(element, line_box);
+
+        let overflow = OverflowBreak { max_length: available };
+        let mut candidates = self.split_at_breaks(&line_box).into_iter().collect::<VecDeque<_>>();
+        let mut lines = Vec::with_capacity(candidates.len());
+
+        while let Some(candidate) = candidates.pop_front() {
+            self.trim_line(&candidate);
+            self.compute_box(&candidate);
+
+            if overflow.r#break(self, &candidate) {
+                let parts = self.split_at_breaks(&candidate);
+                parts.into_iter().skip(1).rev().for_each(|part| candidates.push_front(part));
+                self.trim_line(&candidate);
+                self.compute_box(&candidate);
+            }
+
+            lines.push(candidate);
+        }
+
+        let breaks = self
+            .iter_children(element)
+            .filter(|child| self.is_break(child))
+            .collect::<Vec<_>>();
+        breaks.into_iter().for_each(|brk| self.remove_child(brk));
+
+        // ```spec
+        // Line boxes that contain no text, no preserved white space, no inline boxes with non-zero margins, padding, or borders,
+        // and no other in-flow content must be treated as zero-height line boxes.
+        // ```
+        // They are not generated, nor is the empty line following the last forced line break.
+        let last = lines.len().saturating_sub(1);
+        let root = self.strut(element);
+        let mut y = 0;
+
+        for (index, line) in lines.into_iter().enumerate() {
+            let is_empty = !self.has_inline_content(&line)
+                || (index == last && index > 0 && self.is_blank(&line));
+
+            if is_empty {
+                self.remove_child(line);
+                continue;
+            }
+
+            let (height, baseline) = self.align_line(&line, &root);
+
+            if let Some(mut r#box) = self.boxes.borrow_mut(&line) {
+                r#box.content = BoxContent { width: available, height };
+            }
+
+            if let Some(mut line_box) = self.line_boxes.borrow_mut(&line) {
+                line_box.baseline = baseline;
+            }
+
+            if let Some(mut position) = self.positions.borrow_mut(&line) {
+                *position = Point { x: 0, y };
+            }
+
+            y += height;
+        }
+    }
+}
+
+impl FragmentTree {
+    /// Processes the white space of the text sequences, resolves the edges of the inline boxes, and lays out the atomic inlines.
+    ///
+    /// The text sequences and atomic inlines are collected in order.
+    fn prepare_inline_content(
+        &mut self,
+        fragment: &Fragment,
+        available: i32,
+        ctx: &mut ResolutionContext,
+        after_collapsible_space: &mut bool,
+        leaves: &mut Vec<Fragment>,
+    ) {
+        match self.kind(fragment) {
+            FragmentKind::TextSequence => {
+                let white_space = self
+                    .style
+                    .computed
+                    .borrow(fragment)
+                    .map(|props| props.white_space)
+                    .unwrap_or_default();
+
+                if let Some(mut seq) = self.text_sequences.borrow_mut(fragment) {
+                    let text = white_space.process(seq.as_str(), *after_collapsible_space);
+                    *after_collapsible_space = white_space.collapses_spaces() && (text.ends_with(' ') || (text.is_empty() && *after_collapsible_space));
+                    *seq = TextSequence::from(text.as_str());
+                }
+
+                leaves.push(*fragment);
+            },
+            FragmentKind::InlineBox => {
+                let used = self.used_properties(fragment, ctx);
+                let basis = units_to_px(available);
+                let length = |value: &AutoOrPixelsOrPercentage| value.px(basis).map(px_to_units).unwrap_or_default();
+
+                if let Some(mut r#box) = self.boxes.borrow_mut(fragment) {
+                    // ```spec
+                    // The 'margin-top' and 'margin-bottom' properties have no effect on non-replaced inline elements.
+                    // ```
+                    r#box.margin = BoxEdges {
+                        left: length(&used.margin.left),
+                        right: length(&used.margin.right),
+                        ..Default::default()
+                    };
+                    r#box.padding = BoxEdges {
+                        top: length(&used.padding.top),
+                        bottom: length(&used.padding.bottom),
+                        left: length(&used.padding.left),
+                        right: length(&used.padding.right),
+                    };
+                    r#box.border = BoxEdges {
+                        top: px_to_units(used.border.top),
+                        bottom: px_to_units(used.border.bottom),
+                        left: px_to_units(used.border.left),
+                        right: px_to_units(used.border.right),
+                    };
+                }
+
+                let children = self.iter_children(fragment).collect::<Vec<_>>();
+                for child in children {
+                    self.prepare_inline_content(&child, available, ctx, after_collapsible_space, leaves);
+                }
+            },
+            // The atomic inline is laid out as a block formatting context root, as wide as its content.
+            FragmentKind::AtomicInline => {
+                self.layout_block_level(fragment, available, None, ctx);
+                *after_collapsible_space = false;
+                leaves.push(*fragment);
+            },
+            _ => {}
+        }
+    }
+
+    /// Splits the text sequences at their break opportunities.
+    ///
+    /// The opportunities are found in the text of the whole paragraph, where atomic inlines stand as object replacement characters,
+    /// and tailored by the line breaking properties of each text sequence.
+    fn break_at_opportunities(&mut self, leaves: &[Fragment]) {
+        let mut paragraph = String::new();
+        let ranges = leaves
+            .iter()
+            .map(|leaf| {
+                let start = paragraph.len();
+                match self.text_sequences.borrow(leaf) {
+                    Some(seq) => paragraph.push_str(seq.as_str()),
+                    None => paragraph.push(OBJECT_REPLACEMENT_CHARACTER),
+                }
+                start..paragraph.len()
+            })
+            .collect::<Vec<_>>();
+
+        let opportunities = break_opportunities(&paragraph);
+
+        for (leaf, range) in leaves.iter().zip(ranges) {
+            if !self.is(leaf, FragmentKind::TextSequence) {
+                continue;
+            }
+
+            // The opportunity at the start of the text belongs to the content preceding it.
+            let local = opportunities
+                .iter()
+                .filter(|(offset, _)| *offset > range.start && *offset <= range.end)
+                .map(|(offset, opportunity)| (offset - range.start, *opportunity))
+                .collect::<Vec<_>>();
+
+            let props = self.style.computed.borrow(leaf).map(|props| props.clone()).unwrap_or_default();
+            let text = &paragraph[range];
+            let line_break = LineBreak { opportunities: tailor(text, &local, &props) };
+            Break::<Inline>::r#break(&line_break, self, leaf);
+        }
+    }
+
+    /// Measures the text sequences of the inline-level content.
+    fn measure_inline_content(&mut self, fragment: &Fragment) {
+        match self.kind(fragment) {
+            FragmentKind::TextSequence => self.measure_text_sequence(fragment),
+            FragmentKind::InlineBox => {
+                let children = self.iter_children(fragment).collect::<Vec<_>>();
+                for child in children {
+                    self.measure_inline_content(&child);
+                }
+            },
+            _ => {}
+        }
+    }
+
+    /// Shapes a text sequence, which is as wide as the advance of its glyphs, and as high as the ascent and descent of its fonts.
+    ///
+    /// Without any font, its dimensions are estimated from the font size.
+    pub(super) fn measure_text_sequence(&mut self, fragment: &Fragment) {
+        let text = self.text_sequences.borrow(fragment).map(|seq| seq.as_str().to_owned()).unwrap_or_default();
+        let (runs, content) = self.shape_text(fragment, &text);
+        self.glyph_runs.bind(fragment, runs);
+
+        if let Some(mut r#box) = self.boxes.borrow_mut(fragment) {
+            r#box.content = content;
+        }
+    }
+
+    /// Returns the preferred minimum width and the preferred width of the inline-level content of a block container.
+    ///
+    /// ```spec
+    /// Calculate the preferred width by formatting the content without breaking lines other than where explicit line breaks occur,
+    /// and also calculate the preferred minimum width, e.g., by trying all possible line breaks.
+    /// ```
+    pub(super) fn preferred_inline_widths(&mut self, fragment: &Fragment, ctx: &mut ResolutionContext) -> (i32, i32) {
+        let mut widths = PreferredWidths::default();
+        let children = self.iter_children(fragment).collect::<Vec<_>>();
+        for child in children {
+            self.accumulate_preferred_widths(&child, ctx, &mut widths);
+        }
+
+        widths.forced_break();
+        (widths.min_content, widths.max_content)
+    }
+
+    fn accumulate_preferred_widths(&mut self, fragment: &Fragment, ctx: &mut ResolutionContext, widths: &mut PreferredWidths) {
+        match self.kind(fragment) {
+            FragmentKind::TextSequence => {
+                let props = self.style.computed.borrow(fragment).map(|props| props.clone()).unwrap_or_default();
+                let Some(text) = self
+                    .text_sequences
+                    .borrow(fragment)
+                    .map(|seq| props.white_space.process(seq.as_str(), widths.after_collapsible_space))
+                else {
+                    return;
+                };
+
+                widths.after_collapsible_space = props.white_space.collapses_spaces()
+                    && (text.ends_with(' ') || (text.is_empty() && widths.after_collapsible_space));
+
+                let opportunities = tailor(&text, &break_opportunities(&text), &props);
+                let mut start = 0;
+
+                for (offset, opportunity) in opportunities {
+                    let segment = text[start..offset].trim_end_matches('\n');
+                    let word = self.shape_text(fragment, segment.trim_end_matches(' ')).1.width;
+                    let line = self.shape_text(fragment, segment).1.width;
+                    widths.push(word, line);
+                    start = offset;
+
+                    match opportunity {
+                        BreakOpportunity::Mandatory => widths.forced_break(),
+                        BreakOpportunity::Soft => widths.soft_break(),
+                        BreakOpportunity::Emergency => {},
+                    }
+                }
+
+                let rest = self.shape_text(fragment, &text[start..]).1.width;
+                widths.push(rest, rest);
+            },
+            FragmentKind::InlineBox => {
+                let used = self.used_properties(fragment, ctx);
+                let length = |value: &AutoOrPixelsOrPercentage| value.px(0.0).map(px_to_units).unwrap_or_default();
+                let left = length(&used.margin.left) + length(&used.padding.left) + px_to_units(used.border.left);
+                let right = length(&used.margin.right) + length(&used.padding.right) + px_to_units(used.border.right);
+
+                widths.push(left, left);
+                let children = self.iter_children(fragment).collect::<Vec<_>>();
+                for child in children {
+                    self.accumulate_preferred_widths(&child, ctx, widths);
+                }
+                widths.push(right, right);
+            },
+            // The atomic inline cannot be broken, but the line may end around it.
+            FragmentKind::AtomicInline => {
+                let (min_content, max_content) = self.preferred_outer_widths(fragment, ctx);
+                widths.soft_break();
+                widths.push(min_content, max_content);
+                widths.soft_break();
+                widths.after_collapsible_space = false;
+            },
+            _ => {}
+        }
+    }
+
+    /// Shapes a text with the fonts of the fragment, and returns its glyph runs and dimensions.
+    fn shape_text(&mut self, fragment: &Fragment, text: &str) -> (Vec<GlyphRun>, BoxContent<i32>) {
+        let fonts = self.fonts(fragment);
+        let runs = shape_with_fallback(text, &fonts);
+
+        let content = match runs.as_slice() {
+            [] => {
+                let font_size = self
+                    .style
+                    .computed
+                    .borrow(fragment)
+                    .map(|props| props.font_size.0)
+                    .unwrap_or_default();
+
+                BoxContent {
+                    width: px_to_units(text.chars().count() as f64 * font_size * ESTIMATED_ADVANCE),
+                    height: px_to_units(font_size),
+                }
+            },
+            runs => BoxContent {
+                width: runs.iter().map(GlyphRun::advance).sum(),
+                height: runs.iter().map(|run| run.ascent).max().unwrap_or_default()
+                    + runs.iter().map(|run| run.descent).max().unwrap_or_default(),
+            },
+        };
+
+        (runs, content)
+    }
+
+    /// Splits the inline box, and its descendants, at their breaks.
+    ///
+    /// ```spec
+    /// When an inline box is split, margins, borders, and padding have no visual effect where the split occurs.
+    /// ```
+    ///
+    /// Returns the parts of the fragment, in order.
+    fn split_at_breaks(&mut self, fragment: &Fragment) -> Vec<Fragment> {
+        let children = self.iter_children(fragment).collect::<Vec<_>>();
+        for child in children {
+            if self.is(child, FragmentKind::InlineBox) {
+                self.split_at_breaks(&child);
+            }
+        }
+
+        let parts = fragmentation::fragment(self, fragment);
+        let last = parts.len() - 1;
+
+        for (index, part) in parts.iter().enumerate() {
+            let Some(mut r#box) = self.boxes.borrow_mut(part) else {
+                continue;
+            };
+
+            if index > 0 {
+                r#box.margin.left = 0;
+                r#box.border.left = 0;
+                r#box.padding.left = 0;
+            }
+
+            if index < last {
+                r#box.margin.right = 0;
+                r#box.border.right = 0;
+                r#box.padding.right = 0;
+            }
+        }
+
+        parts
+    }
+
+    /// Removes the collapsible spaces at the end of a line, and at its beginning.
+    ///
+    /// ```spec
+    /// A sequence of collapsible spaces at the end of a line is removed.
+    /// ```
+    fn trim_line(&mut self, segment: &Fragment) {
+        let edges = [
+            self.find_text_edge(segment, |tree, fragment| tree.first_child(fragment)),
+            self.find_text_edge(segment, |tree, fragment| tree.last_child(fragment)),
+        ];
+
+        for (index, maybe_text) in edges.into_iter().enumerate() {
+            let Some(text) = maybe_text else {
+                continue;
+            };
+
+            let collapses = self
+                .style
+                .computed
+                .borrow(&text)
+                .map(|props| props.white_space.collapses_spaces())
+                .unwrap_or_default();
+
+            if !collapses {
+                continue;
+            }
+
+            let trimmed = self.text_sequences.borrow_mut(&text).and_then(|mut seq| {
+                let trimmed = if index == 0 { seq.as_str().trim_start_matches(' ') } else { seq.as_str().trim_end_matches(' ') };
+
+                if trimmed.len() == seq.as_str().len() {
+                    return None;
+                }
+
+                let trimmed = trimmed.to_owned();
+                seq.set_text(&trimmed);
+                Some(())
+            });
+
+            if trimmed.is_some() {
+                self.measure_text_sequence(&text);
+            }
+        }
+    }
+
+    /// Descends into the fragment to find its first, or last, text sequence.
+    fn find_text_edge(&self, fragment: &Fragment, next: impl Fn(&Self, &Fragment) -> Option<Fragment>) -> Option<Fragment> {
+        let mut current = *fragment;
+
+        while let Some(child) = next(self, &current) {
+            if self.is(child, FragmentKind::TextSequence) {
+                return Some(child);
+            }
+            current = child;
+        }
+
+        None
+    }
+
+    /// Compute, or recompute the box of an inline-level fragment.
+    ///
+    /// The content of inline boxes and line boxes is computed from their children.
+    pub fn compute_box(&mut self, from: &Fragment) -> Box<i32> {
+        match self.kind(from) {
+            FragmentKind::InlineBox | FragmentKind::LineBox => {
+                let children = self.iter_children(from).collect::<Vec<_>>();
+                let content = children
+                    .into_iter()
+                    .map(|child| {
+                        self.compute_box(&child);
+                        self.inline_extent(&child)
+                    })
+                    .reduce(Lay::<Inline>::lay)
+                    .unwrap_or_default()
+                    .content;
+
+                let mut r#box = self.boxes.borrow_mut(from).unwrap();
+                r#box.content = content;
+                r#box.clone()
+            },
+            // No dimensions for a line break
+            FragmentKind::Break => Box::default(),
+            _ => self.boxes.borrow(from).map(|r#box| r#box.clone()).unwrap_or_default()
+        }
+    }
+
+    /// Returns the box which the fragment occupies on the line.
+    ///
+    /// The vertical padding and borders of inline boxes do not take room on the line.
+    fn inline_extent(&self, fragment: &Fragment) -> Box<i32> {
+        let mut r#box = self.boxes.borrow(fragment).map(|r#box| r#box.clone()).unwrap_or_default();
+
+        if self.is(fragment, FragmentKind::InlineBox) {
+            r#box.padding.top = 0;
+            r#box.padding.bottom = 0;
+            r#box.border.top = 0;
+            r#box.border.bottom = 0;
+        }
+
+        r#box
+    }
+
+    /// Returns the strut of an inline box, or of a block container: its font metrics and line height.
+    ///
+    /// ```spec
+    /// normal: Tells user agents to set the used value to a "reasonable" value based on the font of the element.
+    /// ```
+    /// The normal line height is the sum of the ascent, descent and line gap of the primary font.
+    fn strut(&mut self, fragment: &Fragment) -> Strut {
+        let mut ctx = self.resolution_context();
+        let used = self.used_properties(fragment, &mut ctx);
+        let metrics = self
+            .fonts(fragment)
+            .first()
+            .map(SizedFont::metrics)
+            .unwrap_or_else(|| FontMetrics::estimate(used.font_size));
+        let line_height = used.line_height.unwrap_or(metrics.ascent + metrics.descent + metrics.line_gap);
+
+        Strut {
+            ascent: px_to_units(metrics.ascent),
+            descent: px_to_units(metrics.descent),
+            x_height: px_to_units(metrics.x_height),
+            font_size: px_to_units(used.font_size),
+            line_height: px_to_units(line_height),
+        }
+    }
+
+    /// Returns the strut of a text sequence, whose content area spans the glyphs of all its fonts.
+    fn text_strut(&mut self, fragment: &Fragment) -> Strut {
+        let mut strut = self.strut(fragment);
+
+        if let Some(runs) = self.glyph_runs.borrow(fragment).filter(|runs| !runs.is_empty()) {
+            strut.ascent = runs.iter().map(|run| run.ascent).max().unwrap_or_default();
+            strut.descent = runs.iter().map(|run| run.descent).max().unwrap_or_default();
+        }
+
+        strut
+    }
+
+    /// Returns the strut of an atomic inline, whose margin box is aligned on its baseline.
+    ///
+    /// ```spec
+    /// The baseline of an 'inline-block' is the baseline of its last line box in the normal flow,
+    /// unless it has no in-flow line boxes, in which case the baseline is the bottom margin edge.
+    /// ```
+    fn atomic_strut(&self, fragment: &Fragment) -> Strut {
+        let r#box = self.boxes.borrow(fragment).map(|r#box| r#box.clone()).unwrap_or_default();
+        let height = r#box.outer().height;
+        let ascent = self
+            .last_line_baseline(fragment)
+            .map(|baseline| r#box.margin.top + baseline)
+            .unwrap_or(height);
+
+        Strut {
+            ascent,
+            descent: height - ascent,
+            line_height: height,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the baseline of the last line box within the fragment, from the top of its border box.
+    fn last_line_baseline(&self, fragment: &Fragment) -> Option<i32> {
+        let offset = self
+            .boxes
+            .borrow(fragment)
+            .map(|r#box| r#box.border.top + r#box.padding.top)
+            .unwrap_or_default();
+
+        let children = self.iter_children(fragment).collect::<Vec<_>>();
+        children.into_iter().rev().find_map(|child| {
+            let y = self.positions.borrow(&child).map(|position| position.y).unwrap_or_default();
+
+            let baseline = match self.kind(child) {
+                FragmentKind::LineBox => self.line_boxes.borrow(&child).map(|line_box| line_box.baseline),
+                FragmentKind::BlockBox | FragmentKind::BlockFormattingContextRoot => self.last_line_baseline(&child),
+                _ => None,
+            };
+
+            baseline.map(|baseline| offset + y + baseline)
+        })
+    }
+
+    fn vertical_align(&self, fragment: &Fragment) -> VerticalAlign {
+        let mut ctx = self.resolution_context();
+        self.used_properties(fragment, &mut ctx).vertical_align
+    }
+
+    /// Aligns the inline-level content of a line box vertically, and positions it.
+    ///
+    /// ```spec
+    /// The line box height is the distance between the uppermost box top and the lowermost box bottom.
+    /// ```
+    /// The line starts with the strut of its block container.
+    ///
+    /// Returns the height of the line box, and the position of its baseline from its top.
+    fn align_line(&mut self, line: &Fragment, root: &Strut) -> (i32, i32) {
+        let mut bounds = (root.top(), root.bottom());
+        let mut aligned = Vec::new();
+        let mut line_relative = Vec::new();
+        self.align_children(line, root, 0, &mut bounds, &mut aligned, &mut line_relative);
+
+        let top = bounds.0;
+        let height = line_relative
+            .iter()
+            .fold(bounds.1 - bounds.0, |height, subtree| height.max(subtree.bounds.1 - subtree.bounds.0));
+
+        // The subtrees aligned with the line box are moved once its height is known, the innermost first,
+        // as they are not moved with the subtrees they are nested in.
+        let mut moved = vec![false; aligned.len()];
+        for subtree in line_relative {
+            let shift = match subtree.vertical_align {
+                VerticalAlign::Bottom => top + height - subtree.bounds.1,
+                _ => top - subtree.bounds.0,
+            };
+
+            for index in subtree.range {
+                if !moved[index] {
+                    aligned[index].1 += shift;
+                    moved[index] = true;
+                }
+            }
+        }
+
+        let baselines = aligned
+            .into_iter()
+            .map(|(fragment, baseline, strut)| (fragment, (baseline - top, strut)))
+            .collect::<HashMap<_, _>>();
+
+        self.place_inline_content(line, 0, &baselines);
+        (height, -top)
+    }
+
+    /// Aligns the children of an inline box, or of a line box, on the baseline of their parent.
+    ///
+    /// ```spec
+    /// baseline: Align the baseline of the box with the baseline of the parent box.
+    /// middle: Align the vertical midpoint of the box with the baseline of the parent box plus half the x-height of the parent.
+    /// sub: Lower the baseline of the box to the proper position for subscripts of the parent's box.
+    /// super: Raise the baseline of the box to the proper position for superscripts of the parent's box.
+    /// text-top: Align the top of the box with the top of the parent's content area.
+    /// text-bottom: Align the bottom of the box with the bottom of the parent's content area.
+    /// <percentage>: Raise (positive value) or lower (negative value) the box by this distance (a percentage of the 'line-height' value).
+    /// <length>: Raise (positive value) or lower (negative value) the box by this distance.
+    /// top: Align the top of the aligned subtree with the top of the line box.
+    /// bottom: Align the bottom of the aligned subtree with the bottom of the line box.
+    /// ```
+    ///
+    /// The baselines are relative to the baseline of the line, and the bounds are the uppermost top and lowermost bottom of the boxes,
+    /// including their half-leading.
+    fn align_children(
+        &mut self,
+        parent: &Fragment,
+        parent_strut: &Strut,
+        parent_baseline: i32,
+        bounds: &mut (i32, i32),
+        aligned: &mut Vec<(Fragment, i32, Strut)>,
+        line_relative: &mut Vec<LineRelativeSubtree>,
+    ) {
+        let children = self.iter_children(parent).collect::<Vec<_>>();
+
+        for child in children {
+            // Text sequences share the style of their parent, and are aligned on its baseline.
+            let (strut, vertical_align) = match self.kind(child) {
+                FragmentKind::TextSequence => (self.text_strut(&child), VerticalAlign::Baseline),
+                FragmentKind::InlineBox => (self.strut(&child), self.vertical_align(&child)),
+                FragmentKind::AtomicInline => (self.atomic_strut(&child), self.vertical_align(&child)),
+                _ => continue,
+            };
+
+            let shift = match vertical_align {
+                VerticalAlign::Baseline | VerticalAlign::Top | VerticalAlign::Bottom => 0,
+                VerticalAlign::Sub => parent_strut.font_size / 5,
+                VerticalAlign::Super => -parent_strut.font_size / 3,
+                VerticalAlign::TextTop => -parent_strut.ascent - strut.top(),
+                VerticalAlign::TextBottom => parent_strut.descent - strut.bottom(),
+                VerticalAlign::Middle => -parent_strut.x_height / 2 - (strut.descent - strut.ascent) / 2,
+                VerticalAlign::Length(length) => -px_to_units(length.value),
+                VerticalAlign::Percentage(percentage) => -(strut.line_height as f64 * *percentage as f64 / 100.0).round() as i32,
+            };
+
+            let is_inline_box = self.is(child, FragmentKind::InlineBox);
+
+            // The subtree is aligned on its own baseline, and moved with the line box later on.
+            if matches!(vertical_align, VerticalAlign::Top | VerticalAlign::Bottom) {
+                let start = aligned.len();
+                let mut subtree_bounds = (strut.top(), strut.bottom());
+                aligned.push((child, 0, strut));
+
+                if is_inline_box {
+                    self.align_children(&child, &strut, 0, &mut subtree_bounds, aligned, line_relative);
+                }
+
+                line_relative.push(LineRelativeSubtree { vertical_align, range: start..aligned.len(), bounds: subtree_bounds });
+                continue;
+            }
+
+            let baseline = parent_baseline + shift;
+            bounds.0 = bounds.0.min(baseline + strut.top());
+            bounds.1 = bounds.1.max(baseline + strut.bottom());
+            aligned.push((child, baseline, strut));
+
+            if is_inline_box {
+                self.align_children(&child, &strut, baseline, bounds, aligned, line_relative);
+            }
+        }
+    }
+
+    /// Positions the children of an inline box next to each other, from the left of its content box,
+    /// and on their aligned baselines.
+    ///
+    /// The baselines, and the top of the content box of the fragment, are positions from the top of the line box.
+    /// The content area of inline boxes spans the ascent and descent of their font.
+    fn place_inline_content(&mut self, fragment: &Fragment, content_top: i32, baselines: &HashMap<Fragment, (i32, Strut)>) {
+        let mut x = 0;
+        let children = self.iter_children(fragment).collect::<Vec<_>>();
+
+        for child in children {
+            let r#box = self.boxes.borrow(&child).map(|r#box| r#box.clone()).unwrap_or_default();
+            let (baseline, strut) = baselines.get(&child).copied().unwrap_or_default();
+            let top = baseline - strut.ascent;
+
+            let y = match self.kind(child) {
+                FragmentKind::InlineBox => top - r#box.padding.top - r#box.border.top - content_top,
+                FragmentKind::AtomicInline => top + r#box.margin.top - content_top,
+                FragmentKind::TextSequence => top - content_top,
+                _ => 0,
+            };
+
+            if let Some(mut position) = self.positions.borrow_mut(&child) {
+                *position = Point { x: x + r#box.margin.left, y };
+            }
+
+            if self.is(child, FragmentKind::InlineBox) {
+                if let Some(mut r#box) = self.boxes.borrow_mut(&child) {
+                    r#box.content.height = strut.ascent + strut.descent;
+                }
+
+                self.place_inline_content(&child, top, baselines);
+            }
+
+            x += r#box.outer().width;
+        }
+    }
+
+    /// Checks if the fragment holds content which requires a line box.
+    fn has_inline_content(&self, fragment: &Fragment) -> bool {
+        match self.kind(fragment) {
+            FragmentKind::TextSequence => {
+                let preserved = self
+                    .style
+                    .computed
+                    .borrow(fragment)
+                    .map(|props| !props.white_space.collapses_spaces())
+                    .unwrap_or_default();
+
+                preserved || self.text_sequences.borrow(fragment).map(|seq| !seq.as_str().is_empty()).unwrap_or_default()
+            },
+            FragmentKind::AtomicInline => true,
+            FragmentKind::InlineBox | FragmentKind::LineBox => {
+                let has_edges = self
+                    .boxes
+                    .borrow(fragment)
+                    .map(|r#box| r#box.outer().width != r#box.content.width)
+                    .unwrap_or_default();
+
+                has_edges || self.iter_children(fragment).any(|child| self.has_inline_content(&child))
+            },
+            _ => false
+        }
+    }
+
+    /// Checks if the fragment holds no text, nor any other content.
+    fn is_blank(&self, fragment: &Fragment) -> bool {
+        match self.kind(fragment) {
+            FragmentKind::TextSequence => self.text_sequences.borrow(fragment).map(|seq| seq.as_str().is_empty()).unwrap_or(true),
+            FragmentKind::InlineBox | FragmentKind::LineBox => self.iter_children(fragment).all(|child| self.is_blank(&child)),
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,134 @@
+//! Implements the line breaking rules from [UAX #14](https://www.unicode.org/reports/tr14/),
+//! tailored by [CSS TEXT 3](https://www.w3.org/TR/css-text-3/#line-breaking)
+use std::collections::BTreeMap;
+
+use unicode_linebreak::linebreaks;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::style::{
+    properties::computed,
+    text::{LineBreak, OverflowWrap, WordBreak},
+};
+
+/// The character standing for an atomic inline in the text of a paragraph.
+pub const OBJECT_REPLACEMENT_CHARACTER: char = '\u{FFFC}';
+
+/// An opportunity to end a line after a character.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakOpportunity {
+    /// The line must end.
+    Mandatory,
+    /// The line may end: a soft wrap opportunity.
+    Soft,
+    /// The line may end, only if no soft wrap opportunity can keep the content from overflowing.
+    Emergency,
+}
+
+/// Returns the break opportunities of the text of a paragraph, as the byte offsets after which a line may, or must, end.
+///
+/// ```spec
+/// LB3: Always break at the end of text.
+/// ```
+/// This one is omitted, unless the text ends with a mandatory break character.
+pub fn break_opportunities(text: &str) -> Vec<(usize, BreakOpportunity)> {
+    let ends_with_break = text
+        .chars()
+        .last()
+        .is_some_and(|ch| matches!(ch, '\n' | '\r' | '\u{0B}' | '\u{0C}' | '\u{85}' | '\u{2028}' | '\u{2029}'));
+
+    linebreaks(text)
+        .filter(|(offset, _)| *offset < text.len() || ends_with_break)
+        .map(|(offset, opportunity)| match opportunity {
+            unicode_linebreak::BreakOpportunity::Mandatory => (offset, BreakOpportunity::Mandatory),
+            unicode_linebreak::BreakOpportunity::Allowed => (offset, BreakOpportunity::Soft),
+        })
+        .collect()
+}
+
+/// Tailors the break opportunities of a text with its line breaking properties.
+///
+/// The opportunities are byte offsets in the text, and may include its end.
+///
+/// ```spec
+/// white-space: nowrap, pre: Lines are only broken at preserved segment breaks.
+/// line-break: anywhere: There is a soft wrap opportunity around every typographic character unit.
+/// word-break: break-all: Breaking is allowed within "words".
+/// word-break: keep-all: Breaking is forbidden within "words".
+/// overflow-wrap: anywhere, break-word: An otherwise unbreakable sequence of characters may be broken at an arbitrary point if there are no otherwise-acceptable break points in the line.
+/// ```
+pub fn tailor(text: &str, opportunities: &[(usize, BreakOpportunity)], props: &computed::Properties) -> Vec<(usize, BreakOpportunity)> {
+    let wraps = props.white_space.wraps();
+    let mut tailored = opportunities
+        .iter()
+        .copied()
+        .filter(|(_, opportunity)| wraps || *opportunity == BreakOpportunity::Mandatory)
+        .collect::<BTreeMap<_, _>>();
+
+    if !wraps {
+        return tailored.into_iter().collect();
+    }
+
+    let ends_word = |offset: usize| text[..offset].chars().last().is_some_and(char::is_alphanumeric);
+    let starts_word = |offset: usize| text[offset..].chars().next().is_some_and(char::is_alphanumeric);
+
+    if props.word_break == WordBreak::KeepAll {
+        tailored.retain(|offset, opportunity| {
+            *opportunity == BreakOpportunity::Mandatory || *offset == text.len() || !(ends_word(*offset) && starts_word(*offset))
+        });
+    }
+
+    let boundaries = text
+        .grapheme_indices(true)
+        .map(|(offset, _)| offset)
+        .filter(|offset| *offset > 0)
+        .collect::<Vec<_>>();
+
+    let anywhere = props.line_break == LineBreak::Anywhere;
+    let break_all = props.word_break == WordBreak::BreakAll;
+
+    for offset in boundaries.iter().copied() {
+        if anywhere || (break_all && ends_word(offset) && starts_word(offset)) {
+            tailored.entry(offset).or_insert(BreakOpportunity::Soft);
+        }
+    }
+
+    if props.overflow_wrap != OverflowWrap::Normal || props.word_break == WordBreak::BreakWord {
+        for offset in boundaries {
+            tailored.entry(offset).or_insert(BreakOpportunity::Emergency);
+        }
+    }
+
+    tailored.into_iter().collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::style::{properties::computed, text::{WhiteSpace, WordBreak}};
+
+    use super::{break_opportunities, tailor, BreakOpportunity};
+
+    #[test]
+    fn test_break_opportunities() {
+        let text = "Hello world,\nagain-and again";
+        let opportunities = break_opportunities(text);
+
+        assert_eq!(opportunities, [
+            (6, BreakOpportunity::Soft),
+            (13, BreakOpportunity::Mandatory),
+            (19, BreakOpportunity::Soft),
+            (23, BreakOpportunity::Soft),
+        ]);
+
+        let nowrap = computed::Properties { white_space: WhiteSpace::PreLine, ..Default::default() };
+        assert_eq!(tailor(text, &opportunities, &nowrap).len(), 4);
+
+        let nowrap = computed::Properties { white_space: WhiteSpace::Nowrap, ..Default::default() };
+        assert_eq!(tailor(text, &opportunities, &nowrap), [(13, BreakOpportunity::Mandatory)]);
+
+        let break_all = computed::Properties { word_break: WordBreak::BreakAll, ..Default::default() };
+        assert_eq!(tailor("abc", &[], &break_all), [(1, BreakOpportunity::Soft), (2, BreakOpportunity::Soft)]);
+
+        let keep_all = computed::Properties { word_break: WordBreak::KeepAll, ..Default::default() };
+        assert_eq!(tailor("日本語", &break_opportunities("日本語"), &keep_all), []);
+    }
+}
@@ -1,29 +1,247 @@
+pub mod block;
 pub mod formatting_context;
 pub mod box_tree;
+pub mod fragment_tree;
+pub mod fragmentation;
+pub mod inline;
+pub mod line_breaking;
+pub mod dump;
+pub mod page;
+pub mod shaping;
 pub mod text_sequence;
 
 use std::hash::Hash;
 
-use box_tree::{BoxFlags, BoxNode};
-use formatting_context::FormattingContext;
+use box_tree::{BoxFlags, BoxNode, BoxNodeKind, ComputedProperties};
+use formatting_context::{FormattingContext, FormattingContextKind};
+use fragment_tree::{Fragment, FragmentKind};
 
-use crate::{dom::TDocumentObjectModelExplorer, ecs::systems::tree::{TreeExplorer, TreeMutator}, style::display::{DisplayInside, DisplayOutside}, RenderingContext};
+use crate::{dom::{NodeKind, TDocumentObjectModelExplorer, TText}, ecs::systems::tree::{TreeExplorer, TreeMutator}, style::{self, display::{Display, DisplayBox, DisplayInside, DisplayOutside}}, RenderingContext};
 
+/// The number of layout units in a CSS pixel.
+///
+/// Fragments are sized and positioned in integral layout units.
+pub const LAYOUT_UNITS_PER_PX: i32 = 64;
+
+/// Converts a length in pixels into layout units.
+pub fn px_to_units(px: f64) -> i32 {
+    (px * LAYOUT_UNITS_PER_PX as f64).round() as i32
+}
+
+/// Converts a length in layout units into pixels.
+pub fn units_to_px(units: i32) -> f64 {
+    units as f64 / LAYOUT_UNITS_PER_PX as f64
+}
+
+/// The block formatting context.
+pub struct Block;
+
+/// The inline formatting context.
+pub struct Inline;
+
+/// Lays out an element within a formatting context.
+pub trait Layout<FormattingContext> {
+    type Element;
+
+    fn layout(&mut self, element: &Self::Element);
+}
+
+/// Lays two boxes after one another within a formatting context,
+/// and returns the box enclosing them.
+pub trait Lay<FormattingContext> {
+    fn lay(self, other: Self) -> Self;
+}
+
+/// Lay out the fragment tree from its root, and fragment it into the page areas of the page boxes.
+///
+/// The initial containing block is the size of the target media, until it becomes the page area of the first page.
+///
+/// ```spec
+/// The initial containing block is the page area of the first page.
+/// ```
+pub fn lay_out_pages<Dom>(ctx: &mut RenderingContext<'_, Dom>, root: &Fragment)
+where Dom: TDocumentObjectModelExplorer + Sync, Dom::NodeId: Hash + Copy + Eq
+{
+    let media = ctx.fragments.initial_containing_block.clone();
+    let page_rules = ctx.dom.styles.page_rules.clone();
+    let root_style = ctx.fragments.style.computed.borrow(root).map(|props| props.clone()).unwrap_or_default();
+
+    let page_context = |index: usize, name: Option<&str>, blank: bool| {
+        page::PageContext::compute(&page_rules, &style::page::Page { index, name, blank }, &root_style, &media)
+    };
+
+    let first_name = fragmentation::page::page_name(&ctx.fragments, root);
+    let first = page_context(0, first_name.as_deref(), false);
+    ctx.fragments.initial_containing_block = first.r#box.content.clone();
+    Layout::<Block>::layout(&mut ctx.fragments, root);
+
+    let mut contexts = vec![first];
+    let pages = fragmentation::page::paginate(&mut ctx.fragments, root, |fragment_tree, index, page| {
+        if index >= contexts.len() {
+            let name = fragmentation::page::page_name(fragment_tree, page);
+            // A blank page is named after the page following it.
+            if index > contexts.len() {
+                contexts.push(page_context(contexts.len(), name.as_deref(), true));
+            }
+            contexts.push(page_context(index, name.as_deref(), false));
+        }
+
+        contexts[index].r#box.content.height
+    });
+
+    let total = pages.len();
+    ctx.fragments.pages = pages
+        .into_iter()
+        .enumerate()
+        .map(|(index, content)| ctx.fragments.generate_page(&page_rules, &contexts[index], content, total))
+        .collect();
+}
+
+/// Generate the fragment tree from the box tree.
+///
+/// Each box generates a single fragment, until it is fragmented.
+pub fn generate_fragment_tree<Dom>(ctx: &mut RenderingContext<'_, Dom>) -> Option<Fragment>
+where Dom: TDocumentObjectModelExplorer + Sync, Dom::NodeId: Hash + Copy + Eq
+{
+    let root = ctx.boxes.root()?;
+    let fragment = generate_fragment_subtree(ctx, &root, None);
+    ctx.fragments.set_root(fragment);
+    Some(fragment)
+}
+
+fn generate_fragment_subtree<Dom>(ctx: &mut RenderingContext<'_, Dom>, box_node: &BoxNode, maybe_parent: Option<Fragment>) -> Fragment
+where Dom: TDocumentObjectModelExplorer + Sync, Dom::NodeId: Hash + Copy + Eq
+{
+    let kind = match ctx.boxes.kind(box_node) {
+        BoxNodeKind::TextSequence => FragmentKind::TextSequence,
+        BoxNodeKind::Box(flags) if flags.is_atomic_inline_level() => FragmentKind::AtomicInline,
+        BoxNodeKind::Box(flags) if flags.is_inline_level() => FragmentKind::InlineBox,
+        BoxNodeKind::Box(_) if ctx.boxes.formatting_contexts.kind(box_node) == Some(FormattingContextKind::BlockFormattingContext) => {
+            FragmentKind::BlockFormattingContextRoot
+        },
+        BoxNodeKind::Box(_) => FragmentKind::BlockBox,
+    };
+
+    let fragment = ctx.fragments.insert_fragment(kind, *box_node, maybe_parent);
+
+    if let Some(style_id) = ctx.boxes.style.computed.component_id(box_node) {
+        ctx.fragments.style.computed.bind_id(&fragment, style_id);
+    }
+
+    if let Some(text_sequence) = ctx.boxes.text_sequences.borrow(box_node).map(|seq| seq.clone()) {
+        ctx.fragments.text_sequences.bind(&fragment, text_sequence);
+    }
+
+    let children = ctx.boxes.iter_children(box_node).collect::<Vec<_>>();
+    for child in children {
+        generate_fragment_subtree(ctx, &child, Some(fragment));
+    }
+
+    fragment
+}
+
+/// Generate the box tree of the whole document.
+pub fn generate_box_tree<Dom>(ctx: &mut RenderingContext<'_, Dom>) -> Option<BoxNode>
+where Dom: TDocumentObjectModelExplorer + Sync, Dom::NodeId: Hash + Copy + Eq
+{
+    let root = ctx.dom.dom.root()?;
+    generate_box_subtree_with_parent(ctx, &root, None)
+}
+
+/// Generate the box subtree of a DOM node, and attach it to the parent box, if any.
+/// 
+/// Returns the principal box generated by the node, if any.
+/// 
 /// ```spec
 /// Floats, absolutely positioned elements, block containers (such as inline-blocks, table-cells, and table-captions) that are not block boxes, and block boxes with 'overflow' other than 'visible' (except when that value has been propagated to the viewport) establish new block formatting contexts for their contents.
 /// ```
-pub fn generate_box_subtree_with_parent<'dom, Dom>(mut ctx: RenderingContext<'dom, Dom>, dom_node: &Dom::NodeId, maybe_parent: Option<BoxNode>) 
+pub fn generate_box_subtree_with_parent<Dom>(ctx: &mut RenderingContext<'_, Dom>, dom_node: &Dom::NodeId, maybe_parent: Option<BoxNode>) -> Option<BoxNode>
 where Dom: TDocumentObjectModelExplorer + Sync, Dom::NodeId: Hash + Copy + Eq
 {
+    if ctx.dom.dom.kind(dom_node) == NodeKind::Text {
+        // A text node generates a text sequence within its parent box.
+        let parent = maybe_parent?;
+        let text = ctx.dom.dom.borrow_text(dom_node).map(|text| text.text().to_owned())?;
+        let text_sequence = ctx.boxes.insert_text_sequence(&text, parent, Some(parent));
+        ctx.boxes.dom.insert(text_sequence, *dom_node);
+        return Some(text_sequence);
+    }
 
-    
+    let display = ctx
+        .dom
+        .styles
+        .computed
+        .borrow(dom_node)
+        .map(|props| props.display)
+        .unwrap_or_default();
+
+    match display.r#box() {
+        // ```spec
+        // The element and its descendants generate no boxes or text runs.
+        // ```
+        Some(DisplayBox::None) => return None,
+        // ```spec
+        // The element itself does not generate any boxes, 
+        // but its children and pseudo-elements still generate boxes and text runs as normal.
+        // ```
+        Some(DisplayBox::Contents) => {
+            if let Some(parent) = maybe_parent {
+                generate_box_children_subtrees(ctx, dom_node, parent);
+            }
+            return None;
+        },
+        None => {}
+    }
+
+    let style_id = ctx.dom.styles.computed_style_id(dom_node);
+    let box_node = ctx.boxes.insert_box(box_flags(&display), style_id, maybe_parent);
+    ctx.boxes.dom.insert(box_node, *dom_node);
+
+    if maybe_parent.is_none() {
+        // The root element's box establishes the initial block formatting context.
+        ctx.boxes.set_root(box_node);
+        establishes_new_bfc(ctx, &box_node);
+    } else if display_establishes_new_bfc(&display) {
+        establishes_new_bfc(ctx, &box_node);
+    }
+
+    generate_box_children_subtrees(ctx, dom_node, box_node);
+    check_if_anonymous_box_is_required(ctx, &box_node);
+    check_if_a_new_inline_formatting_context_must_be_established(ctx, &box_node);
+
+    Some(box_node)
 }
 
 /// Generate the box node from the DOM node's children
 fn generate_box_children_subtrees<Dom>(ctx: &mut RenderingContext<'_, Dom>, dom_node: &Dom::NodeId, parent: BoxNode) 
 where Dom: TDocumentObjectModelExplorer + Sync, Dom::NodeId: Hash + Copy + Eq
 {   
+    let dom = ctx.dom.dom;
 
+    for child in dom.iter_children(dom_node) {
+        generate_box_subtree_with_parent(ctx, &child, Some(parent));
+    }
+}
+
+/// Returns the flags of the principal box generated by an element.
+/// 
+/// Layout-internal display types, and the inner display types which are not supported yet
+/// (table, flex, grid, ruby), generate block containers.
+fn box_flags(display: &Display) -> BoxFlags {
+    let outer = display.outer().unwrap_or(DisplayOutside::Block);
+    let inner = display.inner().unwrap_or(DisplayInside::Flow);
+
+    match (outer, inner) {
+        (DisplayOutside::Block, _) => BoxFlags::block_container(),
+        // A run-in box which is not merged into a following block behaves as an inline box.
+        (DisplayOutside::Inline | DisplayOutside::RunIn, DisplayInside::Flow) => BoxFlags::inline_level(),
+        (DisplayOutside::Inline | DisplayOutside::RunIn, _) => BoxFlags::atomic_inline_level(),
+    }
+}
+
+/// Checks if the display type of the element always establishes a new BFC for its contents.
+fn display_establishes_new_bfc(display: &Display) -> bool {
+    !matches!(display.inner(), Some(DisplayInside::Flow) | None)
 }
 
 /// Establishes a new block formatting context
@@ -53,7 +271,7 @@ where Dom: TDocumentObjectModelExplorer + Sync, Dom::NodeId: Hash + Copy + Eq
     if ctx.boxes.kind(box_node).is_block_container() {
         let root_inline_box = ctx.boxes.insert_box(
             BoxFlags::root_inline_box(), 
-            box_node, 
+            ComputedProperties::Anonymous(*box_node), 
             None
         );
 
@@ -72,33 +290,484 @@ where Dom: TDocumentObjectModelExplorer + Sync, Dom::NodeId: Hash + Copy + Eq
 pub fn check_if_a_new_inline_formatting_context_must_be_established<Dom>(ctx: &mut RenderingContext<'_, Dom>, box_node: &BoxNode) 
 where Dom: TDocumentObjectModelExplorer + Sync, Dom::NodeId: Hash + Copy + Eq
 {
-    if ctx.boxes.kind(box_node).is_block_container() && ctx.boxes.has_only_inline_level_boxes(box_node) {
+    if ctx.boxes.kind(box_node).is_block_container() 
+        && !ctx.boxes.is_leaf(box_node)
+        && ctx.boxes.has_only_inline_level_boxes(box_node) 
+    {
         establish_new_inline_formatting_context(ctx, box_node);
     }
 }
 
+/// If the box is a block container which has both block-level and inline-level boxes,
+/// each run of inline-level boxes is wrapped in an anonymous block container.
+/// 
+/// ```spec
+/// if a block container box has a block-level box inside it, then we force it to have only block-level boxes inside it.
+/// White space content that would subsequently be collapsed away according to the 'white-space' property does not generate any anonymous inline boxes.
+/// ```
 pub fn check_if_anonymous_box_is_required<Dom>(ctx: &mut RenderingContext<'_, Dom>, box_node: &BoxNode) 
 where Dom: TDocumentObjectModelExplorer + Sync, Dom::NodeId: Hash + Copy + Eq
 {
-    let maybe_parent = ctx.boxes.parent(box_node);
+    let requires_anonymous_block_box = ctx.boxes.kind(box_node).is_block_container()
+        && ctx.boxes.has_block_level_boxes(box_node)
+        && ctx.boxes.has_inline_level_boxes(box_node);
 
-    let requires_anonymous_block_box = maybe_parent
-        .map(|parent| 
-            ctx.boxes.kind(parent).is_block_container() 
-            && ctx.boxes.has_inline_level_boxes(parent)
-        )
-        .unwrap_or_default();
+    if !requires_anonymous_block_box {
+        return;
+    }
+
+    let children = ctx.boxes.iter_children(box_node).collect::<Vec<_>>();
+    
+    let runs = children
+        .split(|child| !ctx.boxes.kind(child).is_inline_level())
+        .filter(|run| !run.is_empty())
+        .map(|run| run.to_vec())
+        .collect::<Vec<_>>();
 
+    for run in runs {
+        if run.iter().all(|child| ctx.boxes.is_collapsible_white_space(child)) {
+            run.into_iter().for_each(|child| ctx.boxes.remove_child(child));
+            continue;
+        }
 
-    if requires_anonymous_block_box {
         let anonymous = ctx
             .boxes
             .insert_box(
-                BoxFlags::block_level(), 
-                box_node, 
+                BoxFlags::anonymous_block_container(), 
+                ComputedProperties::Anonymous(*box_node), 
                 None
             );
             
-        ctx.boxes.push_parent(box_node, anonymous);
+        ctx.boxes.push_parent(&run[0], anonymous);
+
+        for child in run.into_iter().skip(1) {
+            ctx.boxes.remove_child(child);
+            ctx.boxes.attach_child(&anonymous, child);
+        }
+
+        check_if_a_new_inline_formatting_context_must_be_established(ctx, &anonymous);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        dom::{DocumentObjectModel, Element},
+        ecs::systems::tree::TreeExplorer,
+        style::{display::Display, properties::computed, values::r#box::BoxContent},
+        testing::{lay_out, lay_out_in, text_content},
+        RenderingContext, RenderingContextArgs,
+    };
+
+    use super::{box_tree::BoxNodeKind, fragment_tree::{Fragment, FragmentKind}, generate_box_tree, px_to_units};
+
+    fn with_display(display: Display) -> computed::Properties {
+        computed::Properties {
+            display,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_generate_box_tree() {
+        let mut dom = DocumentObjectModel::default();
+        let html = dom.insert_element(Element::new("html"), None);
+        let p = dom.insert_element(Element::new("p"), Some(html));
+        dom.insert_text("Hello ", Some(p));
+        let span = dom.insert_element(Element::new("span"), Some(p));
+        dom.insert_text("world", Some(span));
+        dom.insert_text("\n  ", Some(html));
+        let div = dom.insert_element(Element::new("div"), Some(html));
+        let hidden = dom.insert_element(Element::new("div"), Some(div));
+        dom.insert_text("tail", Some(html));
+
+        let mut ctx = RenderingContext::new(RenderingContextArgs {
+            dom: &dom,
+            bucket_size: None,
+            cache_size: None,
+        });
+
+        for node in [html, p, div] {
+            ctx.dom.styles.computed.bind(&node, with_display(Display::block()));
+        }
+        ctx.dom.styles.computed.bind(&span, with_display(Display::inline()));
+        ctx.dom.styles.computed.bind(&hidden, with_display(Display::none()));
+
+        let root = generate_box_tree(&mut ctx).unwrap();
+        let children = ctx.boxes.iter_children(root).collect::<Vec<_>>();
+
+        // p, div, and the anonymous block wrapping "tail"
+        assert_eq!(children.len(), 3);
+        assert_eq!(ctx.boxes.dom.borrow(children[0]).map(|n| *n), Some(p));
+        assert_eq!(ctx.boxes.dom.borrow(children[1]).map(|n| *n), Some(div));
+        assert!(ctx.boxes.is_leaf(children[1]));
+
+        let BoxNodeKind::Box(flags) = ctx.boxes.kind(children[2]) else {
+            panic!("expected an anonymous block box");
+        };
+        assert!(flags.is_anonymous() && flags.is_block_container());
+
+        // p establishes an inline formatting context with a root inline box.
+        assert!(ctx.boxes.formatting_contexts.establishes.borrow(children[0]).is_some());
+        let root_inline = ctx.boxes.first_child(children[0]).unwrap();
+        let BoxNodeKind::Box(flags) = ctx.boxes.kind(root_inline) else {
+            panic!("expected a root inline box");
+        };
+        assert!(flags.is_root_inline_box());
+        assert_eq!(ctx.boxes.iter_children(root_inline).count(), 2);
+    }
+
+    #[test]
+    fn test_block_layout() {
+        let mut dom = DocumentObjectModel::default();
+        let html = dom.insert_element(Element::new("html"), None);
+        let body = dom.insert_element(Element::new("body"), Some(html));
+        let a = dom.insert_element(Element::new("div").with_attribute("id", "a"), Some(body));
+        let b = dom.insert_element(Element::new("div").with_attribute("id", "b"), Some(body));
+        dom.insert_element(Element::new("div").with_attribute("id", "empty"), Some(body));
+        let c = dom.insert_element(Element::new("div").with_attribute("id", "c"), Some(body));
+
+        let (ctx, root) = lay_out(&dom, r#"
+            html, body, div { display: block }
+            body { margin: 8px }
+            #a { margin-top: 16px; margin-bottom: 20px; height: 10px }
+            #b { margin-top: 30px; height: 10px }
+            #empty { margin-top: 40px; margin-bottom: 5px }
+            #c { width: 100px; height: 10px; margin: 0 auto }
+        "#);
+        let source_of = |fragment| ctx.boxes.dom.borrow(*ctx.fragments.sources.borrow(&fragment).unwrap()).map(|n| *n);
+        let fragment_of = |node| ctx.fragments
+            .iter_children(ctx.fragments.first_child(root).unwrap())
+            .find(|fragment| source_of(*fragment) == Some(node))
+            .unwrap();
+        let position = |fragment| *ctx.fragments.positions.borrow(&fragment).unwrap();
+
+        // The top margin of body collapses with the one of its first child.
+        let body_fragment = ctx.fragments.first_child(root).unwrap();
+        assert_eq!(source_of(body_fragment), Some(body));
+        assert_eq!(position(body_fragment).y, px_to_units(16.0));
+        assert_eq!(position(body_fragment).x, px_to_units(8.0));
+        assert_eq!(position(fragment_of(a)).y, 0);
+
+        // Sibling margins collapse, and so do the margins through the empty block.
+        assert_eq!(position(fragment_of(b)).y, px_to_units(40.0));
+        assert_eq!(position(fragment_of(c)).y, px_to_units(90.0));
+
+        // Auto margins center the block horizontally.
+        let c_box = ctx.fragments.boxes.borrow(&fragment_of(c)).unwrap().clone();
+        assert_eq!(c_box.content.width, px_to_units(100.0));
+        assert_eq!(c_box.margin.left, (px_to_units(784.0) - px_to_units(100.0)) / 2);
+
+        // The bottom margin of body collapses with the one of its last child,
+        // but not with the root element's box.
+        let body_box = ctx.fragments.boxes.borrow(&body_fragment).unwrap().clone();
+        assert_eq!(body_box.content.width, px_to_units(784.0));
+        assert_eq!(body_box.content.height, px_to_units(100.0));
+        let html_box = ctx.fragments.boxes.borrow(&root).unwrap().clone();
+        assert_eq!(html_box.content.height, px_to_units(124.0));
+        assert_eq!(source_of(root), Some(html));
+    }
+
+    #[test]
+    fn test_inline_layout() {
+        let mut dom = DocumentObjectModel::default();
+        let html = dom.insert_element(Element::new("html"), None);
+        let p = dom.insert_element(Element::new("p"), Some(html));
+        dom.insert_text("Hello ", Some(p));
+        let span = dom.insert_element(Element::new("span"), Some(p));
+        dom.insert_text("world", Some(span));
+        dom.insert_text("  again", Some(p));
+        let pre = dom.insert_element(Element::new("pre"), Some(html));
+        dom.insert_text("a\n\nb\n", Some(pre));
+
+        let (ctx, root) = lay_out(&dom, r#"
+            html, p, pre { display: block }
+            html { font-size: 20px; width: 100px }
+            pre { white-space: pre }
+        "#);
+        let blocks = ctx.fragments.iter_children(root).collect::<Vec<_>>();
+        let texts = |line| text_content(&ctx.fragments, line);
+
+        // At 20px, "Hello world" overflows the 100px line.
+        let lines = ctx.fragments.iter_children(blocks[0]).collect::<Vec<_>>();
+        let line_height = ctx.fragments.boxes.borrow(&lines[0]).unwrap().content.height;
+        assert!(lines.iter().all(|line| ctx.fragments.is(line, FragmentKind::LineBox)));
+        assert_eq!(lines.iter().map(|line| texts(*line)).collect::<Vec<_>>(), ["Hello", "world", "again"]);
+        assert_eq!(ctx.fragments.positions.borrow(&lines[2]).unwrap().y, 2 * line_height);
+        assert_eq!(ctx.fragments.boxes.borrow(&blocks[0]).unwrap().content.height, 3 * line_height);
+
+        // Preserved line breaks are forced breaks, and the preserved empty line has a height.
+        let lines = ctx.fragments.iter_children(blocks[1]).collect::<Vec<_>>();
+        assert_eq!(lines.iter().map(|line| texts(*line)).collect::<Vec<_>>(), ["a", "", "b"]);
+        assert_eq!(ctx.fragments.boxes.borrow(&blocks[1]).unwrap().content.height, 3 * line_height);
+    }
+
+    #[test]
+    fn test_line_wrapping() {
+        let mut dom = DocumentObjectModel::default();
+        let html = dom.insert_element(Element::new("html"), None);
+        let p = dom.insert_element(Element::new("p"), Some(html));
+        dom.insert_text("aa bb cc dd", Some(p));
+        let word = dom.insert_element(Element::new("p"), Some(html));
+        dom.insert_text("abcdefghij", Some(word));
+        let wrapped = dom.insert_element(Element::new("div"), Some(html));
+        dom.insert_text("abcdefghij", Some(wrapped));
+
+        let (ctx, root) = lay_out(&dom, r#"
+            html, p, div { display: block }
+            html { font-size: 20px; width: 60px }
+            div { overflow-wrap: anywhere }
+        "#);
+        let blocks = ctx.fragments.iter_children(root).collect::<Vec<_>>();
+        let texts = |line| text_content(&ctx.fragments, line);
+        let lines = |block| ctx.fragments.iter_children(block).map(texts).collect::<Vec<_>>();
+        let fits = |block| {
+            ctx.fragments.iter_children(block).all(|line| {
+                let width = ctx
+                    .fragments
+                    .iter_children(line)
+                    .map(|child| ctx.fragments.boxes.borrow(&child).unwrap().outer().width)
+                    .sum::<i32>();
+                width <= px_to_units(60.0)
+            })
+        };
+
+        // Lines are wrapped at the soft wrap opportunities between words, and the spaces at their end are removed.
+        assert!(lines(blocks[0]).len() > 1);
+        assert_eq!(lines(blocks[0]).join(" "), "aa bb cc dd");
+        assert!(fits(blocks[0]));
+
+        // A word without soft wrap opportunity overflows, unless it can be broken at emergency opportunities.
+        assert_eq!(lines(blocks[1]), ["abcdefghij"]);
+        assert!(lines(blocks[2]).len() > 1);
+        assert_eq!(lines(blocks[2]).concat(), "abcdefghij");
+        assert!(fits(blocks[2]));
+    }
+
+    #[test]
+    fn test_inline_block_width() {
+        let mut dom = DocumentObjectModel::default();
+        let html = dom.insert_element(Element::new("html"), None);
+        let p = dom.insert_element(Element::new("p"), Some(html));
+        dom.insert_text("a ", Some(p));
+        let mut span = Element::new("span");
+        span.set_attribute("style", "display:inline-block;height:2000px");
+        let span = dom.insert_element(span, Some(p));
+        dom.insert_text("b", Some(span));
+        dom.insert_text(" c", Some(p));
+
+        let (ctx, root) = lay_out_in(&dom, r#"
+            html, p { display: block }
+            html { font-size: 20px; width: 200px }
+        "#, BoxContent { width: 800.0, height: 10000.0 });
+
+        // The inline-block shrinks to the width of its content, instead of filling the line.
+        let block = ctx.fragments.first_child(root).unwrap();
+        let lines = ctx.fragments.iter_children(block).collect::<Vec<_>>();
+        assert_eq!(lines.len(), 1);
+
+        let root_inline_box = ctx.fragments.first_child(lines[0]).unwrap();
+        let atomic = ctx
+            .fragments
+            .iter_children(root_inline_box)
+            .find(|child| ctx.fragments.is(child, FragmentKind::AtomicInline))
+            .unwrap();
+        let r#box = ctx.fragments.boxes.borrow(&atomic).unwrap().clone();
+        assert!(r#box.content.width > 0 && r#box.content.width < px_to_units(50.0));
+        assert_eq!(r#box.content.height, px_to_units(2000.0));
+    }
+
+    #[test]
+    fn test_long_paragraph() {
+        let text = vec!["word"; 300].join(" ");
+        let mut dom = DocumentObjectModel::default();
+        let html = dom.insert_element(Element::new("html"), None);
+        let p = dom.insert_element(Element::new("p"), Some(html));
+        dom.insert_text(&text, Some(p));
+
+        let (ctx, root) = lay_out_in(&dom, r#"
+            html, p { display: block }
+            html { font-size: 20px; width: 200px }
+        "#, BoxContent { width: 800.0, height: 10000.0 });
+
+        // Each line moves the rest of the paragraph once, which keeps a long paragraph fast to lay out.
+        let block = ctx.fragments.first_child(root).unwrap();
+        let lines = ctx.fragments.iter_children(block).map(|line| text_content(&ctx.fragments, line)).collect::<Vec<_>>();
+        assert!(lines.len() > 30);
+        assert_eq!(lines.join(" "), text);
+    }
+
+    #[test]
+    fn test_vertical_align() {
+        let mut dom = DocumentObjectModel::default();
+        let html = dom.insert_element(Element::new("html"), None);
+        let mut paragraph = |class: &str| {
+            let p = dom.insert_element(Element::new("p"), Some(html));
+            dom.insert_text("x", Some(p));
+            let mut span = Element::new("span");
+            span.set_attribute("class", class);
+            let span = dom.insert_element(span, Some(p));
+            dom.insert_text("y", Some(span));
+        };
+        paragraph("baseline");
+        paragraph("super");
+        paragraph("top");
+
+        let (ctx, root) = lay_out(&dom, r#"
+            html, p { display: block }
+            html { font-size: 20px; line-height: 40px }
+            .super { vertical-align: super }
+            .top { vertical-align: top; line-height: 80px }
+        "#);
+        let lines = ctx
+            .fragments
+            .iter_children(root)
+            .map(|block| ctx.fragments.iter_children(block).next().unwrap())
+            .collect::<Vec<_>>();
+        let height = |line: &Fragment| ctx.fragments.boxes.borrow(line).unwrap().content.height;
+        let baseline = |line: &Fragment| ctx.fragments.line_boxes.borrow(line).unwrap().baseline;
+
+        // The line is as high as the line height, whatever the font.
+        assert_eq!(height(&lines[0]), px_to_units(40.0));
+
+        // The superscript raises its box by a third of the font size of its parent, which makes the line higher.
+        assert_eq!(height(&lines[1]), px_to_units(40.0) + px_to_units(20.0) / 3);
+        assert_eq!(baseline(&lines[1]), baseline(&lines[0]) + px_to_units(20.0) / 3);
+
+        // The box aligned with the top of the line box is higher than the rest of the line.
+        assert_eq!(height(&lines[2]), px_to_units(80.0));
+        assert_eq!(baseline(&lines[2]), baseline(&lines[0]));
+    }
+
+    #[test]
+    fn test_page_fragmentation() {
+        let mut dom = DocumentObjectModel::default();
+        let html = dom.insert_element(Element::new("html"), None);
+        dom.insert_element(Element::new("div").with_attribute("id", "a"), Some(html));
+        let p = dom.insert_element(Element::new("p"), Some(html));
+        dom.insert_text("1\n2\n3\n4\n5", Some(p));
+        let c = dom.insert_element(Element::new("div").with_attribute("id", "c"), Some(html));
+        dom.insert_element(Element::new("div").with_attribute("id", "d"), Some(html));
+
+        let (ctx, root) = lay_out_in(&dom, r#"
+            html, div, p { display: block }
+            p { white-space: pre; line-height: 20px; orphans: 3 }
+            #a { height: 40px }
+            #c { height: 10px; margin-top: 5px; break-before: page }
+            #d { height: 200px; margin-top: 10px }
+        "#, BoxContent { width: 800.0, height: 100.0 });
+        let pages = ctx
+            .fragments
+            .pages
+            .iter()
+            .map(|page| ctx.fragments.first_child(page).unwrap())
+            .collect::<Vec<_>>();
+        let source_of = |fragment| ctx.boxes.dom.borrow(*ctx.fragments.sources.borrow(&fragment).unwrap()).map(|n| *n);
+        let children = |fragment| ctx.fragments.iter_children(fragment).collect::<Vec<_>>();
+        let position = |fragment| ctx.fragments.positions.borrow(&fragment).unwrap().y;
+        let height = |fragment| ctx.fragments.boxes.borrow(&fragment).unwrap().content.height;
+
+        // The paragraph breaks after its third line, to keep three orphans, then #c forces a page break,
+        // and #d overflows the last page, as there is no break point within it.
+        assert_eq!(pages.len(), 4);
+        assert_eq!(pages[0], root);
+        assert!(pages.iter().all(|page| source_of(*page) == Some(html)));
+
+        let first = children(pages[0]);
+        assert_eq!(first.len(), 2);
+        assert_eq!(children(first[1]).len(), 3);
+        assert_eq!(height(first[1]), px_to_units(60.0));
+
+        let second = children(pages[1]);
+        assert_eq!(second.len(), 1);
+        assert_eq!(source_of(second[0]), Some(p));
+        assert_eq!(children(second[0]).len(), 2);
+        assert_eq!(position(second[0]), 0);
+        assert_eq!(position(children(second[0])[0]), 0);
+        assert_eq!(height(pages[1]), px_to_units(40.0));
+
+        // The margin after a forced break is preserved, the one after an unforced break is truncated.
+        let third = children(pages[2]);
+        assert_eq!(source_of(third[0]), Some(c));
+        assert_eq!(position(third[0]), px_to_units(5.0));
+        let fourth = children(pages[3]);
+        assert_eq!(position(fourth[0]), 0);
+        assert_eq!(height(fourth[0]), px_to_units(200.0));
+    }
+
+    #[test]
+    fn test_many_pages() {
+        let mut dom = DocumentObjectModel::default();
+        let html = dom.insert_element(Element::new("html"), None);
+        for _ in 0..400 {
+            dom.insert_element(Element::new("div"), Some(html));
+        }
+
+        let (ctx, _) = lay_out_in(&dom, r#"
+            html, div { display: block }
+            div { height: 10px }
+        "#, BoxContent { width: 800.0, height: 100.0 });
+
+        // Each page moves the rest of the document once, which keeps a long document fast to paginate.
+        let pages = ctx.fragments.pages.clone();
+        assert_eq!(pages.len(), 40);
+        assert!(pages.iter().all(|page| ctx.fragments.first_child(page).map(|root| ctx.fragments.iter_children(root).count()) == Some(10)));
+    }
+
+    #[test]
+    fn test_page_boxes() {
+        let mut dom = DocumentObjectModel::default();
+        let html = dom.insert_element(Element::new("html"), None);
+        for _ in 0..8 {
+            dom.insert_element(Element::new("div"), Some(html));
+        }
+
+        let (ctx, root) = lay_out(&dom, r#"
+            html, div { display: block }
+            div { height: 50px }
+            @page {
+                size: 400px 300px;
+                margin: 50px;
+                @top-center { content: "Title" }
+                @bottom-right { content: counter(page) "/" counter(pages) }
+            }
+            @page :first { margin-top: 100px }
+        "#);
+        let pages = ctx.fragments.pages.clone();
+        let children = |fragment| ctx.fragments.iter_children(fragment).collect::<Vec<_>>();
+        let text = |margin_box| {
+            let line = ctx.fragments.first_child(margin_box).unwrap();
+            let text_sequence = ctx.fragments.first_child(line).unwrap();
+            ctx.fragments.text_sequences.borrow(&text_sequence).unwrap().as_str().to_owned()
+        };
+
+        // The initial containing block is the page area of the first page, which is shorter than the others.
+        let size = ctx.fragments.initial_containing_block.clone();
+        assert_eq!((size.width, size.height), (px_to_units(300.0), px_to_units(150.0)));
+        assert_eq!(pages.len(), 3);
+        assert!(pages.iter().all(|page| ctx.fragments.is(page, FragmentKind::Page)));
+        assert_eq!(ctx.fragments.first_child(pages[0]), Some(root));
+        assert_eq!(children(children(pages[0])[0]).len(), 3);
+        assert_eq!(children(children(pages[1])[0]).len(), 4);
+        assert_eq!(children(children(pages[2])[0]).len(), 1);
+
+        let page = ctx.fragments.boxes.borrow(&pages[1]).unwrap().clone();
+        let size = page.outer();
+        assert_eq!((size.width, size.height), (px_to_units(400.0), px_to_units(300.0)));
+        assert_eq!(page.content.height, px_to_units(200.0));
+
+        // The top center box spans the side of the page area, within the top margin.
+        let margin_boxes = children(pages[1])[1..].to_vec();
+        assert_eq!(margin_boxes.len(), 2);
+        assert!(margin_boxes.iter().all(|margin_box| ctx.fragments.is(margin_box, FragmentKind::MarginBox)));
+        assert_eq!(text(margin_boxes[0]), "Title");
+        let top_center = ctx.fragments.boxes.borrow(&margin_boxes[0]).unwrap().clone();
+        let size = &top_center.content;
+        assert_eq!((size.width, size.height), (px_to_units(300.0), px_to_units(50.0)));
+        let position = *ctx.fragments.positions.borrow(&margin_boxes[0]).unwrap();
+        assert_eq!((position.x, position.y), (0, px_to_units(-50.0)));
+
+        // The page counters resolve to the page number and the total number of pages.
+        assert_eq!(text(margin_boxes[1]), "2/3");
     }
 }
@@ -0,0 +1,407 @@
+//! Implements the page boxes and their page-margin boxes from [CSS PAGED MEDIA 3](https://www.w3.org/TR/css-page-3/)
+//!
+//! ```spec
+//! The page box is a rectangular region that contains two areas:
+//! - the page area, which contains the boxes laid out on that page, and acts as their fragmentainer;
+//! - the margin area, which surrounds the page area, and contains the page-margin boxes.
+//! ```
+use crate::{
+    ecs::systems::tree::{TreeExplorer, TreeMutator},
+    style::{
+        cascade::{cascade_page, Origin},
+        page::{MarginBoxPosition, Page, PageRule},
+        properties::{computed, specified_values, used, PropertyDeclaration},
+        values::{
+            numeric::{AutoOrPixelsOrPercentage, ResolutionContext},
+            r#box::{Box, BoxContent, BoxEdges, Point},
+        },
+    },
+};
+
+use super::{
+    fragment_tree::{Fragment, FragmentKind, FragmentTree, LineBox},
+    px_to_units, text_sequence::TextSequence, units_to_px,
+};
+
+/// The page context of a page, styled by the @page rules matching it.
+pub struct PageContext {
+    pub index: usize,
+    pub name: Option<String>,
+    pub blank: bool,
+    pub properties: computed::Properties,
+    /// The page box, whose content box is the page area.
+    pub r#box: Box<i32>,
+    ctx: ResolutionContext,
+}
+
+impl PageContext {
+    /// Computes the page context of a page.
+    ///
+    /// ```spec
+    /// The page context inherits from the root element.
+    /// Percentages on the margin, border and padding of the page box refer to the dimensions of the page box:
+    /// horizontal ones to its width, and vertical ones to its height.
+    /// ```
+    ///
+    /// The target media has the given size, which is the one of the page box when its size is auto.
+    pub fn compute(page_rules: &[(Origin, PageRule)], page: &Page<'_>, root: &computed::Properties, media: &BoxContent<i32>) -> Self {
+        let media = BoxContent { width: units_to_px(media.width), height: units_to_px(media.height) };
+        let ctx = ResolutionContext {
+            root_font_size: root.font_size.0,
+            viewport: media.clone(),
+            ..Default::default()
+        };
+
+        let (properties, used, ctx) = compute_style(&cascade_page(page_rules, page, None), root, ctx);
+        let size = properties.size.px(&media, &ctx);
+        let mut r#box = edges(&used, &size);
+        r#box.content = BoxContent {
+            width: (px_to_units(size.width) - horizontal(&r#box)).max(0),
+            height: (px_to_units(size.height) - vertical(&r#box)).max(0),
+        };
+
+        Self {
+            index: page.index,
+            name: page.name.map(str::to_owned),
+            blank: page.blank,
+            properties,
+            r#box,
+            ctx,
+        }
+    }
+
+    pub fn page(&self) -> Page<'_> {
+        Page { index: self.index, name: self.name.as_deref(), blank: self.blank }
+    }
+}
+
+/// The alignment of the content of a page-margin box.
+#[derive(Clone, Copy)]
+enum Alignment {
+    Start,
+    Center,
+    End,
+}
+
+impl Alignment {
+    fn offset(&self, free_space: i32) -> i32 {
+        match self {
+            Self::Start => 0,
+            Self::Center => free_space / 2,
+            Self::End => free_space,
+        }
+    }
+}
+
+/// A generated page-margin box, with its box sized to its content, and its specified outer size, if any.
+type MarginBox = (MarginBoxPosition, Fragment, Box<i32>, BoxContent<Option<i32>>);
+
+/// The outer size of a page-margin box along its side of the page.
+#[derive(Clone, Copy)]
+struct Extent {
+    specified: Option<i32>,
+    max_content: i32,
+}
+
+impl FragmentTree {
+    /// Generates the page box of a page, around the fragment of the root laid out in its page area,
+    /// and its page-margin boxes.
+    ///
+    /// The page counter is the page number, and the pages counter the total number of pages.
+    pub(super) fn generate_page(&mut self, page_rules: &[(Origin, PageRule)], context: &PageContext, content: Option<Fragment>, pages: usize) -> Fragment {
+        let page = self.insert_generated_fragment(FragmentKind::Page, None);
+        self.style.computed.bind(&page, context.properties.clone());
+
+        if let Some(mut r#box) = self.boxes.borrow_mut(&page) {
+            *r#box = context.r#box.clone();
+        }
+
+        if let Some(mut position) = self.positions.borrow_mut(&page) {
+            *position = Point { x: context.r#box.margin.left, y: context.r#box.margin.top };
+        }
+
+        if let Some(content) = content {
+            self.attach_child(&page, content);
+        }
+
+        let size = BoxContent {
+            width: units_to_px(context.r#box.outer().width),
+            height: units_to_px(context.r#box.outer().height),
+        };
+
+        let mut margin_boxes = Vec::new();
+
+        for position in MarginBoxPosition::ALL {
+            let cascaded = cascade_page(page_rules, &context.page(), Some(*position));
+            let (properties, used, _) = compute_style(&cascaded, &context.properties, context.ctx.clone());
+
+            // ```spec
+            // A page-margin box is generated if the computed value of its content property is not none.
+            // Like ::before and ::after, normal computes to none for page-margin boxes.
+            // ```
+            let counter = |name: &str| match name {
+                "page" => context.index as i32 + 1,
+                "pages" => pages as i32,
+                _ => 0,
+            };
+
+            let Some(text) = properties.content.text(counter) else {
+                continue;
+            };
+
+            let margin_box = self.insert_generated_fragment(FragmentKind::MarginBox, Some(page));
+            self.style.computed.bind(&margin_box, properties);
+
+            let line = self.insert_generated_fragment(FragmentKind::LineBox, Some(margin_box));
+            self.line_boxes.bind(&line, LineBox { logical_width: 0, baseline: 0 });
+            self.style.computed.share_from(&line, &margin_box);
+
+            let text_sequence = self.insert_generated_fragment(FragmentKind::TextSequence, Some(line));
+            self.text_sequences.bind(&text_sequence, TextSequence::from(text.as_str()));
+            self.style.computed.share_from(&text_sequence, &margin_box);
+            self.measure_text_sequence(&text_sequence);
+
+            let mut r#box = edges(&used, &size);
+            r#box.content = self.boxes.borrow(&text_sequence).map(|text| text.content.clone()).unwrap_or_default();
+
+            if let Some(mut line_box) = self.boxes.borrow_mut(&line) {
+                line_box.content = r#box.content.clone();
+            }
+
+            let specified = BoxContent {
+                width: used.width.px(size.width).map(|width| px_to_units(width) + horizontal(&r#box)),
+                height: used.height.px(size.height).map(|height| px_to_units(height) + vertical(&r#box)),
+            };
+
+            margin_boxes.push((*position, margin_box, r#box, specified));
+        }
+
+        self.place_margin_boxes(&context.r#box, margin_boxes);
+        page
+    }
+
+    /// Sizes and positions the generated page-margin boxes, in the margin area of the page box.
+    ///
+    /// ```spec
+    /// The corner boxes fill the corners of the margin area.
+    /// The other boxes are laid out along the side of the page area between the corners:
+    /// the center box, if generated, is centered on the side, and the boxes at the start and the end are as wide as each other,
+    /// otherwise the boxes at the start and the end share the side in proportion to their max-content sizes.
+    /// The boxes of the top and bottom sides are as high as the margin, the ones of the left and right sides are as wide as it.
+    /// ```
+    fn place_margin_boxes(&mut self, page: &Box<i32>, margin_boxes: Vec<MarginBox>) {
+        use MarginBoxPosition::*;
+
+        let size = page.outer();
+        let margin = &page.margin;
+        // The edges of the page box, from the page area.
+        let left = -(margin.left + page.border.left + page.padding.left);
+        let top = -(margin.top + page.border.top + page.padding.top);
+        let right = left + size.width;
+        let bottom = top + size.height;
+
+        let horizontal_sides = [
+            ([TopLeft, TopCenter, TopRight], top, margin.top),
+            ([BottomLeft, BottomCenter, BottomRight], bottom - margin.bottom, margin.bottom),
+        ];
+
+        let vertical_sides = [
+            ([LeftTop, LeftMiddle, LeftBottom], left, margin.left),
+            ([RightTop, RightMiddle, RightBottom], right - margin.right, margin.right),
+        ];
+
+        let mut slots = vec![
+            (TopLeftCorner, Point { x: left, y: top }, BoxContent { width: margin.left, height: margin.top }),
+            (TopRightCorner, Point { x: right - margin.right, y: top }, BoxContent { width: margin.right, height: margin.top }),
+            (BottomRightCorner, Point { x: right - margin.right, y: bottom - margin.bottom }, BoxContent { width: margin.right, height: margin.bottom }),
+            (BottomLeftCorner, Point { x: left, y: bottom - margin.bottom }, BoxContent { width: margin.left, height: margin.bottom }),
+        ];
+
+        let extent = |position: MarginBoxPosition, along_width: bool| margin_boxes
+            .iter()
+            .find(|(other, ..)| *other == position)
+            .map(|(_, _, r#box, specified)| if along_width {
+                Extent { specified: specified.width, max_content: r#box.content.width + horizontal(r#box) }
+            } else {
+                Extent { specified: specified.height, max_content: r#box.content.height + vertical(r#box) }
+            });
+
+        for (positions, y, height) in horizontal_sides {
+            let available = size.width - margin.left - margin.right;
+            let extents = positions.map(|position| extent(position, true));
+
+            for (position, (offset, width)) in positions.into_iter().zip(resolve_side(available, extents)) {
+                slots.push((position, Point { x: left + margin.left + offset, y }, BoxContent { width, height }));
+            }
+        }
+
+        for (positions, x, width) in vertical_sides {
+            let available = size.height - margin.top - margin.bottom;
+            let extents = positions.map(|position| extent(position, false));
+
+            for (position, (offset, height)) in positions.into_iter().zip(resolve_side(available, extents)) {
+                slots.push((position, Point { x, y: top + margin.top + offset }, BoxContent { width, height }));
+            }
+        }
+
+        for (position, fragment, mut r#box, _) in margin_boxes {
+            let Some((_, origin, slot)) = slots.iter().find(|(other, ..)| *other == position) else {
+                continue;
+            };
+
+            let text = r#box.content.clone();
+            r#box.content = BoxContent {
+                width: (slot.width - horizontal(&r#box)).max(0),
+                height: (slot.height - vertical(&r#box)).max(0),
+            };
+
+            let (text_align, vertical_align) = alignment(position);
+            let line = self.first_child(fragment);
+            let text_sequence = line.and_then(|line| self.first_child(line));
+
+            if let Some(line) = line {
+                let baseline = text_sequence
+                    .and_then(|text| self.glyph_runs.borrow(&text).and_then(|runs| runs.iter().map(|run| run.ascent).max()))
+                    .unwrap_or(text.height);
+
+                if let Some(mut line_box) = self.line_boxes.borrow_mut(&line) {
+                    *line_box = LineBox { logical_width: r#box.content.width, baseline };
+                }
+
+                if let Some(mut line_box) = self.boxes.borrow_mut(&line) {
+                    line_box.content = BoxContent { width: r#box.content.width, height: text.height };
+                }
+
+                if let Some(mut line_position) = self.positions.borrow_mut(&line) {
+                    *line_position = Point { x: 0, y: vertical_align.offset(r#box.content.height - text.height) };
+                }
+            }
+
+            if let Some(mut text_position) = text_sequence.and_then(|text| self.positions.borrow_mut(&text)) {
+                *text_position = Point { x: text_align.offset(r#box.content.width - text.width), y: 0 };
+            }
+
+            if let Some(mut fragment_position) = self.positions.borrow_mut(&fragment) {
+                *fragment_position = Point { x: origin.x + r#box.margin.left, y: origin.y + r#box.margin.top };
+            }
+
+            if let Some(mut fragment_box) = self.boxes.borrow_mut(&fragment) {
+                *fragment_box = r#box;
+            }
+        }
+    }
+}
+
+/// Computes the style of a page context, or of a page-margin box, from its cascaded values and its parent.
+fn compute_style(cascaded: &[PropertyDeclaration], parent: &computed::Properties, mut ctx: ResolutionContext) -> (computed::Properties, used::Properties, ResolutionContext) {
+    let specified = specified_values(cascaded, Some(parent));
+
+    ctx.font_size = parent.font_size.0;
+    ctx.font_weight = parent.font_weight.0;
+    ctx.current_color = specified.current_color(Some(parent));
+    let properties = computed::Properties::compute(&specified, &ctx);
+
+    ctx.font_size = properties.font_size.0;
+    ctx.font_weight = properties.font_weight.0;
+    let used = used::Properties::resolve(&properties, &ctx);
+    (properties, used, ctx)
+}
+
+/// Returns the margins, borders and paddings, with percentages resolved against the size of the page box.
+///
+/// Auto margins are zero.
+fn edges(used: &used::Properties, page: &BoxContent<f64>) -> Box<i32> {
+    let resolve = |edges: &BoxEdges<AutoOrPixelsOrPercentage>| BoxEdges {
+        top: px_to_units(edges.top.px(page.height).unwrap_or_default()),
+        bottom: px_to_units(edges.bottom.px(page.height).unwrap_or_default()),
+        left: px_to_units(edges.left.px(page.width).unwrap_or_default()),
+        right: px_to_units(edges.right.px(page.width).unwrap_or_default()),
+    };
+
+    Box {
+        content: BoxContent::default(),
+        margin: resolve(&used.margin),
+        padding: resolve(&used.padding),
+        border: BoxEdges {
+            top: px_to_units(used.border.top),
+            bottom: px_to_units(used.border.bottom),
+            left: px_to_units(used.border.left),
+            right: px_to_units(used.border.right),
+        },
+    }
+}
+
+fn horizontal(r#box: &Box<i32>) -> i32 {
+    r#box.margin.left + r#box.margin.right + r#box.border.left + r#box.border.right + r#box.padding.left + r#box.padding.right
+}
+
+fn vertical(r#box: &Box<i32>) -> i32 {
+    r#box.margin.top + r#box.margin.bottom + r#box.border.top + r#box.border.bottom + r#box.padding.top + r#box.padding.bottom
+}
+
+/// Resolves the offsets and the outer sizes of the boxes at the start, the center and the end of a side of the page.
+fn resolve_side(available: i32, [start, center, end]: [Option<Extent>; 3]) -> [(i32, i32); 3] {
+    let outer = |extent: Option<Extent>| extent.map(|extent| extent.specified.unwrap_or(extent.max_content)).unwrap_or_default();
+    let proportion = |total: i32, weight: i32, weights: i32| if weights > 0 {
+        (total as i64 * weight as i64 / weights as i64) as i32
+    } else {
+        0
+    };
+
+    let [start_size, center_size, end_size] = match center {
+        Some(center) => {
+            // The center box is sized against an imaginary box twice as wide as the widest side box.
+            let sides = 2 * outer(start).max(outer(end));
+            let center_size = center.specified.unwrap_or_else(|| {
+                if center.max_content + sides > 0 {
+                    proportion(available, center.max_content, center.max_content + sides)
+                } else {
+                    available / 3
+                }
+            });
+
+            let side = (available - center_size) / 2;
+            let side_size = |extent: Option<Extent>| extent.map(|extent| extent.specified.unwrap_or(side)).unwrap_or_default();
+            [side_size(start), center_size, side_size(end)]
+        },
+        None => match (start.map(|extent| extent.specified), end.map(|extent| extent.specified)) {
+            (Some(Some(start_size)), Some(None)) => [start_size, 0, available - start_size],
+            (Some(None), Some(Some(end_size))) => [available - end_size, 0, end_size],
+            (Some(Some(start_size)), Some(Some(end_size))) => [start_size, 0, end_size],
+            (Some(None), Some(None)) => {
+                let (start_weight, end_weight) = match (outer(start), outer(end)) {
+                    (0, 0) => (1, 1),
+                    weights => weights,
+                };
+                let start_size = proportion(available, start_weight, start_weight + end_weight);
+                [start_size, 0, available - start_size]
+            },
+            (Some(specified), None) => [specified.unwrap_or(available), 0, 0],
+            (None, Some(specified)) => [0, 0, specified.unwrap_or(available)],
+            (None, None) => [0, 0, 0],
+        },
+    };
+
+    [(0, start_size), ((available - center_size) / 2, center_size), (available - end_size, end_size)]
+}
+
+/// Returns the default horizontal and vertical alignments of the content of a page-margin box.
+///
+/// ```spec
+/// The boxes of the top and bottom sides are vertically centered, and aligned towards their end of the side;
+/// the corner boxes are aligned towards the page area; the boxes of the left and right sides are horizontally centered.
+/// ```
+fn alignment(position: MarginBoxPosition) -> (Alignment, Alignment) {
+    use MarginBoxPosition::*;
+
+    match position {
+        TopLeftCorner | BottomLeftCorner => (Alignment::End, Alignment::Center),
+        TopRightCorner | BottomRightCorner => (Alignment::Start, Alignment::Center),
+        TopLeft | BottomLeft => (Alignment::Start, Alignment::Center),
+        TopCenter | BottomCenter => (Alignment::Center, Alignment::Center),
+        TopRight | BottomRight => (Alignment::End, Alignment::Center),
+        LeftTop | RightTop => (Alignment::Center, Alignment::Start),
+        LeftMiddle | RightMiddle => (Alignment::Center, Alignment::Center),
+        LeftBottom | RightBottom => (Alignment::Center, Alignment::End),
+    }
+}
@@ -0,0 +1,190 @@
+//! Shapes text sequences into glyph runs, with [rustybuzz](https://github.com/harfbuzz/rustybuzz),
+//! a port of [HarfBuzz](https://harfbuzz.github.io/).
+use std::ops::Range;
+
+use rustybuzz::UnicodeBuffer;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{font::SizedFont, style::values::r#box::Point};
+
+use super::px_to_units;
+
+/// A shaped glyph.
+///
+/// Its advance and offset are in layout units.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Glyph {
+    /// The index of the glyph in the face.
+    pub id: u16,
+    /// The byte offset in the text of the cluster which the glyph belongs to.
+    pub cluster: usize,
+    /// How much the pen moves after drawing the glyph.
+    pub advance: Point<i32>,
+    /// Where the glyph is drawn, relative to the pen.
+    pub offset: Point<i32>,
+}
+
+/// A sequence of glyphs shaped with the same font, along its baseline.
+#[derive(Clone)]
+pub struct GlyphRun {
+    pub font: SizedFont,
+    pub glyphs: Vec<Glyph>,
+    /// The height of the face above the baseline, in layout units.
+    pub ascent: i32,
+    /// The depth of the face below the baseline, in layout units.
+    pub descent: i32,
+}
+
+impl GlyphRun {
+    /// The distance which the run advances the pen along the line.
+    pub fn advance(&self) -> i32 {
+        self.glyphs.iter().map(|glyph| glyph.advance.x).sum()
+    }
+
+    /// The height of the run, from its ascent to its descent.
+    pub fn height(&self) -> i32 {
+        self.ascent + self.descent
+    }
+}
+
+/// Shapes the text with the font.
+///
+/// The direction, script and language are guessed from the text,
+/// and the default features of the script, such as kerning and standard ligatures, are applied.
+///
+/// Returns nothing if the face cannot be parsed.
+pub fn shape(text: &str, font: &SizedFont) -> Option<GlyphRun> {
+    let face = font.face()?;
+    let scale = font.pixels() as f64 / face.units_per_em() as f64;
+    let units = |value: i32| px_to_units(value as f64 * scale);
+
+    let mut buffer = UnicodeBuffer::new();
+    buffer.push_str(text);
+    buffer.guess_segment_properties();
+
+    let output = rustybuzz::shape(face, &[], buffer);
+    let glyphs = output
+        .glyph_infos()
+        .iter()
+        .zip(output.glyph_positions())
+        .map(|(info, position)| Glyph {
+            id: info.glyph_id as u16,
+            cluster: info.cluster as usize,
+            advance: Point { x: units(position.x_advance), y: units(position.y_advance) },
+            offset: Point { x: units(position.x_offset), y: units(position.y_offset) },
+        })
+        .collect();
+
+    Some(GlyphRun {
+        font: font.clone(),
+        glyphs,
+        ascent: units(face.ascender() as i32),
+        descent: units(-(face.descender() as i32)),
+    })
+}
+
+/// Shapes the text with a list of fonts, the first being the primary font.
+///
+/// ```spec
+/// If no matching face exists or the matched face does not contain a glyph for the character to be rendered,
+/// the next family name is selected and the previous steps repeated.
+/// ```
+///
+/// Each grapheme cluster is shaped with the first font which has a glyph for each of its characters,
+/// or with the primary font if none has. Consecutive clusters with the same font are shaped in the same run,
+/// whose clusters are byte offsets in the whole text.
+pub fn shape_with_fallback(text: &str, fonts: &[SizedFont]) -> Vec<GlyphRun> {
+    let Some(primary) = fonts.first() else {
+        return Vec::new();
+    };
+
+    // An empty text still has the metrics of the primary font.
+    if text.is_empty() {
+        return shape(text, primary).into_iter().collect();
+    }
+
+    let mut runs = Vec::new();
+    let mut current: Option<(usize, usize)> = None;
+
+    for (offset, cluster) in text.grapheme_indices(true) {
+        let font = fonts
+            .iter()
+            .position(|font| cluster.chars().all(|ch| is_default_ignorable(ch) || font.has_glyph(ch)))
+            .unwrap_or(0);
+
+        match current {
+            Some((previous, start)) if previous != font => {
+                runs.extend(shape_range(text, start..offset, &fonts[previous]));
+                current = Some((font, offset));
+            },
+            None => current = Some((font, offset)),
+            _ => {},
+        }
+    }
+
+    if let Some((font, start)) = current {
+        runs.extend(shape_range(text, start..text.len(), &fonts[font]));
+    }
+
+    runs
+}
+
+/// Shapes a range of the text, whose clusters are offset by the start of the range.
+fn shape_range(text: &str, range: Range<usize>, font: &SizedFont) -> Option<GlyphRun> {
+    let mut run = shape(&text[range.clone()], font)?;
+    run.glyphs.iter_mut().for_each(|glyph| glyph.cluster += range.start);
+    Some(run)
+}
+
+/// Checks if the character is rendered invisibly, without any glyph.
+fn is_default_ignorable(ch: char) -> bool {
+    ch.is_control() || matches!(ch, '\u{200B}'..='\u{200F}' | '\u{2060}'..='\u{206F}' | '\u{FE00}'..='\u{FE0F}' | '\u{FEFF}')
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::{
+        font::FontCache,
+        style::{
+            font::{FamilyName, FontFamily},
+            properties::computed,
+        },
+        testing::{FONTS_DIRECTORY, FONT_FAMILY},
+    };
+
+    use super::{shape, shape_with_fallback};
+
+    #[test]
+    fn test_shape() {
+        let mut cache = FontCache::default();
+        cache.add_directory(Path::new(FONTS_DIRECTORY));
+        let props = computed::Properties {
+            font_family: FontFamily(vec![FamilyName::Named(FONT_FAMILY.to_owned())]),
+            ..Default::default()
+        };
+        let fonts = cache.select(&props);
+        let font = &fonts[0];
+        assert_eq!(font.font().family_name(), FONT_FAMILY);
+
+        let run = shape("Hello", font).unwrap();
+        assert_eq!(run.glyphs.len(), 5);
+        assert_eq!(run.glyphs.iter().map(|glyph| glyph.cluster).collect::<Vec<_>>(), [0, 1, 2, 3, 4]);
+        assert!(run.advance() > 0);
+        assert!(run.height() > 0);
+
+        // The same text is twice as wide, twice as large.
+        let large = shape("Hello", &font.resize(font.points() * 2.0)).unwrap();
+        assert!((large.advance() - 2 * run.advance()).abs() <= 5);
+
+        // The face is parsed once, for all its sizes.
+        assert!(std::ptr::eq(font.face().unwrap(), large.font.face().unwrap()));
+
+        // The clusters of the runs are offsets in the whole text.
+        let runs = shape_with_fallback("Hi there", &fonts);
+        let clusters = runs.iter().flat_map(|run| run.glyphs.iter().map(|glyph| glyph.cluster)).collect::<Vec<_>>();
+        assert_eq!(clusters.last(), Some(&7));
+        assert_eq!(shape_with_fallback("", &fonts).len(), 1);
+    }
+}
@@ -1,19 +1,79 @@
+use super::line_breaking::BreakOpportunity;
+
 #[derive(Clone)]
 pub struct TextSequence {
     text: String,
+    /// The line break opportunity at the end of the sequence.
+    break_after: Option<BreakOpportunity>,
 }
 
 impl From<&str> for TextSequence {
     fn from(value: &str) -> Self {
-        Self{text: value.to_owned()}
+        Self{text: value.to_owned(), break_after: None}
     }
 }
 
 impl TextSequence {
-    pub fn split_by_line_breaks(&self) -> impl Iterator<Item = TextSequence> + '_ {
-        self.text.split(|ch| ch == '\n').map(|text| TextSequence {
-            text: text.to_owned(),
-        })
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    pub fn break_after(&self) -> Option<BreakOpportunity> {
+        self.break_after
+    }
+
+    /// Replaces the text, but keeps the break opportunity at the end of the sequence.
+    pub fn set_text(&mut self, text: &str) {
+        self.text = text.to_owned();
+    }
+
+    /// Checks if the sequence is only made of document white spaces.
+    pub fn is_white_space(&self) -> bool {
+        self.text.chars().all(|ch| matches!(ch, ' ' | '\t' | '\n' | '\r' | '\x0C'))
+    }
+
+    /// Splits the sequence at its break opportunities, given as byte offsets in ascending order.
+    ///
+    /// Each part ends with a break opportunity, but the last one which gets the opportunity at the end of the sequence, if any.
+    /// The characters of the mandatory breaks are removed, and the spaces preceding a soft wrap opportunity
+    /// are split into their own part, so that they can hang at the end of a line.
+    pub fn split_at_break_opportunities(&self, opportunities: &[(usize, BreakOpportunity)]) -> Vec<TextSequence> {
+        let mut parts = Vec::with_capacity(opportunities.len() + 1);
+        let mut start = 0;
+        let at_end = opportunities
+            .iter()
+            .find(|(offset, _)| *offset == self.text.len())
+            .map(|(_, opportunity)| *opportunity);
+
+        let inner = opportunities
+            .iter()
+            .copied()
+            .filter(|(offset, _)| *offset > 0 && *offset < self.text.len());
+
+        for (offset, opportunity) in inner.chain([(self.text.len(), BreakOpportunity::Soft)]) {
+            let break_after = if offset == self.text.len() { at_end } else { Some(opportunity) };
+            let text = &self.text[start..offset];
+            start = offset;
+
+            match break_after {
+                Some(BreakOpportunity::Mandatory) => parts.push(TextSequence {
+                    text: text.trim_end_matches(is_mandatory_break).to_owned(),
+                    break_after,
+                }),
+                Some(BreakOpportunity::Soft) if text.ends_with(' ') && !text.trim_end_matches(' ').is_empty() => {
+                    let word = text.trim_end_matches(' ');
+                    parts.push(TextSequence { text: word.to_owned(), break_after: None });
+                    parts.push(TextSequence { text: text[word.len()..].to_owned(), break_after });
+                },
+                _ => parts.push(TextSequence { text: text.to_owned(), break_after }),
+            }
+        }
+
+        parts
     }
 }
 
+/// The characters which force a line break.
+fn is_mandatory_break(ch: char) -> bool {
+    matches!(ch, '\n' | '\r' | '\u{0B}' | '\u{0C}' | '\u{85}' | '\u{2028}' | '\u{2029}')
+}
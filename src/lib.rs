@@ -1,22 +1,34 @@
-use std::hash::Hash;
+use std::{hash::Hash, path::Path};
 
 use dom::{DomHandler, TDocumentObjectModelExplorer};
-use layout::box_tree::BoxTree;
-use style::Styles;
+use ecs::systems::tree::TreeExplorer;
+use layout::{box_tree::{BoxNode, BoxTree}, fragment_tree::{Fragment, FragmentTree}, px_to_units, units_to_px};
+use style::{cascade::StyleSheet, values::{numeric::ResolutionContext, r#box::BoxContent}, Styles};
 
+pub mod builder;
 pub mod dom;
 pub mod ecs;
 pub mod font;
+pub mod html;
+pub mod resource;
 pub mod style;
 pub mod layout;
+pub mod output;
+
+pub use builder::RenderingContextBuilder;
+
+#[cfg(test)]
+mod reftest;
+#[cfg(test)]
+mod testing;
 
 
 pub struct RenderingContextArgs<'a, Dom> 
 where Dom: TDocumentObjectModelExplorer, Dom::NodeId: Hash + Copy + Eq
 {
-    dom: &'a Dom,
-    bucket_size: Option<usize>,
-    cache_size: Option<usize>,
+    pub dom: &'a Dom,
+    pub bucket_size: Option<usize>,
+    pub cache_size: Option<usize>,
 }
 
 pub struct RenderingContext<'a, Dom> 
@@ -24,6 +36,9 @@ where Dom: TDocumentObjectModelExplorer, Dom::NodeId: Hash + Copy + Eq
 {
     pub (crate) dom: DomHandler<'a, Dom>,
     pub (crate) boxes: BoxTree<Dom::NodeId>,
+    pub (crate) fragments: FragmentTree,
+    /// The style sheets of the document, in cascade order.
+    pub (crate) stylesheets: Vec<StyleSheet>,
 }
 
 impl<'a, Dom> RenderingContext<'a, Dom>
@@ -43,12 +58,109 @@ where Dom: TDocumentObjectModelExplorer, Dom::NodeId: Hash + Copy + Eq
             args.cache_size.unwrap_or(100)
         );
 
+        // The initial containing block has the dimensions of the viewport.
+        let viewport = ResolutionContext::default().viewport;
+        let fragments = FragmentTree::new(
+            &dom.styles,
+            BoxContent {
+                width: px_to_units(viewport.width),
+                height: px_to_units(viewport.height),
+            },
+            args.bucket_size.unwrap_or(100),
+            args.cache_size.unwrap_or(100)
+        );
+
         Self {
             dom,
-            boxes
+            boxes,
+            fragments,
+            stylesheets: Vec::new(),
         }
     }
 }
+
+impl<'a, Dom> RenderingContext<'a, Dom>
+where Dom: TDocumentObjectModelExplorer + Sync, Dom::NodeId: Hash + Copy + Eq
+{
+    /// Starts building a rendering context for the document.
+    pub fn builder(dom: &'a Dom) -> RenderingContextBuilder<'a, Dom> {
+        RenderingContextBuilder::new(dom)
+    }
+}
+
+impl<'a, Dom> RenderingContext<'a, Dom>
+where Dom: TDocumentObjectModelExplorer, Dom::NodeId: Hash + Copy + Eq
+{
+    /// The rendered document.
+    pub fn dom(&self) -> &'a Dom {
+        self.dom.dom
+    }
+
+    /// The box tree, once the boxes are generated.
+    pub fn boxes(&self) -> &BoxTree<Dom::NodeId> {
+        &self.boxes
+    }
+
+    /// The fragment tree, once the document is laid out.
+    pub fn fragments(&self) -> &FragmentTree {
+        &self.fragments
+    }
+
+    /// Searches the fonts of a directory before the fonts of the system.
+    pub fn add_fonts_directory(&mut self, path: &Path) {
+        self.fragments.fonts.add_directory(path);
+    }
+}
+
+impl<Dom> RenderingContext<'_, Dom>
+where Dom: TDocumentObjectModelExplorer + Sync, Dom::NodeId: Hash + Copy + Eq
+{
+    /// Runs every phase of the rendering, in order, and returns the laid out fragment tree,
+    /// unless the document generates no box.
+    ///
+    /// The phases which already ran, step by step or by a previous call, are not run again.
+    pub fn render(&mut self) -> Option<&FragmentTree> {
+        if self.boxes.root().is_none() {
+            self.style();
+            self.generate_boxes()?;
+        }
+
+        if self.fragments.root().is_none() {
+            self.generate_fragments()?;
+        }
+
+        if self.fragments.pages.is_empty() {
+            self.lay_out()?;
+        }
+
+        Some(&self.fragments)
+    }
+
+    /// Styles the document with its style sheets, whose viewport is the initial containing block.
+    pub fn style(&mut self) {
+        let icb = &self.fragments.initial_containing_block;
+        let viewport = BoxContent { width: units_to_px(icb.width), height: units_to_px(icb.height) };
+        style::style(self.dom.dom, &self.stylesheets, &viewport, &mut self.dom.styles);
+    }
+
+    /// Generates the box tree of the styled document, and returns its root box.
+    pub fn generate_boxes(&mut self) -> Option<BoxNode> {
+        layout::generate_box_tree(self)
+    }
+
+    /// Generates the fragment tree of the box tree, and returns its root fragment.
+    pub fn generate_fragments(&mut self) -> Option<Fragment> {
+        layout::generate_fragment_tree(self)
+    }
+
+    /// Lays out the fragment tree, fragments it into pages, and returns its root fragment.
+    pub fn lay_out(&mut self) -> Option<Fragment> {
+        let root = self.fragments.root()?;
+        layout::lay_out_pages(self, &root);
+        Some(root)
+    }
+}
+
 impl<'a, Dom> Clone for RenderingContext<'a, Dom> 
 where Dom: TDocumentObjectModelExplorer, Dom::NodeId: Hash + Copy + Eq
 {
@@ -56,6 +168,8 @@ where Dom: TDocumentObjectModelExplorer, Dom::NodeId: Hash + Copy + Eq
         Self { 
             dom: self.dom.clone(), 
             boxes: self.boxes.clone(),
+            fragments: self.fragments.clone(),
+            stylesheets: self.stylesheets.clone(),
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,219 @@
+//! Renders an HTML document, styled by its style sheets, into a PDF document, PNG or SVG images of its pages,
+//! or a dump of its box and fragment trees.
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use clap::{Parser, ValueEnum};
+use serde_json::json;
+
+use oleron::{
+    html::HtmlDocument,
+    output::{display_list::DisplayList, pdf::write_pdf, raster::{write_png, CSS_DPI}, svg::write_svg},
+    resource::FileLoader,
+    style::{
+        cascade::{Origin, StyleSheet, PRINT_MEDIA},
+        page::PageSize,
+        parser::parser::parse_list_of_component_values,
+    },
+    RenderingContext,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    Pdf,
+    Png,
+    Svg,
+    /// The box tree and the fragment tree, as indented text.
+    Dump,
+    /// The box tree and the fragment tree, as JSON.
+    DumpJson,
+}
+
+impl Format {
+    fn extension(&self) -> &'static str {
+        match self {
+            Self::Pdf => "pdf",
+            Self::Png => "png",
+            Self::Svg => "svg",
+            Self::Dump => "txt",
+            Self::DumpJson => "json",
+        }
+    }
+}
+
+/// Renders an HTML document into PDF, PNG, SVG, or a dump of its layout.
+#[derive(Parser)]
+#[command(name = "oleron", version)]
+struct Cli {
+    /// The HTML document to render.
+    input: PathBuf,
+
+    /// Author style sheets, applied after the style sheets of the document.
+    #[arg(short, long = "stylesheet", value_name = "CSS")]
+    stylesheets: Vec<PathBuf>,
+
+    /// The output file, or `-` for the standard output.
+    ///
+    /// Defaults to the input file, with the extension of the format.
+    /// The images of the pages of a multi-page document are numbered from 1: `page-1.png`, `page-2.png`...
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// The output format, guessed from the extension of the output file if not given, or PDF.
+    #[arg(short, long, value_enum)]
+    format: Option<Format>,
+
+    /// The size of the pages, as a value of the `size` descriptor (eg. `A4`, `letter landscape`, `20cm 15cm`),
+    /// unless the document sets it.
+    #[arg(long, value_name = "SIZE")]
+    page_size: Option<String>,
+
+    /// The resolution of the PNG images, in dots per inch.
+    #[arg(long, default_value_t = CSS_DPI)]
+    dpi: f32,
+
+    /// The media type matched by the @media rules.
+    #[arg(long, default_value = PRINT_MEDIA)]
+    media: String,
+
+    /// A directory of fonts, searched before the fonts of the system.
+    #[arg(long = "fonts", value_name = "DIRECTORY")]
+    fonts_directories: Vec<PathBuf>,
+}
+
+fn main() -> ExitCode {
+    match render(&Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("oleron: {error}");
+            ExitCode::FAILURE
+        },
+    }
+}
+
+fn render(cli: &Cli) -> Result<(), String> {
+    let format = cli.format.unwrap_or_else(|| {
+        let extension = cli.output.as_ref().and_then(|output| output.extension());
+        Format::value_variants()
+            .iter()
+            .copied()
+            .find(|format| extension.is_some_and(|extension| extension.eq_ignore_ascii_case(format.extension())))
+            .unwrap_or(Format::Pdf)
+    });
+    let output = cli.output.clone().unwrap_or_else(|| cli.input.with_extension(format.extension()));
+
+    let document = HtmlDocument::parse(&read(&cli.input)?);
+    let parse = |css: &str, origin: Origin| StyleSheet::parse_for_media(css, origin, &cli.media);
+
+    let mut stylesheets = Vec::new();
+
+    if let Some(size) = &cli.page_size {
+        stylesheets.push(parse(&page_rule(size)?, Origin::User));
+    }
+
+    for path in &cli.stylesheets {
+        stylesheets.push(parse(&read(path)?, Origin::Author));
+    }
+
+    let base = cli.input.parent().map(Path::to_path_buf).unwrap_or_default();
+    let mut ctx = cli
+        .fonts_directories
+        .iter()
+        .fold(RenderingContext::builder(&document.dom), |builder, directory| builder.fonts_directory(directory))
+        .stylesheets(stylesheets)
+        .media(&cli.media)
+        .resource_loader(FileLoader::new(base))
+        .build();
+
+    ctx.render().ok_or_else(|| format!("{}: the document has nothing to render", cli.input.display()))?;
+
+    let display_list = DisplayList::from(ctx.fragments());
+    let pages = display_list.pages.len();
+
+    match format {
+        Format::Pdf => write(&output, &write_pdf(&display_list)),
+        Format::Png => display_list.pages.iter().enumerate().try_for_each(|(index, page)| {
+            let png = write_png(page, cli.dpi).ok_or_else(|| format!("page {} is empty, or too large", index + 1))?;
+            write(&numbered(&output, index, pages), &png)
+        }),
+        Format::Svg => display_list
+            .pages
+            .iter()
+            .enumerate()
+            .try_for_each(|(index, page)| write(&numbered(&output, index, pages), write_svg(page).as_bytes())),
+        Format::Dump => {
+            let dump = format!("# Box tree\n{}\n# Fragment tree\n{}", ctx.boxes().dump(), ctx.fragments().dump());
+            write(&output, dump.as_bytes())
+        },
+        Format::DumpJson => {
+            let dump = json!({ "boxes": ctx.boxes().dump().to_json(), "fragments": ctx.fragments().dump().to_json() });
+            write(&output, format!("{dump:#}\n").as_bytes())
+        },
+    }
+}
+
+fn read(path: &Path) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|error| format!("{}: {error}", path.display()))
+}
+
+fn write(path: &Path, data: &[u8]) -> Result<(), String> {
+    let result = match path.as_os_str() == "-" {
+        true => io::stdout().write_all(data),
+        false => fs::write(path, data),
+    };
+
+    result.map_err(|error| format!("{}: {error}", path.display()))
+}
+
+/// The page rule setting the size of the pages, once the size is checked to be a single value of the `size` descriptor.
+fn page_rule(size: &str) -> Result<String, String> {
+    let values = parse_list_of_component_values(size).map_err(|error| format!("--page-size: {error:?}"))?;
+    PageSize::try_from(values.as_slice()).map_err(|_| format!("--page-size: `{size}` is not a page size"))?;
+    Ok(format!("@page {{ size: {size} }}"))
+}
+
+/// The path of the image of a page, numbered from 1 if the document has several pages.
+fn numbered(path: &Path, index: usize, pages: usize) -> PathBuf {
+    if pages <= 1 || path.as_os_str() == "-" {
+        return path.to_path_buf();
+    }
+
+    let stem = path.file_stem().map(|stem| stem.to_string_lossy()).unwrap_or_default();
+    let name = match path.extension() {
+        Some(extension) => format!("{stem}-{}.{}", index + 1, extension.to_string_lossy()),
+        None => format!("{stem}-{}", index + 1),
+    };
+
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::{Path, PathBuf};
+
+    use super::{numbered, page_rule};
+
+    #[test]
+    fn test_numbered() {
+        assert_eq!(numbered(Path::new("out/page.png"), 0, 1), PathBuf::from("out/page.png"));
+        assert_eq!(numbered(Path::new("out/page.png"), 1, 3), PathBuf::from("out/page-2.png"));
+        assert_eq!(numbered(Path::new("page"), 0, 2), PathBuf::from("page-1"));
+        assert_eq!(numbered(Path::new("-"), 1, 2), PathBuf::from("-"));
+    }
+
+    #[test]
+    fn test_page_rule() {
+        assert_eq!(page_rule("A4 landscape").unwrap(), "@page { size: A4 landscape }");
+        assert_eq!(page_rule("20cm 15cm").unwrap(), "@page { size: 20cm 15cm }");
+
+        // Anything else than a size is rejected, rather than pasted into the style sheet.
+        assert!(page_rule("A4 } html { display: none").is_err());
+        assert!(page_rule("A4; margin: 0").is_err());
+        assert!(page_rule("A4 {").is_err());
+        assert!(page_rule("").is_err());
+    }
+}
@@ -0,0 +1,302 @@
+//! Builds the display list of the pages: what is painted, and in which order,
+//! following the painting order of [CSS 2.1](https://www.w3.org/TR/CSS21/zindex.html).
+//!
+//! The items are positioned in layout units, from the top left corner of their page,
+//! so that every backend paints the same geometry.
+use crate::{
+    ecs::systems::tree::TreeExplorer,
+    layout::{fragment_tree::{Fragment, FragmentKind, FragmentTree}, shaping::GlyphRun},
+    style::{values::{color::Srgba, r#box::{BoxContent, Point}}, visibility::Visibility},
+};
+
+use super::page_offset;
+
+/// A rectangle, in layout units.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Rect {
+    pub fn is_empty(&self) -> bool {
+        self.width <= 0 || self.height <= 0
+    }
+}
+
+#[derive(Clone)]
+pub enum DisplayItem {
+    /// Fills a rectangle with a color.
+    Rect { rect: Rect, color: Srgba },
+    /// Draws the glyph runs of a text, from the pen position on its baseline.
+    ///
+    /// The clusters of the glyphs are byte offsets in the text.
+    Text { origin: Point<i32>, text: String, runs: Vec<GlyphRun>, color: Srgba },
+    /// Clips the following items to a rectangle, until the matching pop.
+    PushClip(Rect),
+    PopClip,
+}
+
+/// The items of a page, in painting order.
+#[derive(Clone)]
+pub struct DisplayPage {
+    /// The size of the margin box of the page box.
+    pub size: BoxContent<i32>,
+    pub items: Vec<DisplayItem>,
+}
+
+/// The display lists of the pages of a fragment tree.
+#[derive(Clone)]
+pub struct DisplayList {
+    pub pages: Vec<DisplayPage>,
+}
+
+impl From<&FragmentTree> for DisplayList {
+    fn from(fragment_tree: &FragmentTree) -> Self {
+        let pages = fragment_tree
+            .pages
+            .iter()
+            .map(|page| {
+                let size = fragment_tree.boxes.borrow(page).map(|r#box| r#box.outer()).unwrap_or_default();
+                let mut painter = Painter { fragment_tree, items: Vec::new() };
+                painter.paint_page(page, &size);
+                DisplayPage { size, items: painter.items }
+            })
+            .collect();
+
+        Self { pages }
+    }
+}
+
+struct Painter<'a> {
+    fragment_tree: &'a FragmentTree,
+    items: Vec<DisplayItem>,
+}
+
+impl Painter<'_> {
+    /// Paints a page box.
+    ///
+    /// ```spec
+    /// The page background is painted first, over the whole page box, margins included,
+    /// then the page-margin boxes, then the contents of the page area.
+    /// ```
+    ///
+    /// Everything is clipped to the page.
+    fn paint_page(&mut self, page: &Fragment, size: &BoxContent<i32>) {
+        let tree = self.fragment_tree;
+        let bounds = Rect { x: 0, y: 0, width: size.width, height: size.height };
+        self.items.push(DisplayItem::PushClip(bounds));
+        self.paint_decorations_within(page, Some(bounds));
+
+        let (margin_boxes, contents): (Vec<_>, Vec<_>) = tree
+            .iter_children(page)
+            .partition(|child| tree.is(child, FragmentKind::MarginBox));
+
+        for fragment in margin_boxes.iter().chain(&contents) {
+            self.paint_stacking_context(fragment);
+        }
+
+        self.items.push(DisplayItem::PopClip);
+    }
+
+    /// Paints the fragment as the root of a stacking context.
+    ///
+    /// ```spec
+    /// 1. the background and borders of the element forming the stacking context.
+    /// 4. for all its in-flow, non-positioned, block-level descendants in tree order:
+    ///    the background and borders of the element.
+    /// 7. for all its in-flow, non-positioned, block-level descendants in tree order:
+    ///    for each line box of the element, for each box in the line, in tree order:
+    ///    the background and borders of inline boxes, then their text.
+    ///    Inline-block and inline-table elements are painted atomically, as if they generated a new stacking context.
+    /// ```
+    ///
+    /// As there are neither floats, nor positioned boxes, the other steps paint nothing.
+    fn paint_stacking_context(&mut self, fragment: &Fragment) {
+        self.paint_decorations(fragment);
+        self.paint_block_decorations(fragment);
+        self.paint_inline_content(fragment);
+    }
+
+    /// Paints the backgrounds and borders of the block-level descendants, in tree order.
+    fn paint_block_decorations(&mut self, fragment: &Fragment) {
+        let tree = self.fragment_tree;
+
+        for child in tree.iter_children(fragment) {
+            if tree.kind(child).is_block_level() {
+                self.paint_decorations(&child);
+                self.paint_block_decorations(&child);
+            }
+        }
+    }
+
+    /// Paints the content of the line boxes of the block-level descendants, in tree order.
+    fn paint_inline_content(&mut self, fragment: &Fragment) {
+        let tree = self.fragment_tree;
+
+        for child in tree.iter_children(fragment) {
+            match tree.kind(child) {
+                FragmentKind::LineBox | FragmentKind::InlineBox => {
+                    if tree.is(child, FragmentKind::InlineBox) {
+                        self.paint_decorations(&child);
+                    }
+
+                    self.paint_inline_content(&child);
+                },
+                FragmentKind::TextSequence => self.paint_text(&child),
+                FragmentKind::AtomicInline => self.paint_stacking_context(&child),
+                kind if kind.is_block_level() => self.paint_inline_content(&child),
+                _ => {},
+            }
+        }
+    }
+
+    fn is_visible(&self, fragment: &Fragment) -> bool {
+        self.fragment_tree
+            .style
+            .computed
+            .borrow(fragment)
+            .is_some_and(|props| matches!(props.visibility, Visibility::Visible))
+    }
+
+    /// Paints the background of the fragment, then its borders.
+    ///
+    /// ```spec
+    /// The background is painted within the border box, underneath the border.
+    /// ```
+    fn paint_decorations(&mut self, fragment: &Fragment) {
+        self.paint_decorations_within(fragment, None);
+    }
+
+    /// Paints the background of the fragment within the given area, or its border box, then its borders.
+    fn paint_decorations_within(&mut self, fragment: &Fragment, background_area: Option<Rect>) {
+        if !self.is_visible(fragment) {
+            return;
+        }
+
+        let tree = self.fragment_tree;
+        let Some(props) = tree.style.computed.borrow(fragment).map(|props| props.clone()) else {
+            return;
+        };
+
+        let r#box = tree.boxes.borrow(fragment).map(|r#box| r#box.clone()).unwrap_or_default();
+        let origin = page_offset(tree, fragment);
+        let border = &r#box.border;
+        let width = r#box.content.width + r#box.padding.left + r#box.padding.right + border.left + border.right;
+        let height = r#box.content.height + r#box.padding.top + r#box.padding.bottom + border.top + border.bottom;
+        let (x, y) = (origin.x, origin.y);
+
+        self.fill(background_area.unwrap_or(Rect { x, y, width, height }), props.background_color);
+        self.fill(Rect { x, y, width, height: border.top }, props.border_top_color);
+        self.fill(Rect { x, y: y + height - border.bottom, width, height: border.bottom }, props.border_bottom_color);
+        self.fill(Rect { x, y: y + border.top, width: border.left, height: height - border.top - border.bottom }, props.border_left_color);
+        self.fill(
+            Rect { x: x + width - border.right, y: y + border.top, width: border.right, height: height - border.top - border.bottom },
+            props.border_right_color,
+        );
+    }
+
+    /// Fills the rectangle, unless it is empty or transparent.
+    fn fill(&mut self, rect: Rect, color: Srgba) {
+        if !rect.is_empty() && color.alpha > 0.0 {
+            self.items.push(DisplayItem::Rect { rect, color });
+        }
+    }
+
+    /// Paints the glyphs of a text sequence, whose baseline is below the top of its content box by the ascent of its fonts.
+    fn paint_text(&mut self, fragment: &Fragment) {
+        let tree = self.fragment_tree;
+        let Some(runs) = tree.glyph_runs.borrow(fragment).map(|runs| runs.clone()).filter(|runs| !runs.is_empty()) else {
+            return;
+        };
+
+        if !self.is_visible(fragment) {
+            return;
+        }
+
+        let r#box = tree.boxes.borrow(fragment).map(|r#box| r#box.clone()).unwrap_or_default();
+        let origin = page_offset(tree, fragment);
+        let ascent = runs.iter().map(|run| run.ascent).max().unwrap_or_default();
+
+        self.items.push(DisplayItem::Text {
+            origin: Point {
+                x: origin.x + r#box.border.left + r#box.padding.left,
+                y: origin.y + r#box.border.top + r#box.padding.top + ascent,
+            },
+            text: tree.text_sequences.borrow(fragment).map(|seq| seq.as_str().to_owned()).unwrap_or_default(),
+            runs,
+            color: tree.style.computed.borrow(fragment).map(|props| props.color).unwrap_or_default(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        dom::{DocumentObjectModel, Element},
+        layout::px_to_units,
+        style::values::color::Srgba,
+        testing::lay_out,
+    };
+
+    use super::{DisplayItem, DisplayList, Rect};
+
+    #[test]
+    fn test_painting_order() {
+        let mut dom = DocumentObjectModel::default();
+        let html = dom.insert_element(Element::new("html"), None);
+        let outer = dom.insert_element(Element::new("div"), Some(html));
+        let p = dom.insert_element(Element::new("p"), Some(outer));
+        dom.insert_text("Hello", Some(p));
+        dom.insert_element(Element::new("div").with_attribute("id", "after"), Some(html));
+
+        let (ctx, _) = lay_out(&dom, r#"
+            html, div, p { display: block }
+            @page { size: 400px 300px; margin: 10px; background-color: white }
+            div { background-color: red; padding: 5px }
+            p { background-color: lime; border-left: 4px solid blue }
+            #after { height: 20px; background-color: yellow }
+        "#);
+
+        let display_list = DisplayList::from(&ctx.fragments);
+        assert_eq!(display_list.pages.len(), 1);
+
+        let page = &display_list.pages[0];
+        assert_eq!((page.size.width, page.size.height), (px_to_units(400.0), px_to_units(300.0)));
+
+        let colors = page
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                DisplayItem::Rect { color, .. } => Some(color.to_rgba8()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        // The page background, then the block backgrounds and borders in tree order.
+        let [white, red, lime, blue, yellow] = [Srgba::WHITE, Srgba::new(1.0, 0.0, 0.0, 1.0), Srgba::new(0.0, 1.0, 0.0, 1.0), Srgba::new(0.0, 0.0, 1.0, 1.0), Srgba::new(1.0, 1.0, 0.0, 1.0)]
+            .map(|color| color.to_rgba8());
+        assert_eq!(colors, [white, red, lime, blue, yellow]);
+
+        // The page background fills the page, and the paragraph is offset by the page margin and the padding of its parent.
+        let rects = page
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                DisplayItem::Rect { rect, .. } => Some(*rect),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(rects[0], Rect { x: 0, y: 0, width: px_to_units(400.0), height: px_to_units(300.0) });
+        assert_eq!((rects[2].x, rects[2].y), (px_to_units(15.0), px_to_units(15.0)));
+
+        // The text comes after all the block backgrounds, and the page is clipped.
+        assert!(matches!(page.items.first(), Some(DisplayItem::PushClip(_))));
+        assert!(matches!(page.items.last(), Some(DisplayItem::PopClip)));
+
+        let text = page.items.iter().position(|item| matches!(item, DisplayItem::Text { .. })).unwrap();
+        assert!(text > page.items.iter().rposition(|item| matches!(item, DisplayItem::Rect { .. })).unwrap());
+    }
+}
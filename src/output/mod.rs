@@ -0,0 +1,31 @@
+//! Writes the pages of the laid out fragment tree into documents.
+use crate::{
+    ecs::systems::tree::TreeExplorer,
+    layout::fragment_tree::{Fragment, FragmentTree},
+    style::values::r#box::Point,
+};
+
+pub mod display_list;
+pub mod pdf;
+pub mod raster;
+pub mod svg;
+
+/// The number of points in a CSS pixel.
+pub const PT_PER_PX: f32 = 0.75;
+
+/// Returns the origin of the border box of the fragment, in layout units, from the top left corner of its page.
+pub fn page_offset(fragment_tree: &FragmentTree, fragment: &Fragment) -> Point<i32> {
+    let mut offset = fragment_tree.positions.borrow(fragment).map(|position| *position).unwrap_or_default();
+    let mut current = *fragment;
+
+    while let Some(parent) = fragment_tree.parent(current) {
+        let position = fragment_tree.positions.borrow(&parent).map(|position| *position).unwrap_or_default();
+        let r#box = fragment_tree.boxes.borrow(&parent).map(|r#box| r#box.clone()).unwrap_or_default();
+
+        offset.x += position.x + r#box.border.left + r#box.padding.left;
+        offset.y += position.y + r#box.border.top + r#box.padding.top;
+        current = parent;
+    }
+
+    offset
+}
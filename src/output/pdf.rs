@@ -0,0 +1,409 @@
+//! Writes the pages of the fragment tree into a [PDF](https://opensource.adobe.com/dc-acrobat-sdk-docs/pdfstandards/PDF32000_2008.pdf) document,
+//! with [pdf-writer](https://github.com/typst/pdf-writer).
+//!
+//! The rectangles of the display list are filled paths, and the glyphs of its texts are shown
+//! with the faces which shaped them, embedded as subsetted CID-keyed fonts.
+use std::collections::{BTreeMap, HashMap};
+
+use pdf_writer::{
+    types::{CidFontType, FontFlags, SystemInfo},
+    Content, Finish, Name, Pdf, Rect, Ref, Str,
+};
+use rustybuzz::ttf_parser::{Face, GlyphId};
+
+use crate::{
+    font::SizedFont,
+    layout::{shaping::GlyphRun, units_to_px},
+    style::values::color::Srgba,
+};
+
+use super::{display_list::{DisplayItem, DisplayList}, PT_PER_PX};
+
+/// The registry, ordering and supplement of the character collection of Identity-H encoded fonts.
+const SYSTEM_INFO: SystemInfo = SystemInfo {
+    registry: Str(b"Adobe"),
+    ordering: Str(b"Identity"),
+    supplement: 0,
+};
+
+/// Writes the pages of a display list into a PDF document.
+///
+/// Each page is as large as the margin box of its page box.
+pub fn write_pdf(display_list: &DisplayList) -> Vec<u8> {
+    let mut writer = PdfWriter::new();
+    let catalog = writer.alloc();
+    let page_tree = writer.alloc();
+
+    let pages = display_list
+        .pages
+        .iter()
+        .map(|page| {
+            let mut content = Content::new();
+            let (width, height) = (px(page.size.width) * PT_PER_PX, px(page.size.height) * PT_PER_PX);
+
+            // Draw in CSS pixels, from the top left corner of the page.
+            content.transform([PT_PER_PX, 0.0, 0.0, -PT_PER_PX, 0.0, height]);
+            writer.paint(&page.items, &mut content);
+
+            (writer.alloc(), writer.alloc(), Rect::new(0.0, 0.0, width, height), content.finish())
+        })
+        .collect::<Vec<_>>();
+
+    writer.pdf.catalog(catalog).pages(page_tree);
+    writer
+        .pdf
+        .pages(page_tree)
+        .kids(pages.iter().map(|(id, ..)| *id))
+        .count(pages.len() as i32);
+
+    for (id, content_id, media_box, content) in &pages {
+        let mut page = writer.pdf.page(*id);
+        page.parent(page_tree).media_box(*media_box).contents(*content_id);
+
+        let mut resources = page.resources();
+        let mut fonts = resources.fonts();
+        for font in writer.fonts.values() {
+            fonts.pair(Name(font.name.as_bytes()), font.id);
+        }
+        fonts.finish();
+
+        let mut states = resources.ext_g_states();
+        for (name, id) in writer.alphas.values() {
+            states.pair(Name(name.as_bytes()), *id);
+        }
+        states.finish();
+        resources.finish();
+        page.finish();
+
+        writer.pdf.stream(*content_id, content);
+    }
+
+    writer.write_alphas();
+    writer.write_fonts();
+    writer.pdf.finish()
+}
+
+/// A face used by the text of the document, and the glyphs drawn with it.
+struct EmbeddedFont {
+    /// The name of the font in the resources of the pages.
+    name: String,
+    id: Ref,
+    font: SizedFont,
+    /// The glyphs, with the text they represent.
+    glyphs: BTreeMap<u16, String>,
+}
+
+struct PdfWriter {
+    pdf: Pdf,
+    next_id: i32,
+    /// The embedded fonts, by PostScript name and index in their collection.
+    fonts: HashMap<(usize, u32), EmbeddedFont>,
+    /// The graphics states setting the opacity of fills, by alpha byte.
+    alphas: BTreeMap<u8, (String, Ref)>,
+}
+
+impl PdfWriter {
+    fn new() -> Self {
+        Self { pdf: Pdf::new(), next_id: 0, fonts: HashMap::new(), alphas: BTreeMap::new() }
+    }
+
+    fn alloc(&mut self) -> Ref {
+        self.next_id += 1;
+        Ref::new(self.next_id)
+    }
+
+    /// Paints the items of a page, in order.
+    fn paint(&mut self, items: &[DisplayItem], content: &mut Content) {
+        for item in items {
+            match item {
+                DisplayItem::Rect { rect, color } => self.fill_rect(content, *color, [px(rect.x), px(rect.y), px(rect.width), px(rect.height)]),
+                DisplayItem::Text { origin, text, runs, color } => self.show_text(content, text, runs, *color, px(origin.x), px(origin.y)),
+                DisplayItem::PushClip(rect) => {
+                    content.save_state();
+                    content.rect(px(rect.x), px(rect.y), px(rect.width), px(rect.height)).clip_nonzero().end_path();
+                },
+                DisplayItem::PopClip => {
+                    content.restore_state();
+                },
+            }
+        }
+    }
+
+    /// Fills a rectangle, in CSS pixels, unless it is empty or transparent.
+    fn fill_rect(&mut self, content: &mut Content, color: Srgba, [x, y, width, height]: [f32; 4]) {
+        if width <= 0.0 || height <= 0.0 || color.alpha <= 0.0 {
+            return;
+        }
+
+        content.save_state();
+        self.set_fill_color(content, color);
+        content.rect(x, y, width, height).fill_nonzero();
+        content.restore_state();
+    }
+
+    fn set_fill_color(&mut self, content: &mut Content, color: Srgba) {
+        let [red, green, blue, alpha] = color.to_rgba8();
+        content.set_fill_rgb(red as f32 / 255.0, green as f32 / 255.0, blue as f32 / 255.0);
+
+        if alpha < u8::MAX {
+            let count = self.alphas.len();
+            let name = match self.alphas.get(&alpha) {
+                Some((name, _)) => name.clone(),
+                None => {
+                    let name = format!("GS{}", count + 1);
+                    let id = self.alloc();
+                    self.alphas.insert(alpha, (name.clone(), id));
+                    name
+                },
+            };
+
+            content.set_parameters(Name(name.as_bytes()));
+        }
+    }
+
+    /// Shows the glyph runs of a text sequence, from the pen position on its baseline.
+    fn show_text(&mut self, content: &mut Content, text: &str, runs: &[GlyphRun], color: Srgba, x: f32, baseline: f32) {
+        // The text of a glyph is the one of its cluster, which ends where the next one starts.
+        let mut clusters = runs.iter().flat_map(|run| run.glyphs.iter().map(|glyph| glyph.cluster)).collect::<Vec<_>>();
+        clusters.sort_unstable();
+        clusters.dedup();
+        let cluster_text = |cluster: usize| {
+            let end = clusters.iter().find(|other| **other > cluster).copied().unwrap_or(text.len());
+            text.get(cluster..end).unwrap_or_default().to_owned()
+        };
+
+        content.save_state();
+        self.set_fill_color(content, color);
+        content.begin_text();
+
+        let mut pen = x;
+        for run in runs {
+            let name = self.font_name(&run.font);
+            content.set_font(Name(name.as_bytes()), run.font.pixels());
+
+            for glyph in &run.glyphs {
+                let glyph_x = pen + px(glyph.offset.x);
+                let glyph_y = baseline - px(glyph.offset.y);
+
+                // Flip the text space back, as the page is drawn from its top.
+                content.set_text_matrix([1.0, 0.0, 0.0, -1.0, glyph_x, glyph_y]);
+                content.show(Str(&glyph.id.to_be_bytes()));
+
+                if let Some(font) = self.fonts.get_mut(&font_key(&run.font)) {
+                    let represented = font.glyphs.entry(glyph.id).or_default();
+                    if represented.is_empty() {
+                        *represented = cluster_text(glyph.cluster);
+                    }
+                }
+
+                pen += px(glyph.advance.x);
+            }
+        }
+
+        content.end_text();
+        content.restore_state();
+    }
+
+    /// Returns the name of the embedded font for the face.
+    fn font_name(&mut self, font: &SizedFont) -> String {
+        let key = font_key(font);
+
+        if let Some(embedded) = self.fonts.get(&key) {
+            return embedded.name.clone();
+        }
+
+        let name = format!("F{}", self.fonts.len() + 1);
+        let id = self.alloc();
+        self.fonts.insert(key, EmbeddedFont { name: name.clone(), id, font: font.clone(), glyphs: BTreeMap::new() });
+        name
+    }
+
+    fn write_alphas(&mut self) {
+        let alphas = self.alphas.iter().map(|(alpha, (_, id))| (*alpha, *id)).collect::<Vec<_>>();
+
+        for (alpha, id) in alphas {
+            self.pdf.ext_graphics(id).non_stroking_alpha(alpha as f32 / 255.0);
+        }
+    }
+
+    /// Embeds the fonts, each as a Type 0 font with a single CID-keyed descendant,
+    /// whose character codes are the glyph ids of the face.
+    fn write_fonts(&mut self) {
+        let fonts = std::mem::take(&mut self.fonts);
+        let mut fonts = fonts.into_values().collect::<Vec<_>>();
+        fonts.sort_by_key(|font| font.id);
+
+        for (index, font) in fonts.iter().enumerate() {
+            let Some(face) = Face::parse(font.font.data(), font.font.index()).ok() else {
+                self.pdf.type0_font(font.id).base_font(Name(b"Unknown")).encoding_predefined(Name(b"Identity-H"));
+                continue;
+            };
+
+            let cid_font = self.alloc();
+            let descriptor = self.alloc();
+            let cmap = self.alloc();
+            let file = self.alloc();
+
+            // ```spec
+            // For a font subset, the PostScript name of the font shall begin with a tag
+            // consisting of exactly six uppercase letters, followed by a plus sign.
+            // ```
+            let postscript_name = font.font.font().postscript_name().unwrap_or_else(|| String::from("Font"));
+            let tag = (0..6).map(|digit| (b'A' + ((index / 26usize.pow(digit)) % 26) as u8) as char).collect::<String>();
+            let base_font = format!("{tag}+{postscript_name}");
+
+            let glyphs = font.glyphs.keys().copied().collect::<Vec<_>>();
+            let is_cff = face.tables().cff.is_some();
+            let data = subsetter::subset(font.font.data(), font.font.index(), subsetter::Profile::pdf(&glyphs))
+                .unwrap_or_else(|_| font.font.data().to_vec());
+
+            let scale = 1000.0 / face.units_per_em() as f32;
+            let scaled = |value: i16| value as f32 * scale;
+
+            self.pdf
+                .type0_font(font.id)
+                .base_font(Name(base_font.as_bytes()))
+                .encoding_predefined(Name(b"Identity-H"))
+                .descendant_font(cid_font)
+                .to_unicode(cmap);
+
+            let mut cid = self.pdf.cid_font(cid_font);
+            cid.subtype(if is_cff { CidFontType::Type0 } else { CidFontType::Type2 })
+                .base_font(Name(base_font.as_bytes()))
+                .system_info(SYSTEM_INFO)
+                .font_descriptor(descriptor)
+                .default_width(0.0);
+
+            if !is_cff {
+                cid.cid_to_gid_map_predefined(Name(b"Identity"));
+            }
+
+            let mut widths = cid.widths();
+            for glyph in &glyphs {
+                let advance = face.glyph_hor_advance(GlyphId(*glyph)).unwrap_or_default();
+                widths.consecutive(*glyph, [advance as f32 * scale]);
+            }
+            widths.finish();
+            cid.finish();
+
+            let bbox = face.global_bounding_box();
+            let mut flags = FontFlags::SYMBOLIC;
+            flags.set(FontFlags::ITALIC, face.is_italic());
+            flags.set(FontFlags::FIXED_PITCH, face.is_monospaced());
+
+            let mut font_descriptor = self.pdf.font_descriptor(descriptor);
+            font_descriptor
+                .name(Name(base_font.as_bytes()))
+                .flags(flags)
+                .bbox(Rect::new(scaled(bbox.x_min), scaled(bbox.y_min), scaled(bbox.x_max), scaled(bbox.y_max)))
+                .italic_angle(face.italic_angle())
+                .ascent(scaled(face.ascender()))
+                .descent(scaled(face.descender()))
+                .cap_height(scaled(face.capital_height().unwrap_or(face.ascender())))
+                .stem_v(80.0);
+
+            if is_cff {
+                font_descriptor.font_file3(file);
+            } else {
+                font_descriptor.font_file2(file);
+            }
+            font_descriptor.finish();
+
+            let mut unicode_cmap = pdf_writer::types::UnicodeCmap::new(Name(b"Custom"), SYSTEM_INFO);
+            for (glyph, text) in &font.glyphs {
+                if !text.is_empty() {
+                    unicode_cmap.pair_with_multiple(*glyph, text.chars());
+                }
+            }
+            self.pdf.cmap(cmap, &unicode_cmap.finish());
+
+            let mut stream = self.pdf.stream(file, &data);
+            if is_cff {
+                stream.pair(Name(b"Subtype"), Name(b"OpenType"));
+            }
+            stream.finish();
+        }
+    }
+}
+
+/// Converts layout units into CSS pixels.
+fn px(units: i32) -> f32 {
+    units_to_px(units) as f32
+}
+
+/// Identifies a face, whatever its size.
+///
+/// The embedded font holds the face, so that its data outlives the key.
+fn font_key(font: &SizedFont) -> (usize, u32) {
+    font.face_id()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        dom::{DocumentObjectModel, Element},
+        testing::lay_out,
+    };
+
+    use crate::output::display_list::DisplayList;
+
+    use super::write_pdf;
+
+    #[test]
+    fn test_write_pdf() {
+        let mut dom = DocumentObjectModel::default();
+        let html = dom.insert_element(Element::new("html"), None);
+        let p = dom.insert_element(Element::new("p"), Some(html));
+        dom.insert_text("Hello", Some(p));
+        for _ in 0..3 {
+            dom.insert_element(Element::new("div"), Some(html));
+        }
+
+        let (ctx, _) = lay_out(&dom, r#"
+            html, p, div { display: block }
+            @page { size: 400px 300px; margin: 20px }
+            p { background-color: rgb(255 0 0 / 50%); border-top: 2px solid blue }
+            div { height: 200px }
+        "#);
+
+        let pdf = write_pdf(&DisplayList::from(&ctx.fragments));
+        let text = String::from_utf8_lossy(&pdf);
+
+        assert!(pdf.starts_with(b"%PDF-"));
+        assert!(text.trim_end().ends_with("%%EOF"));
+
+        // Each div is on its own page, each as large as the page box, in points.
+        assert_eq!(ctx.fragments.pages.len(), 3);
+        assert!(text.contains("/Count 3"));
+        assert_eq!(text.matches("/MediaBox [0 0 300 225]").count(), 3);
+
+        // The translucent background sets the opacity of the fill.
+        assert!(text.contains("/ca 0.5"));
+
+        // The glyphs are drawn with an embedded subset of their face, whose name is tagged.
+        assert!(text.contains("/Type0"));
+        assert!(text.contains("/Identity-H"));
+        assert!(text.contains("/BaseFont /AAAAAA+OleronTest"));
+        assert!(text.contains("/FontFile2"));
+    }
+
+    #[test]
+    fn test_embed_face_once() {
+        let mut dom = DocumentObjectModel::default();
+        let html = dom.insert_element(Element::new("html"), None);
+        let p = dom.insert_element(Element::new("p"), Some(html));
+        dom.insert_text("Hello ", Some(p));
+        let span = dom.insert_element(Element::new("span"), Some(p));
+        dom.insert_text("world", Some(span));
+
+        let (ctx, _) = lay_out(&dom, r#"
+            html, p { display: block }
+            span { font-size: 30px }
+        "#);
+
+        // The face is embedded once, whatever its sizes.
+        let pdf = write_pdf(&DisplayList::from(&ctx.fragments));
+        let text = String::from_utf8_lossy(&pdf);
+        assert_eq!(text.matches("/Type0").count(), 1);
+    }
+}
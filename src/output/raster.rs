@@ -0,0 +1,280 @@
+//! Rasterizes the pages of a display list on the CPU, with [tiny-skia](https://github.com/RazrFalcon/tiny-skia),
+//! and encodes them as PNG images.
+//!
+//! The glyphs are filled from the outlines of the faces which shaped them.
+use rustybuzz::ttf_parser::{GlyphId, OutlineBuilder};
+use tiny_skia::{Color, FillRule, Mask, MaskType, Paint, Path, PathBuilder, Pixmap, Rect, Transform};
+
+use crate::{
+    layout::{shaping::GlyphRun, units_to_px},
+    style::values::{color::Srgba, r#box::Point},
+};
+
+use super::display_list::{self, DisplayItem, DisplayPage};
+
+/// The resolution of CSS pixels, in dots per inch.
+pub const CSS_DPI: f32 = 96.0;
+
+/// Rasterizes a page of a display list, on a white background, at the given resolution.
+///
+/// A CSS pixel is a pixel of the image at 96 DPI. Returns nothing if the image would be empty, or too large.
+pub fn rasterize(page: &DisplayPage, dpi: f32) -> Option<Pixmap> {
+    let scale = dpi / CSS_DPI;
+    let width = (units_to_px(page.size.width) as f32 * scale).ceil() as u32;
+    let height = (units_to_px(page.size.height) as f32 * scale).ceil() as u32;
+
+    let mut rasterizer = Rasterizer {
+        pixmap: Pixmap::new(width, height)?,
+        transform: Transform::from_scale(scale, scale),
+        clips: Vec::new(),
+    };
+
+    rasterizer.pixmap.fill(Color::WHITE);
+
+    for item in &page.items {
+        match item {
+            DisplayItem::Rect { rect, color } => rasterizer.fill_rect(rect, color),
+            DisplayItem::Text { origin, runs, color, .. } => rasterizer.fill_text(origin, runs, color),
+            DisplayItem::PushClip(rect) => rasterizer.push_clip(rect),
+            DisplayItem::PopClip => {
+                rasterizer.clips.pop();
+            },
+        }
+    }
+
+    Some(rasterizer.pixmap)
+}
+
+/// Rasterizes a page of a display list, and encodes it as a PNG image.
+pub fn write_png(page: &DisplayPage, dpi: f32) -> Option<Vec<u8>> {
+    rasterize(page, dpi)?.encode_png().ok()
+}
+
+struct Rasterizer {
+    pixmap: Pixmap,
+    /// Scales CSS pixels into pixels of the image.
+    transform: Transform,
+    /// The masks of the clips, each intersected with the previous ones.
+    clips: Vec<Mask>,
+}
+
+impl Rasterizer {
+    fn paint(color: &Srgba) -> Paint<'static> {
+        let [red, green, blue, alpha] = color.to_rgba8();
+        let mut paint = Paint::default();
+        paint.set_color_rgba8(red, green, blue, alpha);
+        paint.anti_alias = true;
+        paint
+    }
+
+    fn fill_rect(&mut self, rect: &display_list::Rect, color: &Srgba) {
+        // Filling the rectangle as a path keeps away from the anti-aliased hairlines of thin rectangles.
+        if let Some(rect) = to_rect(rect) {
+            self.fill_path(&PathBuilder::from_rect(rect), &Self::paint(color), self.transform);
+        }
+    }
+
+    /// Pushes the mask of the clip, even if the rectangle has a negative size and hides everything, so that each pop has its push.
+    fn push_clip(&mut self, rect: &display_list::Rect) {
+        let mask = match (self.clips.last(), to_rect(rect).map(PathBuilder::from_rect)) {
+            (Some(previous), Some(path)) => {
+                let mut mask = previous.clone();
+                mask.intersect_path(&path, FillRule::Winding, true, self.transform);
+                mask
+            },
+            (None, Some(path)) => {
+                let mut mask = self.empty_mask();
+                mask.fill_path(&path, FillRule::Winding, true, self.transform);
+                mask
+            },
+            (_, None) => self.empty_mask(),
+        };
+
+        self.clips.push(mask);
+    }
+
+    /// A mask of the size of the image, which hides everything.
+    fn empty_mask(&self) -> Mask {
+        let mut mask = Mask::from_pixmap(self.pixmap.as_ref(), MaskType::Alpha);
+        mask.clear();
+        mask
+    }
+
+    /// Fills the outlines of the glyphs, from the pen position on the baseline.
+    fn fill_text(&mut self, origin: &Point<i32>, runs: &[GlyphRun], color: &Srgba) {
+        let paint = Self::paint(color);
+        let mut pen = origin.x;
+
+        for run in runs {
+            let Some(face) = run.font.face() else {
+                pen += run.advance();
+                continue;
+            };
+
+            // Font units are scaled into CSS pixels, with the y axis pointing down.
+            let scale = run.font.pixels() / face.units_per_em() as f32;
+
+            for glyph in &run.glyphs {
+                let x = units_to_px(pen + glyph.offset.x) as f32;
+                let y = units_to_px(origin.y - glyph.offset.y) as f32;
+                let mut outline = Outline(PathBuilder::new());
+
+                if face.outline_glyph(GlyphId(glyph.id), &mut outline).is_some() {
+                    if let Some(path) = outline.0.finish() {
+                        let transform = self.transform.pre_concat(Transform::from_row(scale, 0.0, 0.0, -scale, x, y));
+                        self.fill_path(&path, &paint, transform);
+                    }
+                }
+
+                pen += glyph.advance.x;
+            }
+        }
+    }
+
+    fn fill_path(&mut self, path: &Path, paint: &Paint<'_>, transform: Transform) {
+        self.pixmap.fill_path(path, paint, FillRule::Winding, transform, self.clips.last());
+    }
+}
+
+fn to_rect(rect: &display_list::Rect) -> Option<Rect> {
+    Rect::from_xywh(
+        units_to_px(rect.x) as f32,
+        units_to_px(rect.y) as f32,
+        units_to_px(rect.width) as f32,
+        units_to_px(rect.height) as f32,
+    )
+}
+
+/// Builds the path of a glyph outline, in font units.
+struct Outline(PathBuilder);
+
+impl OutlineBuilder for Outline {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.0.move_to(x, y);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.0.line_to(x, y);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        self.0.quad_to(x1, y1, x, y);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        self.0.cubic_to(x1, y1, x2, y2, x, y);
+    }
+
+    fn close(&mut self) {
+        self.0.close();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tiny_skia::Pixmap;
+
+    use crate::{
+        dom::{DocumentObjectModel, Element},
+        ecs::systems::tree::TreeExplorer,
+        layout::{px_to_units, units_to_px},
+        output::{display_list::{DisplayItem, DisplayList, DisplayPage, Rect}, page_offset},
+        style::values::{color::Srgba, r#box::BoxContent},
+        testing::lay_out,
+    };
+
+    use super::{rasterize, write_png};
+
+    #[test]
+    fn test_rasterize() {
+        let mut dom = DocumentObjectModel::default();
+        let html = dom.insert_element(Element::new("html"), None);
+        let p = dom.insert_element(Element::new("p"), Some(html));
+        dom.insert_text("Hello", Some(p));
+        dom.insert_element(Element::new("div"), Some(html));
+
+        let (ctx, _) = lay_out(&dom, r#"
+            html, p, div { display: block }
+            @page { size: 100px 80px; margin: 10px }
+            p { margin: 0; color: black }
+            div { height: 20px; background-color: blue; border-top: 5px solid red }
+        "#);
+
+        let display_list = DisplayList::from(&ctx.fragments);
+        let page = &display_list.pages[0];
+        let root = ctx.fragments.first_child(ctx.fragments.pages[0]).unwrap();
+        let div = ctx.fragments.last_child(root).unwrap();
+        let top = units_to_px(page_offset(&ctx.fragments, &div).y) as u32;
+        let pixel = |pixmap: &Pixmap, x: u32, y: u32| {
+            let pixel = pixmap.pixel(x, y).unwrap();
+            [pixel.red(), pixel.green(), pixel.blue(), pixel.alpha()]
+        };
+
+        // A CSS pixel is a pixel at 96 DPI.
+        let pixmap = rasterize(page, 96.0).unwrap();
+        assert_eq!((pixmap.width(), pixmap.height()), (100, 80));
+        assert_eq!(pixel(&pixmap, 2, 2), [255, 255, 255, 255]);
+        assert_eq!(pixel(&pixmap, 50, top + 2), [255, 0, 0, 255]);
+        assert_eq!(pixel(&pixmap, 50, top + 15), [0, 0, 255, 255]);
+
+        // The text is drawn over the white page.
+        let inked = (10..90).flat_map(|x| (10..top).map(move |y| (x, y))).any(|(x, y)| pixel(&pixmap, x, y) != [255, 255, 255, 255]);
+        assert!(inked);
+
+        // Twice the resolution makes an image twice as large.
+        let pixmap = rasterize(page, 192.0).unwrap();
+        assert_eq!((pixmap.width(), pixmap.height()), (200, 160));
+        assert_eq!(pixel(&pixmap, 100, 2 * top + 20), [0, 0, 255, 255]);
+
+        let png = write_png(page, 96.0).unwrap();
+        assert!(png.starts_with(b"\x89PNG"));
+    }
+
+    #[test]
+    fn test_empty_clip() {
+        let size = px_to_units(10.0);
+        let rect = |width| Rect { x: 0, y: 0, width, height: size };
+        let fill = |color| DisplayItem::Rect { rect: rect(size), color };
+        let page = DisplayPage {
+            size: BoxContent { width: size, height: size },
+            items: vec![
+                DisplayItem::PushClip(rect(size / 2)),
+                DisplayItem::PushClip(rect(-size)),
+                fill(Srgba::new(0.0, 0.0, 1.0, 1.0)),
+                DisplayItem::PopClip,
+                fill(Srgba::BLACK),
+                DisplayItem::PopClip,
+            ],
+        };
+        let pixmap = rasterize(&page, 96.0).unwrap();
+        let pixel = |x| {
+            let pixel = pixmap.pixel(x, 5).unwrap();
+            [pixel.red(), pixel.green(), pixel.blue()]
+        };
+
+        // The clip of a negative rectangle is empty, it hides the blue rectangle, and its pop restores the clip of the left half.
+        assert_eq!(pixel(2), [0, 0, 0]);
+        assert_eq!(pixel(7), [255, 255, 255]);
+    }
+
+    #[test]
+    fn test_thin_rect() {
+        let mut dom = DocumentObjectModel::default();
+        let html = dom.insert_element(Element::new("html"), None);
+        let p = dom.insert_element(Element::new("p"), Some(html));
+        dom.insert_text("a ", Some(p));
+        let span = dom.insert_element(Element::new("span"), Some(p));
+        dom.insert_text("bordered text which wraps", Some(span));
+
+        let (ctx, _) = lay_out(&dom, r#"
+            html, p { display: block }
+            @page { size: 100px 200px; margin: 10px }
+            span { border: 1px solid }
+        "#);
+
+        // The borders of the wrapped span are one pixel wide, at fractional positions.
+        let display_list = DisplayList::from(&ctx.fragments);
+        let pixmap = rasterize(&display_list.pages[0], 96.0).unwrap();
+        assert_eq!((pixmap.width(), pixmap.height()), (100, 200));
+    }
+}
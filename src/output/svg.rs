@@ -0,0 +1,196 @@
+//! Writes each page of a display list into a standalone [SVG](https://www.w3.org/TR/SVG2/) document.
+//!
+//! The rectangles are `rect` elements, and the texts are `text` elements referencing their fonts by family,
+//! whose characters are placed at the positions of the glyphs which shaped them.
+use std::fmt::Write;
+
+use font_kit::properties::Style;
+
+use crate::{
+    layout::{shaping::GlyphRun, units_to_px},
+    style::values::{color::Srgba, r#box::Point},
+};
+
+use super::display_list::{DisplayItem, DisplayPage, Rect};
+
+/// Writes a page of a display list into an SVG document, as large as the page, in CSS pixels.
+pub fn write_svg(page: &DisplayPage) -> String {
+    let (width, height) = (number(page.size.width), number(page.size.height));
+    let mut svg = String::new();
+    let mut clips = 0;
+
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+    );
+
+    for item in &page.items {
+        match item {
+            DisplayItem::Rect { rect, color } => {
+                let _ = writeln!(svg, "<rect {}{}/>", rect_attributes(rect), fill(color));
+            },
+            DisplayItem::Text { origin, text, runs, color } => {
+                write_text(&mut svg, origin, text, runs, color);
+            },
+            DisplayItem::PushClip(rect) => {
+                clips += 1;
+                let _ = writeln!(svg, r#"<clipPath id="clip{clips}"><rect {}/></clipPath>"#, rect_attributes(rect));
+                let _ = writeln!(svg, r#"<g clip-path="url(#clip{clips})">"#);
+            },
+            DisplayItem::PopClip => {
+                svg.push_str("</g>\n");
+            },
+        }
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Writes a text element for each glyph run.
+///
+/// Each character of a cluster is placed after the previous one, by an equal part of the advance of the cluster.
+fn write_text(svg: &mut String, origin: &Point<i32>, text: &str, runs: &[GlyphRun], color: &Srgba) {
+    let mut clusters = runs.iter().flat_map(|run| run.glyphs.iter().map(|glyph| glyph.cluster)).collect::<Vec<_>>();
+    clusters.sort_unstable();
+    clusters.dedup();
+
+    let mut pen = origin.x;
+
+    for run in runs {
+        let mut characters = String::new();
+        let mut positions = Vec::new();
+        let mut glyphs = run.glyphs.iter().peekable();
+
+        while let Some(glyph) = glyphs.next() {
+            let x = pen + glyph.offset.x;
+            let mut advance = glyph.advance.x;
+            pen += glyph.advance.x;
+
+            // The glyphs following the first one of a cluster are drawn with it.
+            while glyphs.peek().is_some_and(|next| next.cluster == glyph.cluster) {
+                let next = glyphs.next().unwrap();
+                advance += next.advance.x;
+                pen += next.advance.x;
+            }
+
+            let end = clusters.iter().find(|other| **other > glyph.cluster).copied().unwrap_or(text.len());
+            let cluster = text.get(glyph.cluster..end).unwrap_or_default();
+            let count = cluster.chars().count().max(1) as i32;
+
+            for (index, character) in cluster.chars().enumerate() {
+                characters.push(character);
+                positions.push(number(x + advance * index as i32 / count));
+            }
+        }
+
+        if characters.trim().is_empty() {
+            continue;
+        }
+
+        let properties = run.font.font().properties();
+        let style = match properties.style {
+            Style::Normal => "normal",
+            Style::Italic => "italic",
+            Style::Oblique => "oblique",
+        };
+
+        let _ = writeln!(
+            svg,
+            r#"<text x="{}" y="{}" font-family="{}" font-size="{}" font-weight="{}" font-style="{style}" xml:space="preserve"{}>{}</text>"#,
+            positions.join(" "),
+            number(origin.y),
+            escape(&run.font.font().family_name()),
+            run.font.pixels(),
+            properties.weight.0,
+            fill(color),
+            escape(&characters),
+        );
+    }
+}
+
+fn rect_attributes(rect: &Rect) -> String {
+    format!(
+        r#"x="{}" y="{}" width="{}" height="{}""#,
+        number(rect.x),
+        number(rect.y),
+        number(rect.width),
+        number(rect.height)
+    )
+}
+
+/// The fill attributes of a color: its channels, and its opacity if it is translucent.
+fn fill(color: &Srgba) -> String {
+    let [red, green, blue, alpha] = color.to_rgba8();
+    let mut attributes = format!(r#" fill="rgb({red},{green},{blue})""#);
+
+    if alpha < u8::MAX {
+        let _ = write!(attributes, r#" fill-opacity="{}""#, (alpha as f64 / 255.0 * 1000.0).round() / 1000.0);
+    }
+
+    attributes
+}
+
+/// Formats a length in layout units as CSS pixels, without trailing zeros.
+fn number(units: i32) -> String {
+    let px = units_to_px(units);
+    let formatted = format!("{px:.3}");
+    formatted.trim_end_matches('0').trim_end_matches('.').to_owned()
+}
+
+/// Escapes the characters which cannot appear as such in attributes or text content.
+fn escape(text: &str) -> String {
+    text.chars().fold(String::with_capacity(text.len()), |mut escaped, character| {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            character => escaped.push(character),
+        }
+        escaped
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        dom::{DocumentObjectModel, Element},
+        output::display_list::DisplayList,
+        testing::lay_out,
+    };
+
+    use super::write_svg;
+
+    #[test]
+    fn test_write_svg() {
+        let mut dom = DocumentObjectModel::default();
+        let html = dom.insert_element(Element::new("html"), None);
+        let p = dom.insert_element(Element::new("p"), Some(html));
+        dom.insert_text("a < b & c", Some(p));
+
+        let (ctx, _) = lay_out(&dom, r#"
+            html, p { display: block }
+            @page { size: 200px 100px; margin: 10px }
+            p { margin: 0; background-color: rgb(0 0 255 / 50%); border-bottom: 2px solid red }
+        "#);
+
+        let display_list = DisplayList::from(&ctx.fragments);
+        let svg = write_svg(&display_list.pages[0]);
+
+        assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="100" viewBox="0 0 200 100">"#));
+        assert!(svg.trim_end().ends_with("</svg>"));
+
+        // The page is clipped, and the coordinates of the paragraph are offset by the page margin.
+        assert!(svg.contains(r#"<clipPath id="clip1"><rect x="0" y="0" width="200" height="100"/></clipPath>"#));
+        assert!(svg.contains(r#"<rect x="10" y="10" width="180" "#));
+        assert!(svg.contains(r#"fill="rgb(0,0,255)" fill-opacity="0.502""#));
+        assert!(svg.contains(r#"fill="rgb(255,0,0)"/>"#));
+
+        // The text is escaped.
+        assert!(svg.contains("<text"));
+        assert!(svg.contains("&lt;") && svg.contains("&amp;"));
+        assert!(!svg.contains(" < "));
+    }
+}
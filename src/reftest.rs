@@ -0,0 +1,172 @@
+//! Runs the reference tests of the `tests/reftests` directory.
+//!
+//! Each test is a document `name.html` along with its reference `name-ref.html`,
+//! whose linked style sheets are loaded from the same directory, and which may set their text in the font of the tests.
+//! Both are run through the whole pipeline, and a test passes if their rasterized pages are identical.
+use std::{
+    fmt::{self, Display},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use tiny_skia::Pixmap;
+
+use crate::{
+    html::HtmlDocument,
+    output::{display_list::DisplayList, raster::{rasterize, CSS_DPI}},
+    resource::FileLoader,
+    style::values::r#box::BoxContent,
+    testing::FONTS_DIRECTORY,
+    RenderingContext,
+};
+
+const REFERENCE_SUFFIX: &str = "-ref";
+
+/// A document and its reference.
+pub struct Reftest {
+    pub name: String,
+    pub test: PathBuf,
+    pub reference: PathBuf,
+}
+
+/// How the rendering of a test differs from the rendering of its reference.
+#[derive(Debug)]
+pub enum Difference {
+    /// The document could not be read, or rendered.
+    Error(String),
+    PageCount { test: usize, reference: usize },
+    PageSize { page: usize, test: (u32, u32), reference: (u32, u32) },
+    /// Some pixels of a page differ, within a bounding box given as `(left, top, right, bottom)`.
+    Pixels { page: usize, count: usize, max_channel: u8, bounds: (u32, u32, u32, u32) },
+}
+
+impl Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Error(error) => write!(f, "{error}"),
+            Self::PageCount { test, reference } => write!(f, "{test} pages, expected {reference}"),
+            Self::PageSize { page, test, reference } => {
+                write!(f, "page {page} is {}x{}px, expected {}x{}px", test.0, test.1, reference.0, reference.1)
+            },
+            Self::Pixels { page, count, max_channel, bounds: (left, top, right, bottom) } => write!(
+                f,
+                "page {page}: {count} pixels differ, by up to {max_channel}, between ({left}, {top}) and ({right}, {bottom})"
+            ),
+        }
+    }
+}
+
+/// Finds the tests of a directory, sorted by name: every document which has a reference.
+pub fn collect(directory: &Path) -> Vec<Reftest> {
+    let mut reftests = fs::read_dir(directory)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "html"))
+        .filter_map(|test| {
+            let name = test.file_stem()?.to_str()?.to_owned();
+            let reference = test.with_file_name(format!("{name}{REFERENCE_SUFFIX}.html"));
+            (!name.ends_with(REFERENCE_SUFFIX) && reference.exists()).then_some(Reftest { name, test, reference })
+        })
+        .collect::<Vec<_>>();
+
+    reftests.sort_by(|a, b| a.name.cmp(&b.name));
+    reftests
+}
+
+/// Renders a document into the images of its pages.
+pub fn render(path: &Path) -> Result<Vec<Pixmap>, String> {
+    let html = fs::read_to_string(path).map_err(|error| format!("{}: {error}", path.display()))?;
+    let document = HtmlDocument::parse(&html);
+
+    let mut ctx = RenderingContext::builder(&document.dom)
+        .viewport(BoxContent { width: 800.0, height: 600.0 })
+        .resource_loader(FileLoader::new(path.parent().unwrap_or(Path::new("."))))
+        .fonts_directory(FONTS_DIRECTORY)
+        .build();
+
+    let fragments = ctx.render().ok_or_else(|| format!("{}: the document has no root box", path.display()))?;
+
+    DisplayList::from(fragments)
+        .pages
+        .iter()
+        .enumerate()
+        .map(|(index, page)| rasterize(page, CSS_DPI).ok_or_else(|| format!("{}: page {} is empty", path.display(), index + 1)))
+        .collect()
+}
+
+/// Renders a test and its reference, and returns their differences, if any.
+pub fn run(reftest: &Reftest) -> Vec<Difference> {
+    let (test, reference) = match (render(&reftest.test), render(&reftest.reference)) {
+        (Ok(test), Ok(reference)) => (test, reference),
+        (test, reference) => return test.err().into_iter().chain(reference.err()).map(Difference::Error).collect(),
+    };
+
+    let mut differences = Vec::new();
+
+    if test.len() != reference.len() {
+        differences.push(Difference::PageCount { test: test.len(), reference: reference.len() });
+    }
+
+    for (index, (test, reference)) in test.iter().zip(&reference).enumerate() {
+        differences.extend(compare(index + 1, test, reference));
+    }
+
+    differences
+}
+
+/// Compares the pixels of a page of a test with the pixels of the same page of its reference.
+fn compare(page: usize, test: &Pixmap, reference: &Pixmap) -> Option<Difference> {
+    let (size, reference_size) = ((test.width(), test.height()), (reference.width(), reference.height()));
+
+    if size != reference_size {
+        return Some(Difference::PageSize { page, test: size, reference: reference_size });
+    }
+
+    let mut count = 0;
+    let mut max_channel = 0;
+    let mut bounds = (u32::MAX, u32::MAX, 0, 0);
+
+    for (index, (a, b)) in test.pixels().iter().zip(reference.pixels()).enumerate() {
+        if a == b {
+            continue;
+        }
+
+        let (x, y) = (index as u32 % test.width(), index as u32 / test.width());
+        let channels = [(a.red(), b.red()), (a.green(), b.green()), (a.blue(), b.blue()), (a.alpha(), b.alpha())];
+
+        count += 1;
+        max_channel = channels.iter().map(|(a, b)| a.abs_diff(*b)).fold(max_channel, u8::max);
+        bounds = (bounds.0.min(x), bounds.1.min(y), bounds.2.max(x), bounds.3.max(y));
+    }
+
+    (count > 0).then_some(Difference::Pixels { page, count, max_channel, bounds })
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use super::{collect, run};
+
+    #[test]
+    fn reftests() {
+        let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/reftests");
+        let reftests = collect(&directory);
+        assert!(!reftests.is_empty(), "no reftests in {}", directory.display());
+
+        let failures = reftests
+            .iter()
+            .filter_map(|reftest| {
+                let differences = run(reftest);
+                (!differences.is_empty()).then(|| {
+                    let report = differences.iter().map(|difference| format!("\n    {difference}")).collect::<String>();
+                    format!("{}:{report}", reftest.name)
+                })
+            })
+            .collect::<Vec<_>>();
+
+        assert!(failures.is_empty(), "{} of {} reftests failed:\n{}", failures.len(), reftests.len(), failures.join("\n"));
+    }
+}
@@ -0,0 +1,48 @@
+//! Loads the resources a document refers to, such as its linked style sheets.
+use std::{fs, path::PathBuf};
+
+/// Fetches the resources referenced by a document.
+pub trait ResourceLoader {
+    /// Returns the content of the resource at the URL, relative to the document, if it can be loaded.
+    fn load(&self, url: &str) -> Option<Vec<u8>>;
+}
+
+/// Loads the resources from the file system, relative to the directory of the document.
+///
+/// Only relative URLs, and `file:` URLs, are loaded.
+pub struct FileLoader {
+    base: PathBuf,
+}
+
+impl FileLoader {
+    pub fn new(base: impl Into<PathBuf>) -> Self {
+        Self { base: base.into() }
+    }
+}
+
+impl ResourceLoader for FileLoader {
+    fn load(&self, url: &str) -> Option<Vec<u8>> {
+        let path = match url.strip_prefix("file://") {
+            Some(path) => PathBuf::from(path),
+            None if url.contains(':') => return None,
+            None => self.base.join(url.split(['?', '#']).next().unwrap_or_default()),
+        };
+
+        fs::read(path).ok()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FileLoader, ResourceLoader};
+
+    #[test]
+    fn test_file_loader() {
+        let loader = FileLoader::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/reftests"));
+
+        assert!(loader.load("page-background.css").is_some_and(|css| css.starts_with(b"@page")));
+        assert!(loader.load("page-background.css?version=1").is_some());
+        assert!(loader.load("missing.css").is_none());
+        assert!(loader.load("https://example.com/page-background.css").is_none());
+    }
+}
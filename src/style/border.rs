@@ -1,41 +1,69 @@
-use std::ops::{Deref, DerefMut};
+//! Implements borders from [CSS BACKGROUNDS 3](https://www.w3.org/TR/css-backgrounds-3/#borders)
+use super::parser::parser::{ComponentValue, ParserError, ParserResult};
 
-use super::values::{numeric::AutoOrLengthOrPercentage, r#box::BoxEdges};
+/// <line-style> = none | hidden | dotted | dashed | solid | double | groove | ridge | inset | outset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineStyle {
+    #[default]
+    None,
+    Hidden,
+    Dotted,
+    Dashed,
+    Solid,
+    Double,
+    Groove,
+    Ridge,
+    Inset,
+    Outset,
+}
 
-#[derive(Clone)]
-pub struct Border(BoxEdges<AutoOrLengthOrPercentage>);
+impl TryFrom<&[ComponentValue<'_>]> for LineStyle {
+    type Error = ParserError;
 
-impl Deref for Border {
-    type Target = BoxEdges<AutoOrLengthOrPercentage>;
+    fn try_from(values: &[ComponentValue<'_>]) -> ParserResult<Self> {
+        let keyword = match values {
+            [value] => value.ident().map(str::to_ascii_lowercase),
+            _ => None
+        };
 
-    fn deref(&self) -> &Self::Target {
-        &self.0
+        match keyword.as_deref() {
+            Some("none") => Ok(Self::None),
+            Some("hidden") => Ok(Self::Hidden),
+            Some("dotted") => Ok(Self::Dotted),
+            Some("dashed") => Ok(Self::Dashed),
+            Some("solid") => Ok(Self::Solid),
+            Some("double") => Ok(Self::Double),
+            Some("groove") => Ok(Self::Groove),
+            Some("ridge") => Ok(Self::Ridge),
+            Some("inset") => Ok(Self::Inset),
+            Some("outset") => Ok(Self::Outset),
+            _ => Err(ParserError::UnexpectedToken(format!("{values:?}")))
+        }
     }
 }
 
-impl DerefMut for Border {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+impl LineStyle {
+    /// ```spec
+    /// If the border style is none or hidden, the used border width is 0.
+    /// ```
+    pub fn has_border(&self) -> bool {
+        !matches!(self, Self::None | Self::Hidden)
     }
 }
 
-impl Default for Border {
-    fn default() -> Self {
-        let zero = AutoOrLengthOrPercentage::zero();
-        Self(BoxEdges {
-            top: zero,
-            bottom: zero,
-            left: zero,
-            right: zero,
+impl std::fmt::Display for LineStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::None => "none",
+            Self::Hidden => "hidden",
+            Self::Dotted => "dotted",
+            Self::Dashed => "dashed",
+            Self::Solid => "solid",
+            Self::Double => "double",
+            Self::Groove => "groove",
+            Self::Ridge => "ridge",
+            Self::Inset => "inset",
+            Self::Outset => "outset",
         })
     }
 }
-
-pub mod initial {
-    pub use super::Border;
-}
-
-pub mod computed {
-    pub use super::Border;
-}
-
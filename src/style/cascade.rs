@@ -0,0 +1,425 @@
+//! Implements [CSS CASCADE 4](https://www.w3.org/TR/css-cascade-4/)
+use crate::dom::{TDocumentObjectModelExplorer, TElement};
+
+use super::{
+    parser::{lexer::TokenKind, parser::{
+        parse_list_of_declarations, parse_stylesheet, ComponentValue, Declaration,
+        DeclarationOrAtRule, Rule,
+    }},
+    page::{MarginBoxPosition, Page, PageRule},
+    properties::{PropertyDeclaration, PropertyId},
+    selector::{SelectorList, Specificity},
+    shorthands::parse_declaration,
+};
+
+/// The media type the style sheets target, unless another one is given.
+pub const PRINT_MEDIA: &str = "print";
+
+/// The default style sheet of the rendering engine.
+pub const USER_AGENT_STYLESHEET: &str = include_str!("ua.css");
+
+/// ```spec
+/// Each style rule has a cascade origin, which determines where it enters the cascade.
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Origin {
+    UserAgent,
+    User,
+    Author,
+}
+
+/// A declaration of a style rule.
+#[derive(Clone)]
+pub struct StyleDeclaration {
+    pub property: PropertyDeclaration,
+    pub important: bool,
+}
+
+impl StyleDeclaration {
+    /// Parse a list of declarations, invalid declarations are dropped.
+    ///
+    /// Shorthands are expanded into their longhands.
+    pub fn parse_list(declarations: &[Declaration<'_>]) -> Vec<Self> {
+        declarations
+            .iter()
+            .flat_map(|declaration| {
+                parse_declaration(declaration.name, &declaration.value)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|property| Self { property, important: declaration.important })
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone)]
+pub struct StyleRule {
+    pub selectors: SelectorList,
+    pub declarations: Vec<StyleDeclaration>,
+}
+
+#[derive(Clone)]
+pub struct StyleSheet {
+    pub origin: Origin,
+    pub rules: Vec<StyleRule>,
+    /// The @page rules, which style the page context instead of the elements.
+    pub page_rules: Vec<PageRule>,
+}
+
+impl StyleSheet {
+    /// Parse a style sheet.
+    ///
+    /// Rules with invalid selectors, and invalid declarations, are dropped.
+    pub fn parse(input: &str, origin: Origin) -> Self {
+        Self::parse_for_media(input, origin, PRINT_MEDIA)
+    }
+
+    /// Parse a style sheet, keeping the rules of the @media rules which target the given media type.
+    pub fn parse_for_media(input: &str, origin: Origin, media: &str) -> Self {
+        let stylesheet = parse_stylesheet(input);
+        let mut rules = Vec::default();
+        let mut page_rules = Vec::default();
+        collect_style_rules(stylesheet.rules, media, &mut rules, &mut page_rules);
+        Self { origin, rules, page_rules }
+    }
+
+    /// The user agent style sheet.
+    pub fn user_agent() -> Self {
+        Self::parse(USER_AGENT_STYLESHEET, Origin::UserAgent)
+    }
+}
+
+fn collect_style_rules(rules: Vec<Rule<'_>>, media: &str, style_rules: &mut Vec<StyleRule>, page_rules: &mut Vec<PageRule>) {
+    for rule in rules {
+        match rule {
+            Rule::Qualified(rule) => {
+                if let Ok(selectors) = SelectorList::parse(&rule.prelude) {
+                    let declarations = StyleDeclaration::parse_list(&rule.declarations());
+                    style_rules.push(StyleRule { selectors, declarations });
+                }
+            },
+            Rule::At(rule) if rule.name.eq_ignore_ascii_case("media") => {
+                if matches_media(&rule.prelude, media) {
+                    collect_style_rules(rule.rules(), media, style_rules, page_rules);
+                }
+            },
+            Rule::At(rule) if rule.name.eq_ignore_ascii_case("page") => {
+                if let Ok(page_rule) = PageRule::try_from(&rule) {
+                    page_rules.push(page_rule);
+                }
+            },
+            // Other at-rules are not supported.
+            Rule::At(_) => {}
+        }
+    }
+}
+
+/// Checks if any of the media queries targets the given media type.
+///
+/// Only media types are evaluated, media features are ignored.
+fn matches_media(prelude: &[ComponentValue<'_>], target: &str) -> bool {
+    let is_target = |media: Option<&str>| {
+        media.map(|media| media.eq_ignore_ascii_case(target) || media.eq_ignore_ascii_case("all"))
+    };
+
+    prelude
+        .split(|value| value.is(TokenKind::Comma))
+        .any(|query| {
+            let mut idents = query.iter().filter_map(ComponentValue::ident);
+            match idents.next() {
+                Some(ident) if ident.eq_ignore_ascii_case("not") => !is_target(idents.next()).unwrap_or(true),
+                Some(ident) if ident.eq_ignore_ascii_case("only") => is_target(idents.next()).unwrap_or_default(),
+                Some(media) => is_target(Some(media)).unwrap_or_default(),
+                // Only media features
+                None => true
+            }
+        })
+}
+
+/// ```spec
+/// The cascade sorts declarations according to the following criteria, in descending order of precedence:
+/// - Origin and Importance
+/// - Element-Attached Styles
+/// - Specificity
+/// - Order of Appearance
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct CascadeKey {
+    precedence: u8,
+    element_attached: bool,
+    specificity: Specificity,
+    order: usize,
+}
+
+impl CascadeKey {
+    /// ```spec
+    /// Declarations from origins earlier in this list win over declarations from later origins.
+    /// 1. Transition declarations
+    /// 2. Important user agent declarations
+    /// 3. Important user declarations
+    /// 4. Important author declarations
+    /// 5. Animation declarations
+    /// 6. Normal author declarations
+    /// 7. Normal user declarations
+    /// 8. Normal user agent declarations
+    /// ```
+    fn precedence(origin: Origin, important: bool) -> u8 {
+        match (important, origin) {
+            (false, Origin::UserAgent) => 0,
+            (false, Origin::User) => 1,
+            (false, Origin::Author) => 2,
+            (true, Origin::Author) => 3,
+            (true, Origin::User) => 4,
+            (true, Origin::UserAgent) => 5,
+        }
+    }
+}
+
+/// Returns the cascaded values of the element, the winning declaration of each property.
+///
+/// ```spec
+/// If the cascaded value of a property is the revert keyword,
+/// the behavior depends on the cascade origin to which the declaration belongs:
+/// - user-agent origin: equivalent to unset.
+/// - user origin: rolls back the cascaded value to the user-agent level.
+/// - author origin: rolls back the cascaded value to the user level.
+/// ```
+pub fn cascade<Dom>(dom: &Dom, stylesheets: &[StyleSheet], element: &Dom::NodeId) -> Vec<PropertyDeclaration>
+where Dom: TDocumentObjectModelExplorer
+{
+    let mut declarations = Vec::<(CascadeKey, Origin, &PropertyDeclaration)>::default();
+    let mut order = 0;
+
+    for stylesheet in stylesheets {
+        for rule in stylesheet.rules.iter() {
+            order += 1;
+
+            // The specificity of the most specific selector that matches the element.
+            let Some(specificity) = rule.selectors.0
+                .iter()
+                .filter(|selector| selector.matches(dom, element))
+                .map(|selector| selector.specificity())
+                .max()
+            else {
+                continue;
+            };
+
+            for declaration in rule.declarations.iter() {
+                let key = CascadeKey {
+                    precedence: CascadeKey::precedence(stylesheet.origin, declaration.important),
+                    element_attached: false,
+                    specificity,
+                    order
+                };
+                declarations.push((key, stylesheet.origin, &declaration.property));
+            }
+        }
+    }
+
+    let style_attribute = dom
+        .borrow_element(element)
+        .and_then(|el| el.attribute("style").map(str::to_owned))
+        .unwrap_or_default();
+
+    let attached = parse_list_of_declarations(&style_attribute)
+        .map(|declarations| {
+            declarations
+                .into_iter()
+                .filter_map(|declaration| match declaration {
+                    DeclarationOrAtRule::Declaration(declaration) => Some(declaration),
+                    DeclarationOrAtRule::AtRule(_) => None,
+                })
+                .collect::<Vec<_>>()
+        })
+        .map(|declarations| StyleDeclaration::parse_list(&declarations))
+        .unwrap_or_default();
+
+    for declaration in attached.iter() {
+        let key = CascadeKey {
+            precedence: CascadeKey::precedence(Origin::Author, declaration.important),
+            element_attached: true,
+            specificity: Specificity::default(),
+            order: 0
+        };
+        declarations.push((key, Origin::Author, &declaration.property));
+    }
+
+    cascaded_values(declarations)
+}
+
+/// Returns the cascaded values of a page context, or of one of its page-margin boxes,
+/// from the @page rules matching the page.
+///
+/// ```spec
+/// Declarations in @page rules are sorted by the specificity of the page selectors, then by order of appearance.
+/// The declarations of a margin box are the ones of its margin rules, within the @page rules matching the page.
+/// ```
+pub fn cascade_page(page_rules: &[(Origin, PageRule)], page: &Page<'_>, margin_box: Option<MarginBoxPosition>) -> Vec<PropertyDeclaration> {
+    let mut declarations = Vec::<(CascadeKey, Origin, &PropertyDeclaration)>::default();
+
+    for (order, (origin, rule)) in page_rules.iter().enumerate() {
+        let Some(specificity) = rule.selectors.matches(page) else {
+            continue;
+        };
+
+        let rule_declarations = match margin_box {
+            Some(position) => rule
+                .margin_rules
+                .iter()
+                .filter(|margin_rule| margin_rule.position == position)
+                .flat_map(|margin_rule| margin_rule.declarations.iter())
+                .collect::<Vec<_>>(),
+            None => rule.declarations.iter().collect(),
+        };
+
+        for declaration in rule_declarations {
+            let key = CascadeKey {
+                precedence: CascadeKey::precedence(*origin, declaration.important),
+                element_attached: false,
+                specificity,
+                order,
+            };
+            declarations.push((key, *origin, &declaration.property));
+        }
+    }
+
+    cascaded_values(declarations)
+}
+
+/// Returns the winning declaration of each property.
+fn cascaded_values(mut declarations: Vec<(CascadeKey, Origin, &PropertyDeclaration)>) -> Vec<PropertyDeclaration> {
+    // Walks the declarations from the winning one, down to the least important one.
+    declarations.sort_by_key(|(key, _, _)| std::cmp::Reverse(*key));
+
+    let mut cascaded = Vec::<PropertyDeclaration>::default();
+    let mut reverted = Vec::<(PropertyId, Origin)>::default();
+
+    for (_, origin, declaration) in declarations {
+        let id = declaration.id();
+
+        if cascaded.iter().any(|winner| winner.id() == id) {
+            continue;
+        }
+
+        // The origin has been rolled back.
+        if reverted.iter().any(|(reverted_id, reverted_origin)| *reverted_id == id && origin >= *reverted_origin) {
+            continue;
+        }
+
+        if declaration.is_revert() && origin != Origin::UserAgent {
+            reverted.push((id, origin));
+            continue;
+        }
+
+        cascaded.push(declaration.clone());
+    }
+
+    cascaded
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        dom::{DocumentObjectModel, Element},
+        ecs::systems::tree::TreeExplorer,
+        style::{
+            display::Display, properties::specified_values, style,
+            values::{numeric::AutoOrLengthOrPercentage, r#box::BoxContent},
+            visibility::Visibility, Styles,
+        },
+    };
+
+    use super::{cascade, Origin, StyleSheet};
+
+    fn px(value: &AutoOrLengthOrPercentage) -> Option<f64> {
+        match value {
+            AutoOrLengthOrPercentage::Length(length) => Some(length.value),
+            _ => None
+        }
+    }
+
+    #[test]
+    fn test_cascade_order() {
+        let mut dom = DocumentObjectModel::default();
+        let html = dom.insert_element(Element::new("html"), None);
+        let p = dom.insert_element(
+            Element::new("p")
+                .with_attribute("id", "intro")
+                .with_attribute("style", "margin-top: 4px; margin-bottom: 4px"),
+            Some(html)
+        );
+
+        let user_agent = StyleSheet::parse("p { display: block; margin-left: 1px !important }", Origin::UserAgent);
+        let author = StyleSheet::parse(r#"
+            #intro { margin-top: 1px }
+            p { margin-top: 2px !important; margin-bottom: 2px; margin-left: 2px !important; display: invalid }
+            p { margin-right: 1px }
+            html p { margin-right: 2px }
+            p { margin-right: 3px }
+            @media screen { p { display: none } }
+        "#, Origin::Author);
+
+        let properties = specified_values(&cascade(&dom, &[user_agent, author], &p), None);
+
+        assert!(properties.display == Display::block());
+        // Important author declarations win over element-attached styles.
+        assert_eq!(px(&properties.margin_top), Some(2.0));
+        // Element-attached styles win over any selector.
+        assert_eq!(px(&properties.margin_bottom), Some(4.0));
+        // Important user agent declarations win over important author declarations.
+        assert_eq!(px(&properties.margin_left), Some(1.0));
+        // Specificity, then order of appearance.
+        assert_eq!(px(&properties.margin_right), Some(2.0));
+
+        assert!(!StyleSheet::user_agent().rules.is_empty());
+
+        // The rules of other media types are kept when targeting them.
+        let screen = StyleSheet::parse_for_media("@media screen { p { display: none } }", Origin::Author, "screen");
+        assert_eq!(screen.rules.len(), 1);
+    }
+
+    #[test]
+    fn test_inheritance_and_css_wide_keywords() {
+        let mut dom = DocumentObjectModel::default();
+        let html = dom.insert_element(Element::new("html"), None);
+        let body = dom.insert_element(Element::new("body"), Some(html));
+        let p = dom.insert_element(Element::new("p").with_attribute("class", "unset"), Some(body));
+        let span = dom.insert_element(Element::new("span"), Some(p));
+        let em = dom.insert_element(Element::new("em"), Some(body));
+
+        let user = StyleSheet::parse("body { margin-left: 3px }", Origin::User);
+        let author = StyleSheet::parse(r#"
+            html { visibility: hidden; color: red }
+            span { color: color-mix(in srgb, currentColor, blue) }
+            body { margin-left: 5px; margin-right: 5px }
+            p { margin-left: inherit; margin-right: 1px }
+            .unset { visibility: visible; margin-right: unset }
+            em { margin-left: inherit }
+            body { margin-left: revert }
+        "#, Origin::Author);
+
+        let mut styles = Styles::<crate::dom::NodeId>::new(10, 10);
+        style(&dom, &[user, author], &BoxContent::default(), &mut styles);
+
+        let computed = |node| styles.computed.borrow(&node).unwrap().clone();
+
+        // Inherited properties flow down the tree.
+        assert!(matches!(computed(body).visibility, Visibility::Hidden));
+        assert!(matches!(computed(p).visibility, Visibility::Visible));
+        assert!(matches!(computed(span).visibility, Visibility::Visible));
+        assert!(matches!(computed(em).visibility, Visibility::Hidden));
+        assert_eq!(computed(em).color.to_rgba8(), [255, 0, 0, 255]);
+        assert_eq!(computed(span).color.to_rgba8(), [128, 0, 128, 255]);
+
+        // Non-inherited properties are not.
+        assert_eq!(px(&computed(span).margin_left), Some(0.0));
+        assert_eq!(px(&computed(p).margin_right), Some(0.0));
+
+        // The author declaration is reverted to the user one.
+        assert_eq!(px(&computed(body).margin_left), Some(3.0));
+        assert_eq!(px(&computed(p).margin_left), Some(3.0));
+        assert_eq!(px(&computed(em).margin_left), Some(3.0));
+
+        assert!(computed(dom.root().unwrap()).display == Display::block());
+    }
+}
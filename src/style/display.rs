@@ -1,12 +1,5 @@
 //! Implements [CSS DISPLAY 3](https://www.w3.org/TR/css-display-3/)
-
-pub mod initial {
-    pub use super::Display;
-}
-
-pub mod computed {
-    pub use super::Display;
-}
+use super::parser::parser::{ComponentValue, ParserError, ParserResult};
 
 #[derive(PartialEq, Eq, Clone, Copy)]
 pub struct Display(u16);
@@ -33,6 +26,99 @@ impl Default for Display {
     }
 }
 
+/// display = [ <display-outside> || <display-inside> ] | <display-listitem> | <display-internal> | <display-box> | <display-legacy>
+impl TryFrom<&[ComponentValue<'_>]> for Display {
+    type Error = ParserError;
+
+    fn try_from(values: &[ComponentValue<'_>]) -> ParserResult<Self> {
+        let keywords = values
+            .iter()
+            .filter(|value| !value.is_whitespace())
+            .map(|value| {
+                value
+                    .ident()
+                    .map(str::to_ascii_lowercase)
+                    .ok_or_else(|| ParserError::UnexpectedToken(format!("{value:?}")))
+            })
+            .collect::<ParserResult<Vec<_>>>()?;
+
+        let unexpected = || ParserError::UnexpectedToken(keywords.join(" "));
+
+        if let [keyword] = keywords.as_slice() {
+            let display = match keyword.as_str() {
+                "none" => Some(DisplayBox::None.into_display()),
+                "contents" => Some(DisplayBox::Contents.into_display()),
+                "inline-block" => Some(DisplayLegacy::InlineBlock.into_display()),
+                "inline-table" => Some(DisplayLegacy::InlineTable.into_display()),
+                "inline-flex" => Some(DisplayLegacy::InlineFlex.into_display()),
+                "inline-grid" => Some(DisplayLegacy::InlineGrid.into_display()),
+                "table-row-group" => Some(DisplayInternal::TableRowGroup.into_display()),
+                "table-header-group" => Some(DisplayInternal::TableHeaderGroup.into_display()),
+                "table-footer-group" => Some(DisplayInternal::TableFooterGroup.into_display()),
+                "table-row" => Some(DisplayInternal::TableRow.into_display()),
+                "table-cell" => Some(DisplayInternal::TableCell.into_display()),
+                "table-column-group" => Some(DisplayInternal::TableColumnGroup.into_display()),
+                "table-column" => Some(DisplayInternal::TableColumn.into_display()),
+                "table-caption" => Some(DisplayInternal::TableCaption.into_display()),
+                "ruby-base" => Some(DisplayInternal::RubyBase.into_display()),
+                "ruby-text" => Some(DisplayInternal::RubyText.into_display()),
+                "ruby-base-container" => Some(DisplayInternal::RubyBaseContainer.into_display()),
+                "ruby-text-container" => Some(DisplayInternal::RubyTextContainer.into_display()),
+                _ => None
+            };
+
+            if let Some(display) = display {
+                return Ok(display);
+            }
+        }
+
+        let mut outer = None;
+        let mut inner = None;
+        let mut listitem = false;
+
+        for keyword in keywords.iter() {
+            match keyword.as_str() {
+                "block" if outer.is_none() => outer = Some(DisplayOutside::Block),
+                "inline" if outer.is_none() => outer = Some(DisplayOutside::Inline),
+                "run-in" if outer.is_none() => outer = Some(DisplayOutside::RunIn),
+                "flow" if inner.is_none() => inner = Some(DisplayInside::Flow),
+                "flow-root" if inner.is_none() => inner = Some(DisplayInside::FlowRoot),
+                "table" if inner.is_none() => inner = Some(DisplayInside::Table),
+                "flex" if inner.is_none() => inner = Some(DisplayInside::Flex),
+                "grid" if inner.is_none() => inner = Some(DisplayInside::Grid),
+                "ruby" if inner.is_none() => inner = Some(DisplayInside::Ruby),
+                "list-item" if !listitem => listitem = true,
+                _ => return Err(unexpected())
+            }
+        }
+
+        if listitem && !matches!(inner, None | Some(DisplayInside::Flow | DisplayInside::FlowRoot)) {
+            return Err(unexpected());
+        }
+
+        // If <display-inside> is specified but <display-outside> is omitted,
+        // the outer display type defaults to block, except for ruby which defaults to inline.
+        let outer = match (outer, inner) {
+            (None, None) => return Err(unexpected()),
+            (Some(outer), _) => outer,
+            (None, Some(DisplayInside::Ruby)) => DisplayOutside::Inline,
+            (None, _) => DisplayOutside::Block,
+        };
+
+        let mut display = outer.into_display();
+
+        if let Some(inner) = inner {
+            display = display | inner.into_display();
+        }
+
+        if listitem {
+            display.0 |= Self::DISPLAY_LISTITEM as u16;
+        }
+
+        Ok(display)
+    }
+}
+
 impl Display {
     const DISPLAY_MODE_MASK: u8 = 0b11;
 
@@ -72,7 +158,7 @@ impl Display {
     const DISPLAY_INTERNAL_RUBY_TEXT_CONTAINER: u8 = 0b1100;
 
     const DISPLAY_BOX_SHIFT: u8 = 2;
-    const DISPLAY_BOX_MASK: u16 = 0b0011;
+    const DISPLAY_BOX_MASK: u16 = 0b1100;
     const DISPLAY_BOX_CONTENTS: u8 = 0b1;
     const DISPLAY_NONE_CONTENTS: u8 = 0b10;
 
@@ -96,7 +182,7 @@ impl Display {
 
     /// Get the outer display, if any
     pub fn outer(&self) -> Option<DisplayOutside> {
-        matches!(self.kind(), DisplayKind::InsideOutside)
+        matches!(self.kind(), DisplayKind::InsideOutside | DisplayKind::Listitem)
             .then(|| {
                 let raw =
                     ((self.0 & Self::DISPLAY_OUTSIDE_MASK) >> Self::DISPLAY_OUTSIDE_SHIFT) as u8;
@@ -110,7 +196,11 @@ impl Display {
     }
 
     pub fn set_outer(&mut self, outer: DisplayOutside) {
-        self.0 |= outer.into_display().0
+        if !matches!(self.kind(), DisplayKind::InsideOutside | DisplayKind::Listitem) {
+            self.0 = 0;
+        }
+
+        self.0 = (self.0 & !Self::DISPLAY_OUTSIDE_MASK) | outer.into_display().0
     }
 
     /// Get the inner display
@@ -120,10 +210,10 @@ impl Display {
     /// the element’s inner display type defaults to flow.
     /// ```
     pub fn inner(&self) -> Option<DisplayInside> {
-        let inner = matches!(self.kind(), DisplayKind::InsideOutside)
+        let inner = matches!(self.kind(), DisplayKind::InsideOutside | DisplayKind::Listitem)
             .then(|| {
                 let raw =
-                    ((self.0 & Self::DISPLAY_INSIDE_MASK) >> Self::DISPLAY_OUTSIDE_SHIFT) as u8;
+                    ((self.0 & Self::DISPLAY_INSIDE_MASK) >> Self::DISPLAY_INSIDE_SHIFT) as u8;
                 if raw == 0 {
                     None
                 } else {
@@ -140,7 +230,11 @@ impl Display {
     }
 
     pub fn set_inner(&mut self, inner: DisplayInside) {
-        self.0 |= inner.into_display().0
+        if !matches!(self.kind(), DisplayKind::InsideOutside | DisplayKind::Listitem) {
+            self.0 = 0;
+        }
+
+        self.0 = (self.0 & !Self::DISPLAY_INSIDE_MASK) | inner.into_display().0
     }
 
     /// Get the internal display
@@ -175,45 +269,63 @@ impl Display {
     pub fn listitem(&self) -> Option<DisplayListitem> {
         matches!(self.kind(), DisplayKind::Listitem)
             .then(|| {
-                let inner = self.inner();
-                if inner == Some(DisplayInside::Flow) {
-                    return Some(DisplayListitem(self.0));
-                } else if inner == Some(DisplayInside::FlowRoot) {
-                    return Some(DisplayListitem(self.0));
-                } else {
-                    return None;
-                }
+                matches!(self.inner(), Some(DisplayInside::Flow | DisplayInside::FlowRoot))
+                    .then_some(DisplayListitem(self.0))
             })
             .flatten()
     }
 
-    const fn flow() -> Self {
+    pub const fn flow() -> Self {
         DisplayInside::Flow.into_display()
     }
-    const fn flow_root() -> Self {
+    pub const fn flow_root() -> Self {
         DisplayInside::FlowRoot.into_display()
     }
-    const fn table() -> Self {
+    pub const fn table() -> Self {
         DisplayInside::Table.into_display()
     }
-    const fn flex() -> Self {
+    pub const fn flex() -> Self {
         DisplayInside::Flex.into_display()
     }
-    const fn grid() -> Self {
+    pub const fn grid() -> Self {
         DisplayInside::Grid.into_display()
     }
-    const fn ruby() -> Self {
+    pub const fn ruby() -> Self {
         DisplayInside::Ruby.into_display()
     }
-    const fn block() -> Self {
+    pub const fn block() -> Self {
         DisplayOutside::Block.into_display()
     }
-    const fn inline() -> Self {
+    pub const fn inline() -> Self {
         DisplayOutside::Inline.into_display()
     }
-    const fn run_in() -> Self {
+    pub const fn run_in() -> Self {
         DisplayOutside::RunIn.into_display()
     }
+    pub const fn inline_block() -> Self {
+        DisplayLegacy::InlineBlock.into_display()
+    }
+    pub const fn contents() -> Self {
+        DisplayBox::Contents.into_display()
+    }
+    pub const fn none() -> Self {
+        DisplayBox::None.into_display()
+    }
+    pub const fn list_item() -> Self {
+        Self(Self::block().0 | Self::DISPLAY_LISTITEM as u16)
+    }
+    pub const fn table_row_group() -> Self {
+        DisplayInternal::TableRowGroup.into_display()
+    }
+    pub const fn table_row() -> Self {
+        DisplayInternal::TableRow.into_display()
+    }
+    pub const fn table_cell() -> Self {
+        DisplayInternal::TableCell.into_display()
+    }
+    pub const fn table_caption() -> Self {
+        DisplayInternal::TableCaption.into_display()
+    }
 }
 
 pub struct DisplayListitem(u16);
@@ -306,7 +418,7 @@ impl DisplayInside {
     const fn into_display(self) -> Display {
         Display(
             (self.into_u16() << Display::DISPLAY_INSIDE_SHIFT as u16)
-                | Display::DISPLAY_OUTSIDE_INLINE as u16,
+                | Display::DISPLAY_INSIDE_OUTSIDE as u16,
         )
     }
 }
@@ -341,7 +453,7 @@ impl DisplayOutside {
     const fn into_display(self) -> Display {
         Display(
             (self.into_u16() << Display::DISPLAY_OUTSIDE_SHIFT as u16)
-                | Display::DISPLAY_OUTSIDE_INLINE as u16,
+                | Display::DISPLAY_INSIDE_OUTSIDE as u16,
         )
     }
 }
@@ -440,19 +552,18 @@ pub enum DisplayLegacy {
 impl DisplayLegacy {
     const fn into_display(self) -> Display {
         match self {
-            DisplayLegacy::InlineBlock => {
-                Display(DisplayInside::FlowRoot.into_u16() | DisplayOutside::Block.into_u16())
-            }
-            DisplayLegacy::InlineTable => {
-                Display(DisplayInside::Table.into_u16() | DisplayOutside::Inline.into_u16())
-            }
-            DisplayLegacy::InlineFlex => {
-                Display(DisplayInside::Flex.into_u16() | DisplayOutside::Inline.into_u16())
-            }
-            DisplayLegacy::InlineGrid => {
-                Display(DisplayInside::Grid.into_u16() | DisplayOutside::Inline.into_u16())
-            }
+            DisplayLegacy::InlineBlock => Display(
+                DisplayInside::FlowRoot.into_display().0 | DisplayOutside::Inline.into_display().0,
+            ),
+            DisplayLegacy::InlineTable => Display(
+                DisplayInside::Table.into_display().0 | DisplayOutside::Inline.into_display().0,
+            ),
+            DisplayLegacy::InlineFlex => Display(
+                DisplayInside::Flex.into_display().0 | DisplayOutside::Inline.into_display().0,
+            ),
+            DisplayLegacy::InlineGrid => Display(
+                DisplayInside::Grid.into_display().0 | DisplayOutside::Inline.into_display().0,
+            ),
         }
     }
 }
-
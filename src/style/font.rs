@@ -0,0 +1,389 @@
+//! Implements [CSS FONTS 4](https://www.w3.org/TR/css-fonts-4/)
+use super::{
+    parser::{lexer::TokenKind, parser::{ComponentValue, ParserError, ParserResult}},
+    values::numeric::{Length, LengthUnit, Percentage, ResolutionContext},
+};
+
+/// The size of a medium font, in pixels.
+pub const MEDIUM_FONT_SIZE: f64 = 16.0;
+
+/// font-size = <absolute-size> | <relative-size> | <length-percentage [0,∞]>
+#[derive(Clone, Copy)]
+pub enum FontSize {
+    Absolute(AbsoluteSize),
+    Relative(RelativeSize),
+    Length(Length),
+    Percentage(Percentage),
+}
+
+impl Default for FontSize {
+    fn default() -> Self {
+        Self::Absolute(AbsoluteSize::Medium)
+    }
+}
+
+impl From<ComputedFontSize> for FontSize {
+    fn from(value: ComputedFontSize) -> Self {
+        Self::Length(Length { value: value.0, unit: LengthUnit::Px })
+    }
+}
+
+impl TryFrom<&[ComponentValue<'_>]> for FontSize {
+    type Error = ParserError;
+
+    fn try_from(values: &[ComponentValue<'_>]) -> ParserResult<Self> {
+        let unexpected = || ParserError::UnexpectedToken(format!("{values:?}"));
+
+        let keyword = match values {
+            [value] => value.ident().map(str::to_ascii_lowercase),
+            _ => None
+        };
+
+        let font_size = match keyword.as_deref() {
+            Some("xx-small") => Self::Absolute(AbsoluteSize::XxSmall),
+            Some("x-small") => Self::Absolute(AbsoluteSize::XSmall),
+            Some("small") => Self::Absolute(AbsoluteSize::Small),
+            Some("medium") => Self::Absolute(AbsoluteSize::Medium),
+            Some("large") => Self::Absolute(AbsoluteSize::Large),
+            Some("x-large") => Self::Absolute(AbsoluteSize::XLarge),
+            Some("xx-large") => Self::Absolute(AbsoluteSize::XxLarge),
+            Some("xxx-large") => Self::Absolute(AbsoluteSize::XxxLarge),
+            Some("larger") => Self::Relative(RelativeSize::Larger),
+            Some("smaller") => Self::Relative(RelativeSize::Smaller),
+            Some(_) => return Err(unexpected()),
+            None => match values.first().and_then(ComponentValue::kind) {
+                Some(TokenKind::Percentage(number)) if values.len() == 1 => {
+                    Self::Percentage(Percentage::from(number.value() as f32))
+                },
+                _ => Self::Length(Length::try_from(values)?)
+            }
+        };
+
+        match font_size {
+            Self::Length(length) if length.value < 0.0 => Err(unexpected()),
+            Self::Percentage(percentage) if *percentage < 0.0 => Err(unexpected()),
+            font_size => Ok(font_size)
+        }
+    }
+}
+
+impl FontSize {
+    /// Computes the font size into an absolute length.
+    ///
+    /// The font size of the context must be the one of the parent element,
+    /// as relative values refer to it.
+    pub fn compute(&self, ctx: &ResolutionContext) -> ComputedFontSize {
+        let px = match self {
+            Self::Absolute(size) => size.px(),
+            Self::Relative(RelativeSize::Larger) => ctx.font_size * 1.2,
+            Self::Relative(RelativeSize::Smaller) => ctx.font_size / 1.2,
+            Self::Length(length) => length.to_px(ctx),
+            Self::Percentage(percentage) => ctx.font_size * (**percentage as f64) / 100.0,
+        };
+
+        ComputedFontSize(px)
+    }
+}
+
+/// <absolute-size> = xx-small | x-small | small | medium | large | x-large | xx-large | xxx-large
+#[derive(Clone, Copy)]
+pub enum AbsoluteSize {
+    XxSmall,
+    XSmall,
+    Small,
+    Medium,
+    Large,
+    XLarge,
+    XxLarge,
+    XxxLarge,
+}
+
+impl AbsoluteSize {
+    /// Returns the size in pixels, following the scaling factors of the spec.
+    pub fn px(&self) -> f64 {
+        let factor = match self {
+            Self::XxSmall => 3.0 / 5.0,
+            Self::XSmall => 3.0 / 4.0,
+            Self::Small => 8.0 / 9.0,
+            Self::Medium => 1.0,
+            Self::Large => 6.0 / 5.0,
+            Self::XLarge => 3.0 / 2.0,
+            Self::XxLarge => 2.0,
+            Self::XxxLarge => 3.0,
+        };
+
+        MEDIUM_FONT_SIZE * factor
+    }
+}
+
+/// <relative-size> = larger | smaller
+#[derive(Clone, Copy)]
+pub enum RelativeSize {
+    Larger,
+    Smaller,
+}
+
+/// The computed font size, in pixels.
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+pub struct ComputedFontSize(pub f64);
+
+impl Default for ComputedFontSize {
+    fn default() -> Self {
+        Self(MEDIUM_FONT_SIZE)
+    }
+}
+
+/// font-family = [ <family-name> | <generic-family> ]#
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FontFamily(pub Vec<FamilyName>);
+
+impl Default for FontFamily {
+    /// The initial value depends on the user agent, which picks a serif face as browsers do.
+    fn default() -> Self {
+        Self(vec![FamilyName::Generic(GenericFamily::Serif)])
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum FamilyName {
+    Named(String),
+    Generic(GenericFamily),
+}
+
+/// <generic-family> = serif | sans-serif | cursive | fantasy | monospace | system-ui
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GenericFamily {
+    Serif,
+    SansSerif,
+    Cursive,
+    Fantasy,
+    Monospace,
+    SystemUi,
+}
+
+impl TryFrom<&[ComponentValue<'_>]> for FontFamily {
+    type Error = ParserError;
+
+    /// ```spec
+    /// Font family names must either be given quoted as strings, or unquoted as a sequence of one or more identifiers.
+    /// Unquoted family names that contain the CSS-wide keywords, the generic family names, or default must be quoted.
+    /// ```
+    fn try_from(values: &[ComponentValue<'_>]) -> ParserResult<Self> {
+        let unexpected = || ParserError::UnexpectedToken(format!("{values:?}"));
+
+        values
+            .split(|value| value.is(TokenKind::Comma))
+            .map(|family| {
+                let family = family.iter().filter(|value| !value.is_whitespace()).collect::<Vec<_>>();
+
+                match family.as_slice() {
+                    [value] if value.is(TokenKind::String) => Ok(FamilyName::Named(value.token().unwrap().value.to_owned())),
+                    [value] => match value.ident().map(str::to_ascii_lowercase).as_deref() {
+                        Some("serif") => Ok(FamilyName::Generic(GenericFamily::Serif)),
+                        Some("sans-serif") => Ok(FamilyName::Generic(GenericFamily::SansSerif)),
+                        Some("cursive") => Ok(FamilyName::Generic(GenericFamily::Cursive)),
+                        Some("fantasy") => Ok(FamilyName::Generic(GenericFamily::Fantasy)),
+                        Some("monospace") => Ok(FamilyName::Generic(GenericFamily::Monospace)),
+                        Some("system-ui") => Ok(FamilyName::Generic(GenericFamily::SystemUi)),
+                        Some("inherit" | "initial" | "unset" | "revert" | "default") | None => Err(unexpected()),
+                        Some(_) => Ok(FamilyName::Named(value.ident().unwrap().to_owned())),
+                    },
+                    [] => Err(unexpected()),
+                    idents => idents
+                        .iter()
+                        .map(|value| value.ident().ok_or_else(unexpected))
+                        .collect::<ParserResult<Vec<_>>>()
+                        .map(|idents| FamilyName::Named(idents.join(" "))),
+                }
+            })
+            .collect::<ParserResult<Vec<_>>>()
+            .map(Self)
+    }
+}
+
+/// font-weight = normal | bold | bolder | lighter | <number [1,1000]>
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FontWeight {
+    Absolute(f64),
+    Bolder,
+    Lighter,
+}
+
+impl Default for FontWeight {
+    fn default() -> Self {
+        Self::Absolute(ComputedFontWeight::NORMAL.0)
+    }
+}
+
+impl From<ComputedFontWeight> for FontWeight {
+    fn from(value: ComputedFontWeight) -> Self {
+        Self::Absolute(value.0)
+    }
+}
+
+impl TryFrom<&[ComponentValue<'_>]> for FontWeight {
+    type Error = ParserError;
+
+    fn try_from(values: &[ComponentValue<'_>]) -> ParserResult<Self> {
+        let unexpected = || ParserError::UnexpectedToken(format!("{values:?}"));
+
+        let [value] = values else {
+            return Err(unexpected());
+        };
+
+        match (value.ident().map(str::to_ascii_lowercase).as_deref(), value.kind()) {
+            (Some("normal"), _) => Ok(Self::Absolute(ComputedFontWeight::NORMAL.0)),
+            (Some("bold"), _) => Ok(Self::Absolute(ComputedFontWeight::BOLD.0)),
+            (Some("bolder"), _) => Ok(Self::Bolder),
+            (Some("lighter"), _) => Ok(Self::Lighter),
+            (None, Some(TokenKind::Number(number))) if (1.0..=1000.0).contains(&number.value()) => Ok(Self::Absolute(number.value())),
+            _ => Err(unexpected()),
+        }
+    }
+}
+
+impl FontWeight {
+    /// Computes the weight into a number.
+    ///
+    /// The font weight of the context must be the one of the parent element, as relative weights refer to it.
+    ///
+    /// ```spec
+    /// | Inherited value (w) | bolder | lighter |
+    /// | w < 100             | 400    | No change |
+    /// | 100 ≤ w < 350       | 400    | 100 |
+    /// | 350 ≤ w < 550       | 700    | 100 |
+    /// | 550 ≤ w < 750       | 900    | 400 |
+    /// | 750 ≤ w < 900       | 900    | 700 |
+    /// | 900 ≤ w             | No change | 700 |
+    /// ```
+    pub fn compute(&self, ctx: &ResolutionContext) -> ComputedFontWeight {
+        let inherited = ctx.font_weight;
+
+        let weight = match self {
+            Self::Absolute(weight) => *weight,
+            Self::Bolder if inherited < 350.0 => 400.0,
+            Self::Bolder if inherited < 550.0 => 700.0,
+            Self::Bolder if inherited < 900.0 => 900.0,
+            Self::Bolder => inherited,
+            Self::Lighter if inherited < 100.0 => inherited,
+            Self::Lighter if inherited < 550.0 => 100.0,
+            Self::Lighter if inherited < 750.0 => 400.0,
+            Self::Lighter => 700.0,
+        };
+
+        ComputedFontWeight(weight)
+    }
+}
+
+/// The computed font weight, as a number.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct ComputedFontWeight(pub f64);
+
+impl ComputedFontWeight {
+    pub const NORMAL: Self = Self(400.0);
+    pub const BOLD: Self = Self(700.0);
+}
+
+impl Default for ComputedFontWeight {
+    fn default() -> Self {
+        Self::NORMAL
+    }
+}
+
+/// font-style = normal | italic | oblique
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum FontStyle {
+    #[default]
+    Normal,
+    Italic,
+    Oblique,
+}
+
+impl TryFrom<&[ComponentValue<'_>]> for FontStyle {
+    type Error = ParserError;
+
+    fn try_from(values: &[ComponentValue<'_>]) -> ParserResult<Self> {
+        let keyword = match values {
+            [value] => value.ident().map(str::to_ascii_lowercase),
+            _ => None
+        };
+
+        match keyword.as_deref() {
+            Some("normal") => Ok(Self::Normal),
+            Some("italic") => Ok(Self::Italic),
+            Some("oblique") => Ok(Self::Oblique),
+            _ => Err(ParserError::UnexpectedToken(format!("{values:?}")))
+        }
+    }
+}
+
+/// font-stretch = normal | <percentage [0,∞]> | ultra-condensed | extra-condensed | condensed | semi-condensed
+///              | semi-expanded | expanded | extra-expanded | ultra-expanded
+///
+/// The stretch is a percentage of the normal width of the face.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct FontStretch(pub f64);
+
+impl FontStretch {
+    pub const NORMAL: Self = Self(100.0);
+}
+
+impl Default for FontStretch {
+    fn default() -> Self {
+        Self::NORMAL
+    }
+}
+
+impl TryFrom<&[ComponentValue<'_>]> for FontStretch {
+    type Error = ParserError;
+
+    fn try_from(values: &[ComponentValue<'_>]) -> ParserResult<Self> {
+        let unexpected = || ParserError::UnexpectedToken(format!("{values:?}"));
+
+        let [value] = values else {
+            return Err(unexpected());
+        };
+
+        let percentage = match (value.ident().map(str::to_ascii_lowercase).as_deref(), value.kind()) {
+            (Some("ultra-condensed"), _) => 50.0,
+            (Some("extra-condensed"), _) => 62.5,
+            (Some("condensed"), _) => 75.0,
+            (Some("semi-condensed"), _) => 87.5,
+            (Some("normal"), _) => 100.0,
+            (Some("semi-expanded"), _) => 112.5,
+            (Some("expanded"), _) => 125.0,
+            (Some("extra-expanded"), _) => 150.0,
+            (Some("ultra-expanded"), _) => 200.0,
+            (None, Some(TokenKind::Percentage(number))) if number.value() >= 0.0 => number.value(),
+            _ => return Err(unexpected()),
+        };
+
+        Ok(Self(percentage))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::style::{parser::parser::parse_list_of_component_values, values::numeric::ResolutionContext};
+
+    use super::{FamilyName, FontFamily, FontWeight, GenericFamily};
+
+    #[test]
+    fn test_font_family_and_weight() {
+        let values = parse_list_of_component_values(r#""Noto Serif", DejaVu  Sans , monospace"#).unwrap();
+        assert_eq!(FontFamily::try_from(values.as_slice()).unwrap().0, [
+            FamilyName::Named("Noto Serif".to_owned()),
+            FamilyName::Named("DejaVu Sans".to_owned()),
+            FamilyName::Generic(GenericFamily::Monospace),
+        ]);
+
+        let values = parse_list_of_component_values("Arial, inherit").unwrap();
+        assert!(FontFamily::try_from(values.as_slice()).is_err());
+
+        let ctx = |font_weight| ResolutionContext { font_weight, ..Default::default() };
+        assert_eq!(FontWeight::Bolder.compute(&ctx(400.0)).0, 700.0);
+        assert_eq!(FontWeight::Bolder.compute(&ctx(700.0)).0, 900.0);
+        assert_eq!(FontWeight::Lighter.compute(&ctx(700.0)).0, 400.0);
+        assert_eq!(FontWeight::Lighter.compute(&ctx(50.0)).0, 50.0);
+    }
+}
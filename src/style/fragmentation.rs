@@ -0,0 +1,205 @@
+//! Implements the breaking controls from [CSS FRAGMENTATION 3](https://www.w3.org/TR/css-break-3/)
+use super::{
+    parser::parser::{ComponentValue, ParserError, ParserResult},
+    values::numeric::Integer,
+};
+
+/// break-before, break-after = auto | avoid | always | all | avoid-page | page | left | right | recto | verso | avoid-column | column | avoid-region | region
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BreakBetween {
+    #[default]
+    Auto,
+    Avoid,
+    Always,
+    All,
+    AvoidPage,
+    Page,
+    Left,
+    Right,
+    Recto,
+    Verso,
+    AvoidColumn,
+    Column,
+    AvoidRegion,
+    Region,
+}
+
+impl TryFrom<&[ComponentValue<'_>]> for BreakBetween {
+    type Error = ParserError;
+
+    fn try_from(values: &[ComponentValue<'_>]) -> ParserResult<Self> {
+        let keyword = match values {
+            [value] => value.ident().map(str::to_ascii_lowercase),
+            _ => None
+        };
+
+        match keyword.as_deref() {
+            Some("auto") => Ok(Self::Auto),
+            Some("avoid") => Ok(Self::Avoid),
+            Some("always") => Ok(Self::Always),
+            Some("all") => Ok(Self::All),
+            Some("avoid-page") => Ok(Self::AvoidPage),
+            Some("page") => Ok(Self::Page),
+            Some("left") => Ok(Self::Left),
+            Some("right") => Ok(Self::Right),
+            Some("recto") => Ok(Self::Recto),
+            Some("verso") => Ok(Self::Verso),
+            Some("avoid-column") => Ok(Self::AvoidColumn),
+            Some("column") => Ok(Self::Column),
+            Some("avoid-region") => Ok(Self::AvoidRegion),
+            Some("region") => Ok(Self::Region),
+            _ => Err(ParserError::UnexpectedToken(format!("{values:?}")))
+        }
+    }
+}
+
+impl BreakBetween {
+    /// Parses the legacy page-break-before and page-break-after properties.
+    ///
+    /// ```spec
+    /// page-break-before, page-break-after = auto | always | avoid | left | right
+    /// These properties are to be treated as legacy shorthands for the break-before and break-after properties
+    /// with the following value mappings: auto, left, right and avoid map to the same values, always maps to page.
+    /// ```
+    pub fn parse_legacy(values: &[ComponentValue<'_>]) -> ParserResult<Self> {
+        match Self::try_from(values)? {
+            Self::Always => Ok(Self::Page),
+            value @ (Self::Auto | Self::Avoid | Self::Left | Self::Right) => Ok(value),
+            _ => Err(ParserError::UnexpectedToken(format!("{values:?}")))
+        }
+    }
+
+    /// Checks if the value forces a break between pages.
+    ///
+    /// ```spec
+    /// always: Always force a break before/after the principal box. The type of this break is that of the immediately-containing fragmentation context.
+    /// all: Always force a break before/after the principal box, breaking through all possible fragmentation contexts.
+    /// page, left, right, recto, verso: Always force a page break before/after the principal box.
+    /// ```
+    ///
+    /// Column and region breaks have no effect, as there are only page fragmentation contexts.
+    pub fn forces_page_break(&self) -> bool {
+        matches!(self, Self::Always | Self::All | Self::Page | Self::Left | Self::Right | Self::Recto | Self::Verso)
+    }
+
+    /// ```spec
+    /// avoid: Avoid a break before/after the principal box.
+    /// avoid-page: Avoid a page break before/after the principal box.
+    /// ```
+    pub fn avoids_page_break(&self) -> bool {
+        matches!(self, Self::Avoid | Self::AvoidPage)
+    }
+
+    /// Returns whether the page following a forced break must be a right page, or a left one.
+    ///
+    /// ```spec
+    /// left: Force one or two page breaks so that the next page is formatted as a left page.
+    /// right: Force one or two page breaks so that the next page is formatted as a right page.
+    /// recto: Force one or two page breaks so that the next page is formatted as a recto page.
+    /// verso: Force one or two page breaks so that the next page is formatted as a verso page.
+    /// ```
+    ///
+    /// Recto pages are right pages, with a left-to-right page progression.
+    pub fn requires_right_page(&self) -> Option<bool> {
+        match self {
+            Self::Right | Self::Recto => Some(true),
+            Self::Left | Self::Verso => Some(false),
+            _ => None,
+        }
+    }
+}
+
+/// break-inside = auto | avoid | avoid-page | avoid-column | avoid-region
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BreakInside {
+    #[default]
+    Auto,
+    Avoid,
+    AvoidPage,
+    AvoidColumn,
+    AvoidRegion,
+}
+
+impl TryFrom<&[ComponentValue<'_>]> for BreakInside {
+    type Error = ParserError;
+
+    fn try_from(values: &[ComponentValue<'_>]) -> ParserResult<Self> {
+        let keyword = match values {
+            [value] => value.ident().map(str::to_ascii_lowercase),
+            _ => None
+        };
+
+        match keyword.as_deref() {
+            Some("auto") => Ok(Self::Auto),
+            Some("avoid") => Ok(Self::Avoid),
+            Some("avoid-page") => Ok(Self::AvoidPage),
+            Some("avoid-column") => Ok(Self::AvoidColumn),
+            Some("avoid-region") => Ok(Self::AvoidRegion),
+            _ => Err(ParserError::UnexpectedToken(format!("{values:?}")))
+        }
+    }
+}
+
+impl BreakInside {
+    /// Parses the legacy page-break-inside property.
+    ///
+    /// ```spec
+    /// page-break-inside = auto | avoid
+    /// This property is to be treated as a legacy shorthand for the break-inside property.
+    /// ```
+    pub fn parse_legacy(values: &[ComponentValue<'_>]) -> ParserResult<Self> {
+        match Self::try_from(values)? {
+            value @ (Self::Auto | Self::Avoid) => Ok(value),
+            _ => Err(ParserError::UnexpectedToken(format!("{values:?}")))
+        }
+    }
+
+    /// ```spec
+    /// avoid: Avoid a break within the principal box.
+    /// avoid-page: Avoid a page break within the principal box.
+    /// ```
+    pub fn avoids_page_break(&self) -> bool {
+        matches!(self, Self::Avoid | Self::AvoidPage)
+    }
+}
+
+/// orphans, widows = <integer [1,∞]>
+///
+/// ```spec
+/// The orphans property specifies the minimum number of line boxes in a block container that must be left in a fragment before a fragmentation break.
+/// The widows property specifies the minimum number of line boxes of a block container that must be left in a fragment after a break.
+/// Negative values and zero are invalid and must cause the declaration to be ignored.
+/// ```
+pub fn parse_lines(values: &[ComponentValue<'_>]) -> ParserResult<Integer> {
+    match Integer::try_from(values)? {
+        lines if *lines > 0 => Ok(lines),
+        lines => Err(ParserError::UnexpectedToken(format!("{}", *lines)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::style::parser::parser::{parse_list_of_component_values, ComponentValue};
+
+    use super::{parse_lines, BreakBetween, BreakInside};
+
+    fn parse(css: &str) -> Vec<ComponentValue<'_>> {
+        parse_list_of_component_values(css).unwrap()
+    }
+
+    #[test]
+    fn test_break_properties() {
+        assert_eq!(BreakBetween::try_from(parse("avoid-page").as_slice()).unwrap(), BreakBetween::AvoidPage);
+        assert_eq!(BreakBetween::parse_legacy(parse("always").as_slice()).unwrap(), BreakBetween::Page);
+        assert!(BreakBetween::parse_legacy(parse("column").as_slice()).is_err());
+        assert!(BreakBetween::Recto.forces_page_break());
+        assert!(!BreakBetween::Column.forces_page_break());
+
+        assert_eq!(BreakInside::try_from(parse("avoid").as_slice()).unwrap(), BreakInside::Avoid);
+        assert!(BreakInside::parse_legacy(parse("avoid-page").as_slice()).is_err());
+
+        assert_eq!(*parse_lines(parse("3").as_slice()).unwrap(), 3);
+        assert!(parse_lines(parse("0").as_slice()).is_err());
+        assert!(parse_lines(parse("1.5").as_slice()).is_err());
+    }
+}
@@ -0,0 +1,124 @@
+//! Implements the line height and the vertical alignment of inline-level boxes from [CSS 2.1](https://www.w3.org/TR/CSS21/visudet.html#line-height)
+use super::{
+    parser::{lexer::TokenKind, parser::{ComponentValue, ParserError, ParserResult}},
+    values::numeric::{Length, LengthUnit, Percentage, ResolutionContext},
+};
+
+/// line-height = normal | <number [0,∞]> | <length-percentage [0,∞]>
+///
+/// Relative lengths and percentages compute to absolute lengths, from the font size of the element,
+/// so that its children inherit the same line height. A number is inherited as is, and scales the font size of each element.
+#[derive(Clone, Copy, Default)]
+pub enum LineHeight {
+    #[default]
+    Normal,
+    Number(f64),
+    Length(Length),
+    Percentage(Percentage),
+}
+
+impl TryFrom<&[ComponentValue<'_>]> for LineHeight {
+    type Error = ParserError;
+
+    fn try_from(values: &[ComponentValue<'_>]) -> ParserResult<Self> {
+        let unexpected = || ParserError::UnexpectedToken(format!("{values:?}"));
+
+        let [value] = values else {
+            return Err(unexpected());
+        };
+
+        let line_height = match value.kind() {
+            Some(TokenKind::Ident) if value.is_ident("normal") => Self::Normal,
+            Some(TokenKind::Number(number)) => Self::Number(number.value()),
+            Some(TokenKind::Percentage(number)) => Self::Percentage(Percentage::from(number.value() as f32)),
+            _ => Self::Length(Length::try_from(values)?),
+        };
+
+        match line_height {
+            Self::Number(number) if number < 0.0 => Err(unexpected()),
+            Self::Length(length) if length.value < 0.0 => Err(unexpected()),
+            Self::Percentage(percentage) if *percentage < 0.0 => Err(unexpected()),
+            line_height => Ok(line_height),
+        }
+    }
+}
+
+impl LineHeight {
+    /// Computes the length, or the percentage of the font size of the context, into an absolute length.
+    pub fn compute(&self, ctx: &ResolutionContext) -> Self {
+        match self {
+            Self::Length(length) => Self::Length(length.compute(ctx)),
+            Self::Percentage(percentage) => Self::Length(Length { value: **percentage as f64 * ctx.font_size / 100.0, unit: LengthUnit::Px }),
+            value => *value,
+        }
+    }
+
+    /// Resolves the line height into pixels, from the font size of the context.
+    ///
+    /// ```spec
+    /// normal: Tells user agents to set the used value to a "reasonable" value based on the font of the element.
+    /// ```
+    /// Returns nothing if the value is normal, as it depends on the metrics of the font.
+    pub fn px(&self, ctx: &ResolutionContext) -> Option<f64> {
+        match self {
+            Self::Normal => None,
+            Self::Number(number) => Some(number * ctx.font_size),
+            Self::Length(length) => Some(length.to_px(ctx)),
+            Self::Percentage(percentage) => Some(**percentage as f64 * ctx.font_size / 100.0),
+        }
+    }
+}
+
+/// vertical-align = baseline | sub | super | text-top | text-bottom | middle | top | bottom | <length-percentage>
+#[derive(Clone, Copy, Default)]
+pub enum VerticalAlign {
+    #[default]
+    Baseline,
+    Sub,
+    Super,
+    TextTop,
+    TextBottom,
+    Middle,
+    Top,
+    Bottom,
+    Length(Length),
+    /// A percentage of the line height of the element.
+    Percentage(Percentage),
+}
+
+impl TryFrom<&[ComponentValue<'_>]> for VerticalAlign {
+    type Error = ParserError;
+
+    fn try_from(values: &[ComponentValue<'_>]) -> ParserResult<Self> {
+        let keyword = match values {
+            [value] => value.ident().map(str::to_ascii_lowercase),
+            _ => None
+        };
+
+        match keyword.as_deref() {
+            Some("baseline") => Ok(Self::Baseline),
+            Some("sub") => Ok(Self::Sub),
+            Some("super") => Ok(Self::Super),
+            Some("text-top") => Ok(Self::TextTop),
+            Some("text-bottom") => Ok(Self::TextBottom),
+            Some("middle") => Ok(Self::Middle),
+            Some("top") => Ok(Self::Top),
+            Some("bottom") => Ok(Self::Bottom),
+            Some(_) => Err(ParserError::UnexpectedToken(format!("{values:?}"))),
+            None => match values.first().and_then(ComponentValue::kind) {
+                Some(TokenKind::Percentage(number)) if values.len() == 1 => Ok(Self::Percentage(Percentage::from(number.value() as f32))),
+                _ => Length::try_from(values).map(Self::Length),
+            },
+        }
+    }
+}
+
+impl VerticalAlign {
+    /// Computes the length into an absolute length, a percentage refers to the used line height.
+    pub fn compute(&self, ctx: &ResolutionContext) -> Self {
+        match self {
+            Self::Length(length) => Self::Length(length.compute(ctx)),
+            value => *value,
+        }
+    }
+}
@@ -1,27 +1,35 @@
 use std::hash::Hash;
 
+use cascade::{cascade, Origin, StyleSheet};
 use display::DisplayOutside;
-use pb_arena::ArenaId;
-use properties::{computed, initial, used};
+use properties::{computed, initial, specified_values, used};
 
 use crate::{
-    dom::TDocumentObjectModelExplorer,
-    ecs::{component::Components, systems::tree::walk},
+    dom::{NodeKind, TDocumentObjectModelExplorer},
+    ecs::{arena::ArenaId, component::Components, systems::tree::walk},
 };
+use page::PageRule;
+use values::{numeric::ResolutionContext, r#box::BoxContent};
 
 pub mod parser;
 pub mod border;
+pub mod cascade;
 pub mod display;
-pub mod margin;
+pub mod font;
+pub mod fragmentation;
+pub mod inline;
 pub mod order;
-pub mod padding;
+pub mod page;
 pub mod properties;
+pub mod selector;
+pub mod shorthands;
+pub mod text;
 pub mod values;
 pub mod visibility;
 
 
 #[derive(Clone, Copy, Hash)]
-pub struct ComputedStyleId(ArenaId);
+pub struct ComputedStyleId(pub(crate) ArenaId);
 
 /// Style system which holds all style applied to any document node.
 #[derive(Default, Clone)]
@@ -29,6 +37,8 @@ pub struct Styles<NodeId: Hash + Copy> {
     pub initial:    Components<NodeId, initial::Properties>,
     pub computed:   Components<NodeId, computed::Properties>,
     pub used:       Components<NodeId, used::Properties>,
+    /// The @page rules of the style sheets, with their origin, in order of appearance.
+    pub page_rules: Vec<(Origin, PageRule)>,
 }
 
 impl<NodeId: Hash + Copy> Styles<NodeId> {
@@ -36,28 +46,79 @@ impl<NodeId: Hash + Copy> Styles<NodeId> {
         Self {
             initial:    Components::new(bucket_size, cache_size),
             computed:   Components::new(bucket_size, cache_size),
-            used:       Components::new(bucket_size, cache_size)
+            used:       Components::new(bucket_size, cache_size),
+            page_rules: Vec::new(),
         }
     }
 
+    /// Returns the identifier of the computed style of the node.
+    /// 
+    /// If the node has no computed style yet, the initial values are bound to it.
+    pub fn computed_style_id(&mut self, node: &NodeId) -> ComputedStyleId {
+        if !self.computed.exists(*node) {
+            self.computed.bind_default(node);
+        }
+
+        ComputedStyleId(self.computed.component_id(node).unwrap())
+    }
+
     /// Creates a new style holder which shares the same style pool
     /// as another one.
     pub fn new_shared<OtherNodeId: Hash + Copy>(other: &Styles<OtherNodeId>) -> Self {
         Self {
             initial: Components::new_shared(&other.initial),
             computed: Components::new_shared(&other.computed),
-            used: Components::new_shared(&other.used)
+            used: Components::new_shared(&other.used),
+            page_rules: other.page_rules.clone(),
         }
     }
 }
 
-pub fn style<Dom>(dom: &Dom, style: &mut Styles<Dom::NodeId>)
+/// Cascade the style sheets, compute the values of every element of the document,
+/// and resolve their relative lengths against the viewport.
+///
+/// The @page rules are kept to style the page context during layout.
+pub fn style<Dom>(dom: &Dom, stylesheets: &[StyleSheet], viewport: &BoxContent<f64>, style: &mut Styles<Dom::NodeId>)
 where
     Dom: TDocumentObjectModelExplorer + Sync,
     Dom::NodeId: Hash + Copy + Eq,
 {
+    let mut ctx = ResolutionContext {
+        viewport: viewport.clone(),
+        ..Default::default()
+    };
+
+    style.page_rules = stylesheets
+        .iter()
+        .flat_map(|stylesheet| stylesheet.page_rules.iter().map(|rule| (stylesheet.origin, rule.clone())))
+        .collect();
+
     for node in walk(dom) {
-        if node == dom.root().unwrap() {
+        if dom.kind(&node) != NodeKind::Element {
+            continue;
+        }
+
+        let parent = dom.parent(node).and_then(|parent| style.computed.borrow(&parent).map(|parent| parent.clone()));
+        let specified = specified_values(&cascade(dom, stylesheets, &node), parent.as_ref());
+
+        // Relative font sizes refer to the font size of the parent.
+        ctx.font_size = parent.as_ref().map(|parent| parent.font_size.0).unwrap_or(font::MEDIUM_FONT_SIZE);
+        ctx.font_weight = parent.as_ref().map(|parent| parent.font_weight.0).unwrap_or(font::ComputedFontWeight::NORMAL.0);
+        ctx.current_color = specified.current_color(parent.as_ref());
+        let computed = computed::Properties::compute(&specified, &ctx);
+
+        let is_root = Some(node) == dom.root();
+        if is_root {
+            ctx.root_font_size = computed.font_size.0;
+        }
+
+        ctx.font_size = computed.font_size.0;
+        ctx.font_weight = computed.font_weight.0;
+        style.used.bind(&node, used::Properties::resolve(&computed, &ctx));
+        style.computed.bind(&node, computed);
+        style.initial.bind(&node, specified);
+
+        if is_root {
             blockify(dom, &node, style);
         }
     }
@@ -121,3 +182,74 @@ where
     display.set_outer(DisplayOutside::Block);
 }
 
+
+#[cfg(test)]
+mod test {
+    use crate::dom::{DocumentObjectModel, Element, NodeId};
+
+    use super::{
+        cascade::{Origin, StyleSheet},
+        style,
+        values::r#box::BoxContent,
+        Styles,
+    };
+
+    #[test]
+    fn test_resolve_relative_lengths() {
+        let mut dom = DocumentObjectModel::default();
+        let html = dom.insert_element(Element::new("html"), None);
+        let body = dom.insert_element(Element::new("body"), Some(html));
+        let p = dom.insert_element(Element::new("p"), Some(body));
+
+        let stylesheet = StyleSheet::parse(r#"
+            html { font-size: 20px }
+            body { font-size: 150%; margin-top: 2rem; margin-left: 10vw; padding-left: 1in }
+            p { font-size: 0.5em; margin-top: 2em; margin-bottom: 50%; margin-left: auto; border-top-width: 1ex; border-top-style: solid; padding-top: 1vmin }
+        "#, Origin::Author);
+
+        let mut styles = Styles::<NodeId>::new(10, 10);
+        let viewport = BoxContent { width: 800.0, height: 600.0 };
+        style(&dom, &[stylesheet], &viewport, &mut styles);
+
+        let body = styles.used.borrow(&body).unwrap().clone();
+        assert_eq!(body.font_size, 30.0);
+        assert_eq!(body.margin.top.px(0.0), Some(40.0));
+        assert_eq!(body.margin.left.px(0.0), Some(80.0));
+        assert_eq!(body.padding.left.px(0.0), Some(96.0));
+
+        let p = styles.used.borrow(&p).unwrap().clone();
+        assert_eq!(p.font_size, 15.0);
+        assert_eq!(p.margin.top.px(0.0), Some(30.0));
+        assert_eq!(p.margin.bottom.px(200.0), Some(100.0));
+        assert_eq!(p.margin.left.px(200.0), None);
+        assert_eq!(p.border.top, 7.5);
+        assert_eq!(p.padding.top.px(0.0), Some(6.0));
+    }
+
+    #[test]
+    fn test_inherit_computed_lengths() {
+        let mut dom = DocumentObjectModel::default();
+        let html = dom.insert_element(Element::new("html"), None);
+        let p = dom.insert_element(Element::new("p"), Some(html));
+        let span = dom.insert_element(Element::new("span"), Some(p));
+
+        let stylesheet = StyleSheet::parse(r#"
+            html { font-size: 20px; line-height: 150%; margin-left: 2em }
+            p { font-size: 40px; margin-left: inherit }
+            span { font-size: 10px; line-height: 2 }
+        "#, Origin::Author);
+
+        let mut styles = Styles::<NodeId>::new(10, 10);
+        let viewport = BoxContent { width: 800.0, height: 600.0 };
+        style(&dom, &[stylesheet], &viewport, &mut styles);
+
+        // Relative lengths are absolute once computed, the child inherits them whatever its own font size.
+        let p = styles.used.borrow(&p).unwrap().clone();
+        assert_eq!(p.line_height, Some(30.0));
+        assert_eq!(p.margin.left.px(0.0), Some(40.0));
+
+        // A number is inherited as is, and scales the font size of the element.
+        let span = styles.used.borrow(&span).unwrap().clone();
+        assert_eq!(span.line_height, Some(20.0));
+    }
+}
@@ -1,6 +1,9 @@
 use std::ops::Deref;
 
-use super::values::numeric::Integer;
+use super::{
+    parser::parser::{ComponentValue, ParserError},
+    values::numeric::Integer,
+};
 
 #[derive(Clone, Copy, Default)]
 pub struct Order(Integer);
@@ -13,11 +16,10 @@ impl Deref for Order {
     }
 }
 
-pub mod initial {
-    pub use super::Order;
-}
+impl TryFrom<&[ComponentValue<'_>]> for Order {
+    type Error = ParserError;
 
-pub mod computed {
-    pub use super::Order;
+    fn try_from(values: &[ComponentValue<'_>]) -> Result<Self, Self::Error> {
+        Integer::try_from(values).map(Self)
+    }
 }
-
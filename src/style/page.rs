@@ -0,0 +1,608 @@
+//! Implements the page context from [CSS PAGED MEDIA 3](https://www.w3.org/TR/css-page-3/)
+//!
+//! ```spec
+//! The @page rule is used to specify properties of the page context, its page-margin boxes, and page-based counters.
+//! The page context is the page box, which is a rectangular region that contains two areas:
+//! the page area, where the document content is fragmented into, and the margin area, which holds the page-margin boxes.
+//! ```
+use super::{
+    cascade::StyleDeclaration,
+    parser::{
+        lexer::TokenKind,
+        parser::{AtRule, ComponentValue, DeclarationOrAtRule, ParserError, ParserResult},
+    },
+    selector::Specificity,
+    values::{
+        numeric::{Length, LengthUnit, ResolutionContext},
+        r#box::BoxContent,
+    },
+};
+
+/// A page being styled.
+///
+/// Only left-to-right progression is supported, where the first page is a right page.
+#[derive(Debug, Clone, Copy)]
+pub struct Page<'a> {
+    /// The index of the page in the document, from zero.
+    pub index: usize,
+    /// The name of the page, from the page property of its first content.
+    pub name: Option<&'a str>,
+    /// Whether the page is a blank page, inserted to honor a left or right break.
+    pub blank: bool,
+}
+
+impl Page<'_> {
+    /// ```spec
+    /// The first page of a document with a left-to-right page progression is a right page.
+    /// ```
+    pub fn is_right(&self) -> bool {
+        self.index.is_multiple_of(2)
+    }
+}
+
+/// ```spec
+/// The :first pseudo-class matches the first page of the document.
+/// The :left and :right pseudo-classes match left and right pages.
+/// The :blank pseudo-class matches pages that have no content from the document flow.
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PagePseudoClass {
+    First,
+    Left,
+    Right,
+    Blank,
+}
+
+/// <page-selector> = [ <ident-token>? <pseudo-page>* ]!
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageSelector {
+    pub name: Option<String>,
+    pub pseudo_classes: Vec<PagePseudoClass>,
+}
+
+impl TryFrom<&[ComponentValue<'_>]> for PageSelector {
+    type Error = ParserError;
+
+    /// White space is not allowed within a page selector.
+    fn try_from(values: &[ComponentValue<'_>]) -> ParserResult<Self> {
+        let unexpected = || ParserError::UnexpectedToken(format!("{values:?}"));
+
+        let (name, mut rest) = match values.first().and_then(ComponentValue::ident) {
+            Some(name) => (Some(name.to_owned()), &values[1..]),
+            None => (None, values),
+        };
+
+        let mut pseudo_classes = Vec::new();
+
+        while let [colon, pseudo_class, tail @ ..] = rest {
+            if !colon.is(TokenKind::Colon) {
+                return Err(unexpected());
+            }
+
+            pseudo_classes.push(match pseudo_class.ident().map(str::to_ascii_lowercase).as_deref() {
+                Some("first") => PagePseudoClass::First,
+                Some("left") => PagePseudoClass::Left,
+                Some("right") => PagePseudoClass::Right,
+                Some("blank") => PagePseudoClass::Blank,
+                _ => return Err(unexpected()),
+            });
+
+            rest = tail;
+        }
+
+        if !rest.is_empty() || (name.is_none() && pseudo_classes.is_empty()) {
+            return Err(unexpected());
+        }
+
+        Ok(Self { name, pseudo_classes })
+    }
+}
+
+impl PageSelector {
+    pub fn matches(&self, page: &Page<'_>) -> bool {
+        self.name.as_deref().map(|name| page.name == Some(name)).unwrap_or(true)
+            && self.pseudo_classes.iter().all(|pseudo_class| match pseudo_class {
+                PagePseudoClass::First => page.index == 0,
+                PagePseudoClass::Left => !page.is_right(),
+                PagePseudoClass::Right => page.is_right(),
+                PagePseudoClass::Blank => page.blank,
+            })
+    }
+
+    /// ```spec
+    /// The specificity of a page selector is computed as a triple (f, g, h), where:
+    /// - f is 1 if the page selector has a page type name, 0 otherwise;
+    /// - g is the number of :first or :blank pseudo-classes;
+    /// - h is the number of :left or :right pseudo-classes.
+    /// ```
+    pub fn specificity(&self) -> Specificity {
+        let count = |matches: fn(&PagePseudoClass) -> bool| self.pseudo_classes.iter().filter(|pseudo_class| matches(pseudo_class)).count() as u32;
+
+        Specificity(
+            self.name.is_some() as u32,
+            count(|pseudo_class| matches!(pseudo_class, PagePseudoClass::First | PagePseudoClass::Blank)),
+            count(|pseudo_class| matches!(pseudo_class, PagePseudoClass::Left | PagePseudoClass::Right)),
+        )
+    }
+}
+
+/// <page-selector-list> = <page-selector>#
+///
+/// An empty list matches every page.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageSelectorList(pub Vec<PageSelector>);
+
+impl TryFrom<&[ComponentValue<'_>]> for PageSelectorList {
+    type Error = ParserError;
+
+    fn try_from(values: &[ComponentValue<'_>]) -> ParserResult<Self> {
+        let values = trim_whitespace(values);
+
+        if values.is_empty() {
+            return Ok(Self::default());
+        }
+
+        values
+            .split(|value| value.is(TokenKind::Comma))
+            .map(|selector| PageSelector::try_from(trim_whitespace(selector)))
+            .collect::<ParserResult<Vec<_>>>()
+            .map(Self)
+    }
+}
+
+impl PageSelectorList {
+    /// Returns the specificity of the most specific selector matching the page, if any.
+    pub fn matches(&self, page: &Page<'_>) -> Option<Specificity> {
+        if self.0.is_empty() {
+            return Some(Specificity::default());
+        }
+
+        self.0
+            .iter()
+            .filter(|selector| selector.matches(page))
+            .map(PageSelector::specificity)
+            .max()
+    }
+}
+
+/// The sixteen page-margin boxes, which are laid out in the margin area of the page box.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MarginBoxPosition {
+    TopLeftCorner,
+    TopLeft,
+    TopCenter,
+    TopRight,
+    TopRightCorner,
+    RightTop,
+    RightMiddle,
+    RightBottom,
+    BottomRightCorner,
+    BottomRight,
+    BottomCenter,
+    BottomLeft,
+    BottomLeftCorner,
+    LeftBottom,
+    LeftMiddle,
+    LeftTop,
+}
+
+impl TryFrom<&str> for MarginBoxPosition {
+    type Error = ParserError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::ALL
+            .iter()
+            .find(|position| position.name().eq_ignore_ascii_case(value))
+            .copied()
+            .ok_or_else(|| ParserError::UnexpectedToken(value.to_owned()))
+    }
+}
+
+impl MarginBoxPosition {
+    /// The margin boxes, clockwise from the top left corner.
+    pub const ALL: &'static [Self] = &[
+        Self::TopLeftCorner,
+        Self::TopLeft,
+        Self::TopCenter,
+        Self::TopRight,
+        Self::TopRightCorner,
+        Self::RightTop,
+        Self::RightMiddle,
+        Self::RightBottom,
+        Self::BottomRightCorner,
+        Self::BottomRight,
+        Self::BottomCenter,
+        Self::BottomLeft,
+        Self::BottomLeftCorner,
+        Self::LeftBottom,
+        Self::LeftMiddle,
+        Self::LeftTop,
+    ];
+
+    /// The name of the at-rule of the margin box.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::TopLeftCorner => "top-left-corner",
+            Self::TopLeft => "top-left",
+            Self::TopCenter => "top-center",
+            Self::TopRight => "top-right",
+            Self::TopRightCorner => "top-right-corner",
+            Self::RightTop => "right-top",
+            Self::RightMiddle => "right-middle",
+            Self::RightBottom => "right-bottom",
+            Self::BottomRightCorner => "bottom-right-corner",
+            Self::BottomRight => "bottom-right",
+            Self::BottomCenter => "bottom-center",
+            Self::BottomLeft => "bottom-left",
+            Self::BottomLeftCorner => "bottom-left-corner",
+            Self::LeftBottom => "left-bottom",
+            Self::LeftMiddle => "left-middle",
+            Self::LeftTop => "left-top",
+        }
+    }
+}
+
+/// A margin rule, which styles one of the page-margin boxes.
+#[derive(Clone)]
+pub struct MarginRule {
+    pub position: MarginBoxPosition,
+    pub declarations: Vec<StyleDeclaration>,
+}
+
+/// An @page rule, which styles the page context of the pages it matches.
+#[derive(Clone)]
+pub struct PageRule {
+    pub selectors: PageSelectorList,
+    pub declarations: Vec<StyleDeclaration>,
+    pub margin_rules: Vec<MarginRule>,
+}
+
+impl TryFrom<&AtRule<'_>> for PageRule {
+    type Error = ParserError;
+
+    /// Parse an @page rule, invalid declarations and unknown margin rules are dropped.
+    fn try_from(rule: &AtRule<'_>) -> ParserResult<Self> {
+        let selectors = PageSelectorList::try_from(rule.prelude.as_slice())?;
+        let mut declarations = Vec::new();
+        let mut margin_rules = Vec::new();
+
+        for item in rule.declarations() {
+            match item {
+                DeclarationOrAtRule::Declaration(declaration) => declarations.push(declaration),
+                DeclarationOrAtRule::AtRule(margin_rule) => {
+                    let Ok(position) = MarginBoxPosition::try_from(margin_rule.name) else {
+                        continue;
+                    };
+
+                    let margin_declarations = margin_rule
+                        .declarations()
+                        .into_iter()
+                        .filter_map(|item| match item {
+                            DeclarationOrAtRule::Declaration(declaration) => Some(declaration),
+                            DeclarationOrAtRule::AtRule(_) => None,
+                        })
+                        .collect::<Vec<_>>();
+
+                    margin_rules.push(MarginRule { position, declarations: StyleDeclaration::parse_list(&margin_declarations) });
+                },
+            }
+        }
+
+        Ok(Self {
+            selectors,
+            declarations: StyleDeclaration::parse_list(&declarations),
+            margin_rules,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageOrientation {
+    Portrait,
+    Landscape,
+}
+
+/// <page-size> = A5 | A4 | A3 | B5 | B4 | JIS-B5 | JIS-B4 | letter | legal | ledger
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaperSize {
+    A5,
+    A4,
+    A3,
+    B5,
+    B4,
+    JisB5,
+    JisB4,
+    Letter,
+    Legal,
+    Ledger,
+}
+
+impl PaperSize {
+    /// The portrait dimensions of the paper, in pixels.
+    pub fn px(&self) -> BoxContent<f64> {
+        let mm = |width: f64, height: f64| BoxContent { width: width * 96.0 / 25.4, height: height * 96.0 / 25.4 };
+        let inches = |width: f64, height: f64| BoxContent { width: width * 96.0, height: height * 96.0 };
+
+        match self {
+            Self::A5 => mm(148.0, 210.0),
+            Self::A4 => mm(210.0, 297.0),
+            Self::A3 => mm(297.0, 420.0),
+            Self::B5 => mm(176.0, 250.0),
+            Self::B4 => mm(250.0, 353.0),
+            Self::JisB5 => mm(182.0, 257.0),
+            Self::JisB4 => mm(257.0, 364.0),
+            Self::Letter => inches(8.5, 11.0),
+            Self::Legal => inches(8.5, 14.0),
+            Self::Ledger => inches(11.0, 17.0),
+        }
+    }
+}
+
+/// size = <length [0,∞]>{1,2} | auto | [ <page-size> || [ portrait | landscape ] ]
+#[derive(Clone, Copy, Default)]
+pub enum PageSize {
+    /// The size of the target media, in the given orientation.
+    #[default]
+    Auto,
+    Orientation(PageOrientation),
+    Paper(PaperSize, Option<PageOrientation>),
+    Lengths(Length, Length),
+}
+
+impl TryFrom<&[ComponentValue<'_>]> for PageSize {
+    type Error = ParserError;
+
+    fn try_from(values: &[ComponentValue<'_>]) -> ParserResult<Self> {
+        let unexpected = || ParserError::UnexpectedToken(format!("{values:?}"));
+        let components = values.iter().filter(|value| !value.is_whitespace()).collect::<Vec<_>>();
+
+        let orientation = |value: &ComponentValue<'_>| match value.ident().map(str::to_ascii_lowercase).as_deref() {
+            Some("portrait") => Some(PageOrientation::Portrait),
+            Some("landscape") => Some(PageOrientation::Landscape),
+            _ => None,
+        };
+
+        let paper = |value: &ComponentValue<'_>| match value.ident().map(str::to_ascii_lowercase).as_deref() {
+            Some("a5") => Some(PaperSize::A5),
+            Some("a4") => Some(PaperSize::A4),
+            Some("a3") => Some(PaperSize::A3),
+            Some("b5") => Some(PaperSize::B5),
+            Some("b4") => Some(PaperSize::B4),
+            Some("jis-b5") => Some(PaperSize::JisB5),
+            Some("jis-b4") => Some(PaperSize::JisB4),
+            Some("letter") => Some(PaperSize::Letter),
+            Some("legal") => Some(PaperSize::Legal),
+            Some("ledger") => Some(PaperSize::Ledger),
+            _ => None,
+        };
+
+        let length = |value: &ComponentValue<'_>| Length::try_from(std::slice::from_ref(value))
+            .ok()
+            .filter(|length| length.value >= 0.0 && !matches!(length.unit, LengthUnit::Vw | LengthUnit::Vh | LengthUnit::Vmin | LengthUnit::Vmax));
+
+        let size = match components.as_slice() {
+            [value] if value.is_ident("auto") => Self::Auto,
+            [value] => match (orientation(value), paper(value), length(value)) {
+                (Some(orientation), _, _) => Self::Orientation(orientation),
+                (_, Some(paper), _) => Self::Paper(paper, None),
+                (_, _, Some(length)) => Self::Lengths(length, length),
+                _ => return Err(unexpected()),
+            },
+            [first, second] => match (paper(first), orientation(second), paper(second), orientation(first)) {
+                (Some(paper), Some(orientation), _, _) | (_, _, Some(paper), Some(orientation)) => Self::Paper(paper, Some(orientation)),
+                _ => match (length(first), length(second)) {
+                    (Some(width), Some(height)) => Self::Lengths(width, height),
+                    _ => return Err(unexpected()),
+                },
+            },
+            _ => return Err(unexpected()),
+        };
+
+        Ok(size)
+    }
+}
+
+impl PageSize {
+    /// Resolves the size of the page box, in pixels.
+    ///
+    /// ```spec
+    /// auto: The page box will be set to a size and orientation chosen by the UA.
+    /// landscape, portrait: The page box is the size of the target media, in the given orientation.
+    /// ```
+    ///
+    /// The target media has the given size.
+    pub fn px(&self, media: &BoxContent<f64>, ctx: &ResolutionContext) -> BoxContent<f64> {
+        let orient = |size: BoxContent<f64>, orientation: Option<PageOrientation>| {
+            let (short, long) = (size.width.min(size.height), size.width.max(size.height));
+
+            match orientation {
+                Some(PageOrientation::Portrait) => BoxContent { width: short, height: long },
+                Some(PageOrientation::Landscape) => BoxContent { width: long, height: short },
+                None => size,
+            }
+        };
+
+        match self {
+            Self::Auto => media.clone(),
+            Self::Orientation(orientation) => orient(media.clone(), Some(*orientation)),
+            Self::Paper(paper, orientation) => orient(paper.px(), *orientation),
+            Self::Lengths(width, height) => BoxContent { width: width.to_px(ctx), height: height.to_px(ctx) },
+        }
+    }
+}
+
+/// A part of the generated content.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContentItem {
+    String(String),
+    /// The value of a counter, such as the page or pages counters.
+    Counter(String),
+}
+
+/// content = normal | none | [ <string> | <counter()> ]+
+///
+/// Only the page-margin boxes generate content.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Content {
+    #[default]
+    Normal,
+    None,
+    Items(Vec<ContentItem>),
+}
+
+impl TryFrom<&[ComponentValue<'_>]> for Content {
+    type Error = ParserError;
+
+    fn try_from(values: &[ComponentValue<'_>]) -> ParserResult<Self> {
+        let unexpected = || ParserError::UnexpectedToken(format!("{values:?}"));
+        let components = values.iter().filter(|value| !value.is_whitespace()).collect::<Vec<_>>();
+
+        match components.as_slice() {
+            [value] if value.is_ident("normal") => return Ok(Self::Normal),
+            [value] if value.is_ident("none") => return Ok(Self::None),
+            [] => return Err(ParserError::UnexpectedEof),
+            _ => {},
+        }
+
+        components
+            .iter()
+            .map(|value| match value {
+                ComponentValue::Token(token) if token.kind == TokenKind::String => Ok(ContentItem::String(token.value.to_owned())),
+                ComponentValue::Function(function) if function.name.eq_ignore_ascii_case("counter") => {
+                    match trim_whitespace(&function.arguments) {
+                        [name] => name.ident().map(|name| ContentItem::Counter(name.to_owned())).ok_or_else(unexpected),
+                        _ => Err(unexpected()),
+                    }
+                },
+                _ => Err(unexpected()),
+            })
+            .collect::<ParserResult<Vec<_>>>()
+            .map(Self::Items)
+    }
+}
+
+impl Content {
+    /// Returns the generated text, with the value of each counter, or nothing if no content is generated.
+    pub fn text(&self, counter: impl Fn(&str) -> i32) -> Option<String> {
+        match self {
+            Self::Normal | Self::None => None,
+            Self::Items(items) => Some(
+                items
+                    .iter()
+                    .map(|item| match item {
+                        ContentItem::String(string) => string.clone(),
+                        ContentItem::Counter(name) => counter(name).to_string(),
+                    })
+                    .collect()
+            ),
+        }
+    }
+}
+
+/// page = auto | <custom-ident>
+///
+/// ```spec
+/// The page property is used to specify the type of page on which an element is displayed.
+/// A forced page break is introduced where the value changes between siblings.
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum PageName {
+    #[default]
+    Auto,
+    Named(String),
+}
+
+impl TryFrom<&[ComponentValue<'_>]> for PageName {
+    type Error = ParserError;
+
+    fn try_from(values: &[ComponentValue<'_>]) -> ParserResult<Self> {
+        match trim_whitespace(values) {
+            [value] if value.is_ident("auto") => Ok(Self::Auto),
+            [value] => match value.ident() {
+                Some(name) if !["initial", "inherit", "unset", "revert", "default"].iter().any(|keyword| name.eq_ignore_ascii_case(keyword)) => {
+                    Ok(Self::Named(name.to_owned()))
+                },
+                _ => Err(ParserError::UnexpectedToken(format!("{values:?}"))),
+            },
+            _ => Err(ParserError::UnexpectedToken(format!("{values:?}"))),
+        }
+    }
+}
+
+impl PageName {
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Self::Auto => None,
+            Self::Named(name) => Some(name),
+        }
+    }
+}
+
+fn trim_whitespace<'a, 'i>(values: &'a [ComponentValue<'i>]) -> &'a [ComponentValue<'i>] {
+    let start = values.iter().position(|value| !value.is_whitespace()).unwrap_or(values.len());
+    let end = values.iter().rposition(|value| !value.is_whitespace()).map(|end| end + 1).unwrap_or(start);
+    &values[start..end]
+}
+
+#[cfg(test)]
+mod test {
+    use crate::style::{
+        parser::parser::{parse_list_of_component_values, parse_stylesheet, ComponentValue, Rule},
+        selector::Specificity,
+        values::{numeric::ResolutionContext, r#box::BoxContent},
+    };
+
+    use super::{Content, ContentItem, MarginBoxPosition, Page, PageRule, PageSelectorList, PageSize};
+
+    fn parse(css: &str) -> Vec<ComponentValue<'_>> {
+        parse_list_of_component_values(css).unwrap()
+    }
+
+    #[test]
+    fn test_page_selectors() {
+        let selectors = PageSelectorList::try_from(parse("chapter:first, :left").as_slice()).unwrap();
+        let page = |index, name| Page { index, name, blank: false };
+
+        assert_eq!(selectors.matches(&page(0, Some("chapter"))), Some(Specificity(1, 1, 0)));
+        assert_eq!(selectors.matches(&page(1, None)), Some(Specificity(0, 0, 1)));
+        assert_eq!(selectors.matches(&page(2, Some("chapter"))), None);
+        assert_eq!(PageSelectorList::try_from(parse("").as_slice()).unwrap().matches(&page(3, None)), Some(Specificity::default()));
+        assert!(PageSelectorList::try_from(parse("chapter :first").as_slice()).is_err());
+        assert!(PageSelectorList::try_from(parse(":middle").as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_page_rule() {
+        let stylesheet = parse_stylesheet(r#"
+            @page :first {
+                size: A4 landscape;
+                margin: 1in;
+                @top-center { content: "Page " counter(page) " of " counter(pages) }
+                @middle-center { content: "dropped" }
+            }
+        "#);
+
+        let Some(Rule::At(rule)) = stylesheet.rules.first() else {
+            panic!("expected an at-rule");
+        };
+
+        let rule = PageRule::try_from(rule).unwrap();
+        assert_eq!(rule.declarations.len(), 5);
+        assert_eq!(rule.margin_rules.len(), 1);
+        assert_eq!(rule.margin_rules[0].position, MarginBoxPosition::TopCenter);
+
+        let ctx = ResolutionContext::default();
+        let media = BoxContent { width: 800.0, height: 600.0 };
+        let size = |css: &str| PageSize::try_from(parse(css).as_slice()).unwrap().px(&media, &ctx);
+        assert_eq!(size("landscape A5").width.round(), 794.0);
+        assert_eq!(size("letter").width, 816.0);
+        assert_eq!(size("portrait").width, 600.0);
+        assert_eq!(size("10px 20px").height, 20.0);
+        assert!(PageSize::try_from(parse("A4 A5").as_slice()).is_err());
+
+        let content = Content::try_from(parse(r#""Page " counter(page)"#).as_slice()).unwrap();
+        assert_eq!(content, Content::Items(vec![ContentItem::String("Page ".to_owned()), ContentItem::Counter("page".to_owned())]));
+        assert_eq!(content.text(|_| 3).as_deref(), Some("Page 3"));
+        assert_eq!(Content::None.text(|_| 3), None);
+    }
+}
@@ -1,4 +1,4 @@
-use std::{cmp::min, ops::{Deref, Range, RangeInclusive}, str::CharIndices};
+use std::{ops::{Deref, Range, RangeInclusive}, str::CharIndices};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TokenKind<'input> {
@@ -20,6 +20,7 @@ pub enum TokenKind<'input> {
     LeftCurlyBracket,
     RightCurlyBracket,
     String,
+    BadString,
     Newline,
     Whitespace,
     Function,
@@ -44,13 +45,6 @@ impl<'src> Token<'src> {
     }
 }
 
-#[derive(Debug)]
-pub enum LexicalError {
-    UnexpectedEof
-}
-
-pub type LexerResult<T> = Result<T, LexicalError>;
-
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct Exponent<'input> {
     pub neg: bool,
@@ -65,6 +59,39 @@ pub struct Number<'input> {
     exponent: Option<Exponent<'input>>
 }
 
+impl Number<'_> {
+    /// Returns the numeric value of the number.
+    pub fn value(&self) -> f64 {
+        let mut repr = String::new();
+
+        if self.neg {
+            repr.push('-');
+        }
+
+        repr.push_str(self.integer.unwrap_or("0"));
+
+        if let Some(decimal) = self.decimal {
+            repr.push('.');
+            repr.push_str(decimal);
+        }
+
+        if let Some(exponent) = &self.exponent {
+            repr.push('e');
+            if exponent.neg {
+                repr.push('-');
+            }
+            repr.push_str(exponent.value);
+        }
+
+        repr.parse().unwrap_or_default()
+    }
+
+    /// Checks if the number has an integer type.
+    pub fn is_integer(&self) -> bool {
+        self.decimal.is_none() && self.exponent.is_none()
+    }
+}
+
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct Dimension<'input> {
     pub number: Number<'input>,
@@ -124,39 +151,45 @@ impl<'input> Lexer<'input> {
         }
     }
 
+    /// Returns the next characters, at most `size` of them.
     fn peek_str(&self, size: usize) -> &'input str {
-        if self.current_pos().is_none() {
+        let Some(start) = self.current_pos() else {
             return ""
-        }
+        };
 
-        let start = self.current_pos().unwrap();
-        let end = min(start+size-1, self.input.len() - 1);
-        &self.input.get(start..=end).unwrap_or_default()
+        let end = self.peek_indice(size)
+            .map(|(pos, _)| pos)
+            .unwrap_or(self.input.len());
+
+        &self.input[start..end]
     }
     
     fn current_pos(&self) -> Option<usize> {
         self.peek_indice(0).map(|(pos, _)| pos)
     }
 
-    fn consume_comment(&mut self) -> LexerResult<Span<Token<'input>>>  {
+    fn consume_comment(&mut self) -> Span<Token<'input>>  {
         assert!(self.peek_str(2) == "/*");
 
         let start = self.current_pos().unwrap();
 
-        while self.peek_str(2) != "*/" {
+        // An unclosed comment ends with the input.
+        let end = self.input[start + 2..]
+            .find("*/")
+            .map(|pos| start + 2 + pos + 1)
+            .unwrap_or(self.input.len() - 1);
+
+        while self.current_pos().is_some_and(|pos| pos <= end) {
             self.consume_char(1);
         }
 
-        self.consume_char(2);
-        let end = self.current_pos().unwrap();
-
-        Ok(Span {
+        Span {
             loc: start..=end,
             value: Token {
                 kind: TokenKind::Comment,
                 value: &self.input[start..=end]
             }
-        })
+        }
     }
 
 
@@ -164,187 +197,210 @@ impl<'input> Lexer<'input> {
         self.consume_while(Self::is_whitespace);
     }
 
-    fn consume_whitespace_token(&mut self) -> LexerResult<Span<Token<'input>>> {
+    fn consume_whitespace_token(&mut self) -> Span<Token<'input>> {
         let (loc, value) = self.consume_while(Self::is_whitespace).unwrap();
-        Ok(Span {
+        Span {
             loc,
             value: Token {
                 kind: TokenKind::Whitespace,
                 value
             }
-        })
+        }
     }
 
-    fn consume_string_token(&mut self) -> LexerResult<Span<Token<'input>>> {        
-        let expected_delimiter = self.peek_char(0)
-            .ok_or_else(|| LexicalError::UnexpectedEof)?;
-        
-        let start = self.current_pos()
-            .ok_or_else(|| LexicalError::UnexpectedEof)?;
+    fn consume_string_token(&mut self) -> Span<Token<'input>> {        
+        let (start, expected_delimiter) = self.peek_indice(0).unwrap();
         
         self.consume_char(1); // consume delimiter
 
-        let str_start = self.current_pos()
-            .ok_or_else(|| LexicalError::UnexpectedEof)?;
-        let mut str_end = str_start - 1;
+        let str_start = start + expected_delimiter.len_utf8();
+        let mut str_end = str_start;
+        let mut end = self.input.len() - 1;
+        let mut kind = TokenKind::String;
+
+        while let Some((pos, ch)) = self.peek_indice(0) {
+            // consume the other delimiter
+            if ch == expected_delimiter {
+                end = pos;
+                self.consume_char(1);
+                break;
+            }
+
+            // an unescaped newline makes it a bad string
+            if ch == '\n' {
+                end = pos - 1;
+                kind = TokenKind::BadString;
+                break;
+            }
 
-        while self.peek_char(0) != Some(expected_delimiter) {
             // an escape
             if self.current_is_valid_escape() {
                 self.consume_char(2);
-                str_end += 2;
             } else {
                 self.consume_char(1);
-                str_end += 1;
             }
-        }   
 
-        // consume the other 
-        if self.peek_char(0) == Some(expected_delimiter) {
-            self.consume_char(1);
-        }
-
-        let end = self.current_pos()
-            .ok_or_else(|| LexicalError::UnexpectedEof)?;
+            str_end = self.current_pos().unwrap_or(self.input.len());
+        }   
 
-        let value = &self.input[str_start..=str_end];
+        let value = &self.input[str_start..str_end];
 
-        Ok(Span {
+        Span {
             loc: start..=end,
             value: Token {
-                kind: TokenKind::String,
+                kind,
                 value
             }
-        })
+        }
 
     }
 
-    fn consume_url_token(&mut self) -> LexerResult<Span<Token<'input>>> {
+    fn consume_url_token(&mut self) -> Span<Token<'input>> {
         assert!(self.peek_str(4) == "url(");
         let start = self.current_pos().unwrap();
-        let mut end = start;
         
         self.consume_char(4);
         self.consume_whitespaces();
 
         if self.peek_char(0) == Some('"') || self.peek_char(0) == Some('\'') {
-            end += 3;
-            return Ok(Span {
-                loc: start..=end,
+            return Span {
+                loc: start..=start + 3,
                 value: Token {
                     kind: TokenKind::Function,
-                    value: &"url("
+                    value: "url("
                 }
-            })
+            }
         }
 
-        let mut url_value_start = self.current_pos().unwrap();
+        // The value spans from its start, included, to its end, excluded.
+        let url_value_start = self.current_pos().unwrap_or(self.input.len());
         let mut url_value_end = url_value_start;
 
-        while let Some(ch) = self.peek_char(0) {
-            match ch {
-                // consume whitespaces
-                ch if Self::is_whitespace(ch) => {
+        loop {
+            match self.peek_indice(0) {
+                // eof, a parse error: the url is returned as is
+                None => return self.url_token(start..=self.input.len() - 1, url_value_start..url_value_end),
+                Some((pos, ')')) => {
+                    self.consume_char(1);
+                    return self.url_token(start..=pos, url_value_start..url_value_end)
+                },
+                // trailing whitespaces, which must be followed by the end of the url
+                Some((_, ch)) if Self::is_whitespace(ch) => {
                     self.consume_whitespaces();
+
+                    match self.peek_indice(0) {
+                        None => return self.url_token(start..=self.input.len() - 1, url_value_start..url_value_end),
+                        Some((pos, ')')) => {
+                            self.consume_char(1);
+                            return self.url_token(start..=pos, url_value_start..url_value_end)
+                        },
+                        Some(_) => return self.consume_bad_url_remnants(start, url_value_start)
+                    }
                 },
                 // bad-url
-                '"' | '\'' | '(' => {
-                    end = self.current_pos().unwrap();
-                    self.consume_char(1);
-                    
-                    while let Some((pos, ch)) = self.peek_indice(0)  {
-                        end = pos;
-                        self.consume_char(1);
-                             
-                        if ch == ')' {
-                            break;
-                        } else {
-                            url_value_end = self.current_pos().unwrap()
-                        }
-                    }                   
-                    let value = &self.input[url_value_start..url_value_end];
-                    return Ok(Span {
-                        loc: start..=end,
-                        value: Token {
-                            value,
-                            kind: TokenKind::BadUrl
-                        }
-                    })
+                Some((_, '"' | '\'' | '(')) => return self.consume_bad_url_remnants(start, url_value_start),
+                Some((_, ch)) if Self::is_non_printable(ch) => return self.consume_bad_url_remnants(start, url_value_start),
+                // escape, kept in the value
+                Some((_, '\\')) => {
+                    if self.peek_char(1) == Some('\n') {
+                        return self.consume_bad_url_remnants(start, url_value_start);
+                    }
 
-                },
-                // escape
-                '\\' => {
                     self.consume_char(2);
-                    url_value_start += 2;
+                    url_value_end = self.current_pos().unwrap_or(self.input.len());
                 },
-                ')' => {
-                    let value = &self.input[url_value_start..=url_value_end];
-
-                    end = self.current_pos().unwrap();
-                    self.consume_char(1);
-                    
-                    return Ok(Span {
-                        loc: start..=end,
-                        value: Token {
-                            value,
-                            kind: TokenKind::Url
-                        }
-                    })
-                },
-                _ => {
-                    url_value_end = self.current_pos().unwrap();
+                Some(_) => {
                     self.consume_char(1);
+                    url_value_end = self.current_pos().unwrap_or(self.input.len());
                 }
             }
         }
+    }
 
-        unreachable!("unexpected eof")
+    fn url_token(&self, loc: RangeInclusive<usize>, value: Range<usize>) -> Span<Token<'input>> {
+        Span {
+            loc,
+            value: Token {
+                value: &self.input[value],
+                kind: TokenKind::Url
+            }
+        }
+    }
+
+    /// Consumes the remnants of a bad url, up to the closing parenthesis or the end of the input.
+    fn consume_bad_url_remnants(&mut self, start: usize, url_value_start: usize) -> Span<Token<'input>> {
+        let mut end = self.input.len() - 1;
+        let mut url_value_end = self.input.len();
+
+        while let Some((pos, ch)) = self.peek_indice(0) {
+            if ch == ')' {
+                end = pos;
+                url_value_end = pos;
+                self.consume_char(1);
+                break;
+            }
+
+            // an escaped closing parenthesis does not end the url
+            if ch == '\\' && self.peek_char(1).is_some_and(|ch| ch != '\n') {
+                self.consume_char(2);
+            } else {
+                self.consume_char(1);
+            }
+        }
+
+        Span {
+            loc: start..=end,
+            value: Token {
+                value: &self.input[url_value_start..url_value_end],
+                kind: TokenKind::BadUrl
+            }
+        }
     }
 
     fn consume_ident_sequence(&mut self) -> (RangeInclusive<usize>, &'input str) {
         let start = self.current_pos().unwrap();
-        let mut end = start;
 
         while let Some(ch) = self.peek_char(0) {
             if Self::is_valid_escape(self.peek_str(2)) {
                 self.consume_char(2);
-                end += 2;
             } else if Self::is_ident_code_point(ch) {
                 self.consume_char(1);
-                end += 1;
             } else {
                 break;
             }
         }
 
-        (start..=end-1, &self.input[start..=end-1])
+        let end = self.current_pos().unwrap_or(self.input.len());
+
+        (start..=end-1, &self.input[start..end])
     }
 
-    fn consume_hash(&mut self) -> LexerResult<Span<Token<'input>>> {
+    fn consume_hash(&mut self) -> Span<Token<'input>> {
         assert!(self.peek_char(0) == Some('#'));
         let start = self.current_pos().unwrap();
         
         self.consume_char(1);
 
-        if self.current_would_start_ident_sequence() {
+        if self.current_is_ident_code_point() || self.current_is_valid_escape() {
+            let is_id = self.current_would_start_ident_sequence();
             let (id_loc, value) = self.consume_ident_sequence();
 
-            return Ok(Span {
+            Span {
                 loc: start..=*id_loc.end(),
                 value: Token {
-                    kind: TokenKind::Hash(true),
+                    kind: TokenKind::Hash(is_id),
                     value
                 }
-            })
+            }
 
         } else {
-            Ok(Span {
+            Span {
                 loc: start..=start,
                 value: Token {
                     kind: TokenKind::Delim,
-                    value: &"#"
+                    value: "#"
                 }
-            })
+            }
         }
     }
 
@@ -374,7 +430,7 @@ impl<'input> Lexer<'input> {
     fn consume_signed_digit_sequence(&mut self) -> Option<(RangeInclusive<usize>, &'input str, bool, &'input str)> {
         let start = self.current_pos()?;
 
-        let neg_sign = self.peek_char(0).map(|ch| ch == '-').inspect(|_| self.consume_char(1)).unwrap_or_default();
+        let neg_sign = self.consume_sign();
         
         let (loc, digits) = self.consume_digit_sequence()?;
         let end = *loc.end();
@@ -383,13 +439,28 @@ impl<'input> Lexer<'input> {
 
     }
 
-    fn consume_number(&mut self) -> LexerResult<(RangeInclusive<usize>, Number<'input>)> {
+    /// Consume an optional sign, returns true if it is a minus sign.
+    fn consume_sign(&mut self) -> bool {
+        match self.peek_char(0) {
+            Some('-') => {
+                self.consume_char(1);
+                true
+            },
+            Some('+') => {
+                self.consume_char(1);
+                false
+            },
+            _ => false
+        }
+    }
+
+    fn consume_number(&mut self) -> (RangeInclusive<usize>, Number<'input>) {
         let mut nb = Number::default();
 
         let start = self.current_pos().unwrap();
         let mut end = start;
 
-        nb.neg = self.peek_char(0).map(|ch| ch == '-').inspect(|_| self.consume_char(1)).unwrap_or_default();
+        nb.neg = self.consume_sign();
 
         self.consume_digit_sequence()
         .into_iter()
@@ -398,14 +469,20 @@ impl<'input> Lexer<'input> {
             end = *l.end();
         });
 
-        if self.peek_char(0) == Some('.') {
+        if self.peek_char(0) == Some('.') && self.peek_char(1).map(Self::is_digit).unwrap_or_default() {
             self.consume_char(1);
             let (l, decimal) = self.consume_digit_sequence().unwrap();
             end = *l.end();
             nb.decimal = Some(decimal);
         }
 
-        if self.peek_char(0) == Some('e') || self.peek_char(0) == Some('E') {
+        let starts_exponent = match (self.peek_char(0), self.peek_char(1), self.peek_char(2)) {
+            (Some('e' | 'E'), Some(digit), _) if Self::is_digit(digit) => true,
+            (Some('e' | 'E'), Some('+' | '-'), Some(digit)) => Self::is_digit(digit),
+            _ => false
+        };
+
+        if starts_exponent {
             self.consume_char(1);
             let (l, _, neg, value) = self.consume_signed_digit_sequence().unwrap();
             end = *l.end();
@@ -415,11 +492,11 @@ impl<'input> Lexer<'input> {
             });
         }
 
-        Ok((start..=end, nb))
+        (start..=end, nb)
     }
 
-    fn consume_numeric_token(&mut self) -> LexerResult<Span<Token<'input>>> {
-        let (num_loc, number) = self.consume_number()?;
+    fn consume_numeric_token(&mut self) -> Span<Token<'input>> {
+        let (num_loc, number) = self.consume_number();
 
         let start = *num_loc.start();
         let mut end = *num_loc.end();
@@ -428,39 +505,39 @@ impl<'input> Lexer<'input> {
             let (dim_loc, unit) = self.consume_ident_sequence();
             end = *dim_loc.end();
 
-            return Ok(Span {
+            return Span {
                 loc: start..=end,
                 value: Token {
                     kind: TokenKind::Dimension(Dimension { number, unit }),
                     value: &self.input[start..=end]
                 }
-            });
+            };
         }
 
         if self.current_is_percentage_sign() {
             end = self.current_pos().unwrap();
             self.consume_char(1);
             
-            return Ok(Span {
+            return Span {
                 loc: start..=end,
                 value: Token {
                     kind: TokenKind::Percentage(number),
                     value: &self.input[start..=end]
                 }
-            });
+            };
         }
 
-        return Ok(Span {
+        Span {
             loc: start..=end,
             value: Token {
                 kind: TokenKind::Number(number),
                 value: &self.input[start..=end]
             }
-        });    
+        }
 
     }
 
-    fn consume_ident_token(&mut self) -> LexerResult<Span<Token<'input>>> {
+    fn consume_ident_token(&mut self) -> Span<Token<'input>> {
         // url-token
         if self.peek_str(4) == "url(" {
             return self.consume_url_token();
@@ -472,47 +549,47 @@ impl<'input> Lexer<'input> {
             self.consume_char(1);
             loc = *loc.start()..=*loc.end()+1;
             
-            return Ok(Span {
+            return Span {
                 loc: loc.clone(),
                 value: Token {
                     kind: TokenKind::Function,
                     value: &self.input[loc]
                 }
-            })
+            }
         }
 
         // ident-token
-        Ok(Span {
+        Span {
             loc,
             value: Token {
                 kind: TokenKind::Ident,
                 value
             }
-        })
+        }
     }
 
-    fn consume_at_token(&mut self) -> LexerResult<Span<Token<'input>>> {
+    fn consume_at_token(&mut self) -> Span<Token<'input>> {
         let start = self.current_pos().unwrap();
         self.consume_char(1);
 
         if self.current_would_start_ident_sequence() {
             let (loc, value) = self.consume_ident_sequence();
-            return Ok(Span{
+            return Span{
                 loc: start..=*loc.end(),
                 value: Token {
                     kind: TokenKind::At,
                     value
                 }
-            })
+            }
         }
 
-        return Ok(Span{
+        Span{
             loc: start..=start,
             value: Token {
                 kind: TokenKind::Delim,
                 value: "@"
             }
-        })
+        }
     }
 }
 
@@ -545,10 +622,10 @@ impl Lexer<'_> {
         }
 
         if self.peek_char(0).map(|ch| ch == '.').unwrap_or_default() {
-            return self.peek_char(2).map(Self::is_digit).unwrap_or_default();
+            return self.peek_char(1).map(Self::is_digit).unwrap_or_default();
         }
 
-        return false;
+        false
     }
 
     pub fn current_is_percentage_sign(&self) -> bool {
@@ -558,15 +635,19 @@ impl Lexer<'_> {
 
 impl Lexer<'_> {
     fn is_digit(ch: char) -> bool {
-        ch.is_numeric()
+        ch.is_ascii_digit()
     }
 
     fn is_whitespace(ch: char) -> bool {
-        ch == ' ' || ch == '\t' || ch == '\n'
+        matches!(ch, ' ' | '\t' | '\n' | '\r' | '\x0C')
+    }
+
+    fn is_non_printable(ch: char) -> bool {
+        matches!(ch, '\x00'..='\x08' | '\x0B' | '\x0E'..='\x1F' | '\x7F')
     }
 
     fn is_valid_escape(txt: &str) -> bool {
-        txt.len() == 2 
+        txt.chars().count() == 2 
         && txt.starts_with("\\")
     }
 
@@ -577,7 +658,7 @@ impl Lexer<'_> {
     }
 
     fn is_ident_start_code_point(ch: char) -> bool {
-        ch.is_alphanumeric() || ch == '_'
+        ch.is_alphabetic() || !ch.is_ascii() || ch == '_'
     }
 
     fn would_start_and_ident_sequence(txt: &str) -> bool {
@@ -594,7 +675,7 @@ impl Lexer<'_> {
                 if let Some(second) = txt.chars().nth(1) {
                     return second == '-'
                         || Self::is_ident_code_point(second)
-                        || Self::is_valid_escape(&txt[1..=2]);
+                        || Self::is_valid_escape(&txt[1..]);
                 }
             }
         }
@@ -604,7 +685,7 @@ impl Lexer<'_> {
 }
 
 impl<'input> Lexer<'input> {
-    pub fn peek(&self, n: usize) -> Option<LexerResult<SpannedToken<'input>>> {
+    pub fn peek(&self, n: usize) -> Option<SpannedToken<'input>> {
         self.clone().nth(n)
     }
 
@@ -616,7 +697,7 @@ impl<'input> Lexer<'input> {
 }
 
 impl<'input> Iterator for Lexer<'input> {
-    type Item = LexerResult<Span<Token<'input>>>;
+    type Item = Span<Token<'input>>;
 
     fn next(&mut self) -> Option<Self::Item> {
         self.peek_char(0).map(|ch| match ch {
@@ -627,198 +708,198 @@ impl<'input> Iterator for Lexer<'input> {
             '('  => {
                 let start = self.current_pos().unwrap();
                 self.consume_char(1);
-                Ok(Span {
+                Span {
                     loc: start..=start,
                     value: Token {
                         kind: TokenKind::LeftPar,
-                        value: &"("
+                        value: "("
                     }
-                })
+                }
             },
             ')' => {
                 let start = self.current_pos().unwrap();
                 self.consume_char(1);
-                Ok(Span {
+                Span {
                     loc: start..=start,
                     value: Token {
                         kind: TokenKind::RightPar,
-                        value: &")"
+                        value: ")"
                     }
-                })
+                }
             },
             ',' => {
                 let start = self.current_pos().unwrap();
                 self.consume_char(1);
-                Ok(Span {
+                Span {
                     loc: start..=start,
                     value: Token {
                         kind: TokenKind::Comma,
-                        value: &","
+                        value: ","
                     }
-                })               
+                }               
             },
             '+' if self.current_would_start_number() => self.consume_numeric_token(),
             '+' => {
                 let start = self.current_pos().unwrap();
                 self.consume_char(1);
-                Ok(Span {
+                Span {
                     loc: start..=start,
                     value: Token {
                         kind: TokenKind::Delim,
-                        value: &"+"
+                        value: "+"
                     }
-                })               
+                }               
             },
             '-' if self.current_would_start_number() => self.consume_numeric_token(),
             '-' if self.current_would_start_ident_sequence() => self.consume_ident_token(),
             '-' if self.peek_str(3) == "-->" => {
                 let start = self.current_pos().unwrap();
                 self.consume_char(3);
-                Ok(Span {
+                Span {
                     loc: start..=start+2,
                     value: Token {
                         kind: TokenKind::CDC,
-                        value: &"-->"
+                        value: "-->"
                     }
-                })  
+                }  
             },
             '-' => {
                 let start = self.current_pos().unwrap();
                 self.consume_char(1);
-                Ok(Span {
+                Span {
                     loc: start..=start,
                     value: Token {
                         kind: TokenKind::Delim,
-                        value: &"-"
+                        value: "-"
                     }
-                })   
+                }   
             },
             '.' if self.current_would_start_number() => self.consume_numeric_token(),
             '.' => {
                 let start = self.current_pos().unwrap();
                 self.consume_char(1);
-                Ok(Span {
+                Span {
                     loc: start..=start,
                     value: Token {
                         kind: TokenKind::Delim,
-                        value: &"."
+                        value: "."
                     }
-                })  
+                }  
             },
             ':' => {
                 let start = self.current_pos().unwrap();
                 self.consume_char(1);
-                Ok(Span {
+                Span {
                     loc: start..=start,
                     value: Token {
                         kind: TokenKind::Colon,
-                        value: &":"
+                        value: ":"
                     }
-                })  
+                }  
             },
             ';' => {
                 let start = self.current_pos().unwrap();
                 self.consume_char(1);
-                Ok(Span {
+                Span {
                     loc: start..=start,
                     value: Token {
                         kind: TokenKind::Semicolon,
-                        value: &";"
+                        value: ";"
                     }
-                })  
+                }  
             },
             '<' if self.peek_str(4) == "<!--" => {
                 let start = self.current_pos().unwrap();
                 self.consume_char(4);
-                Ok(Span {
+                Span {
                     loc: start..=start+3,
                     value: Token {
-                        kind: TokenKind::Semicolon,
-                        value: &"<!--"
+                        kind: TokenKind::CDO,
+                        value: "<!--"
                     }
-                })  
+                }  
             },
             '<' => {
                 let start = self.current_pos().unwrap();
                 self.consume_char(1);
-                Ok(Span {
+                Span {
                     loc: start..=start,
                     value: Token {
                         kind: TokenKind::Delim,
-                        value: &"<"
+                        value: "<"
                     }
-                })               
+                }               
             }
             '@' => self.consume_at_token(),
             '[' => {
                 let start = self.current_pos().unwrap();
                 self.consume_char(1);
-                Ok(Span {
+                Span {
                     loc: start..=start,
                     value: Token {
                         kind: TokenKind::LeftSquareBracket,
-                        value: &"["
+                        value: "["
                     }
-                })               
+                }               
             },
             '\\' if self.current_is_valid_escape() => self.consume_ident_token(),
             '\\' => {
                 let start = self.current_pos().unwrap();
                 self.consume_char(1);
-                Ok(Span {
+                Span {
                     loc: start..=start,
                     value: Token {
                         kind: TokenKind::Delim,
-                        value: &"\\"
+                        value: "\\"
                     }
-                })               
+                }               
             },
             ']' => {
                 let start = self.current_pos().unwrap();
                 self.consume_char(1);
-                Ok(Span {
+                Span {
                     loc: start..=start,
                     value: Token {
                         kind: TokenKind::RightSquareBracket,
-                        value: &"]"
+                        value: "]"
                     }
-                })               
+                }               
             },
             '{' => {
                 let start = self.current_pos().unwrap();
                 self.consume_char(1);
-                Ok(Span {
+                Span {
                     loc: start..=start,
                     value: Token {
                         kind: TokenKind::LeftCurlyBracket,
-                        value: &"{"
+                        value: "{"
                     }
-                })               
+                }               
             },
             '}' => {
                 let start = self.current_pos().unwrap();
                 self.consume_char(1);
-                Ok(Span {
+                Span {
                     loc: start..=start,
                     value: Token {
                         kind: TokenKind::RightCurlyBracket,
-                        value: &"}"
+                        value: "}"
                     }
-                })               
+                }               
             },
             ch if Self::is_digit(ch) => self.consume_numeric_token(),
             ch if Self::is_ident_start_code_point(ch) => self.consume_ident_token(),
-            _ => {
+            ch => {
                 let start = self.current_pos().unwrap();
                 let value = self.peek_str(1);
                 self.consume_char(1);
-                Ok(Span {
-                    loc: start..=start,
+                Span {
+                    loc: start..=start + ch.len_utf8() - 1,
                     value: Token {
                         kind: TokenKind::Delim,
                         value
                     }
-                })               
+                }               
             }
         })
     }
@@ -828,12 +909,12 @@ impl<'input> Iterator for Lexer<'input> {
 mod test {
     use crate::style::parser::lexer::Exponent;
 
-    use super::{Lexer, LexerResult, Token, Span, TokenKind, Number, Dimension};
+    use super::{Lexer, Token, Span, TokenKind, Number, Dimension};
 
     #[test]
     fn test_whitespace_token() {
         let lexer = Lexer::new("   ");
-        let tokens = lexer.collect::<LexerResult<Vec<_>>>().ok().unwrap();
+        let tokens = lexer.collect::<Vec<_>>();
 
         assert_eq!(
             tokens,
@@ -852,7 +933,7 @@ mod test {
     #[test]
     fn test_ident_token() {
         let lexer = Lexer::new("--0123id_token");
-        let tokens = lexer.collect::<LexerResult<Vec<_>>>().unwrap();
+        let tokens = lexer.collect::<Vec<_>>();
 
         assert_eq!(
             tokens,
@@ -871,7 +952,7 @@ mod test {
     #[test]
     fn test_url_token() {
         let lexer = Lexer::new("url(  http://www.test.lan  )");
-        let tokens = lexer.collect::<LexerResult<Vec<_>>>().unwrap();
+        let tokens = lexer.collect::<Vec<_>>();
 
         assert_eq!(
             tokens,
@@ -890,7 +971,7 @@ mod test {
     #[test]
     fn test_bad_url_token() {
         let lexer = Lexer::new("url(  (badurl  )");
-        let tokens = lexer.collect::<LexerResult<Vec<_>>>().unwrap();
+        let tokens = lexer.collect::<Vec<_>>();
 
         assert_eq!(
             tokens,
@@ -906,10 +987,34 @@ mod test {
         )
     }
 
+    #[test]
+    fn test_url_token_recovery() {
+        let url = |input| Lexer::new(input).collect::<Vec<_>>();
+
+        // An unclosed url ends with the input.
+        assert_eq!(url("url("), vec![Span::new(Token::new("", TokenKind::Url), 0..=3)]);
+        assert_eq!(url("url(a"), vec![Span::new(Token::new("a", TokenKind::Url), 0..=4)]);
+        assert_eq!(url("url(a b"), vec![Span::new(Token::new("a b", TokenKind::BadUrl), 0..=6)]);
+
+        // Non-ASCII urls are sliced on character boundaries.
+        assert_eq!(url("url(café.png)"), vec![Span::new(Token::new("café.png", TokenKind::Url), 0..=13)]);
+        assert_eq!(url("url(€)"), vec![Span::new(Token::new("€", TokenKind::Url), 0..=7)]);
+
+        assert_eq!(url("url()"), vec![Span::new(Token::new("", TokenKind::Url), 0..=4)]);
+
+        // Escapes are kept in the value, and do not end the url.
+        assert_eq!(url("url(a\\)b)"), vec![Span::new(Token::new("a\\)b", TokenKind::Url), 0..=8)]);
+        assert_eq!(url("url((a\\)b) c"), vec![
+            Span::new(Token::new("(a\\)b", TokenKind::BadUrl), 0..=9),
+            Span::new(Token::new(" ", TokenKind::Whitespace), 10..=10),
+            Span::new(Token::new("c", TokenKind::Ident), 11..=11),
+        ]);
+    }
+
     #[test]
     fn test_function_token() {
         let lexer = Lexer::new("func(");
-        let tokens = lexer.collect::<LexerResult<Vec<_>>>().unwrap();   
+        let tokens = lexer.collect::<Vec<_>>();   
 
         assert_eq!(
             tokens,
@@ -928,7 +1033,7 @@ mod test {
     #[test]
     fn test_function_token_with_url() {
         let lexer = Lexer::new("url('arg')");
-        let tokens = lexer.collect::<LexerResult<Vec<_>>>().unwrap();   
+        let tokens = lexer.collect::<Vec<_>>();   
 
         assert_eq!(
             tokens,
@@ -939,7 +1044,7 @@ mod test {
                 ),
                 Span::new(
                     Token::new("arg",  TokenKind::String),
-                    4..=9                  
+                    4..=8                  
                 ),
                 Span::new(
                     Token::new(")", TokenKind::RightPar),
@@ -952,7 +1057,7 @@ mod test {
     #[test]
     fn test_numeric_token() {
         let lexer = Lexer::new("-12.345e-678");
-        let tokens = lexer.collect::<LexerResult<Vec<_>>>().unwrap();
+        let tokens = lexer.collect::<Vec<_>>();
         
         assert_eq!(
             tokens,
@@ -976,7 +1081,7 @@ mod test {
     #[test]
     fn test_percentage_token() {
         let lexer = Lexer::new("-12.345e-678%");
-        let tokens = lexer.collect::<LexerResult<Vec<_>>>().unwrap();
+        let tokens = lexer.collect::<Vec<_>>();
         
         assert_eq!(
             tokens,
@@ -1000,7 +1105,7 @@ mod test {
     #[test]
     fn test_dimension_token() {
         let lexer = Lexer::new("-12.345e-678px");
-        let tokens = lexer.collect::<LexerResult<Vec<_>>>().unwrap();
+        let tokens = lexer.collect::<Vec<_>>();
         
         assert_eq!(
             tokens,
@@ -1027,7 +1132,7 @@ mod test {
     #[test]
     fn test_at_token() {
         let lexer = Lexer::new("@foo");
-        let tokens = lexer.collect::<LexerResult<Vec<_>>>().unwrap();
+        let tokens = lexer.collect::<Vec<_>>();
 
         assert_eq!(
             tokens,
@@ -1046,7 +1151,7 @@ mod test {
     #[test]
     fn test_hash_token() {
         let lexer = Lexer::new("#foo");
-        let tokens = lexer.collect::<LexerResult<Vec<_>>>().unwrap();
+        let tokens = lexer.collect::<Vec<_>>();
 
         assert_eq!(
             tokens,
@@ -1065,7 +1170,7 @@ mod test {
     #[test]
     fn test_string_token() {
         let lexer = Lexer::new("'this is a string'");
-        let tokens = lexer.collect::<LexerResult<Vec<_>>>().unwrap();
+        let tokens = lexer.collect::<Vec<_>>();
 
         assert_eq!(
             tokens,
@@ -1075,9 +1180,34 @@ mod test {
                         "this is a string",
                         TokenKind::String
                     ),
-                    0..=1
+                    0..=17
                 )
             ]
         )
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_comment_token() {
+        let comment = |input| Lexer::new(input).collect::<Vec<_>>();
+
+        assert_eq!(comment("/* a */"), vec![Span::new(Token::new("/* a */", TokenKind::Comment), 0..=6)]);
+        // An unclosed comment ends with the input.
+        assert_eq!(comment("/* a"), vec![Span::new(Token::new("/* a", TokenKind::Comment), 0..=3)]);
+        // Non-ASCII characters do not end the comment.
+        assert_eq!(
+            comment("/* é */a"), 
+            vec![
+                Span::new(Token::new("/* é */", TokenKind::Comment), 0..=7),
+                Span::new(Token::new("a", TokenKind::Ident), 8..=8)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bad_string_token() {
+        let lexer = Lexer::new("'a\nb'");
+        let tokens = lexer.collect::<Vec<_>>();
+
+        assert_eq!(tokens[0], Span::new(Token::new("a", TokenKind::BadString), 0..=1));
+    }
+}
@@ -1,2 +1,3 @@
 pub mod lexer;
-pub mod parser;
\ No newline at end of file
+#[allow(clippy::module_inception)]
+pub mod parser;
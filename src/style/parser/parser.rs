@@ -1,54 +1,539 @@
-use super::lexer::{Lexer, TokenKind};
+//! Implements the parsing algorithms of [CSS SYNTAX 3](https://www.w3.org/TR/css-syntax-3/#parsing)
+//!
+//! The consumers work on a stream of component values, which can either come from
+//! the tokens of a [Lexer] (see [ComponentValues]) or from already parsed component values,
+//! such as the content of a block.
+use std::iter::Peekable;
+
+use super::lexer::{Lexer, Token, TokenKind};
+
+#[derive(Debug)]
+pub enum ParserError {
+    UnexpectedEof,
+    UnexpectedToken(String),
+}
 
-pub enum ParserError {}
 pub type ParserResult<T> = Result<T, ParserError>;
 
-pub enum Rule {
-    At(AtRule),
-    Qualified(QualifiedRule)
+/// A stylesheet, made of a list of rules.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stylesheet<'i> {
+    pub rules: Vec<Rule<'i>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rule<'i> {
+    At(AtRule<'i>),
+    Qualified(QualifiedRule<'i>)
 }
 
-impl From<AtRule> for Rule {
-    fn from(value: AtRule) -> Self {
+impl<'i> From<AtRule<'i>> for Rule<'i> {
+    fn from(value: AtRule<'i>) -> Self {
         Rule::At(value)
     }
 }
 
-impl From<QualifiedRule> for Rule {
-    fn from(value: QualifiedRule) -> Self {
+impl<'i> From<QualifiedRule<'i>> for Rule<'i> {
+    fn from(value: QualifiedRule<'i>) -> Self {
         Rule::Qualified(value)
     }
 }
 
-pub struct AtRule;
-pub struct QualifiedRule;
+/// ```spec
+/// An at-rule has a name, a prelude consisting of a list of component values,
+/// and an optional block consisting of a simple {} block.
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct AtRule<'i> {
+    pub name: &'i str,
+    pub prelude: Vec<ComponentValue<'i>>,
+    pub block: Option<SimpleBlock<'i>>,
+}
+
+impl<'i> AtRule<'i> {
+    /// Interprets the content of the block as a list of declarations and at-rules.
+    pub fn declarations(&self) -> Vec<DeclarationOrAtRule<'i>> {
+        self.block
+            .as_ref()
+            .map(|block| consume_list_of_declarations(block.values.iter().cloned().map(Ok)))
+            .unwrap_or_default()
+    }
+
+    /// Interprets the content of the block as a list of rules.
+    pub fn rules(&self) -> Vec<Rule<'i>> {
+        self.block
+            .as_ref()
+            .map(|block| consume_list_of_rules(block.values.iter().cloned().map(Ok), false))
+            .unwrap_or_default()
+    }
+}
+
+/// ```spec
+/// A qualified rule has a prelude consisting of a list of component values,
+/// and a block consisting of a simple {} block.
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct QualifiedRule<'i> {
+    pub prelude: Vec<ComponentValue<'i>>,
+    pub block: SimpleBlock<'i>,
+}
+
+impl<'i> QualifiedRule<'i> {
+    /// Interprets the content of the block as a list of declarations.
+    pub fn declarations(&self) -> Vec<Declaration<'i>> {
+        consume_list_of_declarations(self.block.values.iter().cloned().map(Ok))
+            .into_iter()
+            .filter_map(|item| match item {
+                DeclarationOrAtRule::Declaration(declaration) => Some(declaration),
+                DeclarationOrAtRule::AtRule(_) => None,
+            })
+            .collect()
+    }
+}
+
+/// ```spec
+/// Conceptually, declarations are a particular instance of associating a property or descriptor name with a value.
+/// Syntactically, a declaration has a name, a value consisting of a list of component values,
+/// and an important flag which is initially unset.
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Declaration<'i> {
+    pub name: &'i str,
+    pub value: Vec<ComponentValue<'i>>,
+    pub important: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeclarationOrAtRule<'i> {
+    Declaration(Declaration<'i>),
+    AtRule(AtRule<'i>),
+}
+
+/// ```spec
+/// A component value is one of the preserved tokens, a function, or a simple block.
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum ComponentValue<'i> {
+    Token(Token<'i>),
+    Function(Function<'i>),
+    Block(SimpleBlock<'i>),
+}
+
+impl<'i> ComponentValue<'i> {
+    pub fn token(&self) -> Option<&Token<'i>> {
+        match self {
+            ComponentValue::Token(token) => Some(token),
+            _ => None
+        }
+    }
+
+    pub fn kind(&self) -> Option<&TokenKind<'i>> {
+        self.token().map(|token| &token.kind)
+    }
+
+    pub fn is_whitespace(&self) -> bool {
+        matches!(self.kind(), Some(TokenKind::Whitespace))
+    }
+
+    pub fn is(&self, kind: TokenKind<'_>) -> bool {
+        self.kind().map(|k| *k == kind).unwrap_or_default()
+    }
+
+    /// Checks if the value is the given delimiter.
+    pub fn is_delim(&self, delim: &str) -> bool {
+        self.token()
+            .map(|token| token.kind == TokenKind::Delim && token.value == delim)
+            .unwrap_or_default()
+    }
+
+    /// Checks if the value is the given ident, ASCII case-insensitively.
+    pub fn is_ident(&self, ident: &str) -> bool {
+        self.ident()
+            .map(|value| value.eq_ignore_ascii_case(ident))
+            .unwrap_or_default()
+    }
+
+    /// Returns the value of the ident token, if any.
+    pub fn ident(&self) -> Option<&'i str> {
+        self.token()
+            .filter(|token| token.kind == TokenKind::Ident)
+            .map(|token| token.value)
+    }
+}
+
+/// ```spec
+/// A function has a name and a value consisting of a list of component values.
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function<'i> {
+    pub name: &'i str,
+    pub arguments: Vec<ComponentValue<'i>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockKind {
+    /// {}-block
+    Curly,
+    /// []-block
+    Square,
+    /// ()-block
+    Parenthesis,
+}
+
+impl BlockKind {
+    fn ending(&self) -> TokenKind<'static> {
+        match self {
+            BlockKind::Curly => TokenKind::RightCurlyBracket,
+            BlockKind::Square => TokenKind::RightSquareBracket,
+            BlockKind::Parenthesis => TokenKind::RightPar,
+        }
+    }
+}
+
+/// ```spec
+/// A simple block has an associated token (either a <[-token>, <(-token>, or <{-token>)
+/// and a value consisting of a list of component values.
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimpleBlock<'i> {
+    pub kind: BlockKind,
+    pub values: Vec<ComponentValue<'i>>,
+}
+
+/// A stream of component values consumed from the tokens of a lexer.
+pub struct ComponentValues<'a, 'i> {
+    lexer: &'a mut Lexer<'i>,
+}
+
+impl<'a, 'i> ComponentValues<'a, 'i> {
+    pub fn new(lexer: &'a mut Lexer<'i>) -> Self {
+        Self { lexer }
+    }
+}
 
-pub fn consume_at_rule(lexer: &mut Lexer<'_>) -> ParseResult<AtRule> {
+impl<'i> Iterator for ComponentValues<'_, 'i> {
+    type Item = ParserResult<ComponentValue<'i>>;
 
+    fn next(&mut self) -> Option<Self::Item> {
+        next_token(self.lexer).map(|token| Ok(consume_component_value(self.lexer, token)))
+    }
 }
 
-pub fn consume_qualified_rule(lexer: &mut Lexer<'_>) -> ParserResult<QualifiedRule> {
-    
+/// Parse a stylesheet
+///
+/// Parsing never fails, invalid rules are dropped.
+pub fn parse_stylesheet(input: &str) -> Stylesheet<'_> {
+    let mut lexer = Lexer::new(input);
+    let rules = consume_list_of_rules(ComponentValues::new(&mut lexer), true);
+    Stylesheet { rules }
 }
 
-pub fn consume_list_of_rules(lexer: &mut Lexer<'_>) {
+/// Parse a list of declarations, such as the content of a style attribute.
+pub fn parse_list_of_declarations(input: &str) -> ParserResult<Vec<DeclarationOrAtRule<'_>>> {
+    let values = parse_list_of_component_values(input)?;
+    Ok(consume_list_of_declarations(values.into_iter().map(Ok)))
+}
+
+/// Parse a list of component values
+pub fn parse_list_of_component_values(input: &str) -> ParserResult<Vec<ComponentValue<'_>>> {
+    let mut lexer = Lexer::new(input);
+    ComponentValues::new(&mut lexer).collect()
+}
+
+/// Returns the next token, comments are skipped.
+fn next_token<'i>(lexer: &mut Lexer<'i>) -> Option<Token<'i>> {
+    lexer
+        .map(|spanned| spanned.value)
+        .find(|token| token.kind != TokenKind::Comment)
+}
+
+/// Consume a list of rules
+///
+/// A parse error drops the rule, the following rules are kept.
+pub fn consume_list_of_rules<'i, I>(values: I, top_level: bool) -> Vec<Rule<'i>>
+where I: Iterator<Item = ParserResult<ComponentValue<'i>>>
+{
+    let mut values = values.peekable();
     let mut rules = Vec::<Rule>::default();
 
-    while let Some(Ok(tok)) = lexer.peek(0) {
-        if tok.kind == TokenKind::Whitespace {
-            lexer.consume(1);
+    while let Some(value) = values.next() {
+        let Ok(value) = value else {
+            continue;
+        };
+
+        match value.kind() {
+            Some(TokenKind::Whitespace) => continue,
+            Some(TokenKind::CDO | TokenKind::CDC) if top_level => continue,
+            Some(TokenKind::At) => {
+                let name = value.token().unwrap().value;
+                if let Ok(rule) = consume_at_rule(name, &mut values) {
+                    rules.push(rule.into());
+                }
+            },
+            _ => {
+                // A parse error occured, the rule is dropped.
+                if let Ok(Some(rule)) = consume_qualified_rule(value, &mut values) {
+                    rules.push(rule.into());
+                }
+            }
         }
+    }
+
+    rules
+}
+
+/// Consume an at-rule, whose at-keyword has already been consumed.
+pub fn consume_at_rule<'i, I>(name: &'i str, values: &mut Peekable<I>) -> ParserResult<AtRule<'i>>
+where I: Iterator<Item = ParserResult<ComponentValue<'i>>>
+{
+    let mut rule = AtRule { name, prelude: vec![], block: None };
 
-        if matches!(tok.kind, TokenKind::At) {
-            consume_at_rule(lexer)
-                .into_iter()
-                .map(Rule::from)
-                .for_each(|rule| rules.push(rule));
+    for value in values.by_ref() {
+        match value? {
+            value if value.is(TokenKind::Semicolon) => break,
+            ComponentValue::Block(block) if block.kind == BlockKind::Curly => {
+                rule.block = Some(block);
+                break;
+            },
+            value => rule.prelude.push(value)
         }
+    }
 
-        consume_qualified_rule(lexer)
-            .into_iter()
-            .map(Rule::from)
-            .for_each(|rule| rules.push(rule));
+    Ok(rule)
+}
+
+/// Consume a qualified rule, starting with the given component value.
+///
+/// Returns nothing if the end of the stream is reached before the block
+/// (which is a parse error).
+pub fn consume_qualified_rule<'i, I>(first: ComponentValue<'i>, values: &mut Peekable<I>) -> ParserResult<Option<QualifiedRule<'i>>>
+where I: Iterator<Item = ParserResult<ComponentValue<'i>>>
+{
+    let mut prelude = Vec::default();
+    let mut next = Some(Ok(first));
+
+    while let Some(value) = next {
+        match value? {
+            ComponentValue::Block(block) if block.kind == BlockKind::Curly => {
+                return Ok(Some(QualifiedRule { prelude, block }));
+            },
+            value => prelude.push(value)
+        }
+
+        next = values.next();
+    }
+
+    Ok(None)
+}
+
+/// Consume a component value, starting with the given token.
+pub fn consume_component_value<'i>(lexer: &mut Lexer<'i>, token: Token<'i>) -> ComponentValue<'i> {
+    match token.kind {
+        TokenKind::LeftCurlyBracket => ComponentValue::Block(consume_simple_block(lexer, BlockKind::Curly)),
+        TokenKind::LeftSquareBracket => ComponentValue::Block(consume_simple_block(lexer, BlockKind::Square)),
+        TokenKind::LeftPar => ComponentValue::Block(consume_simple_block(lexer, BlockKind::Parenthesis)),
+        TokenKind::Function => ComponentValue::Function(consume_function(lexer, token)),
+        _ => ComponentValue::Token(token)
+    }
+}
+
+/// Consume a simple block, whose opening token has already been consumed.
+pub fn consume_simple_block<'i>(lexer: &mut Lexer<'i>, kind: BlockKind) -> SimpleBlock<'i> {
+    let mut block = SimpleBlock { kind, values: vec![] };
+    let ending = kind.ending();
+
+    // An EOF is a parse error, but the block is returned as is.
+    while let Some(token) = next_token(lexer) {
+        if token.kind == ending {
+            break;
+        }
+
+        block.values.push(consume_component_value(lexer, token));
+    }
+
+    block
+}
+
+/// Consume a function, whose function token has already been consumed.
+pub fn consume_function<'i>(lexer: &mut Lexer<'i>, token: Token<'i>) -> Function<'i> {
+    let name = token.value.strip_suffix('(').unwrap_or(token.value);
+    let mut function = Function { name, arguments: vec![] };
+
+    // An EOF is a parse error, but the function is returned as is.
+    while let Some(token) = next_token(lexer) {
+        if token.kind == TokenKind::RightPar {
+            break;
+        }
+
+        function.arguments.push(consume_component_value(lexer, token));
     }
-}
\ No newline at end of file
+
+    function
+}
+
+/// Consume a list of declarations.
+///
+/// Invalid declarations are dropped.
+pub fn consume_list_of_declarations<'i, I>(values: I) -> Vec<DeclarationOrAtRule<'i>>
+where I: Iterator<Item = ParserResult<ComponentValue<'i>>>
+{
+    let mut values = values.peekable();
+    let mut declarations = Vec::default();
+
+    while let Some(Ok(value)) = values.next() {
+        match value.kind() {
+            Some(TokenKind::Whitespace | TokenKind::Semicolon) => continue,
+            Some(TokenKind::At) => {
+                let name = value.token().unwrap().value;
+                if let Ok(rule) = consume_at_rule(name, &mut values) {
+                    declarations.push(DeclarationOrAtRule::AtRule(rule));
+                }
+            },
+            Some(TokenKind::Ident) => {
+                let mut tmp = vec![value];
+
+                while let Some(Ok(value)) = values.next_if(|value| !matches!(value, Ok(value) if value.is(TokenKind::Semicolon))) {
+                    tmp.push(value);
+                }
+
+                if let Ok(declaration) = consume_declaration(&tmp) {
+                    declarations.push(DeclarationOrAtRule::Declaration(declaration));
+                }
+            },
+            _ => {
+                // Parse error, throw away everything until the next semicolon.
+                while values.next_if(|value| !matches!(value, Ok(value) if value.is(TokenKind::Semicolon))).is_some() {}
+            }
+        }
+    }
+
+    declarations
+}
+
+/// Consume a declaration from a list of component values
+/// starting with the name of the declaration.
+pub fn consume_declaration<'i>(values: &[ComponentValue<'i>]) -> ParserResult<Declaration<'i>> {
+    let mut values = values.iter();
+
+    let name = values
+        .next()
+        .and_then(ComponentValue::ident)
+        .ok_or(ParserError::UnexpectedEof)?;
+
+    let mut values = values.skip_while(|value| value.is_whitespace());
+
+    match values.next() {
+        Some(value) if value.is(TokenKind::Colon) => {},
+        Some(value) => return Err(ParserError::UnexpectedToken(format!("{value:?}"))),
+        None => return Err(ParserError::UnexpectedEof)
+    }
+
+    let mut value = values
+        .skip_while(|value| value.is_whitespace())
+        .cloned()
+        .collect::<Vec<_>>();
+
+    trim_end_whitespaces(&mut value);
+
+    // Detects the !important flag
+    let mut important = false;
+    if let [.., bang, last] = value.as_slice() {
+        if bang.is_delim("!") && last.is_ident("important") {
+            value.truncate(value.len() - 2);
+            trim_end_whitespaces(&mut value);
+            important = true;
+        }
+    }
+
+    Ok(Declaration { name, value, important })
+}
+
+fn trim_end_whitespaces(values: &mut Vec<ComponentValue<'_>>) {
+    while values.last().map(ComponentValue::is_whitespace).unwrap_or_default() {
+        values.pop();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::style::parser::lexer::TokenKind;
+
+    use super::{parse_list_of_declarations, parse_stylesheet, BlockKind, ComponentValue, DeclarationOrAtRule, Rule};
+
+    #[test]
+    fn test_parse_qualified_rule() {
+        let stylesheet = parse_stylesheet("p > a { color: red; margin : 0 auto !important }");
+        assert_eq!(stylesheet.rules.len(), 1);
+
+        let Rule::Qualified(rule) = &stylesheet.rules[0] else {
+            panic!("expected a qualified rule");
+        };
+
+        assert_eq!(rule.prelude.len(), 6);
+        assert_eq!(rule.block.kind, BlockKind::Curly);
+
+        let declarations = rule.declarations();
+        assert_eq!(declarations.len(), 2);
+        assert_eq!(declarations[0].name, "color");
+        assert!(!declarations[0].important);
+        assert_eq!(declarations[0].value.len(), 1);
+        assert!(declarations[0].value[0].is_ident("red"));
+
+        assert_eq!(declarations[1].name, "margin");
+        assert!(declarations[1].important);
+        assert_eq!(declarations[1].value.len(), 3);
+    }
+
+    #[test]
+    fn test_parse_at_rules() {
+        let stylesheet = parse_stylesheet(r#"
+            @import "style.css";
+            /* a comment */
+            @media print { h1 { color: blue } }
+            div { width: calc(100% - 2px) }
+        "#);
+
+        assert_eq!(stylesheet.rules.len(), 3);
+
+        let Rule::At(import) = &stylesheet.rules[0] else {
+            panic!("expected an at-rule");
+        };
+        assert_eq!(import.name, "import");
+        assert!(import.block.is_none());
+
+        let Rule::At(media) = &stylesheet.rules[1] else {
+            panic!("expected an at-rule");
+        };
+        assert_eq!(media.name, "media");
+        assert_eq!(media.rules().len(), 1);
+
+        let Rule::Qualified(div) = &stylesheet.rules[2] else {
+            panic!("expected a qualified rule");
+        };
+        let declarations = div.declarations();
+        let [ComponentValue::Function(calc)] = declarations[0].value.as_slice() else {
+            panic!("expected a function");
+        };
+        assert_eq!(calc.name, "calc");
+        assert!(matches!(calc.arguments[0].kind(), Some(TokenKind::Percentage(_))));
+    }
+
+    #[test]
+    fn test_error_recovery() {
+        // The first declaration is invalid, the unterminated rule is dropped.
+        let declarations = parse_list_of_declarations("color red; 12px; width: 10px; @top-left { content: 'a' }").unwrap();
+        assert_eq!(declarations.len(), 2);
+        assert!(matches!(&declarations[0], DeclarationOrAtRule::Declaration(decl) if decl.name == "width"));
+        assert!(matches!(&declarations[1], DeclarationOrAtRule::AtRule(rule) if rule.name == "top-left"));
+
+        let stylesheet = parse_stylesheet("a { color: red } b");
+        assert_eq!(stylesheet.rules.len(), 1);
+
+        let stylesheet = parse_stylesheet("/* é */ a { color: red } b { color: blue }");
+        assert_eq!(stylesheet.rules.len(), 2);
+
+        // A bad string only breaks its declaration.
+        let stylesheet = parse_stylesheet("a { content: 'unclosed\n; color: red } b { color: blue }");
+        assert_eq!(stylesheet.rules.len(), 2);
+    }
+}
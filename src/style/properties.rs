@@ -1,36 +1,871 @@
-pub mod initial {
-    use crate::style::{border, display, margin, order, padding, visibility};
+use super::{
+    border::LineStyle,
+    display::Display,
+    font::{ComputedFontSize, ComputedFontWeight, FontFamily, FontSize, FontStretch, FontStyle, FontWeight},
+    fragmentation::{parse_lines, BreakBetween, BreakInside},
+    inline::{LineHeight, VerticalAlign},
+    order::Order,
+    page::{Content, PageName, PageSize},
+    parser::parser::{ComponentValue, ParserError, ParserResult},
+    values::{
+        color::{Color, ColorBase, Srgba},
+        numeric::{AutoOrLengthOrPercentage, Integer, Length, ResolutionContext},
+        r#box::BoxEdges,
+    },
+    text::{LineBreak, OverflowWrap, WhiteSpace, WordBreak},
+    visibility::Visibility,
+};
 
-    #[derive(Default, Clone)]
-    pub struct Properties {
-        // CSS Display 3
-        pub display: display::initial::Display,
-        pub order: order::initial::Order,
-        pub visibility: visibility::initial::Visibility,
-        // CSS BOX 3
-        pub margin: margin::initial::Margin,
-        pub padding: padding::initial::Padding,
-        pub border: border::initial::Border,
+/// Declares the longhand properties.
+///
+/// Each longhand is declared once, with:
+/// - its identifier, its name, and the field holding its value in the style structs;
+/// - the types of its specified and computed values;
+/// - its initial (specified) value, and whether it is inherited;
+/// - the parser of its value;
+/// - the computation of the specified value, from the resolution context of the element;
+/// - the conversion of the computed value of the parent into a specified value, for inheritance.
+///
+/// The property identifiers, the declarations, and the style structs are generated from it.
+macro_rules! longhands {
+    (
+        $(
+            $(#[$meta:meta])*
+            $id:ident: $name:literal, $field:ident, $specified:ty => $computed:ty {
+                initial: $initial:expr,
+                inherited: $inherited:literal,
+                parse: $parse:path,
+                compute: $compute:expr,
+                inherit: $inherit:expr $(,)?
+            }
+        )*
+    ) => {
+        pub mod initial {
+            use super::*;
+
+            /// The specified values of the properties.
+            #[derive(Clone)]
+            pub struct Properties {
+                $(pub $field: $specified,)*
+            }
+
+            impl Default for Properties {
+                fn default() -> Self {
+                    Self {
+                        $($field: $initial,)*
+                    }
+                }
+            }
+        }
+
+        pub mod computed {
+            use super::*;
+
+            /// The computed values of the properties.
+            #[derive(Clone)]
+            pub struct Properties {
+                $(pub $field: $computed,)*
+            }
+
+            impl Default for Properties {
+                fn default() -> Self {
+                    Self::compute(&initial::Properties::default(), &ResolutionContext::default())
+                }
+            }
+
+            impl Properties {
+                /// Computes the specified values.
+                ///
+                /// The font size of the context must be the one of the parent element,
+                /// and its current color the one of the element.
+                ///
+                /// The font size of the element is computed first, as relative lengths refer to it.
+                #[allow(clippy::redundant_closure_call)]
+                pub fn compute(specified: &initial::Properties, parent: &ResolutionContext) -> Self {
+                    let ctx = &ResolutionContext { font_size: specified.font_size.compute(parent).0, ..parent.clone() };
+
+                    Self {
+                        $($field: {
+                            let compute: fn(&$specified, &ResolutionContext) -> $computed = $compute;
+                            compute(&specified.$field, ctx)
+                        },)*
+                    }
+                }
+            }
+        }
+
+        /// Identifier of a longhand property.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum PropertyId {
+            $($(#[$meta])* $id,)*
+        }
+
+        impl TryFrom<&str> for PropertyId {
+            type Error = ParserError;
+
+            fn try_from(value: &str) -> Result<Self, Self::Error> {
+                match value.to_ascii_lowercase().as_str() {
+                    $($name => Ok(Self::$id),)*
+                    name => Err(ParserError::UnexpectedToken(name.to_owned()))
+                }
+            }
+        }
+
+        impl PropertyId {
+            pub const ALL: &'static [Self] = &[$(Self::$id),*];
+
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(Self::$id => $name,)*
+                }
+            }
+
+            /// ```spec
+            /// Some properties are inherited properties, as defined in their property definition table.
+            /// This means that, unless the cascade results in a value,
+            /// the value will be determined by inheritance.
+            /// ```
+            pub fn is_inherited(&self) -> bool {
+                match self {
+                    $(Self::$id => $inherited,)*
+                }
+            }
+
+            /// Set the specified value of the property to the computed value of the parent.
+            #[allow(clippy::redundant_closure_call)]
+            pub fn inherit(&self, specified: &mut initial::Properties, parent: &computed::Properties) {
+                match self {
+                    $(Self::$id => {
+                        let inherit: fn(&$computed) -> $specified = $inherit;
+                        specified.$field = inherit(&parent.$field);
+                    },)*
+                }
+            }
+
+            /// Returns a declaration of the initial value of the property.
+            pub fn initial_declaration(&self) -> PropertyDeclaration {
+                let initial = initial::Properties::default();
+
+                match self {
+                    $(Self::$id => PropertyDeclaration::$id(initial.$field),)*
+                }
+            }
+
+            /// Set the specified value of the property to its initial value.
+            pub fn reset(&self, specified: &mut initial::Properties) {
+                let initial = initial::Properties::default();
+
+                match self {
+                    $(Self::$id => specified.$field = initial.$field,)*
+                }
+            }
+        }
+
+        /// A declared value of a longhand property.
+        #[derive(Clone)]
+        pub enum PropertyDeclaration {
+            CssWide(PropertyId, CssWideKeyword),
+            $($id($specified),)*
+        }
+
+        impl PropertyDeclaration {
+            /// Parse the value of a property.
+            pub fn parse(name: &str, value: &[ComponentValue<'_>]) -> ParserResult<Self> {
+                let id = PropertyId::try_from(name)?;
+
+                if let Ok(keyword) = CssWideKeyword::try_from(value) {
+                    return Ok(Self::CssWide(id, keyword));
+                }
+
+                match id {
+                    $(PropertyId::$id => $parse(value).map(Self::$id),)*
+                }
+            }
+
+            pub fn id(&self) -> PropertyId {
+                match self {
+                    Self::CssWide(id, _) => *id,
+                    $(Self::$id(_) => PropertyId::$id,)*
+                }
+            }
+
+            pub fn is_revert(&self) -> bool {
+                matches!(self, Self::CssWide(_, CssWideKeyword::Revert))
+            }
+
+            /// Set the specified value from the cascaded value.
+            ///
+            /// ```spec
+            /// - inherit: the specified value is the computed value of the parent.
+            /// - initial: the specified value is the initial value.
+            /// - unset: acts as inherit if the property is inherited, otherwise as initial.
+            /// ```
+            ///
+            /// The revert keyword must be resolved by the cascade, it acts as unset here.
+            pub fn apply(&self, specified: &mut initial::Properties, parent: Option<&computed::Properties>) {
+                let inherit = |id: PropertyId, specified: &mut initial::Properties| match parent {
+                    Some(parent) => id.inherit(specified, parent),
+                    None => id.reset(specified),
+                };
+
+                match self.clone() {
+                    Self::CssWide(id, CssWideKeyword::Inherit) => inherit(id, specified),
+                    Self::CssWide(id, CssWideKeyword::Initial) => id.reset(specified),
+                    Self::CssWide(id, CssWideKeyword::Unset | CssWideKeyword::Revert) => {
+                        if id.is_inherited() {
+                            inherit(id, specified)
+                        } else {
+                            id.reset(specified)
+                        }
+                    },
+                    $(Self::$id(value) => specified.$field = value,)*
+                }
+            }
+        }
+    };
+}
+
+longhands! {
+    // CSS Color 4
+    /// ```spec
+    /// If currentcolor is the specified value of the color property,
+    /// it is treated as if the specified value was inherit.
+    /// ```
+    ///
+    ///
+    /// The current color is resolved against the parent before computing the other properties.
+    Color: "color", color, Color => Srgba {
+        initial: Color::default(),
+        inherited: true,
+        parse: Color::try_from,
+        compute: |_, ctx| ctx.current_color,
+        inherit: |value| Color::from(*value),
+    }
+
+    // CSS Display 3
+    Display: "display", display, Display => Display {
+        initial: Display::default(),
+        inherited: false,
+        parse: Display::try_from,
+        compute: computed_as_specified,
+        inherit: Clone::clone,
+    }
+    Order: "order", order, Order => Order {
+        initial: Order::default(),
+        inherited: false,
+        parse: Order::try_from,
+        compute: computed_as_specified,
+        inherit: Clone::clone,
+    }
+    Visibility: "visibility", visibility, Visibility => Visibility {
+        initial: Visibility::default(),
+        inherited: true,
+        parse: Visibility::try_from,
+        compute: computed_as_specified,
+        inherit: Clone::clone,
+    }
+
+    // CSS Fonts 4
+    /// The font size is computed against the parent before computing the other properties.
+    FontSize: "font-size", font_size, FontSize => ComputedFontSize {
+        initial: FontSize::default(),
+        inherited: true,
+        parse: FontSize::try_from,
+        compute: |_, ctx| ComputedFontSize(ctx.font_size),
+        inherit: |value| FontSize::from(*value),
+    }
+    FontFamily: "font-family", font_family, FontFamily => FontFamily {
+        initial: FontFamily::default(),
+        inherited: true,
+        parse: FontFamily::try_from,
+        compute: computed_as_specified,
+        inherit: Clone::clone,
+    }
+    FontWeight: "font-weight", font_weight, FontWeight => ComputedFontWeight {
+        initial: FontWeight::default(),
+        inherited: true,
+        parse: FontWeight::try_from,
+        compute: FontWeight::compute,
+        inherit: |value| FontWeight::from(*value),
+    }
+    FontStyle: "font-style", font_style, FontStyle => FontStyle {
+        initial: FontStyle::default(),
+        inherited: true,
+        parse: FontStyle::try_from,
+        compute: computed_as_specified,
+        inherit: Clone::clone,
+    }
+    FontStretch: "font-stretch", font_stretch, FontStretch => FontStretch {
+        initial: FontStretch::default(),
+        inherited: true,
+        parse: FontStretch::try_from,
+        compute: computed_as_specified,
+        inherit: Clone::clone,
+    }
+
+    // CSS 2.1
+    LineHeight: "line-height", line_height, LineHeight => LineHeight {
+        initial: LineHeight::default(),
+        inherited: true,
+        parse: LineHeight::try_from,
+        compute: LineHeight::compute,
+        inherit: Clone::clone,
+    }
+    VerticalAlign: "vertical-align", vertical_align, VerticalAlign => VerticalAlign {
+        initial: VerticalAlign::default(),
+        inherited: false,
+        parse: VerticalAlign::try_from,
+        compute: VerticalAlign::compute,
+        inherit: Clone::clone,
+    }
+
+    // CSS Box 3
+    MarginTop: "margin-top", margin_top, AutoOrLengthOrPercentage => AutoOrLengthOrPercentage {
+        initial: AutoOrLengthOrPercentage::zero(),
+        inherited: false,
+        parse: AutoOrLengthOrPercentage::try_from,
+        compute: AutoOrLengthOrPercentage::compute,
+        inherit: Clone::clone,
+    }
+    MarginRight: "margin-right", margin_right, AutoOrLengthOrPercentage => AutoOrLengthOrPercentage {
+        initial: AutoOrLengthOrPercentage::zero(),
+        inherited: false,
+        parse: AutoOrLengthOrPercentage::try_from,
+        compute: AutoOrLengthOrPercentage::compute,
+        inherit: Clone::clone,
+    }
+    MarginBottom: "margin-bottom", margin_bottom, AutoOrLengthOrPercentage => AutoOrLengthOrPercentage {
+        initial: AutoOrLengthOrPercentage::zero(),
+        inherited: false,
+        parse: AutoOrLengthOrPercentage::try_from,
+        compute: AutoOrLengthOrPercentage::compute,
+        inherit: Clone::clone,
+    }
+    MarginLeft: "margin-left", margin_left, AutoOrLengthOrPercentage => AutoOrLengthOrPercentage {
+        initial: AutoOrLengthOrPercentage::zero(),
+        inherited: false,
+        parse: AutoOrLengthOrPercentage::try_from,
+        compute: AutoOrLengthOrPercentage::compute,
+        inherit: Clone::clone,
+    }
+    PaddingTop: "padding-top", padding_top, AutoOrLengthOrPercentage => AutoOrLengthOrPercentage {
+        initial: AutoOrLengthOrPercentage::zero(),
+        inherited: false,
+        parse: parse_padding,
+        compute: AutoOrLengthOrPercentage::compute,
+        inherit: Clone::clone,
+    }
+    PaddingRight: "padding-right", padding_right, AutoOrLengthOrPercentage => AutoOrLengthOrPercentage {
+        initial: AutoOrLengthOrPercentage::zero(),
+        inherited: false,
+        parse: parse_padding,
+        compute: AutoOrLengthOrPercentage::compute,
+        inherit: Clone::clone,
+    }
+    PaddingBottom: "padding-bottom", padding_bottom, AutoOrLengthOrPercentage => AutoOrLengthOrPercentage {
+        initial: AutoOrLengthOrPercentage::zero(),
+        inherited: false,
+        parse: parse_padding,
+        compute: AutoOrLengthOrPercentage::compute,
+        inherit: Clone::clone,
+    }
+    PaddingLeft: "padding-left", padding_left, AutoOrLengthOrPercentage => AutoOrLengthOrPercentage {
+        initial: AutoOrLengthOrPercentage::zero(),
+        inherited: false,
+        parse: parse_padding,
+        compute: AutoOrLengthOrPercentage::compute,
+        inherit: Clone::clone,
+    }
+
+    // CSS Backgrounds 3
+    BorderTopWidth: "border-top-width", border_top_width, AutoOrLengthOrPercentage => AutoOrLengthOrPercentage {
+        initial: AutoOrLengthOrPercentage::Length(Length::px(3)),
+        inherited: false,
+        parse: parse_border_width,
+        compute: AutoOrLengthOrPercentage::compute,
+        inherit: Clone::clone,
+    }
+    BorderRightWidth: "border-right-width", border_right_width, AutoOrLengthOrPercentage => AutoOrLengthOrPercentage {
+        initial: AutoOrLengthOrPercentage::Length(Length::px(3)),
+        inherited: false,
+        parse: parse_border_width,
+        compute: AutoOrLengthOrPercentage::compute,
+        inherit: Clone::clone,
+    }
+    BorderBottomWidth: "border-bottom-width", border_bottom_width, AutoOrLengthOrPercentage => AutoOrLengthOrPercentage {
+        initial: AutoOrLengthOrPercentage::Length(Length::px(3)),
+        inherited: false,
+        parse: parse_border_width,
+        compute: AutoOrLengthOrPercentage::compute,
+        inherit: Clone::clone,
+    }
+    BorderLeftWidth: "border-left-width", border_left_width, AutoOrLengthOrPercentage => AutoOrLengthOrPercentage {
+        initial: AutoOrLengthOrPercentage::Length(Length::px(3)),
+        inherited: false,
+        parse: parse_border_width,
+        compute: AutoOrLengthOrPercentage::compute,
+        inherit: Clone::clone,
+    }
+    BorderTopStyle: "border-top-style", border_top_style, LineStyle => LineStyle {
+        initial: LineStyle::None,
+        inherited: false,
+        parse: LineStyle::try_from,
+        compute: computed_as_specified,
+        inherit: Clone::clone,
+    }
+    BorderRightStyle: "border-right-style", border_right_style, LineStyle => LineStyle {
+        initial: LineStyle::None,
+        inherited: false,
+        parse: LineStyle::try_from,
+        compute: computed_as_specified,
+        inherit: Clone::clone,
+    }
+    BorderBottomStyle: "border-bottom-style", border_bottom_style, LineStyle => LineStyle {
+        initial: LineStyle::None,
+        inherited: false,
+        parse: LineStyle::try_from,
+        compute: computed_as_specified,
+        inherit: Clone::clone,
+    }
+    BorderLeftStyle: "border-left-style", border_left_style, LineStyle => LineStyle {
+        initial: LineStyle::None,
+        inherited: false,
+        parse: LineStyle::try_from,
+        compute: computed_as_specified,
+        inherit: Clone::clone,
+    }
+    BorderTopColor: "border-top-color", border_top_color, Color => Srgba {
+        initial: Color::CurrentColor,
+        inherited: false,
+        parse: Color::try_from,
+        compute: |value, ctx| value.resolve(ctx.current_color),
+        inherit: |value| Color::from(*value),
+    }
+    BorderRightColor: "border-right-color", border_right_color, Color => Srgba {
+        initial: Color::CurrentColor,
+        inherited: false,
+        parse: Color::try_from,
+        compute: |value, ctx| value.resolve(ctx.current_color),
+        inherit: |value| Color::from(*value),
+    }
+    BorderBottomColor: "border-bottom-color", border_bottom_color, Color => Srgba {
+        initial: Color::CurrentColor,
+        inherited: false,
+        parse: Color::try_from,
+        compute: |value, ctx| value.resolve(ctx.current_color),
+        inherit: |value| Color::from(*value),
+    }
+    BorderLeftColor: "border-left-color", border_left_color, Color => Srgba {
+        initial: Color::CurrentColor,
+        inherited: false,
+        parse: Color::try_from,
+        compute: |value, ctx| value.resolve(ctx.current_color),
+        inherit: |value| Color::from(*value),
+    }
+    BackgroundColor: "background-color", background_color, Color => Srgba {
+        initial: Color::ColorBase(ColorBase::Transparent),
+        inherited: false,
+        parse: Color::try_from,
+        compute: |value, ctx| value.resolve(ctx.current_color),
+        inherit: |value| Color::from(*value),
+    }
+
+    // CSS Text 3
+    WhiteSpace: "white-space", white_space, WhiteSpace => WhiteSpace {
+        initial: WhiteSpace::default(),
+        inherited: true,
+        parse: WhiteSpace::try_from,
+        compute: computed_as_specified,
+        inherit: Clone::clone,
+    }
+    LineBreak: "line-break", line_break, LineBreak => LineBreak {
+        initial: LineBreak::default(),
+        inherited: true,
+        parse: LineBreak::try_from,
+        compute: computed_as_specified,
+        inherit: Clone::clone,
+    }
+    WordBreak: "word-break", word_break, WordBreak => WordBreak {
+        initial: WordBreak::default(),
+        inherited: true,
+        parse: WordBreak::try_from,
+        compute: computed_as_specified,
+        inherit: Clone::clone,
+    }
+    OverflowWrap: "overflow-wrap", overflow_wrap, OverflowWrap => OverflowWrap {
+        initial: OverflowWrap::default(),
+        inherited: true,
+        parse: OverflowWrap::try_from,
+        compute: computed_as_specified,
+        inherit: Clone::clone,
+    }
+
+    // CSS Fragmentation 3
+    BreakBefore: "break-before", break_before, BreakBetween => BreakBetween {
+        initial: BreakBetween::default(),
+        inherited: false,
+        parse: BreakBetween::try_from,
+        compute: computed_as_specified,
+        inherit: Clone::clone,
+    }
+    BreakAfter: "break-after", break_after, BreakBetween => BreakBetween {
+        initial: BreakBetween::default(),
+        inherited: false,
+        parse: BreakBetween::try_from,
+        compute: computed_as_specified,
+        inherit: Clone::clone,
+    }
+    BreakInside: "break-inside", break_inside, BreakInside => BreakInside {
+        initial: BreakInside::default(),
+        inherited: false,
+        parse: BreakInside::try_from,
+        compute: computed_as_specified,
+        inherit: Clone::clone,
+    }
+    Orphans: "orphans", orphans, Integer => Integer {
+        initial: Integer::from(2),
+        inherited: true,
+        parse: parse_lines,
+        compute: computed_as_specified,
+        inherit: Clone::clone,
+    }
+    Widows: "widows", widows, Integer => Integer {
+        initial: Integer::from(2),
+        inherited: true,
+        parse: parse_lines,
+        compute: computed_as_specified,
+        inherit: Clone::clone,
+    }
+
+    // CSS Paged Media 3
+    /// ```spec
+    /// The size descriptor specifies the target size and orientation of the page box's containing block.
+    /// ```
+    ///
+    /// It only applies to the page context.
+    Size: "size", size, PageSize => PageSize {
+        initial: PageSize::default(),
+        inherited: false,
+        parse: PageSize::try_from,
+        compute: computed_as_specified,
+        inherit: Clone::clone,
+    }
+    Page: "page", page, PageName => PageName {
+        initial: PageName::default(),
+        inherited: false,
+        parse: PageName::try_from,
+        compute: computed_as_specified,
+        inherit: Clone::clone,
+    }
+
+    // CSS Generated Content 3
+    Content: "content", content, Content => Content {
+        initial: Content::default(),
+        inherited: false,
+        parse: Content::try_from,
+        compute: computed_as_specified,
+        inherit: Clone::clone,
+    }
+
+    // CSS Sizing 3
+    Width: "width", width, AutoOrLengthOrPercentage => AutoOrLengthOrPercentage {
+        initial: AutoOrLengthOrPercentage::Auto,
+        inherited: false,
+        parse: parse_size,
+        compute: AutoOrLengthOrPercentage::compute,
+        inherit: Clone::clone,
+    }
+    Height: "height", height, AutoOrLengthOrPercentage => AutoOrLengthOrPercentage {
+        initial: AutoOrLengthOrPercentage::Auto,
+        inherited: false,
+        parse: parse_size,
+        compute: AutoOrLengthOrPercentage::compute,
+        inherit: Clone::clone,
+    }
+
+    // CSS Position 3
+    Top: "top", top, AutoOrLengthOrPercentage => AutoOrLengthOrPercentage {
+        initial: AutoOrLengthOrPercentage::Auto,
+        inherited: false,
+        parse: AutoOrLengthOrPercentage::try_from,
+        compute: AutoOrLengthOrPercentage::compute,
+        inherit: Clone::clone,
+    }
+    Right: "right", right, AutoOrLengthOrPercentage => AutoOrLengthOrPercentage {
+        initial: AutoOrLengthOrPercentage::Auto,
+        inherited: false,
+        parse: AutoOrLengthOrPercentage::try_from,
+        compute: AutoOrLengthOrPercentage::compute,
+        inherit: Clone::clone,
+    }
+    Bottom: "bottom", bottom, AutoOrLengthOrPercentage => AutoOrLengthOrPercentage {
+        initial: AutoOrLengthOrPercentage::Auto,
+        inherited: false,
+        parse: AutoOrLengthOrPercentage::try_from,
+        compute: AutoOrLengthOrPercentage::compute,
+        inherit: Clone::clone,
+    }
+    Left: "left", left, AutoOrLengthOrPercentage => AutoOrLengthOrPercentage {
+        initial: AutoOrLengthOrPercentage::Auto,
+        inherited: false,
+        parse: AutoOrLengthOrPercentage::try_from,
+        compute: AutoOrLengthOrPercentage::compute,
+        inherit: Clone::clone,
     }
 }
 
-pub mod computed {
-    use crate::style::{border, display, margin, order, padding, visibility};
+/// The computed value is the specified value.
+fn computed_as_specified<T: Clone>(value: &T, _ctx: &ResolutionContext) -> T {
+    value.clone()
+}
 
+impl initial::Properties {
+    /// Returns the current color of the element, which is the computed value of its color property.
+    ///
+    /// A current color in the color property refers to the color of the parent.
+    pub fn current_color(&self, parent: Option<&computed::Properties>) -> Srgba {
+        self.color.resolve(parent.map(|parent| parent.color).unwrap_or_default())
+    }
+}
+
+impl computed::Properties {
+    /// Returns the properties of a box which inherits from the given parent.
+    ///
+    /// Inherited properties get the computed value of the parent,
+    /// non-inherited properties have their initial value.
+    pub fn inherit(parent: &Self) -> Self {
+        let specified = specified_values(&[], Some(parent));
+        let ctx = ResolutionContext { current_color: parent.color, ..Default::default() };
+        Self::compute(&specified, &ctx)
+    }
+
+    pub fn margin(&self) -> BoxEdges<AutoOrLengthOrPercentage> {
+        BoxEdges {
+            top: self.margin_top,
+            bottom: self.margin_bottom,
+            left: self.margin_left,
+            right: self.margin_right,
+        }
+    }
+
+    pub fn padding(&self) -> BoxEdges<AutoOrLengthOrPercentage> {
+        BoxEdges {
+            top: self.padding_top,
+            bottom: self.padding_bottom,
+            left: self.padding_left,
+            right: self.padding_right,
+        }
+    }
+
+    /// Returns the border widths, which are zero when there is no border.
+    pub fn border_width(&self) -> BoxEdges<AutoOrLengthOrPercentage> {
+        let width = |width: AutoOrLengthOrPercentage, style: LineStyle| {
+            if style.has_border() { width } else { AutoOrLengthOrPercentage::zero() }
+        };
+
+        BoxEdges {
+            top: width(self.border_top_width, self.border_top_style),
+            bottom: width(self.border_bottom_width, self.border_bottom_style),
+            left: width(self.border_left_width, self.border_left_style),
+            right: width(self.border_right_width, self.border_right_style),
+        }
+    }
+
+    pub fn border_style(&self) -> BoxEdges<LineStyle> {
+        BoxEdges {
+            top: self.border_top_style,
+            bottom: self.border_bottom_style,
+            left: self.border_left_style,
+            right: self.border_right_style,
+        }
+    }
+
+    pub fn border_color(&self) -> BoxEdges<Srgba> {
+        BoxEdges {
+            top: self.border_top_color,
+            bottom: self.border_bottom_color,
+            left: self.border_left_color,
+            right: self.border_right_color,
+        }
+    }
+
+    pub fn inset(&self) -> BoxEdges<AutoOrLengthOrPercentage> {
+        BoxEdges {
+            top: self.top,
+            bottom: self.bottom,
+            left: self.left,
+            right: self.right,
+        }
+    }
+}
+
+pub mod used {
+    use crate::style::{
+        inline::VerticalAlign,
+        values::{
+            numeric::{AutoOrLengthOrPercentage, AutoOrPixelsOrPercentage, ResolutionContext},
+            r#box::BoxEdges,
+        },
+    };
+
+    /// The properties with relative lengths resolved into pixels.
+    ///
+    /// Percentages are left as is, as they depend on the containing block.
     #[derive(Default, Clone)]
     pub struct Properties {
-        // CSS Display 3
-        pub display: display::computed::Display,
-        pub order: order::computed::Order,
-        pub visibility: visibility::computed::Visibility,
-        // CSS BOX 3
-        pub margin: margin::computed::Margin,
-        pub padding: padding::computed::Padding,
-        pub border: border::computed::Border,
+        pub font_size: f64,
+        pub width: AutoOrPixelsOrPercentage,
+        pub height: AutoOrPixelsOrPercentage,
+        pub margin: BoxEdges<AutoOrPixelsOrPercentage>,
+        pub padding: BoxEdges<AutoOrPixelsOrPercentage>,
+        pub border: BoxEdges<f64>,
+        /// The line height in pixels, or nothing if it is normal.
+        pub line_height: Option<f64>,
+        pub vertical_align: VerticalAlign,
+    }
+
+    impl Properties {
+        /// Resolves the computed values.
+        ///
+        /// The font size of the context must be the computed one of the element.
+        pub fn resolve(computed: &super::computed::Properties, ctx: &ResolutionContext) -> Self {
+            let edges = |edges: BoxEdges<AutoOrLengthOrPercentage>| BoxEdges {
+                top: edges.top.resolve(ctx),
+                bottom: edges.bottom.resolve(ctx),
+                left: edges.left.resolve(ctx),
+                right: edges.right.resolve(ctx),
+            };
+
+            let border = edges(computed.border_width());
+
+            Self {
+                font_size: computed.font_size.0,
+                width: computed.width.resolve(ctx),
+                height: computed.height.resolve(ctx),
+                margin: edges(computed.margin()),
+                padding: edges(computed.padding()),
+                border: BoxEdges {
+                    top: border.top.px(0.0).unwrap_or_default(),
+                    bottom: border.bottom.px(0.0).unwrap_or_default(),
+                    left: border.left.px(0.0).unwrap_or_default(),
+                    right: border.right.px(0.0).unwrap_or_default(),
+                },
+                line_height: computed.line_height.px(ctx),
+                vertical_align: computed.vertical_align,
+            }
+        }
     }
 }
 
-pub mod used {
-    pub struct Properties {}
+/// The CSS-wide keywords, which can be used as the value of any property.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CssWideKeyword {
+    Initial,
+    Inherit,
+    Unset,
+    Revert,
+}
+
+impl TryFrom<&[ComponentValue<'_>]> for CssWideKeyword {
+    type Error = ParserError;
+
+    fn try_from(values: &[ComponentValue<'_>]) -> Result<Self, Self::Error> {
+        match values {
+            [value] if value.is_ident("initial") => Ok(Self::Initial),
+            [value] if value.is_ident("inherit") => Ok(Self::Inherit),
+            [value] if value.is_ident("unset") => Ok(Self::Unset),
+            [value] if value.is_ident("revert") => Ok(Self::Revert),
+            _ => Err(ParserError::UnexpectedToken(format!("{values:?}")))
+        }
+    }
+}
+
+impl std::fmt::Display for CssWideKeyword {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Initial => "initial",
+            Self::Inherit => "inherit",
+            Self::Unset => "unset",
+            Self::Revert => "revert",
+        })
+    }
+}
+
+/// Returns the specified values of an element from its cascaded values.
+///
+/// ```spec
+/// The specified value is determined as follows:
+/// - If the cascade results in a value, use it.
+/// - Otherwise, if the property is inherited and the element is not the root of the document,
+///   use the computed value of the parent element.
+/// - Otherwise, use the property’s initial value.
+/// ```
+pub fn specified_values(cascaded: &[PropertyDeclaration], parent: Option<&computed::Properties>) -> initial::Properties {
+    let mut specified = initial::Properties::default();
+
+    if let Some(parent) = parent {
+        PropertyId::ALL
+            .iter()
+            .filter(|id| id.is_inherited())
+            .for_each(|id| id.inherit(&mut specified, parent));
+    }
+
+    for declaration in cascaded {
+        declaration.apply(&mut specified, parent);
+    }
+
+    specified
+}
+
+/// padding-* = <length-percentage [0,∞]>
+fn parse_padding(value: &[ComponentValue<'_>]) -> ParserResult<AutoOrLengthOrPercentage> {
+    match parse_size(value)? {
+        AutoOrLengthOrPercentage::Auto => Err(ParserError::UnexpectedToken("auto".to_owned())),
+        value => Ok(value)
+    }
+}
+
+/// width, height = auto | <length-percentage [0,∞]>
+fn parse_size(value: &[ComponentValue<'_>]) -> ParserResult<AutoOrLengthOrPercentage> {
+    match AutoOrLengthOrPercentage::try_from(value)? {
+        AutoOrLengthOrPercentage::Length(length) if length.value < 0.0 => Err(ParserError::UnexpectedToken(format!("{}", length.value))),
+        AutoOrLengthOrPercentage::Percentage(percentage) if *percentage < 0.0 => Err(ParserError::UnexpectedToken(format!("{}%", *percentage))),
+        value => Ok(value)
+    }
+}
+
+/// border-*-width = <line-width> = <length [0,∞]> | thin | medium | thick
+fn parse_border_width(value: &[ComponentValue<'_>]) -> ParserResult<AutoOrLengthOrPercentage> {
+    let length = match value {
+        [keyword] if keyword.is_ident("thin") => Length::px(1),
+        [keyword] if keyword.is_ident("medium") => Length::px(3),
+        [keyword] if keyword.is_ident("thick") => Length::px(5),
+        value => Length::try_from(value)?
+    };
+
+    if length.value < 0.0 {
+        return Err(ParserError::UnexpectedToken(format!("{}", length.value)));
+    }
+
+    Ok(AutoOrLengthOrPercentage::Length(length))
 }
 
+#[cfg(test)]
+mod test {
+    use super::{computed, PropertyId};
+
+    #[test]
+    fn test_property_registry() {
+        for id in PropertyId::ALL {
+            assert_eq!(PropertyId::try_from(id.name()).unwrap(), *id);
+        }
+
+        assert_eq!(PropertyId::try_from("Margin-Top").unwrap(), PropertyId::MarginTop);
+        assert!(PropertyId::Color.is_inherited());
+        assert!(!PropertyId::Display.is_inherited());
+
+        let initial = computed::Properties::default();
+        assert_eq!(initial.font_size.0, 16.0);
+        assert_eq!(initial.color.to_rgba8(), [0, 0, 0, 255]);
+    }
+}
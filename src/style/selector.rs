@@ -0,0 +1,868 @@
+//! Implements [SELECTORS 4](https://www.w3.org/TR/selectors-4/) parsing and matching.
+use crate::{
+    dom::{NodeKind, TDocumentObjectModelExplorer, TElement, TText},
+    ecs::systems::tree::walk_from,
+};
+
+use super::parser::{
+    lexer::TokenKind,
+    parser::{parse_list_of_component_values, BlockKind, ComponentValue, ParserError, ParserResult},
+};
+
+/// ```spec
+/// A selector list is a comma-separated list of selectors.
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SelectorList(pub Vec<ComplexSelector>);
+
+/// ```spec
+/// A complex selector is a sequence of one or more compound selectors separated by combinators.
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComplexSelector {
+    pub head: CompoundSelector,
+    pub tail: Vec<(Combinator, CompoundSelector)>,
+}
+
+/// A complex selector anchored to an element, as used by `:has()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RelativeSelector {
+    pub combinator: Combinator,
+    pub selector: ComplexSelector,
+}
+
+/// ```spec
+/// A compound selector is a sequence of simple selectors that are not separated by a combinator.
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CompoundSelector(pub Vec<SimpleSelector>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Combinator {
+    /// A B
+    Descendant,
+    /// A > B
+    Child,
+    /// A + B
+    NextSibling,
+    /// A ~ B
+    SubsequentSibling,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SimpleSelector {
+    /// *
+    Universal,
+    /// E
+    Type(String),
+    /// #id
+    Id(String),
+    /// .class
+    Class(String),
+    /// [attr], [attr=value], ...
+    Attribute(AttributeSelector),
+    /// :pseudo-class
+    PseudoClass(PseudoClass),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeSelector {
+    pub name: String,
+    pub matcher: Option<AttributeMatcher>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeMatcher {
+    pub operator: AttributeOperator,
+    pub value: String,
+    pub case_insensitive: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeOperator {
+    /// [attr=value]
+    Equal,
+    /// [attr~=value]
+    Includes,
+    /// [attr|=value]
+    DashMatch,
+    /// [attr^=value]
+    Prefix,
+    /// [attr$=value]
+    Suffix,
+    /// [attr*=value]
+    Substring,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PseudoClass {
+    Not(SelectorList),
+    Is(SelectorList),
+    Where(SelectorList),
+    Has(Vec<RelativeSelector>),
+    Root,
+    Empty,
+    FirstChild,
+    LastChild,
+    OnlyChild,
+    FirstOfType,
+    LastOfType,
+    OnlyOfType,
+    NthChild(AnPlusB, Option<SelectorList>),
+    NthLastChild(AnPlusB, Option<SelectorList>),
+    NthOfType(AnPlusB),
+    NthLastOfType(AnPlusB),
+}
+
+/// The An+B microsyntax, see [CSS SYNTAX 3](https://www.w3.org/TR/css-syntax-3/#anb-microsyntax)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnPlusB(pub i32, pub i32);
+
+impl AnPlusB {
+    /// Checks if the 1-based index is represented by An+B for some n >= 0.
+    pub fn matches(&self, index: i32) -> bool {
+        let AnPlusB(a, b) = *self;
+
+        if a == 0 {
+            return index == b;
+        }
+
+        let n = index - b;
+        n % a == 0 && n / a >= 0
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        let value = value.trim().to_ascii_lowercase();
+
+        match value.as_str() {
+            "odd" => return Some(Self(2, 1)),
+            "even" => return Some(Self(2, 0)),
+            _ => {}
+        }
+
+        let Some((a, b)) = value.split_once('n') else {
+            return parse_integer(&value, true).map(|b| Self(0, b));
+        };
+
+        let a = match a {
+            "" | "+" => 1,
+            "-" => -1,
+            a => parse_integer(a, true)?,
+        };
+
+        let b = b.trim_start();
+        let b = if b.is_empty() {
+            0
+        } else {
+            let (neg, b) = match b.split_at(1) {
+                ("+", b) => (false, b.trim_start()),
+                ("-", b) => (true, b.trim_start()),
+                _ => return None,
+            };
+            let b = parse_integer(b, false)?;
+            if neg { -b } else { b }
+        };
+
+        Some(Self(a, b))
+    }
+}
+
+fn parse_integer(value: &str, signed: bool) -> Option<i32> {
+    let digits = if signed {
+        value.strip_prefix(['+', '-']).unwrap_or(value)
+    } else {
+        value
+    };
+
+    if digits.is_empty() || !digits.chars().all(|ch| ch.is_ascii_digit()) {
+        return None;
+    }
+
+    value.parse().ok()
+}
+
+/// ```spec
+/// A selector’s specificity is calculated for a given element as follows:
+/// - count the number of ID selectors in the selector (= A)
+/// - count the number of class selectors, attributes selectors, and pseudo-classes in the selector (= B)
+/// - count the number of type selectors and pseudo-elements in the selector (= C)
+/// - ignore the universal selector
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Specificity(pub u32, pub u32, pub u32);
+
+impl std::ops::Add for Specificity {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self(self.0 + rhs.0, self.1 + rhs.1, self.2 + rhs.2)
+    }
+}
+
+impl SelectorList {
+    /// Parse a selector list from component values, such as the prelude of a qualified rule.
+    pub fn parse(values: &[ComponentValue<'_>]) -> ParserResult<Self> {
+        split_by_comma(values)
+            .map(|values| ComplexSelector::parse(values))
+            .collect::<ParserResult<Vec<_>>>()
+            .map(Self)
+    }
+
+    /// Parse a forgiving selector list, invalid selectors are dropped.
+    pub fn parse_forgiving(values: &[ComponentValue<'_>]) -> Self {
+        Self(
+            split_by_comma(values)
+                .filter_map(|values| ComplexSelector::parse(values).ok())
+                .collect()
+        )
+    }
+
+    /// Returns the specificity of the most specific selector of the list.
+    pub fn specificity(&self) -> Specificity {
+        self.0.iter().map(ComplexSelector::specificity).max().unwrap_or_default()
+    }
+
+    /// Checks if any of the selector matches the element.
+    pub fn matches<Dom: TDocumentObjectModelExplorer>(&self, dom: &Dom, element: &Dom::NodeId) -> bool {
+        self.0.iter().any(|selector| selector.matches(dom, element))
+    }
+}
+
+impl TryFrom<&str> for SelectorList {
+    type Error = ParserError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::parse(&parse_list_of_component_values(value)?)
+    }
+}
+
+impl ComplexSelector {
+    pub fn parse(values: &[ComponentValue<'_>]) -> ParserResult<Self> {
+        let mut cursor = Cursor::new(values);
+        cursor.skip_whitespaces();
+
+        let selector = Self::consume(&mut cursor)?;
+
+        match cursor.peek() {
+            None => Ok(selector),
+            Some(value) => Err(unexpected(value))
+        }
+    }
+
+    fn consume(cursor: &mut Cursor<'_, '_>) -> ParserResult<Self> {
+        let head = CompoundSelector::consume(cursor)?;
+        let mut tail = Vec::default();
+
+        while let Some(combinator) = Combinator::consume(cursor)? {
+            tail.push((combinator, CompoundSelector::consume(cursor)?));
+        }
+
+        Ok(Self { head, tail })
+    }
+
+    pub fn specificity(&self) -> Specificity {
+        self.tail
+            .iter()
+            .map(|(_, compound)| compound.specificity())
+            .fold(self.head.specificity(), std::ops::Add::add)
+    }
+
+    /// Returns the compound selector at the given index, starting from the left.
+    fn compound(&self, index: usize) -> &CompoundSelector {
+        match index {
+            0 => &self.head,
+            i => &self.tail[i - 1].1,
+        }
+    }
+
+    pub fn matches<Dom: TDocumentObjectModelExplorer>(&self, dom: &Dom, element: &Dom::NodeId) -> bool {
+        self.matches_from(dom, self.tail.len(), element, None)
+    }
+
+    /// Matches the selector, from the compound at the given index down to the leftmost one.
+    ///
+    /// If an anchor is given, the leftmost compound must be related to it through the combinator.
+    fn matches_from<Dom: TDocumentObjectModelExplorer>(
+        &self,
+        dom: &Dom,
+        index: usize,
+        element: &Dom::NodeId,
+        anchor: Option<(Combinator, &Dom::NodeId)>
+    ) -> bool {
+        if !self.compound(index).matches(dom, element) {
+            return false;
+        }
+
+        if index == 0 {
+            return anchor
+                .map(|(combinator, anchor)| {
+                    related_elements(dom, combinator, element).any(|candidate| candidate == *anchor)
+                })
+                .unwrap_or(true);
+        }
+
+        let combinator = self.tail[index - 1].0;
+
+        related_elements(dom, combinator, element)
+            .any(|candidate| self.matches_from(dom, index - 1, &candidate, anchor))
+    }
+}
+
+impl RelativeSelector {
+    fn parse(values: &[ComponentValue<'_>]) -> ParserResult<Self> {
+        let mut cursor = Cursor::new(values);
+        cursor.skip_whitespaces();
+
+        let combinator = Combinator::consume_explicit(&mut cursor)?.unwrap_or(Combinator::Descendant);
+        cursor.skip_whitespaces();
+
+        let selector = ComplexSelector::consume(&mut cursor)?;
+
+        match cursor.peek() {
+            None => Ok(Self { combinator, selector }),
+            Some(value) => Err(unexpected(value))
+        }
+    }
+
+    pub fn matches<Dom: TDocumentObjectModelExplorer>(&self, dom: &Dom, anchor: &Dom::NodeId) -> bool {
+        let last = self.selector.tail.len();
+
+        let candidates: Vec<Dom::NodeId> = match self.combinator {
+            Combinator::Descendant | Combinator::Child => walk_from(dom, anchor).skip(1).collect(),
+            Combinator::NextSibling | Combinator::SubsequentSibling => following_elements(dom, anchor)
+                .flat_map(|sibling| walk_from(dom, &sibling).collect::<Vec<_>>())
+                .collect()
+        };
+
+        candidates
+            .iter()
+            .filter(|candidate| dom.kind(candidate) == NodeKind::Element)
+            .any(|candidate| self.selector.matches_from(dom, last, candidate, Some((self.combinator, anchor))))
+    }
+}
+
+impl Combinator {
+    /// Consume a combinator between two compound selectors.
+    ///
+    /// Returns nothing if the end of the complex selector is reached.
+    fn consume(cursor: &mut Cursor<'_, '_>) -> ParserResult<Option<Self>> {
+        let has_whitespace = cursor.skip_whitespaces();
+
+        if cursor.peek().map(|value| value.is(TokenKind::Comma)).unwrap_or(true) {
+            return Ok(None);
+        }
+
+        let combinator = match Self::consume_explicit(cursor)? {
+            Some(combinator) => combinator,
+            None if has_whitespace => Self::Descendant,
+            None => return Err(cursor.peek().map(unexpected).unwrap_or(ParserError::UnexpectedEof))
+        };
+
+        cursor.skip_whitespaces();
+        Ok(Some(combinator))
+    }
+
+    fn consume_explicit(cursor: &mut Cursor<'_, '_>) -> ParserResult<Option<Self>> {
+        let combinator = match cursor.peek() {
+            Some(value) if value.is_delim(">") => Self::Child,
+            Some(value) if value.is_delim("+") => Self::NextSibling,
+            Some(value) if value.is_delim("~") => Self::SubsequentSibling,
+            _ => return Ok(None)
+        };
+
+        cursor.next();
+        Ok(Some(combinator))
+    }
+}
+
+impl CompoundSelector {
+    fn consume(cursor: &mut Cursor<'_, '_>) -> ParserResult<Self> {
+        let mut simples = Vec::default();
+
+        // Type selector
+        match cursor.peek() {
+            Some(value) if value.is_delim("*") => {
+                cursor.next();
+                simples.push(SimpleSelector::Universal);
+            },
+            Some(value) if value.ident().is_some() => {
+                cursor.next();
+                simples.push(SimpleSelector::Type(value.ident().unwrap().to_ascii_lowercase()));
+            },
+            _ => {}
+        }
+
+        // Subclass selectors
+        while let Some(simple) = SimpleSelector::consume_subclass(cursor)? {
+            simples.push(simple);
+        }
+
+        if simples.is_empty() {
+            return Err(cursor.peek().map(unexpected).unwrap_or(ParserError::UnexpectedEof));
+        }
+
+        Ok(Self(simples))
+    }
+
+    pub fn specificity(&self) -> Specificity {
+        self.0
+            .iter()
+            .map(SimpleSelector::specificity)
+            .fold(Specificity::default(), std::ops::Add::add)
+    }
+
+    pub fn matches<Dom: TDocumentObjectModelExplorer>(&self, dom: &Dom, element: &Dom::NodeId) -> bool {
+        dom.kind(element) == NodeKind::Element
+            && self.0.iter().all(|simple| simple.matches(dom, element))
+    }
+}
+
+impl SimpleSelector {
+    fn consume_subclass(cursor: &mut Cursor<'_, '_>) -> ParserResult<Option<Self>> {
+        let Some(value) = cursor.peek() else {
+            return Ok(None);
+        };
+
+        let simple = match value {
+            ComponentValue::Token(token) if token.kind == TokenKind::Hash(true) => {
+                cursor.next();
+                Self::Id(token.value.to_owned())
+            },
+            value if value.is_delim(".") => {
+                cursor.next();
+                let class = cursor
+                    .next()
+                    .and_then(ComponentValue::ident)
+                    .ok_or(ParserError::UnexpectedEof)?;
+                Self::Class(class.to_owned())
+            },
+            ComponentValue::Block(block) if block.kind == BlockKind::Square => {
+                cursor.next();
+                Self::Attribute(AttributeSelector::parse(&block.values)?)
+            },
+            value if value.is(TokenKind::Colon) => {
+                cursor.next();
+                Self::PseudoClass(PseudoClass::consume(cursor)?)
+            },
+            _ => return Ok(None)
+        };
+
+        Ok(Some(simple))
+    }
+
+    pub fn specificity(&self) -> Specificity {
+        match self {
+            Self::Universal => Specificity::default(),
+            Self::Type(_) => Specificity(0, 0, 1),
+            Self::Id(_) => Specificity(1, 0, 0),
+            Self::Class(_) | Self::Attribute(_) => Specificity(0, 1, 0),
+            Self::PseudoClass(pseudo) => pseudo.specificity(),
+        }
+    }
+
+    pub fn matches<Dom: TDocumentObjectModelExplorer>(&self, dom: &Dom, element: &Dom::NodeId) -> bool {
+        if let Self::PseudoClass(pseudo) = self {
+            return pseudo.matches(dom, element);
+        }
+
+        let Some(el) = dom.borrow_element(element) else {
+            return false;
+        };
+
+        match self {
+            Self::Universal => true,
+            Self::Type(name) => el.local_name().eq_ignore_ascii_case(name),
+            Self::Id(id) => el.id() == Some(id.as_str()),
+            Self::Class(class) => el.has_class(class),
+            Self::Attribute(attribute) => attribute.matches(&*el),
+            Self::PseudoClass(_) => unreachable!()
+        }
+    }
+}
+
+impl AttributeSelector {
+    fn parse(values: &[ComponentValue<'_>]) -> ParserResult<Self> {
+        let mut cursor = Cursor::new(values);
+        cursor.skip_whitespaces();
+
+        let name = cursor
+            .next()
+            .and_then(ComponentValue::ident)
+            .ok_or(ParserError::UnexpectedEof)?
+            .to_owned();
+
+        cursor.skip_whitespaces();
+
+        let Some(value) = cursor.next() else {
+            return Ok(Self { name, matcher: None });
+        };
+
+        let operator = match value.token().filter(|token| token.kind == TokenKind::Delim).map(|token| token.value) {
+            Some("=") => AttributeOperator::Equal,
+            Some(delim @ ("~" | "|" | "^" | "$" | "*")) => {
+                match cursor.next() {
+                    Some(value) if value.is_delim("=") => {},
+                    Some(value) => return Err(unexpected(value)),
+                    None => return Err(ParserError::UnexpectedEof)
+                }
+
+                match delim {
+                    "~" => AttributeOperator::Includes,
+                    "|" => AttributeOperator::DashMatch,
+                    "^" => AttributeOperator::Prefix,
+                    "$" => AttributeOperator::Suffix,
+                    _ => AttributeOperator::Substring,
+                }
+            },
+            _ => return Err(unexpected(value))
+        };
+
+        cursor.skip_whitespaces();
+
+        let value = match cursor.next().and_then(ComponentValue::token) {
+            Some(token) if matches!(token.kind, TokenKind::Ident | TokenKind::String) => token.value.to_owned(),
+            _ => return Err(ParserError::UnexpectedEof)
+        };
+
+        cursor.skip_whitespaces();
+
+        let mut case_insensitive = false;
+        if let Some(modifier) = cursor.peek().and_then(ComponentValue::ident) {
+            match modifier.to_ascii_lowercase().as_str() {
+                "i" => case_insensitive = true,
+                "s" => {},
+                _ => return Err(ParserError::UnexpectedToken(modifier.to_owned()))
+            }
+            cursor.next();
+            cursor.skip_whitespaces();
+        }
+
+        if let Some(value) = cursor.peek() {
+            return Err(unexpected(value));
+        }
+
+        Ok(Self { name, matcher: Some(AttributeMatcher { operator, value, case_insensitive }) })
+    }
+
+    pub fn matches<Element: TElement + ?Sized>(&self, element: &Element) -> bool {
+        let Some(actual) = element.attribute(&self.name) else {
+            return false;
+        };
+
+        let Some(matcher) = &self.matcher else {
+            return true;
+        };
+
+        let (actual, expected) = if matcher.case_insensitive {
+            (actual.to_lowercase(), matcher.value.to_lowercase())
+        } else {
+            (actual.to_owned(), matcher.value.clone())
+        };
+
+        match matcher.operator {
+            AttributeOperator::Equal => actual == expected,
+            AttributeOperator::Includes => actual.split_ascii_whitespace().any(|word| word == expected),
+            AttributeOperator::DashMatch => {
+                actual == expected
+                    || actual.strip_prefix(&expected).map(|rest| rest.starts_with('-')).unwrap_or_default()
+            },
+            AttributeOperator::Prefix => !expected.is_empty() && actual.starts_with(&expected),
+            AttributeOperator::Suffix => !expected.is_empty() && actual.ends_with(&expected),
+            AttributeOperator::Substring => !expected.is_empty() && actual.contains(&expected),
+        }
+    }
+}
+
+impl PseudoClass {
+    fn consume(cursor: &mut Cursor<'_, '_>) -> ParserResult<Self> {
+        match cursor.next() {
+            Some(ComponentValue::Token(token)) if token.kind == TokenKind::Ident => {
+                match token.value.to_ascii_lowercase().as_str() {
+                    "root" => Ok(Self::Root),
+                    "empty" => Ok(Self::Empty),
+                    "first-child" => Ok(Self::FirstChild),
+                    "last-child" => Ok(Self::LastChild),
+                    "only-child" => Ok(Self::OnlyChild),
+                    "first-of-type" => Ok(Self::FirstOfType),
+                    "last-of-type" => Ok(Self::LastOfType),
+                    "only-of-type" => Ok(Self::OnlyOfType),
+                    name => Err(ParserError::UnexpectedToken(name.to_owned()))
+                }
+            },
+            Some(ComponentValue::Function(function)) => {
+                let arguments = function.arguments.as_slice();
+
+                match function.name.to_ascii_lowercase().as_str() {
+                    "not" => SelectorList::parse(arguments).map(Self::Not),
+                    "is" => Ok(Self::Is(SelectorList::parse_forgiving(arguments))),
+                    "where" => Ok(Self::Where(SelectorList::parse_forgiving(arguments))),
+                    "has" => split_by_comma(arguments)
+                        .map(RelativeSelector::parse)
+                        .collect::<ParserResult<Vec<_>>>()
+                        .map(Self::Has),
+                    "nth-child" => parse_nth_of(arguments).map(|(anb, of)| Self::NthChild(anb, of)),
+                    "nth-last-child" => parse_nth_of(arguments).map(|(anb, of)| Self::NthLastChild(anb, of)),
+                    "nth-of-type" => parse_nth(arguments).map(Self::NthOfType),
+                    "nth-last-of-type" => parse_nth(arguments).map(Self::NthLastOfType),
+                    name => Err(ParserError::UnexpectedToken(name.to_owned()))
+                }
+            },
+            Some(value) => Err(unexpected(value)),
+            None => Err(ParserError::UnexpectedEof)
+        }
+    }
+
+    /// ```spec
+    /// The specificity of an :is(), :not(), or :has() pseudo-class is replaced by the specificity of the most specific complex selector in its selector list argument.
+    /// The specificity of an :nth-child() or :nth-last-child() selector is the specificity of the pseudo class itself (counting as one pseudo-class selector) plus the specificity of the most specific complex selector in its selector list argument (if any).
+    /// The specificity of a :where() pseudo-class is replaced by zero.
+    /// ```
+    pub fn specificity(&self) -> Specificity {
+        match self {
+            Self::Not(list) | Self::Is(list) => list.specificity(),
+            Self::Where(_) => Specificity::default(),
+            Self::Has(selectors) => selectors
+                .iter()
+                .map(|relative| relative.selector.specificity())
+                .max()
+                .unwrap_or_default(),
+            Self::NthChild(_, Some(list)) | Self::NthLastChild(_, Some(list)) => {
+                Specificity(0, 1, 0) + list.specificity()
+            },
+            _ => Specificity(0, 1, 0)
+        }
+    }
+
+    pub fn matches<Dom: TDocumentObjectModelExplorer>(&self, dom: &Dom, element: &Dom::NodeId) -> bool {
+        let local_name = || {
+            dom.borrow_element(element)
+                .map(|el| el.local_name().to_ascii_lowercase())
+                .unwrap_or_default()
+        };
+
+        let same_type = |sibling: &Dom::NodeId| {
+            dom.borrow_element(sibling)
+                .map(|el| el.local_name().eq_ignore_ascii_case(&local_name()))
+                .unwrap_or_default()
+        };
+
+        match self {
+            Self::Not(list) => !list.matches(dom, element),
+            Self::Is(list) | Self::Where(list) => list.matches(dom, element),
+            Self::Has(selectors) => selectors.iter().any(|relative| relative.matches(dom, element)),
+            Self::Root => dom.root().as_ref() == Some(element),
+            Self::Empty => {
+                dom.iter_children(element).all(|child| {
+                    dom.kind(&child) != NodeKind::Element
+                        && dom.borrow_text(&child).map(|text| text.text().is_empty()).unwrap_or(true)
+                })
+            },
+            Self::FirstChild => preceding_elements(dom, element).next().is_none(),
+            Self::LastChild => following_elements(dom, element).next().is_none(),
+            Self::OnlyChild => {
+                preceding_elements(dom, element).next().is_none()
+                    && following_elements(dom, element).next().is_none()
+            },
+            Self::FirstOfType => !preceding_elements(dom, element).any(|sibling| same_type(&sibling)),
+            Self::LastOfType => !following_elements(dom, element).any(|sibling| same_type(&sibling)),
+            Self::OnlyOfType => {
+                !preceding_elements(dom, element).any(|sibling| same_type(&sibling))
+                    && !following_elements(dom, element).any(|sibling| same_type(&sibling))
+            },
+            Self::NthChild(anb, of) => {
+                let of = |node: &Dom::NodeId| of.as_ref().map(|list| list.matches(dom, node)).unwrap_or(true);
+                of(element) && anb.matches(1 + preceding_elements(dom, element).filter(of).count() as i32)
+            },
+            Self::NthLastChild(anb, of) => {
+                let of = |node: &Dom::NodeId| of.as_ref().map(|list| list.matches(dom, node)).unwrap_or(true);
+                of(element) && anb.matches(1 + following_elements(dom, element).filter(of).count() as i32)
+            },
+            Self::NthOfType(anb) => {
+                anb.matches(1 + preceding_elements(dom, element).filter(same_type).count() as i32)
+            },
+            Self::NthLastOfType(anb) => {
+                anb.matches(1 + following_elements(dom, element).filter(same_type).count() as i32)
+            },
+        }
+    }
+}
+
+/// Parse the arguments of :nth-of-type() and :nth-last-of-type()
+fn parse_nth(arguments: &[ComponentValue<'_>]) -> ParserResult<AnPlusB> {
+    AnPlusB::parse(&serialize(arguments))
+        .ok_or_else(|| ParserError::UnexpectedToken(serialize(arguments)))
+}
+
+/// Parse the arguments of :nth-child() and :nth-last-child(), `An+B [of S]?`
+fn parse_nth_of(arguments: &[ComponentValue<'_>]) -> ParserResult<(AnPlusB, Option<SelectorList>)> {
+    match arguments.iter().position(|value| value.is_ident("of")) {
+        Some(of) => Ok((parse_nth(&arguments[..of])?, Some(SelectorList::parse(&arguments[of + 1..])?))),
+        None => Ok((parse_nth(arguments)?, None))
+    }
+}
+
+/// Serialize the raw tokens.
+fn serialize(values: &[ComponentValue<'_>]) -> String {
+    values
+        .iter()
+        .map(|value| match value {
+            ComponentValue::Token(token) if token.kind == TokenKind::Whitespace => " ",
+            ComponentValue::Token(token) => token.value,
+            _ => "\u{FFFD}"
+        })
+        .collect()
+}
+
+fn split_by_comma<'a, 'i>(values: &'a [ComponentValue<'i>]) -> impl Iterator<Item = &'a [ComponentValue<'i>]> {
+    values.split(|value| value.is(TokenKind::Comma))
+}
+
+fn unexpected(value: &ComponentValue<'_>) -> ParserError {
+    ParserError::UnexpectedToken(format!("{value:?}"))
+}
+
+/// Returns the elements related to the given one through the combinator,
+/// that is the candidates for the compound selector at the left of the combinator.
+fn related_elements<'a, Dom: TDocumentObjectModelExplorer>(
+    dom: &'a Dom,
+    combinator: Combinator,
+    element: &Dom::NodeId
+) -> Box<dyn Iterator<Item = Dom::NodeId> + 'a> {
+    match combinator {
+        Combinator::Descendant => Box::new(std::iter::successors(dom.parent(element), |node| dom.parent(node))),
+        Combinator::Child => Box::new(dom.parent(element).into_iter()),
+        Combinator::NextSibling => Box::new(preceding_elements(dom, element).take(1)),
+        Combinator::SubsequentSibling => Box::new(preceding_elements(dom, element)),
+    }
+}
+
+/// Iterates over the preceding sibling elements, starting from the closest one.
+fn preceding_elements<'a, Dom: TDocumentObjectModelExplorer>(dom: &'a Dom, element: &Dom::NodeId) -> impl Iterator<Item = Dom::NodeId> + 'a {
+    std::iter::successors(dom.previous_sibling(element), |node| dom.previous_sibling(node))
+        .filter(|node| dom.kind(node) == NodeKind::Element)
+}
+
+/// Iterates over the following sibling elements, starting from the closest one.
+fn following_elements<'a, Dom: TDocumentObjectModelExplorer>(dom: &'a Dom, element: &Dom::NodeId) -> impl Iterator<Item = Dom::NodeId> + 'a {
+    std::iter::successors(dom.next_sibling(element), |node| dom.next_sibling(node))
+        .filter(|node| dom.kind(node) == NodeKind::Element)
+}
+
+/// A cursor over a list of component values.
+struct Cursor<'a, 'i> {
+    values: &'a [ComponentValue<'i>],
+    position: usize,
+}
+
+impl<'a, 'i> Cursor<'a, 'i> {
+    fn new(values: &'a [ComponentValue<'i>]) -> Self {
+        Self { values, position: 0 }
+    }
+
+    fn peek(&self) -> Option<&'a ComponentValue<'i>> {
+        self.values.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<&'a ComponentValue<'i>> {
+        let value = self.peek();
+        self.position += value.is_some() as usize;
+        value
+    }
+
+    /// Skips whitespaces, returns true if any were skipped.
+    fn skip_whitespaces(&mut self) -> bool {
+        let start = self.position;
+        while self.peek().map(ComponentValue::is_whitespace).unwrap_or_default() {
+            self.position += 1;
+        }
+        start != self.position
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dom::{DocumentObjectModel, Element, NodeId};
+
+    use super::{AnPlusB, SelectorList, Specificity};
+
+    fn selector(value: &str) -> SelectorList {
+        SelectorList::try_from(value).unwrap()
+    }
+
+    /// html > body > (div#main.a.b > p*3 + span[lang=fr-CA])
+    fn document() -> (DocumentObjectModel, Vec<NodeId>) {
+        let mut dom = DocumentObjectModel::default();
+        let html = dom.insert_element(Element::new("html"), None);
+        let body = dom.insert_element(Element::new("body"), Some(html));
+        let div = dom.insert_element(
+            Element::new("div").with_attribute("id", "main").with_attribute("class", "a b"),
+            Some(body)
+        );
+        let p1 = dom.insert_element(Element::new("p"), Some(div));
+        dom.insert_text("text", Some(div));
+        let p2 = dom.insert_element(Element::new("p"), Some(div));
+        let p3 = dom.insert_element(Element::new("p"), Some(div));
+        let span = dom.insert_element(Element::new("span").with_attribute("lang", "fr-CA"), Some(div));
+
+        (dom, vec![html, body, div, p1, p2, p3, span])
+    }
+
+    #[test]
+    fn test_parse_selectors() {
+        assert!(SelectorList::try_from("div > p.a#b[title~=x i]:first-child, a + b ~ c").is_ok());
+        assert!(SelectorList::try_from(":is(p, ::before) :where(a) :has(> img, + p)").is_ok());
+        assert!(SelectorList::try_from("div >").is_err());
+        assert!(SelectorList::try_from("p:unknown").is_err());
+        assert!(SelectorList::try_from("a,").is_err());
+    }
+
+    #[test]
+    fn test_an_plus_b() {
+        assert_eq!(AnPlusB::parse("odd"), Some(AnPlusB(2, 1)));
+        assert_eq!(AnPlusB::parse("-n+ 3"), Some(AnPlusB(-1, 3)));
+        assert_eq!(AnPlusB::parse("2n - 1"), Some(AnPlusB(2, -1)));
+        assert_eq!(AnPlusB::parse("+5"), Some(AnPlusB(0, 5)));
+        assert_eq!(AnPlusB::parse("2 n"), None);
+
+        assert!(AnPlusB(-1, 3).matches(3));
+        assert!(!AnPlusB(-1, 3).matches(4));
+        assert!(AnPlusB(2, 1).matches(5));
+    }
+
+    #[test]
+    fn test_specificity() {
+        assert_eq!(selector("div#main.a > p").specificity(), Specificity(1, 1, 2));
+        assert_eq!(selector(":where(#main) p:not(.a, #b)").specificity(), Specificity(1, 0, 1));
+        assert_eq!(selector("li:nth-child(2n of .a)").specificity(), Specificity(0, 2, 1));
+    }
+
+    #[test]
+    fn test_match_selectors() {
+        let (dom, nodes) = document();
+        let [html, body, div, p1, p2, p3, span] = nodes.try_into().unwrap();
+
+        let matching = |value: &str| {
+            let selector = selector(value);
+            [html, body, div, p1, p2, p3, span]
+                .into_iter()
+                .filter(|node| selector.matches(&dom, node))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(matching(":root"), vec![html]);
+        assert_eq!(matching("body div.a.b#main"), vec![div]);
+        assert_eq!(matching("html > div"), vec![]);
+        assert_eq!(matching("p + p"), vec![p2, p3]);
+        assert_eq!(matching("p:first-child ~ span"), vec![span]);
+        assert_eq!(matching("[lang|=fr]"), vec![span]);
+        assert_eq!(matching("[lang$=ca i]"), vec![span]);
+        assert_eq!(matching("p:nth-child(2n+1)"), vec![p1, p3]);
+        assert_eq!(matching("div > :nth-last-child(2)"), vec![p3]);
+        assert_eq!(matching("p:last-of-type, span:only-of-type"), vec![p3, span]);
+        assert_eq!(matching(":not(p, html, body)"), vec![div, span]);
+        assert_eq!(matching("div:has(> span[lang])"), vec![div]);
+        assert_eq!(matching("body:has(> p)"), vec![]);
+        assert_eq!(matching("p:has(~ span)"), vec![p1, p2, p3]);
+        assert_eq!(matching(":is(span, p):empty"), vec![p1, p2, p3, span]);
+    }
+}
@@ -0,0 +1,409 @@
+//! Implements the shorthand properties, which set several longhand properties at once.
+use super::{
+    fragmentation::{BreakBetween, BreakInside},
+    parser::parser::{ComponentValue, ParserError, ParserResult},
+    properties::{CssWideKeyword, PropertyDeclaration, PropertyId},
+};
+
+/// Identifier of a shorthand property.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ShorthandId {
+    Margin,
+    Padding,
+    Inset,
+    BorderWidth,
+    BorderStyle,
+    BorderColor,
+    BorderTop,
+    BorderRight,
+    BorderBottom,
+    BorderLeft,
+    Border,
+}
+
+impl TryFrom<&str> for ShorthandId {
+    type Error = ParserError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.to_ascii_lowercase().as_str() {
+            "margin" => Ok(Self::Margin),
+            "padding" => Ok(Self::Padding),
+            "inset" => Ok(Self::Inset),
+            "border-width" => Ok(Self::BorderWidth),
+            "border-style" => Ok(Self::BorderStyle),
+            "border-color" => Ok(Self::BorderColor),
+            "border-top" => Ok(Self::BorderTop),
+            "border-right" => Ok(Self::BorderRight),
+            "border-bottom" => Ok(Self::BorderBottom),
+            "border-left" => Ok(Self::BorderLeft),
+            "border" => Ok(Self::Border),
+            name => Err(ParserError::UnexpectedToken(name.to_owned()))
+        }
+    }
+}
+
+impl ShorthandId {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Margin => "margin",
+            Self::Padding => "padding",
+            Self::Inset => "inset",
+            Self::BorderWidth => "border-width",
+            Self::BorderStyle => "border-style",
+            Self::BorderColor => "border-color",
+            Self::BorderTop => "border-top",
+            Self::BorderRight => "border-right",
+            Self::BorderBottom => "border-bottom",
+            Self::BorderLeft => "border-left",
+            Self::Border => "border",
+        }
+    }
+
+    /// Returns the longhands set by the shorthand.
+    ///
+    /// The longhands of the box edges are ordered as top, right, bottom and left.
+    pub fn longhands(&self) -> &'static [PropertyId] {
+        match self {
+            Self::Margin => &[PropertyId::MarginTop, PropertyId::MarginRight, PropertyId::MarginBottom, PropertyId::MarginLeft],
+            Self::Padding => &[PropertyId::PaddingTop, PropertyId::PaddingRight, PropertyId::PaddingBottom, PropertyId::PaddingLeft],
+            Self::Inset => &[PropertyId::Top, PropertyId::Right, PropertyId::Bottom, PropertyId::Left],
+            Self::BorderWidth => &[
+                PropertyId::BorderTopWidth, PropertyId::BorderRightWidth,
+                PropertyId::BorderBottomWidth, PropertyId::BorderLeftWidth
+            ],
+            Self::BorderStyle => &[
+                PropertyId::BorderTopStyle, PropertyId::BorderRightStyle,
+                PropertyId::BorderBottomStyle, PropertyId::BorderLeftStyle
+            ],
+            Self::BorderColor => &[
+                PropertyId::BorderTopColor, PropertyId::BorderRightColor,
+                PropertyId::BorderBottomColor, PropertyId::BorderLeftColor
+            ],
+            Self::BorderTop => &[PropertyId::BorderTopWidth, PropertyId::BorderTopStyle, PropertyId::BorderTopColor],
+            Self::BorderRight => &[PropertyId::BorderRightWidth, PropertyId::BorderRightStyle, PropertyId::BorderRightColor],
+            Self::BorderBottom => &[PropertyId::BorderBottomWidth, PropertyId::BorderBottomStyle, PropertyId::BorderBottomColor],
+            Self::BorderLeft => &[PropertyId::BorderLeftWidth, PropertyId::BorderLeftStyle, PropertyId::BorderLeftColor],
+            Self::Border => &[
+                PropertyId::BorderTopWidth, PropertyId::BorderTopStyle, PropertyId::BorderTopColor,
+                PropertyId::BorderRightWidth, PropertyId::BorderRightStyle, PropertyId::BorderRightColor,
+                PropertyId::BorderBottomWidth, PropertyId::BorderBottomStyle, PropertyId::BorderBottomColor,
+                PropertyId::BorderLeftWidth, PropertyId::BorderLeftStyle, PropertyId::BorderLeftColor,
+            ],
+        }
+    }
+
+    /// Parse the value of the shorthand, and expands it into its longhands.
+    pub fn parse(&self, value: &[ComponentValue<'_>]) -> ParserResult<Vec<PropertyDeclaration>> {
+        if let Ok(keyword) = CssWideKeyword::try_from(value) {
+            return Ok(self.longhands().iter().map(|id| PropertyDeclaration::CssWide(*id, keyword)).collect());
+        }
+
+        let components = value.iter().filter(|value| !value.is_whitespace()).collect::<Vec<_>>();
+
+        match self {
+            Self::Margin | Self::Padding | Self::Inset | Self::BorderWidth | Self::BorderStyle | Self::BorderColor => {
+                parse_edges(self.longhands(), &components)
+            },
+            Self::BorderTop | Self::BorderRight | Self::BorderBottom | Self::BorderLeft => {
+                parse_border_side(self.longhands(), &components)
+            },
+            // The border shorthand sets the same value on each side.
+            Self::Border => {
+                let side = parse_border_side(Self::BorderTop.longhands(), &components)?;
+
+                Ok(self.longhands()
+                    .iter()
+                    .zip(side.iter().cycle())
+                    .map(|(id, declaration)| with_id(declaration, *id))
+                    .collect())
+            }
+        }
+    }
+
+    /// Serializes the longhands into the shortest equivalent value of the shorthand.
+    ///
+    /// Returns none if a longhand is not declared, or if they cannot be expressed by the shorthand.
+    pub fn serialize(&self, declarations: &[PropertyDeclaration]) -> Option<String> {
+        let longhands = self.longhands()
+            .iter()
+            .map(|id| declarations.iter().rev().find(|declaration| declaration.id() == *id))
+            .collect::<Option<Vec<_>>>()?;
+
+        let keywords = longhands
+            .iter()
+            .map(|declaration| match declaration {
+                PropertyDeclaration::CssWide(_, keyword) => Some(*keyword),
+                _ => None
+            })
+            .collect::<Vec<_>>();
+
+        // A CSS-wide keyword can only be serialized if it applies to every longhand.
+        if keywords.iter().any(Option::is_some) {
+            return match keywords[0] {
+                Some(keyword) if keywords.iter().all(|other| *other == Some(keyword)) => Some(keyword.to_string()),
+                _ => None
+            };
+        }
+
+        let values = longhands.iter().map(|declaration| serialize_value(declaration)).collect::<Option<Vec<_>>>()?;
+
+        match self {
+            Self::Margin | Self::Padding | Self::Inset | Self::BorderWidth | Self::BorderStyle | Self::BorderColor => {
+                Some(serialize_edges(&values))
+            },
+            Self::BorderTop | Self::BorderRight | Self::BorderBottom | Self::BorderLeft => {
+                Some(serialize_border_side(self.longhands(), &values))
+            },
+            Self::Border => {
+                let (top, others) = values.split_at(3);
+
+                if others.chunks(3).any(|side| side != top) {
+                    return None;
+                }
+
+                Some(serialize_border_side(Self::BorderTop.longhands(), top))
+            }
+        }
+    }
+}
+
+/// Parse the declaration of either a longhand or a shorthand, into longhand declarations.
+pub fn parse_declaration(name: &str, value: &[ComponentValue<'_>]) -> ParserResult<Vec<PropertyDeclaration>> {
+    // ```spec
+    // For legacy reasons, UAs must treat word-wrap as a legacy name alias of the overflow-wrap property.
+    // ```
+    let name = if name.eq_ignore_ascii_case("word-wrap") { "overflow-wrap" } else { name };
+
+    // ```spec
+    // page-break-before, page-break-after and page-break-inside are legacy shorthands for break-before, break-after and break-inside.
+    // ```
+    match name.to_ascii_lowercase().as_str() {
+        "page-break-before" | "page-break-after" | "page-break-inside" => return parse_legacy_page_break(name, value),
+        _ => {},
+    }
+
+    match ShorthandId::try_from(name) {
+        Ok(shorthand) => shorthand.parse(value),
+        Err(_) => PropertyDeclaration::parse(name, value).map(|declaration| vec![declaration])
+    }
+}
+
+/// Parses a legacy page break property into its modern longhand.
+fn parse_legacy_page_break(name: &str, value: &[ComponentValue<'_>]) -> ParserResult<Vec<PropertyDeclaration>> {
+    let id = match name.to_ascii_lowercase().as_str() {
+        "page-break-before" => PropertyId::BreakBefore,
+        "page-break-after" => PropertyId::BreakAfter,
+        _ => PropertyId::BreakInside,
+    };
+
+    if let Ok(keyword) = CssWideKeyword::try_from(value) {
+        return Ok(vec![PropertyDeclaration::CssWide(id, keyword)]);
+    }
+
+    let declaration = match id {
+        PropertyId::BreakBefore => PropertyDeclaration::BreakBefore(BreakBetween::parse_legacy(value)?),
+        PropertyId::BreakAfter => PropertyDeclaration::BreakAfter(BreakBetween::parse_legacy(value)?),
+        _ => PropertyDeclaration::BreakInside(BreakInside::parse_legacy(value)?),
+    };
+
+    Ok(vec![declaration])
+}
+
+/// ```spec
+/// If there is only one component value, it applies to all sides.
+/// If there are two values, the top and bottom are set to the first value and the right and left are set to the second.
+/// If there are three values, the top is set to the first value, the left and right are set to the second, and the bottom is set to the third.
+/// If there are four values they apply to the top, right, bottom, and left, respectively.
+/// ```
+fn parse_edges(longhands: &[PropertyId], components: &[&ComponentValue<'_>]) -> ParserResult<Vec<PropertyDeclaration>> {
+    let [top, right, bottom, left] = match components {
+        [all] => [all, all, all, all],
+        [vertical, horizontal] => [vertical, horizontal, vertical, horizontal],
+        [top, horizontal, bottom] => [top, horizontal, bottom, horizontal],
+        [top, right, bottom, left] => [top, right, bottom, left],
+        [] => return Err(ParserError::UnexpectedEof),
+        components => return Err(ParserError::UnexpectedToken(format!("{components:?}")))
+    };
+
+    longhands
+        .iter()
+        .zip([top, right, bottom, left])
+        .map(|(id, value)| parse_longhand(*id, value))
+        .collect()
+}
+
+/// border-top = <line-width> || <line-style> || <color>
+///
+/// The longhands must be ordered as width, style and color. Omitted values are set to their initial value.
+fn parse_border_side(longhands: &[PropertyId], components: &[&ComponentValue<'_>]) -> ParserResult<Vec<PropertyDeclaration>> {
+    if components.is_empty() || components.len() > longhands.len() {
+        return Err(ParserError::UnexpectedToken(format!("{components:?}")));
+    }
+
+    let mut declarations: Vec<Option<PropertyDeclaration>> = vec![None; longhands.len()];
+
+    for component in components {
+        let parsed = longhands
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| declarations[*index].is_none())
+            .find_map(|(index, id)| parse_longhand(*id, component).ok().map(|declaration| (index, declaration)));
+
+        match parsed {
+            Some((index, declaration)) => declarations[index] = Some(declaration),
+            None => return Err(ParserError::UnexpectedToken(format!("{component:?}")))
+        }
+    }
+
+    Ok(declarations
+        .into_iter()
+        .zip(longhands)
+        .map(|(declaration, id)| declaration.unwrap_or_else(|| id.initial_declaration()))
+        .collect())
+}
+
+fn parse_longhand(id: PropertyId, value: &ComponentValue<'_>) -> ParserResult<PropertyDeclaration> {
+    match PropertyDeclaration::parse(id.name(), std::slice::from_ref(value))? {
+        PropertyDeclaration::CssWide(..) => Err(ParserError::UnexpectedToken(format!("{value:?}"))),
+        declaration => Ok(declaration)
+    }
+}
+
+/// Returns the same value, declared for another longhand of the same type.
+fn with_id(declaration: &PropertyDeclaration, id: PropertyId) -> PropertyDeclaration {
+    match (declaration, id) {
+        (PropertyDeclaration::BorderTopWidth(value), PropertyId::BorderRightWidth) => PropertyDeclaration::BorderRightWidth(*value),
+        (PropertyDeclaration::BorderTopWidth(value), PropertyId::BorderBottomWidth) => PropertyDeclaration::BorderBottomWidth(*value),
+        (PropertyDeclaration::BorderTopWidth(value), PropertyId::BorderLeftWidth) => PropertyDeclaration::BorderLeftWidth(*value),
+        (PropertyDeclaration::BorderTopStyle(value), PropertyId::BorderRightStyle) => PropertyDeclaration::BorderRightStyle(*value),
+        (PropertyDeclaration::BorderTopStyle(value), PropertyId::BorderBottomStyle) => PropertyDeclaration::BorderBottomStyle(*value),
+        (PropertyDeclaration::BorderTopStyle(value), PropertyId::BorderLeftStyle) => PropertyDeclaration::BorderLeftStyle(*value),
+        (PropertyDeclaration::BorderTopColor(value), PropertyId::BorderRightColor) => PropertyDeclaration::BorderRightColor(value.clone()),
+        (PropertyDeclaration::BorderTopColor(value), PropertyId::BorderBottomColor) => PropertyDeclaration::BorderBottomColor(value.clone()),
+        (PropertyDeclaration::BorderTopColor(value), PropertyId::BorderLeftColor) => PropertyDeclaration::BorderLeftColor(value.clone()),
+        (declaration, _) => declaration.clone()
+    }
+}
+
+/// Serializes the value of a longhand which is part of a shorthand.
+fn serialize_value(declaration: &PropertyDeclaration) -> Option<String> {
+    match declaration {
+        PropertyDeclaration::MarginTop(value)
+        | PropertyDeclaration::MarginRight(value)
+        | PropertyDeclaration::MarginBottom(value)
+        | PropertyDeclaration::MarginLeft(value)
+        | PropertyDeclaration::PaddingTop(value)
+        | PropertyDeclaration::PaddingRight(value)
+        | PropertyDeclaration::PaddingBottom(value)
+        | PropertyDeclaration::PaddingLeft(value)
+        | PropertyDeclaration::Top(value)
+        | PropertyDeclaration::Right(value)
+        | PropertyDeclaration::Bottom(value)
+        | PropertyDeclaration::Left(value)
+        | PropertyDeclaration::BorderTopWidth(value)
+        | PropertyDeclaration::BorderRightWidth(value)
+        | PropertyDeclaration::BorderBottomWidth(value)
+        | PropertyDeclaration::BorderLeftWidth(value) => Some(value.to_string()),
+        PropertyDeclaration::BorderTopStyle(value)
+        | PropertyDeclaration::BorderRightStyle(value)
+        | PropertyDeclaration::BorderBottomStyle(value)
+        | PropertyDeclaration::BorderLeftStyle(value) => Some(value.to_string()),
+        PropertyDeclaration::BorderTopColor(value)
+        | PropertyDeclaration::BorderRightColor(value)
+        | PropertyDeclaration::BorderBottomColor(value)
+        | PropertyDeclaration::BorderLeftColor(value) => Some(value.to_string()),
+        _ => None
+    }
+}
+
+/// Serializes the values of the top, right, bottom and left edges with as few values as possible.
+fn serialize_edges(values: &[String]) -> String {
+    let [top, right, bottom, left] = values else {
+        return values.join(" ");
+    };
+
+    if left != right {
+        format!("{top} {right} {bottom} {left}")
+    } else if top != bottom {
+        format!("{top} {right} {bottom}")
+    } else if top != right {
+        format!("{top} {right}")
+    } else {
+        top.clone()
+    }
+}
+
+/// Serializes the width, style and color of a border, initial values are omitted.
+fn serialize_border_side(longhands: &[PropertyId], values: &[String]) -> String {
+    let parts = longhands
+        .iter()
+        .zip(values)
+        .filter(|(id, value)| serialize_value(&id.initial_declaration()).as_ref() != Some(*value))
+        .map(|(_, value)| value.as_str())
+        .collect::<Vec<_>>();
+
+    if parts.is_empty() {
+        // Every value is the initial one.
+        "none".to_owned()
+    } else {
+        parts.join(" ")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::style::{
+        parser::parser::parse_list_of_component_values,
+        properties::{PropertyDeclaration, PropertyId},
+    };
+
+    use super::{parse_declaration, ShorthandId};
+
+    fn expand(name: &str, value: &str) -> Vec<PropertyDeclaration> {
+        let values = parse_list_of_component_values(value).unwrap();
+        parse_declaration(name, &values).unwrap()
+    }
+
+    fn roundtrip(name: &str, value: &str) -> Option<String> {
+        ShorthandId::try_from(name).unwrap().serialize(&expand(name, value))
+    }
+
+    #[test]
+    fn test_expand_shorthands() {
+        let margin = expand("margin", "1px 2px 3px");
+        let ids = margin.iter().map(PropertyDeclaration::id).collect::<Vec<_>>();
+        assert_eq!(ids, [PropertyId::MarginTop, PropertyId::MarginRight, PropertyId::MarginBottom, PropertyId::MarginLeft]);
+        assert!(matches!(&margin[3], PropertyDeclaration::MarginLeft(value) if value.to_string() == "2px"));
+
+        assert_eq!(expand("border", "thin solid red").len(), 12);
+        assert_eq!(expand("inset", "inherit").len(), 4);
+        assert_eq!(expand("padding-top", "1em").len(), 1);
+
+        let values = parse_list_of_component_values("1px 2px 3px 4px 5px").unwrap();
+        assert!(parse_declaration("margin", &values).is_err());
+        let values = parse_list_of_component_values("solid solid").unwrap();
+        assert!(parse_declaration("border", &values).is_err());
+        let values = parse_list_of_component_values("-1px").unwrap();
+        assert!(parse_declaration("padding", &values).is_err());
+    }
+
+    #[test]
+    fn test_serialize_shorthands() {
+        assert_eq!(roundtrip("margin", "1px 1px 1px 1px").as_deref(), Some("1px"));
+        assert_eq!(roundtrip("margin", "1px auto 1px auto").as_deref(), Some("1px auto"));
+        assert_eq!(roundtrip("padding", "1px 2em 3%").as_deref(), Some("1px 2em 3%"));
+        assert_eq!(roundtrip("inset", "0 1px 0 2px").as_deref(), Some("0px 1px 0px 2px"));
+        assert_eq!(roundtrip("border-style", "solid dashed").as_deref(), Some("solid dashed"));
+        assert_eq!(roundtrip("border", "red solid").as_deref(), Some("solid red"));
+        assert_eq!(roundtrip("border-top", "medium").as_deref(), Some("none"));
+        assert_eq!(roundtrip("border-color", "#FF0 rgb(0 0 255 / 50%)").as_deref(), Some("#ff0 rgb(0 0 255 / 50%)"));
+        assert_eq!(roundtrip("margin", "unset").as_deref(), Some("unset"));
+
+        // Different sides cannot be expressed by the border shorthand.
+        let mut declarations = expand("border", "solid");
+        declarations.extend(expand("border-left-width", "1px"));
+        assert_eq!(ShorthandId::Border.serialize(&declarations), None);
+        assert_eq!(ShorthandId::BorderTop.serialize(&declarations).as_deref(), Some("solid"));
+        assert_eq!(ShorthandId::BorderWidth.serialize(&declarations).as_deref(), Some("3px 3px 3px 1px"));
+    }
+}
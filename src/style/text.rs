@@ -0,0 +1,243 @@
+//! Implements white space processing and line breaking from [CSS TEXT 3](https://www.w3.org/TR/css-text-3/)
+use super::parser::parser::{ComponentValue, ParserError, ParserResult};
+
+/// white-space = normal | pre | nowrap | pre-wrap | break-spaces | pre-line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WhiteSpace {
+    #[default]
+    Normal,
+    Pre,
+    Nowrap,
+    PreWrap,
+    BreakSpaces,
+    PreLine,
+}
+
+impl TryFrom<&[ComponentValue<'_>]> for WhiteSpace {
+    type Error = ParserError;
+
+    fn try_from(values: &[ComponentValue<'_>]) -> ParserResult<Self> {
+        let keyword = match values {
+            [value] => value.ident().map(str::to_ascii_lowercase),
+            _ => None
+        };
+
+        match keyword.as_deref() {
+            Some("normal") => Ok(Self::Normal),
+            Some("pre") => Ok(Self::Pre),
+            Some("nowrap") => Ok(Self::Nowrap),
+            Some("pre-wrap") => Ok(Self::PreWrap),
+            Some("break-spaces") => Ok(Self::BreakSpaces),
+            Some("pre-line") => Ok(Self::PreLine),
+            _ => Err(ParserError::UnexpectedToken(format!("{values:?}")))
+        }
+    }
+}
+
+impl WhiteSpace {
+    /// ```spec
+    /// normal, nowrap, pre-line: Sequences of white space are collapsed into a single character.
+    /// ```
+    pub fn collapses_spaces(&self) -> bool {
+        matches!(self, Self::Normal | Self::Nowrap | Self::PreLine)
+    }
+
+    /// ```spec
+    /// pre, pre-wrap, break-spaces, pre-line: Lines are only broken at preserved newline characters [...].
+    /// ```
+    pub fn preserves_segment_breaks(&self) -> bool {
+        !matches!(self, Self::Normal | Self::Nowrap)
+    }
+
+    /// Checks if lines may be wrapped at soft wrap opportunities.
+    pub fn wraps(&self) -> bool {
+        !matches!(self, Self::Pre | Self::Nowrap)
+    }
+
+    /// Applies the white space processing rules to a text.
+    ///
+    /// ```spec
+    /// If white-space is set to normal, nowrap, or pre-line:
+    /// - Any sequence of collapsible spaces and tabs immediately preceding or following a segment break is removed.
+    /// - Collapsible segment breaks are transformed for rendering: into spaces for normal and nowrap, and preserved for pre-line.
+    /// - Every collapsible tab is converted to a collapsible space (U+0020).
+    /// - Any collapsible space immediately following another collapsible space is collapsed to have zero advance width.
+    /// ```
+    ///
+    /// Returns the processed text. The leading space is dropped if the previous text ended with a collapsible space.
+    pub fn process(&self, text: &str, after_collapsible_space: bool) -> String {
+        if !self.collapses_spaces() {
+            return text.to_owned();
+        }
+
+        let mut processed = String::with_capacity(text.len());
+        let mut pending_space = false;
+        let mut collapsed = after_collapsible_space;
+
+        for ch in text.chars() {
+            match ch {
+                '\n' | '\r' if self.preserves_segment_breaks() => {
+                    processed.push('\n');
+                    pending_space = false;
+                    collapsed = true;
+                },
+                ' ' | '\t' | '\n' | '\r' | '\x0C' => pending_space = true,
+                ch => {
+                    if pending_space && !collapsed {
+                        processed.push(' ');
+                    }
+                    processed.push(ch);
+                    pending_space = false;
+                    collapsed = false;
+                }
+            }
+        }
+
+        if pending_space && !collapsed {
+            processed.push(' ');
+        }
+
+        processed
+    }
+}
+
+impl std::fmt::Display for WhiteSpace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Normal => "normal",
+            Self::Pre => "pre",
+            Self::Nowrap => "nowrap",
+            Self::PreWrap => "pre-wrap",
+            Self::BreakSpaces => "break-spaces",
+            Self::PreLine => "pre-line",
+        })
+    }
+}
+
+/// Parses a single keyword, into one of the given values.
+fn parse_keyword<T: Copy>(values: &[ComponentValue<'_>], keywords: &[(&str, T)]) -> ParserResult<T> {
+    let keyword = match values {
+        [value] => value.ident().map(str::to_ascii_lowercase),
+        _ => None
+    };
+
+    keywords
+        .iter()
+        .find(|(name, _)| Some(*name) == keyword.as_deref())
+        .map(|(_, value)| *value)
+        .ok_or_else(|| ParserError::UnexpectedToken(format!("{values:?}")))
+}
+
+/// line-break = auto | loose | normal | strict | anywhere
+///
+/// The strictness levels only differ in CJK text, they all apply the default UAX #14 rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineBreak {
+    #[default]
+    Auto,
+    Loose,
+    Normal,
+    Strict,
+    Anywhere,
+}
+
+impl LineBreak {
+    const KEYWORDS: [(&'static str, Self); 5] = [
+        ("auto", Self::Auto),
+        ("loose", Self::Loose),
+        ("normal", Self::Normal),
+        ("strict", Self::Strict),
+        ("anywhere", Self::Anywhere),
+    ];
+}
+
+impl TryFrom<&[ComponentValue<'_>]> for LineBreak {
+    type Error = ParserError;
+
+    fn try_from(values: &[ComponentValue<'_>]) -> ParserResult<Self> {
+        parse_keyword(values, &Self::KEYWORDS)
+    }
+}
+
+impl std::fmt::Display for LineBreak {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(Self::KEYWORDS.iter().find(|(_, value)| value == self).unwrap().0)
+    }
+}
+
+/// word-break = normal | break-all | keep-all | break-word
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WordBreak {
+    #[default]
+    Normal,
+    BreakAll,
+    KeepAll,
+    BreakWord,
+}
+
+impl WordBreak {
+    const KEYWORDS: [(&'static str, Self); 4] = [
+        ("normal", Self::Normal),
+        ("break-all", Self::BreakAll),
+        ("keep-all", Self::KeepAll),
+        ("break-word", Self::BreakWord),
+    ];
+}
+
+impl TryFrom<&[ComponentValue<'_>]> for WordBreak {
+    type Error = ParserError;
+
+    fn try_from(values: &[ComponentValue<'_>]) -> ParserResult<Self> {
+        parse_keyword(values, &Self::KEYWORDS)
+    }
+}
+
+impl std::fmt::Display for WordBreak {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(Self::KEYWORDS.iter().find(|(_, value)| value == self).unwrap().0)
+    }
+}
+
+/// overflow-wrap = normal | break-word | anywhere
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowWrap {
+    #[default]
+    Normal,
+    BreakWord,
+    Anywhere,
+}
+
+impl OverflowWrap {
+    const KEYWORDS: [(&'static str, Self); 3] = [
+        ("normal", Self::Normal),
+        ("break-word", Self::BreakWord),
+        ("anywhere", Self::Anywhere),
+    ];
+}
+
+impl TryFrom<&[ComponentValue<'_>]> for OverflowWrap {
+    type Error = ParserError;
+
+    fn try_from(values: &[ComponentValue<'_>]) -> ParserResult<Self> {
+        parse_keyword(values, &Self::KEYWORDS)
+    }
+}
+
+impl std::fmt::Display for OverflowWrap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(Self::KEYWORDS.iter().find(|(_, value)| value == self).unwrap().0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::WhiteSpace;
+
+    #[test]
+    fn test_white_space_processing() {
+        assert_eq!(WhiteSpace::Normal.process("  Hello \n\t world  ", false), " Hello world ");
+        assert_eq!(WhiteSpace::Normal.process("  Hello", true), "Hello");
+        assert_eq!(WhiteSpace::PreLine.process("Hello  \n  world", false), "Hello\nworld");
+        assert_eq!(WhiteSpace::Pre.process("  Hello\n", false), "  Hello\n");
+    }
+}
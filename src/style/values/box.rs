@@ -73,3 +73,10 @@ impl<U: std::ops::Add<U, Output = U> + Copy> Box<U> {
         self.content.clone() + self.padding.clone() + self.margin.clone() + self.border.clone()
     }
 }
+
+/// The position of a box, from the top-left corner of a reference box.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Point<U> {
+    pub x: U,
+    pub y: U,
+}
@@ -1,3 +1,64 @@
+//! Implements [CSS COLOR 4](https://www.w3.org/TR/css-color-4/),
+//! and color-mix() from [CSS COLOR 5](https://www.w3.org/TR/css-color-5/#color-mix).
+use crate::style::parser::{
+    lexer::TokenKind,
+    parser::{ComponentValue, ParserError, ParserResult},
+};
+
+use super::numeric::{Angle, AngleUnit, Number, NumberOrAngle, NumberOrPercentage, Percentage};
+
+/// A concrete color in the sRGB color space, with a straight alpha.
+///
+/// Channels range from 0 to 1, but are not clamped so that out-of-gamut colors
+/// survive conversions. They are clamped when converted to bytes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Srgba {
+    pub red: f32,
+    pub green: f32,
+    pub blue: f32,
+    pub alpha: f32,
+}
+
+impl Default for Srgba {
+    fn default() -> Self {
+        Self::BLACK
+    }
+}
+
+impl Srgba {
+    pub const BLACK: Self = Self::new(0.0, 0.0, 0.0, 1.0);
+    pub const WHITE: Self = Self::new(1.0, 1.0, 1.0, 1.0);
+    pub const TRANSPARENT: Self = Self::new(0.0, 0.0, 0.0, 0.0);
+
+    pub const fn new(red: f32, green: f32, blue: f32, alpha: f32) -> Self {
+        Self { red, green, blue, alpha }
+    }
+
+    pub fn from_rgba8(red: u8, green: u8, blue: u8, alpha: u8) -> Self {
+        Self::new(
+            red as f32 / 255.0,
+            green as f32 / 255.0,
+            blue as f32 / 255.0,
+            alpha as f32 / 255.0
+        )
+    }
+
+    /// Returns the channels as bytes, clamped into the sRGB gamut.
+    pub fn to_rgba8(&self) -> [u8; 4] {
+        [self.red, self.green, self.blue, self.alpha].map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8)
+    }
+
+    fn from_rgb(rgb: [f32; 3], alpha: f32) -> Self {
+        Self::new(rgb[0], rgb[1], rgb[2], alpha)
+    }
+
+    fn rgb(&self) -> [f32; 3] {
+        [self.red, self.green, self.blue]
+    }
+}
+
+/// <color> = <color-base> | currentColor | <system-color> | <device-cmyk()> | <light-dark()>
+#[derive(Clone)]
 pub enum Color {
     ColorBase(ColorBase),
     CurrentColor,
@@ -6,63 +67,1368 @@ pub enum Color {
     LightDark(LightDark),
 }
 
+impl Default for Color {
+    /// The initial value of the color property.
+    fn default() -> Self {
+        Self::SystemColor(SystemColor::CanvasText)
+    }
+}
+
+impl From<Srgba> for Color {
+    fn from(value: Srgba) -> Self {
+        let channel = |value: f32| Some(NumberOrPercentage::Number(Number::from(value * 255.0)));
+
+        Self::ColorBase(ColorBase::ColorFunction(ColorFunction::Rgba(Rgba {
+            red: channel(value.red),
+            green: channel(value.green),
+            blue: channel(value.blue),
+            alpha: Some(NumberOrPercentage::Number(Number::from(value.alpha))),
+        })))
+    }
+}
+
+/// <color-base> = <hex-color> | <color-function> | <named-color> | <color-mix()> | transparent
+#[derive(Clone)]
 pub enum ColorBase {
     HexColor(HexColor),
     ColorFunction(ColorFunction),
     NamedColor(NamedColor),
     ColorMix(ColorMix),
+    Transparent,
 }
 
+/// The hexadecimal digits of a hex color (#rgb, #rgba, #rrggbb or #rrggbbaa).
+#[derive(Clone)]
 pub struct HexColor(String);
 
+/// <color-function> = <rgb()> | <rgba()> | <hsl()> | <hsla()> | <hwb()> | <lab()> | <lch()> | <oklab()> | <oklch()>
+#[derive(Clone)]
 pub enum ColorFunction {
     Rgba(Rgba),
     Hsla(Hsla),
     Hwba(Hwba),
-    Laba(Lab),
+    Lab(Lab),
+    Lch(Lch),
+    Oklab(Lab),
+    Oklch(Lch),
 }
 
+/// A channel set to none is a missing component.
+pub type Channel<T> = Option<T>;
+
+#[derive(Clone)]
 pub struct Lch {
-    pub from: Option<Color>,
-    pub lightness: Option<NumberOrPercentage>,
-    pub chroma: Option<NumberOrPercentage>,
-    pub hue: Option<NumberOrAngle>,
-    pub alpha: Option<NumberOrPercentage>,
+    pub lightness: Channel<NumberOrPercentage>,
+    pub chroma: Channel<NumberOrPercentage>,
+    pub hue: Channel<NumberOrAngle>,
+    pub alpha: Channel<NumberOrPercentage>,
 }
 
+#[derive(Clone)]
 pub struct Lab {
-    pub l: Option<NumberOrPercentage>,
-    pub a: Option<NumberOrPercentage>,
-    pub b: Option<NumberOrPercentage>,
-    pub alpha: Option<NumberOrPercentage>,
+    pub l: Channel<NumberOrPercentage>,
+    pub a: Channel<NumberOrPercentage>,
+    pub b: Channel<NumberOrPercentage>,
+    pub alpha: Channel<NumberOrPercentage>,
 }
 
+#[derive(Clone)]
 pub struct Hwba {
-    pub hue: Option<NumberOrAngle>,
-    pub whiteness: Option<Percentage>,
-    pub blackness: Option<Percentage>,
-    pub alpha: Option<Percentage>,
+    pub hue: Channel<NumberOrAngle>,
+    pub whiteness: Channel<NumberOrPercentage>,
+    pub blackness: Channel<NumberOrPercentage>,
+    pub alpha: Channel<NumberOrPercentage>,
 }
 
+#[derive(Clone)]
 pub struct Hsla {
-    pub hue: Option<Angle>,
-    pub saturation: Option<Percentage>,
-    pub lightness: Option<Percentage>,
-    pub alpha: Option<Percentage>,
+    pub hue: Channel<NumberOrAngle>,
+    pub saturation: Channel<NumberOrPercentage>,
+    pub lightness: Channel<NumberOrPercentage>,
+    pub alpha: Channel<NumberOrPercentage>,
 }
 
+#[derive(Clone)]
 pub struct Rgba {
-    pub red: Option<NumberOrPercentage>,
-    pub blue: Option<NumberOrPercentage>,
-    pub green: Option<NumberOrPercentage>,
-    pub alpha: Option<Percentage>,
+    pub red: Channel<NumberOrPercentage>,
+    pub green: Channel<NumberOrPercentage>,
+    pub blue: Channel<NumberOrPercentage>,
+    pub alpha: Channel<NumberOrPercentage>,
+}
+
+/// One of the 148 named colors, as an index into the table of named colors.
+#[derive(Clone, Copy)]
+pub struct NamedColor(usize);
+
+impl NamedColor {
+    pub fn name(&self) -> &'static str {
+        NAMED_COLORS[self.0].0
+    }
+
+    pub fn rgb(&self) -> u32 {
+        NAMED_COLORS[self.0].1
+    }
+}
+
+impl TryFrom<&str> for NamedColor {
+    type Error = ParserError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        NAMED_COLORS
+            .iter()
+            .position(|(name, _)| name.eq_ignore_ascii_case(value))
+            .map(Self)
+            .ok_or_else(|| ParserError::UnexpectedToken(value.to_owned()))
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum SystemColor {
+    AccentColor,
+    AccentColorText,
+    ActiveText,
+    ButtonBorder,
+    ButtonFace,
+    ButtonText,
+    Canvas,
+    CanvasText,
+    Field,
+    FieldText,
+    GrayText,
+    Highlight,
+    HighlightText,
+    LinkText,
+    Mark,
+    MarkText,
+    SelectedItem,
+    SelectedItemText,
+    VisitedText,
+}
+
+impl TryFrom<&str> for SystemColor {
+    type Error = ParserError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.to_ascii_lowercase().as_str() {
+            "accentcolor" => Ok(Self::AccentColor),
+            "accentcolortext" => Ok(Self::AccentColorText),
+            "activetext" => Ok(Self::ActiveText),
+            "buttonborder" => Ok(Self::ButtonBorder),
+            "buttonface" => Ok(Self::ButtonFace),
+            "buttontext" => Ok(Self::ButtonText),
+            "canvas" => Ok(Self::Canvas),
+            "canvastext" => Ok(Self::CanvasText),
+            "field" => Ok(Self::Field),
+            "fieldtext" => Ok(Self::FieldText),
+            "graytext" => Ok(Self::GrayText),
+            "highlight" => Ok(Self::Highlight),
+            "highlighttext" => Ok(Self::HighlightText),
+            "linktext" => Ok(Self::LinkText),
+            "mark" => Ok(Self::Mark),
+            "marktext" => Ok(Self::MarkText),
+            "selecteditem" => Ok(Self::SelectedItem),
+            "selecteditemtext" => Ok(Self::SelectedItemText),
+            "visitedtext" => Ok(Self::VisitedText),
+            value => Err(ParserError::UnexpectedToken(value.to_owned()))
+        }
+    }
+}
+
+impl SystemColor {
+    /// Returns the color used by the renderer, following a light color scheme.
+    pub fn rgb(&self) -> u32 {
+        match self {
+            Self::AccentColor | Self::Highlight | Self::SelectedItem => 0x0078d7,
+            Self::AccentColorText | Self::HighlightText | Self::SelectedItemText => 0xffffff,
+            Self::ActiveText => 0xff0000,
+            Self::ButtonBorder => 0x767676,
+            Self::ButtonFace => 0xefefef,
+            Self::ButtonText | Self::CanvasText | Self::FieldText | Self::MarkText => 0x000000,
+            Self::Canvas | Self::Field => 0xffffff,
+            Self::GrayText => 0x808080,
+            Self::LinkText => 0x0000ee,
+            Self::Mark => 0xffff00,
+            Self::VisitedText => 0x551a8b,
+        }
+    }
+}
+
+/// device-cmyk() = device-cmyk( [ <number> | <percentage> ]{4} [ / <alpha-value> ]? )
+#[derive(Clone)]
+pub struct DeviceCmyk {
+    pub cyan: Channel<NumberOrPercentage>,
+    pub magenta: Channel<NumberOrPercentage>,
+    pub yellow: Channel<NumberOrPercentage>,
+    pub black: Channel<NumberOrPercentage>,
+    pub alpha: Channel<NumberOrPercentage>,
+}
+
+/// light-dark() = light-dark( <color>, <color> )
+#[derive(Clone)]
+pub struct LightDark {
+    pub light: Box<Color>,
+    pub dark: Box<Color>,
+}
+
+/// color-mix() = color-mix( <color-interpolation-method> , [ <color> && <percentage [0,100]>? ]#{2})
+#[derive(Clone)]
+pub struct ColorMix {
+    pub space: ColorSpace,
+    pub hue: HueInterpolation,
+    pub colors: [(Box<Color>, Option<Percentage>); 2],
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ColorSpace {
+    Srgb,
+    SrgbLinear,
+    Lab,
+    Oklab,
+    XyzD50,
+    XyzD65,
+    Hsl,
+    Hwb,
+    Lch,
+    Oklch,
+}
+
+impl TryFrom<&str> for ColorSpace {
+    type Error = ParserError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.to_ascii_lowercase().as_str() {
+            "srgb" => Ok(Self::Srgb),
+            "srgb-linear" => Ok(Self::SrgbLinear),
+            "lab" => Ok(Self::Lab),
+            "oklab" => Ok(Self::Oklab),
+            "xyz-d50" => Ok(Self::XyzD50),
+            "xyz" | "xyz-d65" => Ok(Self::XyzD65),
+            "hsl" => Ok(Self::Hsl),
+            "hwb" => Ok(Self::Hwb),
+            "lch" => Ok(Self::Lch),
+            "oklch" => Ok(Self::Oklch),
+            value => Err(ParserError::UnexpectedToken(value.to_owned()))
+        }
+    }
+}
+
+impl ColorSpace {
+    /// Returns the index of the hue channel, for polar color spaces.
+    fn hue_index(&self) -> Option<usize> {
+        match self {
+            Self::Hsl | Self::Hwb => Some(0),
+            Self::Lch | Self::Oklch => Some(2),
+            _ => None
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum HueInterpolation {
+    #[default]
+    Shorter,
+    Longer,
+    Increasing,
+    Decreasing,
+}
+
+impl TryFrom<&[ComponentValue<'_>]> for Color {
+    type Error = ParserError;
+
+    fn try_from(values: &[ComponentValue<'_>]) -> ParserResult<Self> {
+        match values {
+            [value] => Self::parse(value),
+            [] => Err(ParserError::UnexpectedEof),
+            values => Err(unexpected(&values[1]))
+        }
+    }
+}
+
+impl Color {
+    fn parse(value: &ComponentValue<'_>) -> ParserResult<Self> {
+        match value {
+            ComponentValue::Token(token) => match &token.kind {
+                TokenKind::Hash(_) => parse_hex_color(token.value).map(|hex| Self::ColorBase(ColorBase::HexColor(hex))),
+                TokenKind::Ident if token.value.eq_ignore_ascii_case("currentcolor") => Ok(Self::CurrentColor),
+                TokenKind::Ident if token.value.eq_ignore_ascii_case("transparent") => Ok(Self::ColorBase(ColorBase::Transparent)),
+                TokenKind::Ident => NamedColor::try_from(token.value)
+                    .map(|named| Self::ColorBase(ColorBase::NamedColor(named)))
+                    .or_else(|_| SystemColor::try_from(token.value).map(Self::SystemColor)),
+                _ => Err(unexpected(value))
+            },
+            ComponentValue::Function(function) => {
+                let arguments = function.arguments.as_slice();
+
+                match function.name.to_ascii_lowercase().as_str() {
+                    "rgb" | "rgba" => parse_rgb(arguments).map(ColorFunction::Rgba),
+                    "hsl" | "hsla" => parse_hsl(arguments).map(ColorFunction::Hsla),
+                    "hwb" => parse_hwb(arguments).map(ColorFunction::Hwba),
+                    "lab" => parse_lab(arguments).map(ColorFunction::Lab),
+                    "oklab" => parse_lab(arguments).map(ColorFunction::Oklab),
+                    "lch" => parse_lch(arguments).map(ColorFunction::Lch),
+                    "oklch" => parse_lch(arguments).map(ColorFunction::Oklch),
+                    "color-mix" => return parse_color_mix(arguments).map(|mix| Self::ColorBase(ColorBase::ColorMix(mix))),
+                    "light-dark" => return parse_light_dark(arguments).map(Self::LightDark),
+                    "device-cmyk" => return parse_device_cmyk(arguments).map(Self::DeviceCmyk),
+                    name => Err(ParserError::UnexpectedToken(name.to_owned()))
+                }
+                .map(|function| Self::ColorBase(ColorBase::ColorFunction(function)))
+            },
+            ComponentValue::Block(_) => Err(unexpected(value))
+        }
+    }
+
+    /// Resolves the color into a concrete sRGB color.
+    ///
+    /// The current color is the value of the color property.
+    pub fn resolve(&self, current_color: Srgba) -> Srgba {
+        match self {
+            Self::ColorBase(base) => base.resolve(current_color),
+            Self::CurrentColor => current_color,
+            Self::SystemColor(system) => rgb_to_srgba(system.rgb()),
+            Self::DeviceCmyk(cmyk) => cmyk.resolve(),
+            // Only the light color scheme is supported.
+            Self::LightDark(light_dark) => light_dark.light.resolve(current_color),
+        }
+    }
+}
+
+impl ColorBase {
+    pub fn resolve(&self, current_color: Srgba) -> Srgba {
+        match self {
+            Self::HexColor(hex) => hex.resolve(),
+            Self::ColorFunction(function) => function.resolve(),
+            Self::NamedColor(named) => rgb_to_srgba(named.rgb()),
+            Self::ColorMix(mix) => mix.resolve(current_color),
+            Self::Transparent => Srgba::TRANSPARENT,
+        }
+    }
+}
+
+impl HexColor {
+    pub fn resolve(&self) -> Srgba {
+        let digits = self.0.chars().filter_map(|ch| ch.to_digit(16)).map(|digit| digit as u8).collect::<Vec<_>>();
+
+        let byte = |hi: u8, lo: u8| hi * 16 + lo;
+
+        match digits.as_slice() {
+            [r, g, b] => Srgba::from_rgba8(byte(*r, *r), byte(*g, *g), byte(*b, *b), 255),
+            [r, g, b, a] => Srgba::from_rgba8(byte(*r, *r), byte(*g, *g), byte(*b, *b), byte(*a, *a)),
+            [r1, r2, g1, g2, b1, b2] => Srgba::from_rgba8(byte(*r1, *r2), byte(*g1, *g2), byte(*b1, *b2), 255),
+            [r1, r2, g1, g2, b1, b2, a1, a2] => Srgba::from_rgba8(byte(*r1, *r2), byte(*g1, *g2), byte(*b1, *b2), byte(*a1, *a2)),
+            _ => Srgba::BLACK
+        }
+    }
+}
+
+impl ColorFunction {
+    pub fn resolve(&self) -> Srgba {
+        match self {
+            Self::Rgba(rgba) => {
+                let channel = |channel: &Channel<NumberOrPercentage>| {
+                    channel.map(|value| value.value(255.0).clamp(0.0, 255.0) / 255.0).unwrap_or_default()
+                };
+                Srgba::new(channel(&rgba.red), channel(&rgba.green), channel(&rgba.blue), alpha(&rgba.alpha))
+            },
+            Self::Hsla(hsla) => {
+                let rgb = hsl_to_srgb([
+                    hue(&hsla.hue),
+                    percent(&hsla.saturation).max(0.0),
+                    percent(&hsla.lightness)
+                ]);
+                Srgba::from_rgb(rgb, alpha(&hsla.alpha))
+            },
+            Self::Hwba(hwba) => {
+                let rgb = hwb_to_srgb([hue(&hwba.hue), percent(&hwba.whiteness), percent(&hwba.blackness)]);
+                Srgba::from_rgb(rgb, alpha(&hwba.alpha))
+            },
+            Self::Lab(lab) => {
+                let lab_coords = [
+                    channel(&lab.l, 100.0).max(0.0),
+                    channel(&lab.a, 125.0),
+                    channel(&lab.b, 125.0)
+                ];
+                Srgba::from_rgb(from_space(ColorSpace::Lab, lab_coords), alpha(&lab.alpha))
+            },
+            Self::Lch(lch) => {
+                let lch_coords = [
+                    channel(&lch.lightness, 100.0).max(0.0),
+                    channel(&lch.chroma, 150.0).max(0.0),
+                    hue(&lch.hue)
+                ];
+                Srgba::from_rgb(from_space(ColorSpace::Lch, lch_coords), alpha(&lch.alpha))
+            },
+            Self::Oklab(lab) => {
+                let lab_coords = [
+                    channel(&lab.l, 1.0).clamp(0.0, 1.0),
+                    channel(&lab.a, 0.4),
+                    channel(&lab.b, 0.4)
+                ];
+                Srgba::from_rgb(from_space(ColorSpace::Oklab, lab_coords), alpha(&lab.alpha))
+            },
+            Self::Oklch(lch) => {
+                let lch_coords = [
+                    channel(&lch.lightness, 1.0).clamp(0.0, 1.0),
+                    channel(&lch.chroma, 0.4).max(0.0),
+                    hue(&lch.hue)
+                ];
+                Srgba::from_rgb(from_space(ColorSpace::Oklch, lch_coords), alpha(&lch.alpha))
+            },
+        }
+    }
+}
+
+impl DeviceCmyk {
+    /// ```spec
+    /// The naive conversion from CMYK to sRGB:
+    /// red = 1 - min(1, cyan * (1 - black) + black)
+    /// ```
+    pub fn resolve(&self) -> Srgba {
+        let black = channel(&self.black, 1.0);
+        let convert = |value: &Channel<NumberOrPercentage>| 1.0 - (channel(value, 1.0) * (1.0 - black) + black).min(1.0);
+        Srgba::new(convert(&self.cyan), convert(&self.magenta), convert(&self.yellow), alpha(&self.alpha))
+    }
+}
+
+impl ColorMix {
+    /// ```spec
+    /// 1. Normalize the percentages.
+    /// 2. Convert both colors to the interpolation color space, with premultiplied alpha.
+    /// 3. Interpolate, then undo the premultiplication.
+    /// 4. If the sum of the percentages is less than 100%, multiply the alpha by the sum.
+    /// ```
+    pub fn resolve(&self, current_color: Srgba) -> Srgba {
+        let [(first, p1), (second, p2)] = &self.colors;
+
+        let (p1, p2) = match (p1.map(|p| *p), p2.map(|p| *p)) {
+            (None, None) => (50.0, 50.0),
+            (Some(p1), None) => (p1, 100.0 - p1),
+            (None, Some(p2)) => (100.0 - p2, p2),
+            (Some(p1), Some(p2)) => (p1, p2),
+        };
+
+        let sum = p1 + p2;
+        if sum <= 0.0 {
+            return Srgba::TRANSPARENT;
+        }
+
+        let (w1, w2) = (p1 / sum, p2 / sum);
+        let alpha_multiplier = (sum / 100.0).min(1.0);
+
+        let first = first.resolve(current_color);
+        let second = second.resolve(current_color);
+
+        let mut c1 = to_space(self.space, first.rgb());
+        let mut c2 = to_space(self.space, second.rgb());
+        let hue_index = self.space.hue_index();
+
+        if let Some(index) = hue_index {
+            fix_hues(&mut c1[index], &mut c2[index], self.hue);
+        }
+
+        let alpha = first.alpha * w1 + second.alpha * w2;
+        let mut mixed = [0.0; 3];
+
+        for i in 0..3 {
+            mixed[i] = if Some(i) == hue_index {
+                (c1[i] * w1 + c2[i] * w2).rem_euclid(360.0)
+            } else if alpha > 0.0 {
+                (c1[i] * first.alpha * w1 + c2[i] * second.alpha * w2) / alpha
+            } else {
+                c1[i] * w1 + c2[i] * w2
+            };
+        }
+
+        Srgba::from_rgb(from_space(self.space, mixed), alpha * alpha_multiplier)
+    }
+}
+
+/// Adjusts the hues according to the hue interpolation method.
+///
+/// Powerless hues (NaN) take the value of the other one.
+fn fix_hues(h1: &mut f32, h2: &mut f32, method: HueInterpolation) {
+    match (h1.is_nan(), h2.is_nan()) {
+        (true, true) => { *h1 = 0.0; *h2 = 0.0 },
+        (true, false) => *h1 = *h2,
+        (false, true) => *h2 = *h1,
+        _ => {}
+    }
+
+    let diff = *h2 - *h1;
+
+    match method {
+        HueInterpolation::Shorter if diff > 180.0 => *h1 += 360.0,
+        HueInterpolation::Shorter if diff < -180.0 => *h2 += 360.0,
+        HueInterpolation::Longer if (0.0..180.0).contains(&diff) && diff > 0.0 => *h1 += 360.0,
+        HueInterpolation::Longer if (-180.0..=0.0).contains(&diff) && diff < 0.0 => *h2 += 360.0,
+        HueInterpolation::Increasing if diff < 0.0 => *h2 += 360.0,
+        HueInterpolation::Decreasing if diff > 0.0 => *h1 += 360.0,
+        _ => {}
+    }
+}
+
+fn rgb_to_srgba(rgb: u32) -> Srgba {
+    Srgba::from_rgba8((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8, 255)
+}
+
+/// Returns the value of a channel, a missing component is zero.
+fn channel(value: &Channel<NumberOrPercentage>, hundred_percent: f32) -> f32 {
+    value.map(|value| value.value(hundred_percent)).unwrap_or_default()
+}
+
+/// Returns the value of a percentage channel in [0, 1], numbers are interpreted as percentages.
+fn percent(value: &Channel<NumberOrPercentage>) -> f32 {
+    channel(value, 100.0) / 100.0
+}
+
+fn hue(value: &Channel<NumberOrAngle>) -> f32 {
+    value.map(|hue| hue.degrees().rem_euclid(360.0)).unwrap_or_default()
+}
+
+fn alpha(value: &Channel<NumberOrPercentage>) -> f32 {
+    channel(value, 1.0).clamp(0.0, 1.0)
+}
+
+fn unexpected(value: &ComponentValue<'_>) -> ParserError {
+    ParserError::UnexpectedToken(format!("{value:?}"))
+}
+
+fn parse_hex_color(digits: &str) -> ParserResult<HexColor> {
+    if matches!(digits.len(), 3 | 4 | 6 | 8) && digits.chars().all(|ch| ch.is_ascii_hexdigit()) {
+        Ok(HexColor(digits.to_owned()))
+    } else {
+        Err(ParserError::UnexpectedToken(format!("#{digits}")))
+    }
+}
+
+/// The arguments of a color function.
+struct ColorArguments<'a, 'i> {
+    channels: Vec<&'a ComponentValue<'i>>,
+    alpha: Option<&'a ComponentValue<'i>>,
+    /// The legacy, comma-separated, syntax is used.
+    legacy: bool,
+}
+
+impl<'a, 'i> ColorArguments<'a, 'i> {
+    /// Parse either the modern syntax `a b c [/ alpha]` or the legacy syntax `a, b, c[, alpha]`.
+    fn parse(arguments: &'a [ComponentValue<'i>], count: usize) -> ParserResult<Self> {
+        let values = arguments.iter().filter(|value| !value.is_whitespace()).collect::<Vec<_>>();
+
+        if values.iter().any(|value| value.is(TokenKind::Comma)) {
+            let parts = values.split(|value| value.is(TokenKind::Comma)).collect::<Vec<_>>();
+
+            if !(parts.len() == count || parts.len() == count + 1) || parts.iter().any(|part| part.len() != 1) {
+                return Err(ParserError::UnexpectedToken(format!("{arguments:?}")));
+            }
+
+            let mut channels = parts.into_iter().map(|part| part[0]).collect::<Vec<_>>();
+            let alpha = (channels.len() > count).then(|| channels.pop().unwrap());
+
+            if channels.iter().chain(alpha.iter()).any(|value| value.is_ident("none")) {
+                return Err(ParserError::UnexpectedToken("none".to_owned()));
+            }
+
+            return Ok(Self { channels, alpha, legacy: true });
+        }
+
+        let (channels, alpha) = match values.iter().position(|value| value.is_delim("/")) {
+            Some(slash) => match &values[slash + 1..] {
+                [alpha] => (values[..slash].to_vec(), Some(*alpha)),
+                _ => return Err(ParserError::UnexpectedToken(format!("{arguments:?}")))
+            },
+            None => (values, None)
+        };
+
+        if channels.len() != count {
+            return Err(ParserError::UnexpectedToken(format!("{arguments:?}")));
+        }
+
+        Ok(Self { channels, alpha, legacy: false })
+    }
+
+    fn alpha(&self) -> ParserResult<Channel<NumberOrPercentage>> {
+        match self.alpha {
+            Some(value) => number_or_percentage(value),
+            None => Ok(Some(NumberOrPercentage::Number(Number::from(1.0))))
+        }
+    }
+}
+
+/// Parse a number, a percentage, or none.
+fn number_or_percentage(value: &ComponentValue<'_>) -> ParserResult<Channel<NumberOrPercentage>> {
+    match value.kind() {
+        Some(TokenKind::Ident) if value.is_ident("none") => Ok(None),
+        Some(TokenKind::Number(number)) => Ok(Some(NumberOrPercentage::Number(Number::from(number.value() as f32)))),
+        Some(TokenKind::Percentage(number)) => Ok(Some(NumberOrPercentage::Percentage(Percentage::from(number.value() as f32)))),
+        _ => Err(unexpected(value))
+    }
+}
+
+/// Parse a hue, either a number, an angle, or none.
+fn number_or_angle(value: &ComponentValue<'_>) -> ParserResult<Channel<NumberOrAngle>> {
+    match value.kind() {
+        Some(TokenKind::Ident) if value.is_ident("none") => Ok(None),
+        Some(TokenKind::Number(number)) => Ok(Some(NumberOrAngle::Number(Number::from(number.value() as f32)))),
+        Some(TokenKind::Dimension(dimension)) => Ok(Some(NumberOrAngle::Angle(Angle {
+            value: dimension.number.value() as f32,
+            unit: AngleUnit::try_from(dimension.unit)?
+        }))),
+        _ => Err(unexpected(value))
+    }
+}
+
+fn percentage(value: &ComponentValue<'_>) -> ParserResult<Channel<NumberOrPercentage>> {
+    match number_or_percentage(value)? {
+        Some(NumberOrPercentage::Number(_)) => Err(unexpected(value)),
+        channel => Ok(channel)
+    }
+}
+
+/// rgb() = rgb( [<number> | <percentage> | none]{3} [ / [<alpha-value> | none] ]? )
+/// rgb() = rgb( <percentage>#{3} , <alpha-value>? ) | rgb( <number>#{3} , <alpha-value>? )
+fn parse_rgb(arguments: &[ComponentValue<'_>]) -> ParserResult<Rgba> {
+    let args = ColorArguments::parse(arguments, 3)?;
+
+    let channels = args.channels
+        .iter()
+        .map(|value| number_or_percentage(value))
+        .collect::<ParserResult<Vec<_>>>()?;
+
+    if args.legacy {
+        let percentages = channels.iter().filter(|channel| matches!(channel, Some(NumberOrPercentage::Percentage(_)))).count();
+        if percentages != 0 && percentages != 3 {
+            return Err(ParserError::UnexpectedToken(format!("{arguments:?}")));
+        }
+    }
+
+    Ok(Rgba { red: channels[0], green: channels[1], blue: channels[2], alpha: args.alpha()? })
 }
 
-pub struct NamedColor;
+/// hsl() = hsl( [<hue> | none] [<percentage> | <number> | none] [<percentage> | <number> | none] [ / [<alpha-value> | none] ]? )
+/// hsl() = hsl( <hue>, <percentage>, <percentage>, <alpha-value>? )
+fn parse_hsl(arguments: &[ComponentValue<'_>]) -> ParserResult<Hsla> {
+    let args = ColorArguments::parse(arguments, 3)?;
+    let channel = if args.legacy { percentage } else { number_or_percentage };
 
-pub struct SystemColor;
+    Ok(Hsla {
+        hue: number_or_angle(args.channels[0])?,
+        saturation: channel(args.channels[1])?,
+        lightness: channel(args.channels[2])?,
+        alpha: args.alpha()?
+    })
+}
+
+/// hwb() = hwb( [<hue> | none] [<percentage> | <number> | none] [<percentage> | <number> | none] [ / [<alpha-value> | none] ]? )
+fn parse_hwb(arguments: &[ComponentValue<'_>]) -> ParserResult<Hwba> {
+    let args = modern_arguments(arguments, 3)?;
+
+    Ok(Hwba {
+        hue: number_or_angle(args.channels[0])?,
+        whiteness: number_or_percentage(args.channels[1])?,
+        blackness: number_or_percentage(args.channels[2])?,
+        alpha: args.alpha()?
+    })
+}
+
+/// lab() = lab( [<percentage> | <number> | none] [<percentage> | <number> | none] [<percentage> | <number> | none] [ / [<alpha-value> | none] ]? )
+fn parse_lab(arguments: &[ComponentValue<'_>]) -> ParserResult<Lab> {
+    let args = modern_arguments(arguments, 3)?;
+
+    Ok(Lab {
+        l: number_or_percentage(args.channels[0])?,
+        a: number_or_percentage(args.channels[1])?,
+        b: number_or_percentage(args.channels[2])?,
+        alpha: args.alpha()?
+    })
+}
+
+/// lch() = lch( [<percentage> | <number> | none] [<percentage> | <number> | none] [<hue> | none] [ / [<alpha-value> | none] ]? )
+fn parse_lch(arguments: &[ComponentValue<'_>]) -> ParserResult<Lch> {
+    let args = modern_arguments(arguments, 3)?;
+
+    Ok(Lch {
+        lightness: number_or_percentage(args.channels[0])?,
+        chroma: number_or_percentage(args.channels[1])?,
+        hue: number_or_angle(args.channels[2])?,
+        alpha: args.alpha()?
+    })
+}
+
+fn parse_device_cmyk(arguments: &[ComponentValue<'_>]) -> ParserResult<DeviceCmyk> {
+    let args = ColorArguments::parse(arguments, 4)?;
+
+    Ok(DeviceCmyk {
+        cyan: number_or_percentage(args.channels[0])?,
+        magenta: number_or_percentage(args.channels[1])?,
+        yellow: number_or_percentage(args.channels[2])?,
+        black: number_or_percentage(args.channels[3])?,
+        alpha: args.alpha()?
+    })
+}
+
+/// Parse the arguments of a color function which has no legacy syntax.
+fn modern_arguments<'a, 'i>(arguments: &'a [ComponentValue<'i>], count: usize) -> ParserResult<ColorArguments<'a, 'i>> {
+    let args = ColorArguments::parse(arguments, count)?;
+
+    if args.legacy {
+        return Err(ParserError::UnexpectedToken(format!("{arguments:?}")));
+    }
+
+    Ok(args)
+}
+
+/// Split comma-separated arguments, and trim their whitespaces.
+fn split_arguments<'a, 'i>(arguments: &'a [ComponentValue<'i>]) -> Vec<Vec<&'a ComponentValue<'i>>> {
+    arguments
+        .split(|value| value.is(TokenKind::Comma))
+        .map(|part| part.iter().filter(|value| !value.is_whitespace()).collect())
+        .collect()
+}
+
+fn parse_light_dark(arguments: &[ComponentValue<'_>]) -> ParserResult<LightDark> {
+    let parts = split_arguments(arguments);
+
+    match parts.iter().map(Vec::as_slice).collect::<Vec<_>>().as_slice() {
+        [[light], [dark]] => Ok(LightDark {
+            light: Box::new(Color::parse(light)?),
+            dark: Box::new(Color::parse(dark)?),
+        }),
+        _ => Err(ParserError::UnexpectedToken(format!("{arguments:?}")))
+    }
+}
+
+/// color-mix() = color-mix( <color-interpolation-method> , [ <color> && <percentage [0,100]>? ]#{2})
+/// <color-interpolation-method> = in [ <rectangular-color-space> | <polar-color-space> <hue-interpolation-method>? ]
+fn parse_color_mix(arguments: &[ComponentValue<'_>]) -> ParserResult<ColorMix> {
+    let unexpected_arguments = || ParserError::UnexpectedToken(format!("{arguments:?}"));
+    let parts = split_arguments(arguments);
+
+    let [method, first, second] = parts.as_slice() else {
+        return Err(unexpected_arguments());
+    };
+
+    let idents = method
+        .iter()
+        .map(|value| value.ident().ok_or_else(|| unexpected(value)))
+        .collect::<ParserResult<Vec<_>>>()?;
+
+    let (space, hue) = match idents.as_slice() {
+        [keyword, space] if keyword.eq_ignore_ascii_case("in") => (ColorSpace::try_from(*space)?, HueInterpolation::default()),
+        [keyword, space, method, hue] if keyword.eq_ignore_ascii_case("in") && hue.eq_ignore_ascii_case("hue") => {
+            let space = ColorSpace::try_from(*space)?;
+
+            if space.hue_index().is_none() {
+                return Err(unexpected_arguments());
+            }
+
+            let method = match method.to_ascii_lowercase().as_str() {
+                "shorter" => HueInterpolation::Shorter,
+                "longer" => HueInterpolation::Longer,
+                "increasing" => HueInterpolation::Increasing,
+                "decreasing" => HueInterpolation::Decreasing,
+                _ => return Err(unexpected_arguments())
+            };
+
+            (space, method)
+        },
+        _ => return Err(unexpected_arguments())
+    };
+
+    let color_and_percentage = |values: &[&ComponentValue<'_>]| -> ParserResult<(Box<Color>, Option<Percentage>)> {
+        let as_percentage = |value: &ComponentValue<'_>| match value.kind() {
+            Some(TokenKind::Percentage(number)) if (0.0..=100.0).contains(&number.value()) => Some(Percentage::from(number.value() as f32)),
+            _ => None
+        };
+
+        match values {
+            [color] => Ok((Box::new(Color::parse(color)?), None)),
+            [color, percentage] | [percentage, color] if as_percentage(percentage).is_some() => {
+                Ok((Box::new(Color::parse(color)?), as_percentage(percentage)))
+            },
+            _ => Err(unexpected_arguments())
+        }
+    };
+
+    Ok(ColorMix {
+        space,
+        hue,
+        colors: [color_and_percentage(first)?, color_and_percentage(second)?]
+    })
+}
+
+type Matrix = [[f32; 3]; 3];
+
+fn multiply(m: &Matrix, v: [f32; 3]) -> [f32; 3] {
+    [
+        m[0][0] * v[0] + m[0][1] * v[1] + m[0][2] * v[2],
+        m[1][0] * v[0] + m[1][1] * v[1] + m[1][2] * v[2],
+        m[2][0] * v[0] + m[2][1] * v[1] + m[2][2] * v[2],
+    ]
+}
+
+const LINEAR_SRGB_TO_XYZ_D65: Matrix = [
+    [0.412_390_8, 0.357_584_33, 0.180_480_8],
+    [0.212_639, 0.715_168_7, 0.072_192_32],
+    [0.019_330_818, 0.119_194_78, 0.950_532_14],
+];
+
+const XYZ_D65_TO_LINEAR_SRGB: Matrix = [
+    [3.240_97, -1.537_383_2, -0.498_610_76],
+    [-0.969_243_65, 1.875_967_5, 0.041_555_06],
+    [0.055_630_08, -0.203_976_96, 1.056_971_5],
+];
+
+const XYZ_D65_TO_D50: Matrix = [
+    [1.047_929_8, 0.022_946_794, -0.050_192_23],
+    [0.029_627_815, 0.990_434_5, -0.017_073_825],
+    [-0.009_243_058, 0.015_055_145, 0.751_874_3],
+];
+
+const XYZ_D50_TO_D65: Matrix = [
+    [0.955_473_4, -0.023_098_537, 0.063_259_31],
+    [-0.028_369_706, 1.009_995_5, 0.021_041_399],
+    [0.012_314_002, -0.020_507_696, 1.330_366],
+];
+
+const D50_WHITE: [f32; 3] = [0.3457 / 0.3585, 1.0, (1.0 - 0.3457 - 0.3585) / 0.3585];
+
+const LAB_KAPPA: f32 = 24389.0 / 27.0;
+const LAB_EPSILON: f32 = 216.0 / 24389.0;
+
+fn srgb_to_linear(rgb: [f32; 3]) -> [f32; 3] {
+    rgb.map(|c| {
+        let abs = c.abs();
+        if abs <= 0.04045 {
+            c / 12.92
+        } else {
+            c.signum() * ((abs + 0.055) / 1.055).powf(2.4)
+        }
+    })
+}
+
+fn linear_to_srgb(rgb: [f32; 3]) -> [f32; 3] {
+    rgb.map(|c| {
+        let abs = c.abs();
+        if abs > 0.003_130_8 {
+            c.signum() * (1.055 * abs.powf(1.0 / 2.4) - 0.055)
+        } else {
+            12.92 * c
+        }
+    })
+}
+
+fn xyz_d50_to_lab(xyz: [f32; 3]) -> [f32; 3] {
+    let f = [0, 1, 2].map(|i| {
+        let value = xyz[i] / D50_WHITE[i];
+        if value > LAB_EPSILON { value.cbrt() } else { (LAB_KAPPA * value + 16.0) / 116.0 }
+    });
+
+    [116.0 * f[1] - 16.0, 500.0 * (f[0] - f[1]), 200.0 * (f[1] - f[2])]
+}
+
+fn lab_to_xyz_d50(lab: [f32; 3]) -> [f32; 3] {
+    let [l, a, b] = lab;
+    let f1 = (l + 16.0) / 116.0;
+    let f0 = a / 500.0 + f1;
+    let f2 = f1 - b / 200.0;
+
+    let x = if f0.powi(3) > LAB_EPSILON { f0.powi(3) } else { (116.0 * f0 - 16.0) / LAB_KAPPA };
+    let y = if l > LAB_KAPPA * LAB_EPSILON { f1.powi(3) } else { l / LAB_KAPPA };
+    let z = if f2.powi(3) > LAB_EPSILON { f2.powi(3) } else { (116.0 * f2 - 16.0) / LAB_KAPPA };
+
+    [x * D50_WHITE[0], y * D50_WHITE[1], z * D50_WHITE[2]]
+}
+
+fn linear_srgb_to_oklab(rgb: [f32; 3]) -> [f32; 3] {
+    let lms = multiply(&[
+        [0.412_221_46, 0.536_332_55, 0.051_445_995],
+        [0.211_903_5, 0.680_699_5, 0.107_396_96],
+        [0.088_302_46, 0.281_718_85, 0.629_978_7],
+    ], rgb).map(f32::cbrt);
+
+    multiply(&[
+        [0.210_454_26, 0.793_617_8, -0.004_072_047],
+        [1.977_998_5, -2.428_592_2, 0.450_593_7],
+        [0.025_904_037, 0.782_771_77, -0.808_675_77],
+    ], lms)
+}
+
+fn oklab_to_linear_srgb(lab: [f32; 3]) -> [f32; 3] {
+    let lms = multiply(&[
+        [1.0, 0.396_337_78, 0.215_803_76],
+        [1.0, -0.105_561_346, -0.063_854_17],
+        [1.0, -0.089_484_18, -1.291_485_5],
+    ], lab).map(|c| c.powi(3));
+
+    multiply(&[
+        [4.076_741_7, -3.307_711_6, 0.230_969_94],
+        [-1.268_438, 2.609_757_4, -0.341_319_38],
+        [-0.004_196_086_3, -0.703_418_6, 1.707_614_7],
+    ], lms)
+}
 
-pub struct DeviceCmyk;
+/// Converts rectangular coordinates (L, a, b) into polar ones (L, C, H).
+///
+/// The hue is powerless (NaN) if the chroma is almost null.
+fn lab_to_lch(lab: [f32; 3], epsilon: f32) -> [f32; 3] {
+    let [l, a, b] = lab;
+    let chroma = (a * a + b * b).sqrt();
+    let hue = if chroma <= epsilon { f32::NAN } else { b.atan2(a).to_degrees().rem_euclid(360.0) };
+    [l, chroma, hue]
+}
+
+fn lch_to_lab(lch: [f32; 3]) -> [f32; 3] {
+    let [l, c, h] = lch;
+    let h = if h.is_nan() { 0.0 } else { h.to_radians() };
+    [l, c * h.cos(), c * h.sin()]
+}
+
+fn hsl_to_srgb(hsl: [f32; 3]) -> [f32; 3] {
+    let [h, s, l] = hsl;
+    let h = if h.is_nan() { 0.0 } else { h };
+
+    let f = |n: f32| {
+        let k = (n + h / 30.0) % 12.0;
+        let a = s * l.min(1.0 - l);
+        l - a * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0)
+    };
+
+    [f(0.0), f(8.0), f(4.0)]
+}
+
+fn srgb_to_hsl(rgb: [f32; 3]) -> [f32; 3] {
+    let [r, g, b] = rgb;
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let d = max - min;
+    let l = (min + max) / 2.0;
+
+    if d.abs() < 1e-6 {
+        return [f32::NAN, 0.0, l];
+    }
+
+    let s = if l == 0.0 || l == 1.0 { 0.0 } else { (max - l) / l.min(1.0 - l) };
+
+    let h = if max == r {
+        (g - b) / d + if g < b { 6.0 } else { 0.0 }
+    } else if max == g {
+        (b - r) / d + 2.0
+    } else {
+        (r - g) / d + 4.0
+    };
+
+    [h * 60.0, s, l]
+}
+
+fn hwb_to_srgb(hwb: [f32; 3]) -> [f32; 3] {
+    let [h, w, b] = hwb;
+
+    if w + b >= 1.0 {
+        let gray = w / (w + b);
+        return [gray; 3];
+    }
 
-pub struct LightDark;
+    hsl_to_srgb([h, 1.0, 0.5]).map(|c| c * (1.0 - w - b) + w)
+}
+
+fn srgb_to_hwb(rgb: [f32; 3]) -> [f32; 3] {
+    let [h, _, _] = srgb_to_hsl(rgb);
+    let w = rgb[0].min(rgb[1]).min(rgb[2]);
+    let b = 1.0 - rgb[0].max(rgb[1]).max(rgb[2]);
+    [h, w, b]
+}
+
+/// Converts gamma-encoded sRGB coordinates into the color space.
+fn to_space(space: ColorSpace, rgb: [f32; 3]) -> [f32; 3] {
+    let linear = || srgb_to_linear(rgb);
+    let xyz_d65 = || multiply(&LINEAR_SRGB_TO_XYZ_D65, linear());
+    let xyz_d50 = || multiply(&XYZ_D65_TO_D50, xyz_d65());
+
+    match space {
+        ColorSpace::Srgb => rgb,
+        ColorSpace::SrgbLinear => linear(),
+        ColorSpace::XyzD65 => xyz_d65(),
+        ColorSpace::XyzD50 => xyz_d50(),
+        ColorSpace::Lab => xyz_d50_to_lab(xyz_d50()),
+        ColorSpace::Lch => lab_to_lch(xyz_d50_to_lab(xyz_d50()), 0.0015),
+        ColorSpace::Oklab => linear_srgb_to_oklab(linear()),
+        ColorSpace::Oklch => lab_to_lch(linear_srgb_to_oklab(linear()), 0.000_004),
+        ColorSpace::Hsl => srgb_to_hsl(rgb),
+        ColorSpace::Hwb => srgb_to_hwb(rgb),
+    }
+}
+
+/// Converts coordinates of the color space into gamma-encoded sRGB ones.
+fn from_space(space: ColorSpace, coords: [f32; 3]) -> [f32; 3] {
+    let from_xyz_d65 = |xyz| linear_to_srgb(multiply(&XYZ_D65_TO_LINEAR_SRGB, xyz));
+    let from_xyz_d50 = |xyz| from_xyz_d65(multiply(&XYZ_D50_TO_D65, xyz));
+
+    match space {
+        ColorSpace::Srgb => coords,
+        ColorSpace::SrgbLinear => linear_to_srgb(coords),
+        ColorSpace::XyzD65 => from_xyz_d65(coords),
+        ColorSpace::XyzD50 => from_xyz_d50(coords),
+        ColorSpace::Lab => from_xyz_d50(lab_to_xyz_d50(coords)),
+        ColorSpace::Lch => from_xyz_d50(lab_to_xyz_d50(lch_to_lab(coords))),
+        ColorSpace::Oklab => linear_to_srgb(oklab_to_linear_srgb(coords)),
+        ColorSpace::Oklch => linear_to_srgb(oklab_to_linear_srgb(lch_to_lab(coords))),
+        ColorSpace::Hsl => hsl_to_srgb(coords),
+        ColorSpace::Hwb => hwb_to_srgb(coords),
+    }
+}
+
+impl std::fmt::Display for Color {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ColorBase(base) => base.fmt(f),
+            Self::CurrentColor => f.write_str("currentcolor"),
+            Self::SystemColor(system) => system.fmt(f),
+            Self::DeviceCmyk(cmyk) => write_function(
+                f,
+                "device-cmyk",
+                &[to_css(&cmyk.cyan), to_css(&cmyk.magenta), to_css(&cmyk.yellow), to_css(&cmyk.black)],
+                &cmyk.alpha
+            ),
+            Self::LightDark(light_dark) => write!(f, "light-dark({}, {})", light_dark.light, light_dark.dark),
+        }
+    }
+}
+
+impl std::fmt::Display for ColorBase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::HexColor(hex) => write!(f, "#{}", hex.0.to_ascii_lowercase()),
+            Self::ColorFunction(function) => function.fmt(f),
+            Self::NamedColor(named) => f.write_str(named.name()),
+            Self::ColorMix(mix) => mix.fmt(f),
+            Self::Transparent => f.write_str("transparent"),
+        }
+    }
+}
+
+impl std::fmt::Display for ColorFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Rgba(rgba) => write_function(f, "rgb", &[to_css(&rgba.red), to_css(&rgba.green), to_css(&rgba.blue)], &rgba.alpha),
+            Self::Hsla(hsla) => write_function(f, "hsl", &[to_css(&hsla.hue), to_css(&hsla.saturation), to_css(&hsla.lightness)], &hsla.alpha),
+            Self::Hwba(hwba) => write_function(f, "hwb", &[to_css(&hwba.hue), to_css(&hwba.whiteness), to_css(&hwba.blackness)], &hwba.alpha),
+            Self::Lab(lab) => write_function(f, "lab", &[to_css(&lab.l), to_css(&lab.a), to_css(&lab.b)], &lab.alpha),
+            Self::Lch(lch) => write_function(f, "lch", &[to_css(&lch.lightness), to_css(&lch.chroma), to_css(&lch.hue)], &lch.alpha),
+            Self::Oklab(lab) => write_function(f, "oklab", &[to_css(&lab.l), to_css(&lab.a), to_css(&lab.b)], &lab.alpha),
+            Self::Oklch(lch) => write_function(f, "oklch", &[to_css(&lch.lightness), to_css(&lch.chroma), to_css(&lch.hue)], &lch.alpha),
+        }
+    }
+}
 
+impl std::fmt::Display for SystemColor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::AccentColor => "AccentColor",
+            Self::AccentColorText => "AccentColorText",
+            Self::ActiveText => "ActiveText",
+            Self::ButtonBorder => "ButtonBorder",
+            Self::ButtonFace => "ButtonFace",
+            Self::ButtonText => "ButtonText",
+            Self::Canvas => "Canvas",
+            Self::CanvasText => "CanvasText",
+            Self::Field => "Field",
+            Self::FieldText => "FieldText",
+            Self::GrayText => "GrayText",
+            Self::Highlight => "Highlight",
+            Self::HighlightText => "HighlightText",
+            Self::LinkText => "LinkText",
+            Self::Mark => "Mark",
+            Self::MarkText => "MarkText",
+            Self::SelectedItem => "SelectedItem",
+            Self::SelectedItemText => "SelectedItemText",
+            Self::VisitedText => "VisitedText",
+        })
+    }
+}
+
+impl std::fmt::Display for ColorMix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "color-mix(in {}", self.space)?;
+
+        if self.hue != HueInterpolation::default() {
+            write!(f, " {} hue", self.hue)?;
+        }
+
+        for (color, percentage) in self.colors.iter() {
+            write!(f, ", {color}")?;
+
+            if let Some(percentage) = percentage {
+                write!(f, " {percentage}")?;
+            }
+        }
+
+        f.write_str(")")
+    }
+}
+
+impl std::fmt::Display for ColorSpace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Srgb => "srgb",
+            Self::SrgbLinear => "srgb-linear",
+            Self::Lab => "lab",
+            Self::Oklab => "oklab",
+            Self::XyzD50 => "xyz-d50",
+            Self::XyzD65 => "xyz-d65",
+            Self::Hsl => "hsl",
+            Self::Hwb => "hwb",
+            Self::Lch => "lch",
+            Self::Oklch => "oklch",
+        })
+    }
+}
+
+impl std::fmt::Display for HueInterpolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Shorter => "shorter",
+            Self::Longer => "longer",
+            Self::Increasing => "increasing",
+            Self::Decreasing => "decreasing",
+        })
+    }
+}
+
+fn to_css<T: std::fmt::Display>(channel: &Channel<T>) -> String {
+    channel.as_ref().map(T::to_string).unwrap_or_else(|| "none".to_owned())
+}
+
+/// Writes a color function with the modern syntax, an opaque alpha is omitted.
+fn write_function(
+    f: &mut std::fmt::Formatter<'_>,
+    name: &str,
+    channels: &[String],
+    alpha: &Channel<NumberOrPercentage>
+) -> std::fmt::Result {
+    write!(f, "{name}({}", channels.join(" "))?;
+
+    match alpha {
+        Some(NumberOrPercentage::Number(number)) if **number == 1.0 => {},
+        Some(NumberOrPercentage::Percentage(percentage)) if **percentage == 100.0 => {},
+        alpha => write!(f, " / {}", to_css(alpha))?
+    }
+
+    f.write_str(")")
+}
+
+const NAMED_COLORS: [(&str, u32); 148] = [
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
+
+#[cfg(test)]
+mod test {
+    use crate::style::parser::parser::parse_list_of_component_values;
+
+    use super::{Color, Srgba};
+
+    fn rgba8(value: &str) -> [u8; 4] {
+        let values = parse_list_of_component_values(value).unwrap();
+        Color::try_from(values.as_slice()).unwrap().resolve(Srgba::from_rgba8(1, 2, 3, 255)).to_rgba8()
+    }
+
+    fn is_invalid(value: &str) -> bool {
+        let values = parse_list_of_component_values(value).unwrap();
+        Color::try_from(values.as_slice()).is_err()
+    }
+
+    #[test]
+    fn test_parse_colors() {
+        assert_eq!(rgba8("#f00"), [255, 0, 0, 255]);
+        assert_eq!(rgba8("#00ff0080"), [0, 255, 0, 128]);
+        assert_eq!(rgba8("RebeccaPurple"), [0x66, 0x33, 0x99, 255]);
+        assert_eq!(rgba8("transparent"), [0, 0, 0, 0]);
+        assert_eq!(rgba8("currentColor"), [1, 2, 3, 255]);
+        assert_eq!(rgba8("CanvasText"), [0, 0, 0, 255]);
+
+        assert_eq!(rgba8("rgb(255, 128, 0)"), [255, 128, 0, 255]);
+        assert_eq!(rgba8("rgba(100%, 0%, 0%, 0.5)"), [255, 0, 0, 128]);
+        assert_eq!(rgba8("rgb(255 none 50% / 25%)"), [255, 0, 128, 64]);
+        assert_eq!(rgba8("hsl(120deg 100% 25%)"), [0, 128, 0, 255]);
+        assert_eq!(rgba8("hsla(0.5turn, 100%, 50%, 1)"), [0, 255, 255, 255]);
+        assert_eq!(rgba8("hwb(0 20% 20%)"), [204, 51, 51, 255]);
+        assert_eq!(rgba8("device-cmyk(0 1 1 0)"), [255, 0, 0, 255]);
+        assert_eq!(rgba8("light-dark(white, black)"), [255, 255, 255, 255]);
+
+        assert!(is_invalid("#12345"));
+        assert!(is_invalid("rgb(255, 50%, 0)"));
+        assert!(is_invalid("rgb(255, none, 0)"));
+        assert!(is_invalid("hwb(0, 20%, 20%)"));
+        assert!(is_invalid("notacolor"));
+    }
+
+    #[test]
+    fn test_lab_colors() {
+        assert_eq!(rgba8("lab(100 0 0)"), [255, 255, 255, 255]);
+        assert_eq!(rgba8("lab(54.29 80.82 69.89)"), [255, 0, 0, 255]);
+        assert_eq!(rgba8("lch(54.29 106.84 40.85)"), [255, 0, 0, 255]);
+        assert_eq!(rgba8("oklab(62.8% 0.2249 0.1258)"), [255, 0, 0, 255]);
+        assert_eq!(rgba8("oklch(0.628 0.2577 29.23)"), [255, 0, 0, 255]);
+        assert_eq!(rgba8("oklch(0 0 none)"), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_color_mix() {
+        assert_eq!(rgba8("color-mix(in srgb, red, blue)"), [128, 0, 128, 255]);
+        assert_eq!(rgba8("color-mix(in srgb, red 25%, blue)"), [64, 0, 191, 255]);
+        assert_eq!(rgba8("color-mix(in srgb, red 20%, blue 30%)"), [102, 0, 153, 128]);
+        assert_eq!(rgba8("color-mix(in srgb, currentColor, transparent)"), [1, 2, 3, 128]);
+        assert_eq!(rgba8("color-mix(in hsl, red, blue)"), [255, 0, 255, 255]);
+        assert_eq!(rgba8("color-mix(in hsl longer hue, red, blue)"), [0, 255, 0, 255]);
+        assert_eq!(rgba8("color-mix(in oklab, white, black)"), [99, 99, 99, 255]);
+
+        assert!(is_invalid("color-mix(in srgb longer hue, red, blue)"));
+        assert!(is_invalid("color-mix(red, blue)"));
+    }
+}
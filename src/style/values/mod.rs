@@ -1,3 +1,4 @@
 pub mod r#box;
+pub mod color;
 pub mod numeric;
 
@@ -1,5 +1,12 @@
 use std::ops::Deref;
 
+use crate::style::parser::{
+    lexer::TokenKind,
+    parser::{ComponentValue, ParserError, ParserResult},
+};
+
+use super::{color::Srgba, r#box::BoxContent};
+
 #[derive(Clone, Copy, Default)]
 pub struct Integer(i32);
 
@@ -11,6 +18,23 @@ impl Deref for Integer {
     }
 }
 
+impl From<i32> for Integer {
+    fn from(value: i32) -> Self {
+        Self(value)
+    }
+}
+
+impl TryFrom<&[ComponentValue<'_>]> for Integer {
+    type Error = ParserError;
+
+    fn try_from(values: &[ComponentValue<'_>]) -> ParserResult<Self> {
+        match single_kind(values)? {
+            TokenKind::Number(number) if number.is_integer() => Ok(Self(number.value() as i32)),
+            _ => Err(unexpected(values))
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct Number(f32);
 
@@ -22,6 +46,12 @@ impl Deref for Number {
     }
 }
 
+impl From<f32> for Number {
+    fn from(value: f32) -> Self {
+        Self(value)
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct Percentage(f32);
 
@@ -33,6 +63,12 @@ impl Deref for Percentage {
     }
 }
 
+impl From<f32> for Percentage {
+    fn from(value: f32) -> Self {
+        Self(value)
+    }
+}
+
 #[derive(Clone, Copy)]
 pub enum AutoOrLengthOrPercentage {
     Auto,
@@ -46,16 +82,96 @@ impl AutoOrLengthOrPercentage {
     }
 }
 
+impl AutoOrLengthOrPercentage {
+    /// Computes the length into an absolute length, percentages are kept as they refer to the containing block.
+    pub fn compute(&self, ctx: &ResolutionContext) -> Self {
+        match self {
+            Self::Length(length) => Self::Length(length.compute(ctx)),
+            value => *value,
+        }
+    }
+
+    /// Resolves the length into pixels.
+    pub fn resolve(&self, ctx: &ResolutionContext) -> AutoOrPixelsOrPercentage {
+        match self {
+            Self::Auto => AutoOrPixelsOrPercentage::Auto,
+            Self::Length(length) => AutoOrPixelsOrPercentage::Pixels(length.to_px(ctx)),
+            Self::Percentage(percentage) => AutoOrPixelsOrPercentage::Percentage(*percentage),
+        }
+    }
+}
+
+impl TryFrom<&[ComponentValue<'_>]> for AutoOrLengthOrPercentage {
+    type Error = ParserError;
+
+    fn try_from(values: &[ComponentValue<'_>]) -> ParserResult<Self> {
+        match single_kind(values)? {
+            TokenKind::Ident if values[0].is_ident("auto") => Ok(Self::Auto),
+            TokenKind::Percentage(number) => Ok(Self::Percentage(Percentage(number.value() as f32))),
+            _ => Length::try_from(values).map(Self::Length)
+        }
+    }
+}
+
+/// A length resolved into pixels.
+#[derive(Clone, Copy)]
+pub enum AutoOrPixelsOrPercentage {
+    Auto,
+    Pixels(f64),
+    Percentage(Percentage),
+}
+
+impl Default for AutoOrPixelsOrPercentage {
+    fn default() -> Self {
+        Self::Pixels(0.0)
+    }
+}
+
+impl AutoOrPixelsOrPercentage {
+    /// Returns the value in pixels, percentages are resolved against the given basis.
+    ///
+    /// Returns nothing if the value is auto.
+    pub fn px(&self, percentage_basis: f64) -> Option<f64> {
+        match self {
+            Self::Auto => None,
+            Self::Pixels(px) => Some(*px),
+            Self::Percentage(percentage) => Some(percentage_basis * (**percentage as f64) / 100.0),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
 pub enum NumberOrPercentage {
     Number(Number),
     Percentage(Percentage),
 }
 
+impl NumberOrPercentage {
+    /// Returns the value, a percentage of 100% being equal to the given reference number.
+    pub fn value(&self, hundred_percent: f32) -> f32 {
+        match self {
+            Self::Number(number) => **number,
+            Self::Percentage(percentage) => **percentage * hundred_percent / 100.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
 pub enum NumberOrAngle {
     Number(Number),
     Angle(Angle<f32>),
 }
 
+impl NumberOrAngle {
+    /// Returns the angle in degrees, a number is interpreted as degrees.
+    pub fn degrees(&self) -> f32 {
+        match self {
+            Self::Number(number) => **number,
+            Self::Angle(angle) => angle.degrees(),
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct Dimension<Numeric, Unit> {
     pub value: Numeric,
@@ -73,6 +189,22 @@ impl Length {
     }
 }
 
+impl TryFrom<&[ComponentValue<'_>]> for Length {
+    type Error = ParserError;
+
+    /// Parse a length, a unitless zero is allowed.
+    fn try_from(values: &[ComponentValue<'_>]) -> ParserResult<Self> {
+        match single_kind(values)? {
+            TokenKind::Number(number) if number.value() == 0.0 => Ok(Self::px(0)),
+            TokenKind::Dimension(dimension) => Ok(Self {
+                value: dimension.number.value(),
+                unit: LengthUnit::try_from(dimension.unit)?
+            }),
+            _ => Err(unexpected(values))
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 pub enum LengthUnit {
     Em,
@@ -92,6 +224,93 @@ pub enum LengthUnit {
     Px,
 }
 
+impl Length {
+    /// Returns the length in pixels, relative units are resolved from the context.
+    pub fn to_px(&self, ctx: &ResolutionContext) -> f64 {
+        let px = match self.unit {
+            LengthUnit::Px => 1.0,
+            LengthUnit::In => 96.0,
+            LengthUnit::Cm => 96.0 / 2.54,
+            LengthUnit::Mm => 96.0 / 25.4,
+            LengthUnit::Q => 96.0 / 101.6,
+            LengthUnit::Pt => 96.0 / 72.0,
+            LengthUnit::Pc => 16.0,
+            LengthUnit::Em => ctx.font_size,
+            // Without font metrics, 1ex and 1ch are assumed to be 0.5em.
+            LengthUnit::Ex | LengthUnit::Ch => ctx.font_size / 2.0,
+            LengthUnit::Rem => ctx.root_font_size,
+            LengthUnit::Vw => ctx.viewport.width / 100.0,
+            LengthUnit::Vh => ctx.viewport.height / 100.0,
+            LengthUnit::Vmin => ctx.viewport.width.min(ctx.viewport.height) / 100.0,
+            LengthUnit::Vmax => ctx.viewport.width.max(ctx.viewport.height) / 100.0,
+        };
+
+        self.value * px
+    }
+
+    /// Computes the length into an absolute length, relative units are converted into pixels from the context.
+    pub fn compute(&self, ctx: &ResolutionContext) -> Self {
+        if self.unit.is_absolute() {
+            *self
+        } else {
+            Self { value: self.to_px(ctx), unit: LengthUnit::Px }
+        }
+    }
+}
+
+/// The context in which relative values are resolved.
+#[derive(Clone)]
+pub struct ResolutionContext {
+    /// The color of the element, to which currentColor refers.
+    pub current_color: Srgba,
+    /// The font size of the element, in pixels.
+    pub font_size: f64,
+    /// The font size of the root element, in pixels.
+    pub root_font_size: f64,
+    /// The font weight of the element.
+    pub font_weight: f64,
+    /// The size of the viewport, or of the page area in paged media, in pixels.
+    pub viewport: BoxContent<f64>,
+}
+
+impl Default for ResolutionContext {
+    fn default() -> Self {
+        Self {
+            current_color: Srgba::BLACK,
+            font_size: 16.0,
+            root_font_size: 16.0,
+            font_weight: 400.0,
+            // A4 page
+            viewport: BoxContent { width: 793.7, height: 1122.5 },
+        }
+    }
+}
+
+impl TryFrom<&str> for LengthUnit {
+    type Error = ParserError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.to_ascii_lowercase().as_str() {
+            "em" => Ok(Self::Em),
+            "ex" => Ok(Self::Ex),
+            "ch" => Ok(Self::Ch),
+            "rem" => Ok(Self::Rem),
+            "vw" => Ok(Self::Vw),
+            "vh" => Ok(Self::Vh),
+            "vmin" => Ok(Self::Vmin),
+            "vmax" => Ok(Self::Vmax),
+            "cm" => Ok(Self::Cm),
+            "mm" => Ok(Self::Mm),
+            "q" => Ok(Self::Q),
+            "in" => Ok(Self::In),
+            "pt" => Ok(Self::Pt),
+            "pc" => Ok(Self::Pc),
+            "px" => Ok(Self::Px),
+            unit => Err(ParserError::UnexpectedToken(unit.to_owned()))
+        }
+    }
+}
+
 impl LengthUnit {
     pub fn is_relative(&self) -> bool {
         matches!(
@@ -114,6 +333,7 @@ impl LengthUnit {
     }
 }
 
+#[derive(Clone, Copy)]
 pub enum AngleUnit {
     Deg,
     Grad,
@@ -121,8 +341,33 @@ pub enum AngleUnit {
     Turn,
 }
 
+impl TryFrom<&str> for AngleUnit {
+    type Error = ParserError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.to_ascii_lowercase().as_str() {
+            "deg" => Ok(Self::Deg),
+            "grad" => Ok(Self::Grad),
+            "rad" => Ok(Self::Rad),
+            "turn" => Ok(Self::Turn),
+            unit => Err(ParserError::UnexpectedToken(unit.to_owned()))
+        }
+    }
+}
+
 pub type Angle<Numeric> = Dimension<Numeric, AngleUnit>;
 
+impl Angle<f32> {
+    pub fn degrees(&self) -> f32 {
+        match self.unit {
+            AngleUnit::Deg => self.value,
+            AngleUnit::Grad => self.value * 0.9,
+            AngleUnit::Rad => self.value.to_degrees(),
+            AngleUnit::Turn => self.value * 360.0,
+        }
+    }
+}
+
 pub enum FrequencyUnit {
     Hz,
     KHz,
@@ -138,3 +383,101 @@ pub enum ResolutionUnit {
 
 pub type Resolution<Numeric> = Dimension<Numeric, ResolutionUnit>;
 
+
+/// Returns the kind of the token, if the values consist of a single token.
+fn single_kind<'a, 'i>(values: &'a [ComponentValue<'i>]) -> ParserResult<&'a TokenKind<'i>> {
+    match values {
+        [value] => value.kind().ok_or_else(|| unexpected(values)),
+        [] => Err(ParserError::UnexpectedEof),
+        _ => Err(unexpected(values))
+    }
+}
+
+fn unexpected(values: &[ComponentValue<'_>]) -> ParserError {
+    ParserError::UnexpectedToken(format!("{values:?}"))
+}
+
+impl std::fmt::Display for Integer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::fmt::Display for Number {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::fmt::Display for Percentage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}%", self.0)
+    }
+}
+
+impl std::fmt::Display for NumberOrPercentage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Number(number) => number.fmt(f),
+            Self::Percentage(percentage) => percentage.fmt(f),
+        }
+    }
+}
+
+impl std::fmt::Display for NumberOrAngle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Number(number) => number.fmt(f),
+            Self::Angle(angle) => angle.fmt(f),
+        }
+    }
+}
+
+impl<Numeric: std::fmt::Display, Unit: std::fmt::Display> std::fmt::Display for Dimension<Numeric, Unit> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.value, self.unit)
+    }
+}
+
+impl std::fmt::Display for LengthUnit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Em => "em",
+            Self::Ex => "ex",
+            Self::Ch => "ch",
+            Self::Rem => "rem",
+            Self::Vw => "vw",
+            Self::Vh => "vh",
+            Self::Vmin => "vmin",
+            Self::Vmax => "vmax",
+            Self::Cm => "cm",
+            Self::Mm => "mm",
+            Self::Q => "q",
+            Self::In => "in",
+            Self::Pt => "pt",
+            Self::Pc => "pc",
+            Self::Px => "px",
+        })
+    }
+}
+
+impl std::fmt::Display for AngleUnit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Deg => "deg",
+            Self::Grad => "grad",
+            Self::Rad => "rad",
+            Self::Turn => "turn",
+        })
+    }
+}
+
+impl std::fmt::Display for AutoOrLengthOrPercentage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Auto => f.write_str("auto"),
+            Self::Length(length) => length.fmt(f),
+            Self::Percentage(percentage) => percentage.fmt(f),
+        }
+    }
+}
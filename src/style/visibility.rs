@@ -1,21 +1,22 @@
-#[derive(Clone, Copy)]
+use super::parser::parser::{ComponentValue, ParserError, ParserResult};
+
+#[derive(Clone, Copy, Default)]
 pub enum Visibility {
     Collapse,
     Hidden,
+    #[default]
     Visible,
 }
 
-impl Default for Visibility {
-    fn default() -> Self {
-        Self::Visible
-    }
-}
+impl TryFrom<&[ComponentValue<'_>]> for Visibility {
+    type Error = ParserError;
 
-pub mod initial {
-    pub use super::Visibility;
-}
-
-pub mod computed {
-    pub use super::Visibility;
+    fn try_from(values: &[ComponentValue<'_>]) -> ParserResult<Self> {
+        match values {
+            [value] if value.is_ident("visible") => Ok(Self::Visible),
+            [value] if value.is_ident("hidden") => Ok(Self::Hidden),
+            [value] if value.is_ident("collapse") => Ok(Self::Collapse),
+            _ => Err(ParserError::UnexpectedToken(format!("{values:?}")))
+        }
+    }
 }
-
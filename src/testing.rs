@@ -0,0 +1,63 @@
+//! Lays out the documents of the unit tests.
+use crate::{
+    dom::DocumentObjectModel,
+    ecs::systems::tree::TreeExplorer,
+    layout::fragment_tree::{Fragment, FragmentTree},
+    style::{cascade::{Origin, StyleSheet}, values::r#box::BoxContent},
+    RenderingContext,
+};
+
+/// The viewport of the tests, in CSS pixels.
+pub const VIEWPORT: BoxContent<f64> = BoxContent { width: 800.0, height: 600.0 };
+
+/// The fonts shipped with the tests, so that the text is shaped whatever the fonts of the system.
+pub const FONTS_DIRECTORY: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fonts");
+
+/// The family of the font shipped with the tests.
+pub const FONT_FAMILY: &str = "Oleron Test";
+
+/// Lays out the document, styled by the style sheet alone, in the viewport of the tests.
+///
+/// Returns the context, and the root fragment.
+pub fn lay_out<'a>(dom: &'a DocumentObjectModel, css: &str) -> (RenderingContext<'a, DocumentObjectModel>, Fragment) {
+    lay_out_in(dom, css, VIEWPORT)
+}
+
+/// Lays out the document, styled by the style sheet alone, in a viewport of the given size.
+///
+/// The text is set in the font of the tests, unless the style sheet sets another family.
+pub fn lay_out_in<'a>(
+    dom: &'a DocumentObjectModel,
+    css: &str,
+    viewport: BoxContent<f64>,
+) -> (RenderingContext<'a, DocumentObjectModel>, Fragment) {
+    let font = format!(r#"html {{ font-family: "{FONT_FAMILY}" }}"#);
+    let mut ctx = RenderingContext::builder(dom)
+        .user_agent_stylesheet(false)
+        .fonts_directory(FONTS_DIRECTORY)
+        .stylesheet(StyleSheet::parse(&font, Origin::User))
+        .stylesheet(StyleSheet::parse(css, Origin::Author))
+        .viewport(viewport)
+        .build();
+
+    ctx.style();
+    ctx.generate_boxes().unwrap();
+    ctx.generate_fragments().unwrap();
+    let root = ctx.lay_out().unwrap();
+    (ctx, root)
+}
+
+/// The text of the text sequences of the fragment and its descendants, in tree order.
+pub fn text_content(fragments: &FragmentTree, fragment: Fragment) -> String {
+    let mut texts = Vec::new();
+    let mut stack = vec![fragment];
+
+    while let Some(fragment) = stack.pop() {
+        if let Some(seq) = fragments.text_sequences.borrow(&fragment) {
+            texts.push(seq.as_str().to_owned());
+        }
+        stack.extend(fragments.iter_children(fragment).collect::<Vec<_>>().into_iter().rev());
+    }
+
+    texts.concat()
+}